@@ -1,15 +1,46 @@
 //! Alerting module for threshold-based notifications.
 
-use crate::metrics::{CpuMetrics, DiskMetrics, MemoryMetrics, NetworkMetrics};
-use crate::process::ProcessMetrics;
+use crate::display::format_age;
+use crate::metrics::{CpuMetrics, DiskMetrics, MemoryMetrics, NetworkMetrics, PsiMetrics};
+use crate::process::{ProcessMetrics, ProcessState};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// Samples the leak detector fits its growth line to
+const LEAK_WINDOW: usize = 30;
+/// With fewer samples a short burst looks like steady growth
+const LEAK_MIN_SAMPLES: usize = 10;
+/// How closely usage must follow the fitted line (R²) to count as steady growth
+const LEAK_MIN_FIT: f64 = 0.8;
 
 /// Alert severity level
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Severity {
     Warning,
     Critical,
+    /// A previously alerted condition returned to normal
+    Resolved,
+}
+
+/// Minimum alert severity that makes the run fail (--fail-on-alert)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum FailOn {
+    /// Fail on any warning or critical alert
+    Warning,
+    /// Fail only on critical alerts
+    Critical,
+}
+
+impl FailOn {
+    /// Whether an alert of this severity fails the run
+    pub fn matches(self, severity: Severity) -> bool {
+        match severity {
+            Severity::Warning => self == FailOn::Warning,
+            Severity::Critical => true,
+            Severity::Resolved => false,
+        }
+    }
 }
 
 /// An alert triggered by a threshold breach
@@ -18,9 +49,27 @@ pub struct Alert {
     pub timestamp: DateTime<Utc>,
     pub severity: Severity,
     pub category: String,
+    /// Identifies the alert condition (e.g., "cpu_warn", "disk_sda_crit")
+    pub key: String,
     pub message: String,
 }
 
+/// Key of the alert raised when the monitored process is OOM-killed
+pub const OOM_KILL_KEY: &str = "process_oom_kill";
+
+impl Alert {
+    /// Critical alert for an OOM kill of the monitored process (an event, so it never resolves)
+    pub fn oom_kill(pid: u32, name: &str) -> Self {
+        Self {
+            timestamp: Utc::now(),
+            severity: Severity::Critical,
+            category: "Process".to_string(),
+            key: OOM_KILL_KEY.to_string(),
+            message: format!("Process {} (PID {}) was OOM-killed", name, pid),
+        }
+    }
+}
+
 /// Alert threshold configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AlertThresholds {
@@ -49,6 +98,11 @@ pub struct AlertThresholds {
     /// Disk queue depth critical threshold
     pub disk_queue_crit: f64,
 
+    /// Watched filesystem usage warning threshold (%)
+    pub fs_used_warn: f64,
+    /// Inode usage warning threshold (%) for the spill directory and watched filesystems
+    pub inodes_used_warn: f64,
+
     /// IO wait warning threshold (%)
     pub iowait_warn: f64,
     /// IO wait critical threshold (%)
@@ -58,6 +112,48 @@ pub struct AlertThresholds {
     pub process_rss_warn: Option<u64>,
     /// Process RSS critical threshold (bytes)
     pub process_rss_crit: Option<u64>,
+
+    /// Process open file descriptor count warning threshold
+    pub process_fd_warn: Option<u64>,
+    /// Process open file descriptor count critical threshold
+    pub process_fd_crit: Option<u64>,
+    /// Process open fds as % of its soft RLIMIT_NOFILE for a warning
+    pub process_fd_limit_warn: f64,
+    /// Process open fds as % of its soft RLIMIT_NOFILE for a critical alert
+    pub process_fd_limit_crit: f64,
+    /// Process thread count warning threshold
+    pub process_threads_warn: Option<u64>,
+    /// Process thread count critical threshold
+    pub process_threads_crit: Option<u64>,
+
+    /// Dirty + writeback pages as % of the throttling limit (vm.dirty_ratio) for a warning
+    pub dirty_limit_warn: f64,
+    /// Dirty + writeback pages as % of the throttling limit for a critical alert
+    pub dirty_limit_crit: f64,
+
+    /// Seconds in uninterruptible sleep (D state) before a process warning
+    pub process_dstate_warn_secs: f64,
+    /// Seconds in uninterruptible sleep (D state) before a process critical
+    pub process_dstate_crit_secs: f64,
+
+    /// Memory PSI some avg10 warning threshold (%)
+    pub psi_mem_warn: f64,
+    /// Memory PSI some avg10 critical threshold (%)
+    pub psi_mem_crit: f64,
+    /// I/O PSI some avg10 warning threshold (%)
+    pub psi_io_warn: f64,
+    /// I/O PSI some avg10 critical threshold (%)
+    pub psi_io_crit: f64,
+
+    /// Per-interface RX+TX errors per second warning threshold
+    pub net_error_rate_warn: f64,
+    /// Per-interface RX+TX drops per second warning threshold
+    pub net_drop_rate_warn: f64,
+    /// Retransmitted share of sent TCP segments warning threshold (%)
+    pub tcp_retransmit_warn: f64,
+
+    /// Warn when steady memory growth would reach the limit within this many seconds (None disables)
+    pub leak_horizon_secs: Option<f64>,
 }
 
 impl Default for AlertThresholds {
@@ -73,10 +169,30 @@ impl Default for AlertThresholds {
             disk_util_crit: 90.0,
             disk_queue_warn: 5.0,
             disk_queue_crit: 20.0,
+            fs_used_warn: 90.0,
+            inodes_used_warn: 90.0,
             iowait_warn: 30.0,
             iowait_crit: 60.0,
             process_rss_warn: None,
             process_rss_crit: None,
+            process_fd_warn: None,
+            process_fd_crit: None,
+            process_fd_limit_warn: 80.0,
+            process_fd_limit_crit: 95.0,
+            process_threads_warn: None,
+            process_threads_crit: None,
+            dirty_limit_warn: 80.0,
+            dirty_limit_crit: 95.0,
+            process_dstate_warn_secs: 10.0,
+            process_dstate_crit_secs: 30.0,
+            psi_mem_warn: 10.0,
+            psi_mem_crit: 30.0,
+            psi_io_warn: 20.0,
+            psi_io_crit: 50.0,
+            net_error_rate_warn: 1.0,
+            net_drop_rate_warn: 100.0,
+            tcp_retransmit_warn: 2.0,
+            leak_horizon_secs: None,
         }
     }
 }
@@ -84,34 +200,71 @@ impl Default for AlertThresholds {
 /// Alert checker that maintains state to avoid duplicate alerts
 pub struct AlertChecker {
     thresholds: AlertThresholds,
-    active_alerts: Vec<String>, // Track active alert keys to avoid duplicates
+    /// Last alert fired per condition that has not recovered yet
+    active_alerts: std::collections::HashMap<String, Alert>,
     cooldown_secs: i64,
     last_alert_time: std::collections::HashMap<String, DateTime<Utc>>,
+    /// PID and start time of the monitored process's current D-state streak
+    dstate_since: Option<(u32, DateTime<Utc>)>,
+    /// Cgroup swap usage at the previous check
+    prev_cgroup_swap: Option<u64>,
+    /// Per-interface (errors, drops) totals and the time of the previous check
+    prev_net_counters: std::collections::HashMap<String, (u64, u64)>,
+    prev_net_check: Option<DateTime<Utc>>,
+    /// Consecutive breaching samples required before an alert fires
+    sustained_samples: u32,
+    /// Consecutive breaching samples per condition (alert key without _warn/_crit)
+    breach_streaks: std::collections::HashMap<String, u32>,
+    /// Conditions breached during the current check
+    breached: std::collections::HashSet<String>,
+    /// Current reading of each condition whose metric was present during the current check
+    readings: std::collections::HashMap<String, String>,
+    /// Recent (time, bytes) readings of the memory the leak detector follows
+    leak_history: VecDeque<(DateTime<Utc>, u64)>,
+    /// PID whose RSS `leak_history` holds (None for cgroup usage)
+    leak_pid: Option<u32>,
 }
 
 impl AlertChecker {
     pub fn new(thresholds: AlertThresholds) -> Self {
         Self {
             thresholds,
-            active_alerts: Vec::new(),
+            active_alerts: std::collections::HashMap::new(),
             cooldown_secs: 10, // Don't repeat same alert for 10 seconds
             last_alert_time: std::collections::HashMap::new(),
+            dstate_since: None,
+            prev_cgroup_swap: None,
+            prev_net_counters: std::collections::HashMap::new(),
+            prev_net_check: None,
+            sustained_samples: 1,
+            breach_streaks: std::collections::HashMap::new(),
+            breached: std::collections::HashSet::new(),
+            readings: std::collections::HashMap::new(),
+            leak_history: VecDeque::with_capacity(LEAK_WINDOW + 1),
+            leak_pid: None,
         }
     }
 
+    /// Only fire an alert once its condition has held for this many consecutive samples
+    pub fn set_sustained_samples(&mut self, samples: u32) {
+        self.sustained_samples = samples.max(1);
+    }
+
     /// Check metrics and return any new alerts
     pub fn check(
         &mut self,
         cpu: &CpuMetrics,
         memory: &MemoryMetrics,
         disk: &DiskMetrics,
-        _network: &NetworkMetrics,
+        network: &NetworkMetrics,
+        psi: Option<&PsiMetrics>,
         process: Option<&ProcessMetrics>,
     ) -> Vec<Alert> {
         let mut alerts = Vec::new();
         let now = Utc::now();
 
         // CPU alerts
+        self.observe("cpu", format!("{:.1}%", cpu.total_utilization));
         if cpu.total_utilization >= self.thresholds.cpu_crit {
             self.maybe_alert(
                 &mut alerts,
@@ -133,6 +286,7 @@ impl AlertChecker {
         }
 
         // IO Wait alerts
+        self.observe("iowait", format!("IOWait {:.1}%", cpu.iowait_percent));
         if cpu.iowait_percent >= self.thresholds.iowait_crit {
             self.maybe_alert(
                 &mut alerts,
@@ -154,6 +308,7 @@ impl AlertChecker {
         }
 
         // Memory alerts
+        self.observe("memory", format!("{:.1}% used", memory.used_percent));
         if memory.used_percent >= self.thresholds.memory_crit {
             self.maybe_alert(
                 &mut alerts,
@@ -174,8 +329,34 @@ impl AlertChecker {
             );
         }
 
+        // Dirty page throttling alerts (writers stall in balance_dirty_pages at the limit)
+        if let Some(dirty_pct) = memory.dirty_limit_percent() {
+            let growth = memory.dirty_growth_bytes_per_sec.unwrap_or(0.0);
+            self.observe("dirty", format!("dirty pages at {:.0}% of throttle limit", dirty_pct));
+            if dirty_pct >= self.thresholds.dirty_limit_crit {
+                self.maybe_alert(
+                    &mut alerts,
+                    now,
+                    "dirty_crit",
+                    Severity::Critical,
+                    "Memory",
+                    format!("Dirty pages critical: {:.0}% of throttle limit ({:+.1} MB/s)", dirty_pct, growth / (1024.0 * 1024.0)),
+                );
+            } else if dirty_pct >= self.thresholds.dirty_limit_warn {
+                self.maybe_alert(
+                    &mut alerts,
+                    now,
+                    "dirty_warn",
+                    Severity::Warning,
+                    "Memory",
+                    format!("Dirty pages warning: {:.0}% of throttle limit ({:+.1} MB/s)", dirty_pct, growth / (1024.0 * 1024.0)),
+                );
+            }
+        }
+
         // Cgroup memory alerts
         if let Some(cgroup_pct) = memory.cgroup_usage_percent {
+            self.observe("cgroup", format!("cgroup memory {:.1}%", cgroup_pct));
             if cgroup_pct >= self.thresholds.cgroup_crit {
                 self.maybe_alert(
                     &mut alerts,
@@ -197,7 +378,28 @@ impl AlertChecker {
             }
         }
 
+        // Cgroup swap alert: growing swap usage means this container is swapping out right now
+        if let (Some(prev), Some(current)) = (self.prev_cgroup_swap, memory.cgroup_swap_current) {
+            self.observe("cgroup_swap", format!("{:.1} MB in cgroup swap", current as f64 / (1024.0 * 1024.0)));
+            if current > prev {
+                self.maybe_alert(
+                    &mut alerts,
+                    now,
+                    "cgroup_swap",
+                    Severity::Warning,
+                    "Memory",
+                    format!(
+                        "Cgroup swapping: +{:.1} MB since last sample ({:.1} MB in swap)",
+                        (current - prev) as f64 / (1024.0 * 1024.0),
+                        current as f64 / (1024.0 * 1024.0)
+                    ),
+                );
+            }
+        }
+        self.prev_cgroup_swap = memory.cgroup_swap_current;
+
         // Swap usage alert
+        self.observe("swap", format!("swap {:.1}% used", memory.swap_percent));
         if memory.swap_used > 0 {
             self.maybe_alert(
                 &mut alerts,
@@ -214,6 +416,8 @@ impl AlertChecker {
 
         // Disk alerts
         for d in &disk.disks {
+            self.observe(&format!("disk_{}", d.device), format!("Disk {} {:.1}%", d.device, d.utilization_percent));
+            self.observe(&format!("disk_{}_queue", d.device), format!("Disk {} queue {:.1}", d.device, d.queue_depth));
             if d.utilization_percent >= self.thresholds.disk_util_crit {
                 self.maybe_alert(
                     &mut alerts,
@@ -255,8 +459,142 @@ impl AlertChecker {
             }
         }
 
+        // Watched filesystem space alerts
+        for fs in &disk.watched_fs {
+            self.observe(&format!("fs_{}", fs.path), format!("Filesystem {} {:.1}% used", fs.path, fs.used_percent));
+            if fs.used_percent >= self.thresholds.fs_used_warn {
+                self.maybe_alert(
+                    &mut alerts,
+                    now,
+                    &format!("fs_{}_warn", fs.path),
+                    Severity::Warning,
+                    "Disk",
+                    format!(
+                        "Filesystem {} warning: {:.1}% used ({} bytes free)",
+                        fs.path, fs.used_percent, fs.available_bytes
+                    ),
+                );
+            }
+        }
+
+        // Inode exhaustion breaks file creation even with plenty of free bytes
+        for fs in disk.spill_dir_info.iter().chain(&disk.watched_fs) {
+            if fs.inodes_total > 0 {
+                self.observe(
+                    &format!("fs_{}_inodes", fs.path),
+                    format!("Filesystem {} inodes {:.1}% used", fs.path, fs.inodes_used_percent),
+                );
+            }
+            if fs.inodes_total > 0 && fs.inodes_used_percent >= self.thresholds.inodes_used_warn {
+                self.maybe_alert(
+                    &mut alerts,
+                    now,
+                    &format!("fs_{}_inodes_warn", fs.path),
+                    Severity::Warning,
+                    "Disk",
+                    format!(
+                        "Filesystem {} inodes warning: {:.1}% used ({} free)",
+                        fs.path, fs.inodes_used_percent, fs.inodes_free
+                    ),
+                );
+            }
+        }
+
+        // Network alerts: the interface counters are totals, so compare against the previous check
+        let elapsed_secs = self.prev_net_check
+            .map(|prev| (now - prev).num_milliseconds() as f64 / 1000.0)
+            .filter(|secs| *secs > 0.0);
+        let mut net_counters = std::collections::HashMap::new();
+        for iface in &network.interfaces {
+            let errors = iface.rx_errors + iface.tx_errors;
+            let drops = iface.rx_drops + iface.tx_drops;
+            if let (Some(&(prev_errors, prev_drops)), Some(secs)) =
+                (self.prev_net_counters.get(&iface.interface), elapsed_secs)
+            {
+                let error_rate = errors.saturating_sub(prev_errors) as f64 / secs;
+                let drop_rate = drops.saturating_sub(prev_drops) as f64 / secs;
+                let name = &iface.interface;
+                self.observe(&format!("net_{}_errors", name), format!("Interface {} errors {:.1}/s", name, error_rate));
+                self.observe(&format!("net_{}_drops", name), format!("Interface {} drops {:.1}/s", name, drop_rate));
+                if error_rate > 0.0 && error_rate >= self.thresholds.net_error_rate_warn {
+                    self.maybe_alert(
+                        &mut alerts,
+                        now,
+                        &format!("net_{}_errors_warn", iface.interface),
+                        Severity::Warning,
+                        "Network",
+                        format!("Interface {} errors: {:.1}/s", iface.interface, error_rate),
+                    );
+                }
+                if drop_rate > 0.0 && drop_rate >= self.thresholds.net_drop_rate_warn {
+                    self.maybe_alert(
+                        &mut alerts,
+                        now,
+                        &format!("net_{}_drops_warn", iface.interface),
+                        Severity::Warning,
+                        "Network",
+                        format!("Interface {} drops: {:.1}/s", iface.interface, drop_rate),
+                    );
+                }
+            }
+            net_counters.insert(iface.interface.clone(), (errors, drops));
+        }
+        self.prev_net_counters = net_counters;
+        self.prev_net_check = Some(now);
+
+        if let Some(retx_pct) = network.tcp.retransmit_percent() {
+            self.observe("tcp_retransmit", format!("TCP retransmits {:.1}%", retx_pct));
+            if retx_pct > 0.0 && retx_pct >= self.thresholds.tcp_retransmit_warn {
+                self.maybe_alert(
+                    &mut alerts,
+                    now,
+                    "tcp_retransmit_warn",
+                    Severity::Warning,
+                    "Network",
+                    format!(
+                        "TCP retransmits: {:.1}% of sent segments ({} retransmitted)",
+                        retx_pct,
+                        network.tcp.retransmits_delta.unwrap_or(0)
+                    ),
+                );
+            }
+        }
+
+        // Pressure stall alerts: share of the last 10s some task waited on memory or I/O
+        if let Some(psi) = psi {
+            let pressures = [
+                ("mem", "Memory", psi.memory.some_avg10, self.thresholds.psi_mem_warn, self.thresholds.psi_mem_crit),
+                ("io", "I/O", psi.io.some_avg10, self.thresholds.psi_io_warn, self.thresholds.psi_io_crit),
+            ];
+            for (key, resource, some_avg10, warn, crit) in pressures {
+                self.observe(&format!("psi_{}", key), format!("{} pressure {:.1}%", resource, some_avg10));
+                if some_avg10 >= crit {
+                    self.maybe_alert(
+                        &mut alerts,
+                        now,
+                        &format!("psi_{}_crit", key),
+                        Severity::Critical,
+                        "PSI",
+                        format!("{} pressure critical: {:.1}% stalled (some avg10)", resource, some_avg10),
+                    );
+                } else if some_avg10 >= warn {
+                    self.maybe_alert(
+                        &mut alerts,
+                        now,
+                        &format!("psi_{}_warn", key),
+                        Severity::Warning,
+                        "PSI",
+                        format!("{} pressure warning: {:.1}% stalled (some avg10)", resource, some_avg10),
+                    );
+                }
+            }
+        }
+
         // Process alerts
         if let Some(proc) = process {
+            if self.thresholds.process_rss_warn.is_some() || self.thresholds.process_rss_crit.is_some() {
+                self.observe("process_rss", format!("Process {} RSS {} bytes", proc.name, proc.rss_bytes));
+            }
             if let Some(rss_crit) = self.thresholds.process_rss_crit {
                 if proc.rss_bytes >= rss_crit {
                     self.maybe_alert(
@@ -292,11 +630,220 @@ impl AlertChecker {
                     );
                 }
             }
+
+            // Descriptor and thread counts: at the kernel limits open() fails with
+            // EMFILE and thread creation with EAGAIN, far from where the cause shows
+            let counts = [
+                ("fds", "open fds", proc.num_fds, self.thresholds.process_fd_warn, self.thresholds.process_fd_crit),
+                ("threads", "threads", proc.num_threads, self.thresholds.process_threads_warn, self.thresholds.process_threads_crit),
+            ];
+            for (key, what, count, warn, crit) in counts {
+                self.observe(&format!("process_{}", key), format!("Process {} {} {}", proc.name, what, count));
+                if crit.is_some_and(|crit| count >= crit) {
+                    self.maybe_alert(
+                        &mut alerts,
+                        now,
+                        &format!("process_{}_crit", key),
+                        Severity::Critical,
+                        "Process",
+                        format!("Process {} {} critical: {}", proc.name, what, count),
+                    );
+                } else if warn.is_some_and(|warn| count >= warn) {
+                    self.maybe_alert(
+                        &mut alerts,
+                        now,
+                        &format!("process_{}_warn", key),
+                        Severity::Warning,
+                        "Process",
+                        format!("Process {} {} warning: {}", proc.name, what, count),
+                    );
+                }
+            }
+            if let (Some(pct), Some(limit)) = (proc.fd_limit_percent, proc.fd_limit) {
+                self.observe("process_fd_limit", format!("Process {} at {:.0}% of its open file limit", proc.name, pct));
+                if pct >= self.thresholds.process_fd_limit_crit {
+                    self.maybe_alert(
+                        &mut alerts,
+                        now,
+                        "process_fd_limit_crit",
+                        Severity::Critical,
+                        "Process",
+                        format!("Process {} at {:.0}% of its open file limit ({})", proc.name, pct, limit),
+                    );
+                } else if pct >= self.thresholds.process_fd_limit_warn {
+                    self.maybe_alert(
+                        &mut alerts,
+                        now,
+                        "process_fd_limit_warn",
+                        Severity::Warning,
+                        "Process",
+                        format!("Process {} at {:.0}% of its open file limit ({})", proc.name, pct, limit),
+                    );
+                }
+            }
+
+            // Uninterruptible sleep: a brief D state is normal, a long streak
+            // usually means stuck I/O or a hung mount
+            if proc.state == ProcessState::DiskSleep {
+                let since = match self.dstate_since {
+                    Some((pid, since)) if pid == proc.pid => since,
+                    _ => {
+                        self.dstate_since = Some((proc.pid, now));
+                        now
+                    }
+                };
+                let stuck_secs = (now - since).num_milliseconds() as f64 / 1000.0;
+                self.observe(
+                    "process_dstate",
+                    format!("Process {} in uninterruptible sleep for {:.0}s", proc.name, stuck_secs),
+                );
+
+                if stuck_secs >= self.thresholds.process_dstate_crit_secs {
+                    self.maybe_alert(
+                        &mut alerts,
+                        now,
+                        "process_dstate_crit",
+                        Severity::Critical,
+                        "Process",
+                        format!(
+                            "Process {} (PID {}) stuck in uninterruptible sleep for {:.0}s",
+                            proc.name, proc.pid, stuck_secs
+                        ),
+                    );
+                } else if stuck_secs >= self.thresholds.process_dstate_warn_secs {
+                    self.maybe_alert(
+                        &mut alerts,
+                        now,
+                        "process_dstate_warn",
+                        Severity::Warning,
+                        "Process",
+                        format!(
+                            "Process {} (PID {}) in uninterruptible sleep for {:.0}s",
+                            proc.name, proc.pid, stuck_secs
+                        ),
+                    );
+                }
+            } else {
+                self.dstate_since = None;
+                self.observe("process_dstate", format!("Process {} {}", proc.name, proc.state));
+            }
+        } else {
+            self.dstate_since = None;
+        }
+
+        if let Some(horizon_secs) = self.thresholds.leak_horizon_secs {
+            self.check_leak(&mut alerts, now, horizon_secs, memory, process);
+        }
+
+        // A condition not breached this sample (below its warn level) starts over
+        let breached = std::mem::take(&mut self.breached);
+        self.breach_streaks.retain(|condition, _| breached.contains(condition));
+
+        // Alerted conditions whose metric is still reported but no longer breaches any
+        // threshold have recovered; a metric that went missing says nothing either way
+        let readings = std::mem::take(&mut self.readings);
+        let mut recovered: Vec<String> = self.active_alerts
+            .keys()
+            .filter(|condition| !breached.contains(*condition) && readings.contains_key(*condition))
+            .cloned()
+            .collect();
+        recovered.sort();
+        for condition in recovered {
+            let last = match self.active_alerts.remove(&condition) {
+                Some(last) => last,
+                None => continue,
+            };
+            // A new incident of the same condition should alert without waiting out the cooldown
+            for suffix in ["", "_warn", "_crit"] {
+                self.last_alert_time.remove(&format!("{}{}", condition, suffix));
+            }
+            alerts.push(Alert {
+                timestamp: now,
+                severity: Severity::Resolved,
+                category: last.category.clone(),
+                key: format!("{}_resolved", condition),
+                message: format!("{} recovered: {} (was: {})", last.category, readings[&condition], last.message),
+            });
         }
 
         alerts
     }
 
+    /// Warn when process RSS (or cgroup usage without a process) grows steadily
+    /// enough to reach its limit within `horizon_secs`
+    ///
+    /// A leak stays well under any fixed threshold until shortly before the OOM
+    /// killer steps in, so the growth rate is projected instead.
+    fn check_leak(
+        &mut self,
+        alerts: &mut Vec<Alert>,
+        now: DateTime<Utc>,
+        horizon_secs: f64,
+        memory: &MemoryMetrics,
+        process: Option<&ProcessMetrics>,
+    ) {
+        let cgroup_headroom = memory.cgroup_limit
+            .zip(memory.cgroup_current)
+            .map(|(limit, current)| limit.saturating_sub(current));
+        let (pid, what, used, headroom) = match process {
+            Some(proc) => {
+                // Without an RSS cap the process can grow until the host or its cgroup runs out
+                let headroom = match self.thresholds.process_rss_crit {
+                    Some(crit) => crit.saturating_sub(proc.rss_bytes),
+                    None => cgroup_headroom.map_or(memory.available, |h| h.min(memory.available)),
+                };
+                (Some(proc.pid), format!("Process {} RSS", proc.name), proc.rss_bytes, headroom)
+            }
+            None => match (memory.cgroup_current, cgroup_headroom) {
+                (Some(current), Some(headroom)) => (None, "Cgroup memory".to_string(), current, headroom),
+                _ => {
+                    self.leak_history.clear();
+                    return;
+                }
+            },
+        };
+
+        if pid != self.leak_pid {
+            self.leak_history.clear();
+            self.leak_pid = pid;
+        }
+        self.leak_history.push_back((now, used));
+        if self.leak_history.len() > LEAK_WINDOW {
+            self.leak_history.pop_front();
+        }
+
+        let Some(bytes_per_sec) = steady_growth(&self.leak_history) else {
+            self.observe("memory_leak", format!("{} not growing steadily", what));
+            return;
+        };
+        let secs_to_limit = headroom as f64 / bytes_per_sec;
+        self.observe(
+            "memory_leak",
+            format!("{} growing {:.1} MB/min", what, bytes_per_sec * 60.0 / (1024.0 * 1024.0)),
+        );
+        if secs_to_limit <= horizon_secs {
+            self.maybe_alert(
+                alerts,
+                now,
+                "memory_leak_warn",
+                Severity::Warning,
+                "Memory",
+                format!(
+                    "{} growing {:.1} MB/min, limit in ~{}",
+                    what,
+                    bytes_per_sec * 60.0 / (1024.0 * 1024.0),
+                    format_age(secs_to_limit as u64)
+                ),
+            );
+        }
+    }
+
+    /// Record the current reading of a condition (alert key without _warn/_crit),
+    /// which lets an active alert on it recover
+    fn observe(&mut self, condition: &str, reading: String) {
+        self.readings.insert(condition.to_string(), reading);
+    }
+
     fn maybe_alert(
         &mut self,
         alerts: &mut Vec<Alert>,
@@ -306,6 +853,16 @@ impl AlertChecker {
         category: &str,
         message: String,
     ) {
+        // Warn and crit levels of a condition share one streak, so escalating
+        // from warning to critical doesn't restart the count
+        let condition = key.trim_end_matches("_crit").trim_end_matches("_warn");
+        if self.breached.insert(condition.to_string()) {
+            *self.breach_streaks.entry(condition.to_string()).or_default() += 1;
+        }
+        if self.breach_streaks[condition] < self.sustained_samples {
+            return;
+        }
+
         // Check cooldown
         if let Some(last_time) = self.last_alert_time.get(key) {
             let elapsed = (now - *last_time).num_seconds();
@@ -316,12 +873,15 @@ impl AlertChecker {
 
         self.last_alert_time.insert(key.to_string(), now);
 
-        alerts.push(Alert {
+        let alert = Alert {
             timestamp: now,
             severity,
             category: category.to_string(),
+            key: key.to_string(),
             message,
-        });
+        };
+        self.active_alerts.insert(condition.to_string(), alert.clone());
+        alerts.push(alert);
     }
 
     /// Get current thresholds
@@ -334,3 +894,124 @@ impl AlertChecker {
         self.thresholds = thresholds;
     }
 }
+
+/// Slope in bytes/s of the least-squares line through (time, bytes) readings,
+/// if they rise steadily along it rather than jumping around
+fn steady_growth(history: &VecDeque<(DateTime<Utc>, u64)>) -> Option<f64> {
+    if history.len() < LEAK_MIN_SAMPLES {
+        return None;
+    }
+    let start = history.front()?.0;
+    let points: Vec<(f64, f64)> = history
+        .iter()
+        .map(|(at, bytes)| ((*at - start).num_milliseconds() as f64 / 1000.0, *bytes as f64))
+        .collect();
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+
+    let (mut sxx, mut sxy, mut syy) = (0.0, 0.0, 0.0);
+    for (x, y) in &points {
+        let (dx, dy) = (x - mean_x, y - mean_y);
+        sxx += dx * dx;
+        sxy += dx * dy;
+        syy += dy * dy;
+    }
+    if sxx <= 0.0 || syy <= 0.0 {
+        return None;
+    }
+    let slope = sxy / sxx;
+    let r_squared = sxy * sxy / (sxx * syy);
+    (slope > 0.0 && r_squared >= LEAK_MIN_FIT).then_some(slope)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::network::InterfaceStats;
+
+    fn network(rx_errors: u64, rx_drops: u64) -> NetworkMetrics {
+        NetworkMetrics {
+            interfaces: vec![InterfaceStats {
+                interface: "eth0".to_string(),
+                rx_errors,
+                rx_drops,
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    /// Keys of the alerts raised for one check of `network`
+    fn check_network(checker: &mut AlertChecker, network: &NetworkMetrics) -> Vec<String> {
+        let (cpu, memory, disk) = (CpuMetrics::default(), MemoryMetrics::default(), DiskMetrics::default());
+        checker.check(&cpu, &memory, &disk, network, None, None)
+            .into_iter()
+            .map(|a| a.key)
+            .collect()
+    }
+
+    #[test]
+    fn rising_rx_errors_alert() {
+        let mut checker = AlertChecker::new(AlertThresholds::default());
+        // The first check only records the totals
+        assert!(!check_network(&mut checker, &network(100, 0)).contains(&"net_eth0_errors_warn".to_string()));
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        assert!(check_network(&mut checker, &network(10_000, 0)).contains(&"net_eth0_errors_warn".to_string()));
+    }
+
+    #[test]
+    fn steady_error_total_does_not_alert() {
+        let mut checker = AlertChecker::new(AlertThresholds::default());
+        check_network(&mut checker, &network(100, 500));
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        let keys = check_network(&mut checker, &network(100, 500));
+        assert!(!keys.iter().any(|k| k.starts_with("net_eth0")));
+    }
+
+    #[test]
+    fn drops_below_threshold_do_not_alert() {
+        let thresholds = AlertThresholds { net_drop_rate_warn: 1e12, ..Default::default() };
+        let mut checker = AlertChecker::new(thresholds);
+        check_network(&mut checker, &network(0, 0));
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        let keys = check_network(&mut checker, &network(0, 1000));
+        assert!(!keys.contains(&"net_eth0_drops_warn".to_string()));
+    }
+
+    /// Alerts raised for one check at `utilization` percent CPU
+    fn check_cpu(checker: &mut AlertChecker, utilization: f64) -> Vec<Alert> {
+        let cpu = CpuMetrics { total_utilization: utilization, ..Default::default() };
+        let (memory, disk, network) = (MemoryMetrics::default(), DiskMetrics::default(), NetworkMetrics::default());
+        checker.check(&cpu, &memory, &disk, &network, None, None)
+    }
+
+    #[test]
+    fn recovery_reports_the_current_reading() {
+        let mut checker = AlertChecker::new(AlertThresholds::default());
+        assert!(check_cpu(&mut checker, 99.0).iter().any(|a| a.key == "cpu_crit"));
+        let alerts = check_cpu(&mut checker, 42.0);
+        let resolved = alerts.iter().find(|a| a.key == "cpu_resolved").expect("cpu should recover");
+        assert_eq!(resolved.severity, Severity::Resolved);
+        assert!(resolved.message.starts_with("CPU recovered: 42.0%"), "{}", resolved.message);
+    }
+
+    #[test]
+    fn missing_data_does_not_recover() {
+        let thresholds = AlertThresholds { tcp_retransmit_warn: 1.0, ..Default::default() };
+        let mut checker = AlertChecker::new(thresholds);
+        let mut retransmitting = NetworkMetrics::default();
+        retransmitting.tcp.out_segs_delta = Some(100);
+        retransmitting.tcp.retransmits_delta = Some(10);
+        let keys = check_network(&mut checker, &retransmitting);
+        assert!(keys.contains(&"tcp_retransmit_warn".to_string()), "{:?}", keys);
+
+        // No TCP deltas on this sample: the alert stays active instead of recovering
+        let keys = check_network(&mut checker, &NetworkMetrics::default());
+        assert!(!keys.contains(&"tcp_retransmit_resolved".to_string()), "{:?}", keys);
+
+        retransmitting.tcp.retransmits_delta = Some(0);
+        let keys = check_network(&mut checker, &retransmitting);
+        assert!(keys.contains(&"tcp_retransmit_resolved".to_string()), "{:?}", keys);
+    }
+}
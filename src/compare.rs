@@ -0,0 +1,127 @@
+//! Before/after comparison of two recorded runs (--compare).
+//!
+//! Each run is summarized with the same accumulator as a live session, then the
+//! summaries are printed side by side with the change from baseline to candidate.
+
+use crate::display::{format_bytes, format_throughput};
+use crate::logging::{MetricsSummary, SummaryAccumulator};
+use crate::replay;
+use anyhow::Result;
+use std::fmt::Write as _;
+use std::path::Path;
+
+/// How a compared value is formatted
+#[derive(Clone, Copy)]
+enum Unit {
+    Percent,
+    Seconds,
+    Count,
+    Bytes,
+    Rate,
+}
+
+impl Unit {
+    fn format(self, value: f64) -> String {
+        match self {
+            Unit::Percent => format!("{:.1}%", value),
+            Unit::Seconds => format!("{:.1} s", value),
+            Unit::Count => format!("{:.0}", value),
+            Unit::Bytes => format_bytes(value as u64),
+            Unit::Rate => format_throughput(value),
+        }
+    }
+
+    /// Change from `base` to `cand`: percentage points for percentages, absolute and relative otherwise
+    fn change(self, base: f64, cand: f64) -> String {
+        let delta = cand - base;
+        if delta == 0.0 {
+            return "no change".to_string();
+        }
+        if let Unit::Percent = self {
+            return format!("{:+.1} pp", delta);
+        }
+        let sign = if delta < 0.0 { "-" } else { "+" };
+        let absolute = format!("{}{}", sign, self.format(delta.abs()));
+        if base != 0.0 {
+            format!("{} ({:+.1}%)", absolute, 100.0 * delta / base)
+        } else {
+            absolute
+        }
+    }
+}
+
+/// Summarize a recorded run like a live session would
+fn summarize(path: &Path) -> Result<MetricsSummary> {
+    let mut accumulator = SummaryAccumulator::new();
+    for sample in replay::load_samples(path)? {
+        accumulator.add_sample(sample);
+    }
+    accumulator
+        .generate_summary()
+        .ok_or_else(|| anyhow::anyhow!("No samples found in {}", path.display()))
+}
+
+/// Side-by-side text diff of the summaries of two runs
+pub fn summary_diff(baseline: &Path, candidate: &Path) -> Result<String> {
+    let base = summarize(baseline)?;
+    let cand = summarize(candidate)?;
+
+    let mut rows: Vec<(&str, Unit, Option<f64>, Option<f64>)> = vec![
+        ("Duration", Unit::Seconds, Some(base.duration_secs), Some(cand.duration_secs)),
+        ("Samples", Unit::Count, Some(base.samples_count as f64), Some(cand.samples_count as f64)),
+        ("CPU avg", Unit::Percent, Some(base.cpu_avg_utilization), Some(cand.cpu_avg_utilization)),
+        ("CPU max", Unit::Percent, Some(base.cpu_max_utilization), Some(cand.cpu_max_utilization)),
+        ("CPU iowait avg", Unit::Percent, Some(base.cpu_avg_iowait), Some(cand.cpu_avg_iowait)),
+        ("CPU steal avg", Unit::Percent, Some(base.cpu_avg_steal), Some(cand.cpu_avg_steal)),
+        ("Memory avg", Unit::Percent, Some(base.memory_avg_used_percent), Some(cand.memory_avg_used_percent)),
+        ("Memory max", Unit::Percent, Some(base.memory_max_used_percent), Some(cand.memory_max_used_percent)),
+        ("Memory peak", Unit::Bytes, Some(base.memory_max_used_bytes as f64), Some(cand.memory_max_used_bytes as f64)),
+        ("Cgroup memory max", Unit::Percent, base.cgroup_max_usage_percent, cand.cgroup_max_usage_percent),
+        ("Swap peak", Unit::Bytes, Some(base.swap_max_used as f64), Some(cand.swap_max_used as f64)),
+        ("Disk read max", Unit::Rate, Some(base.disk_max_read_throughput), Some(cand.disk_max_read_throughput)),
+        ("Disk write max", Unit::Rate, Some(base.disk_max_write_throughput), Some(cand.disk_max_write_throughput)),
+        ("Disk utilization max", Unit::Percent, Some(base.disk_max_utilization), Some(cand.disk_max_utilization)),
+        ("Network RX total", Unit::Bytes, Some(base.network_total_rx_bytes as f64), Some(cand.network_total_rx_bytes as f64)),
+        ("Network TX total", Unit::Bytes, Some(base.network_total_tx_bytes as f64), Some(cand.network_total_tx_bytes as f64)),
+        ("Network RX max", Unit::Rate, Some(base.network_max_rx_throughput), Some(cand.network_max_rx_throughput)),
+        ("Network TX max", Unit::Rate, Some(base.network_max_tx_throughput), Some(cand.network_max_tx_throughput)),
+        ("Process CPU max", Unit::Percent, base.process_max_cpu, cand.process_max_cpu),
+        ("Process RSS peak", Unit::Bytes, base.process_max_rss.map(|b| b as f64), cand.process_max_rss.map(|b| b as f64)),
+        ("Process FDs max", Unit::Count, base.process_max_fds.map(|n| n as f64), cand.process_max_fds.map(|n| n as f64)),
+    ];
+    // Rows neither run has data for (no cgroup, no process) are left out
+    rows.retain(|(_, _, b, c)| b.is_some() || c.is_some());
+
+    let name = |path: &Path| path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    let mut out = String::new();
+    writeln!(out, "{}", "=".repeat(76))?;
+    writeln!(out, "COMPARISON: {} (baseline) vs {} (candidate)", name(baseline), name(candidate))?;
+    writeln!(out, "{}", "=".repeat(76))?;
+    writeln!(out, "{:<22} {:>14} {:>14}  Change", "Metric", "Baseline", "Candidate")?;
+    for (label, unit, b, c) in rows {
+        let show = |v: Option<f64>| v.map(|v| unit.format(v)).unwrap_or_else(|| "-".to_string());
+        let change = match (b, c) {
+            (Some(b), Some(c)) => unit.change(b, c),
+            _ => String::new(),
+        };
+        writeln!(out, "{:<22} {:>14} {:>14}  {}", label, show(b), show(c), change)?;
+    }
+
+    // Bottlenecks that appeared or went away
+    let new: Vec<&String> = cand.bottleneck_indicators.iter().filter(|i| !base.bottleneck_indicators.contains(i)).collect();
+    let gone: Vec<&String> = base.bottleneck_indicators.iter().filter(|i| !cand.bottleneck_indicators.contains(i)).collect();
+    if !new.is_empty() {
+        writeln!(out, "\nNew in candidate:")?;
+        for indicator in new {
+            writeln!(out, "  + {}", indicator)?;
+        }
+    }
+    if !gone.is_empty() {
+        writeln!(out, "\nResolved in candidate:")?;
+        for indicator in gone {
+            writeln!(out, "  - {}", indicator)?;
+        }
+    }
+    writeln!(out, "{}", "=".repeat(76))?;
+    Ok(out)
+}
@@ -0,0 +1,287 @@
+//! TOML config file (--config) with monitoring defaults.
+//!
+//! Keys mirror the long command-line flags (`cpu-warn = 70`, `log = "run.csv"`).
+//! A value from the file only applies when the flag was not given on the command
+//! line, so a committed profile can still be adjusted per run.
+//!
+//! Named profiles (--profile, --save-profile) are config files kept in
+//! `$XDG_CONFIG_HOME/monperf/profiles/<name>.toml`.
+
+use crate::alert::FailOn;
+use crate::display::Theme;
+use crate::logging::OutputFormat;
+use crate::process::MatchChoice;
+use crate::Args;
+use anyhow::{Context, Result};
+use clap::parser::ValueSource;
+use clap::{ArgMatches, Parser, ValueEnum};
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+
+/// Settings loaded from a config file; every field is optional
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct ConfigFile {
+    // Process selection
+    process_name: Option<String>,
+    no_process_filter: Option<bool>,
+    process_match: Option<String>,
+    tree: Option<bool>,
+    threads: Option<usize>,
+
+    // Sampling
+    interval: Option<f64>,
+    duration: Option<u64>,
+    auto_interval: Option<bool>,
+    min_interval: Option<f64>,
+    max_interval: Option<f64>,
+    no_tui: Option<bool>,
+    format: Option<String>,
+    summary: Option<bool>,
+
+    // Logging
+    log: Option<PathBuf>,
+    csv_precision: Option<usize>,
+    csv_preamble: Option<bool>,
+    text_log: Option<PathBuf>,
+    per_core_log: Option<PathBuf>,
+    capture: Option<PathBuf>,
+    json_log: Option<PathBuf>,
+    influx_file: Option<PathBuf>,
+    append: Option<bool>,
+    skip_first: Option<bool>,
+    flush_interval: Option<u64>,
+    compress_logs: Option<bool>,
+    split_on_process: Option<bool>,
+    log_max_size: Option<u64>,
+    log_rotate_interval: Option<u64>,
+    compact_on_exit: Option<bool>,
+    delete_segments: Option<bool>,
+
+    // Disks and filesystems
+    spill_dir: Option<PathBuf>,
+    disk_include_partitions: Option<bool>,
+    disk_device: Option<Vec<String>>,
+    watch_fs: Option<Vec<PathBuf>>,
+    fs_warn: Option<f64>,
+    inode_warn: Option<f64>,
+
+    // Alert thresholds
+    cpu_warn: Option<f64>,
+    cpu_crit: Option<f64>,
+    mem_warn: Option<f64>,
+    mem_crit: Option<f64>,
+    cgroup_warn: Option<f64>,
+    cgroup_crit: Option<f64>,
+    psi_mem_warn: Option<f64>,
+    psi_mem_crit: Option<f64>,
+    psi_io_warn: Option<f64>,
+    psi_io_crit: Option<f64>,
+    net_error_warn: Option<f64>,
+    net_drop_warn: Option<f64>,
+    retransmit_warn: Option<f64>,
+    dstate_warn: Option<f64>,
+    dstate_crit: Option<f64>,
+    fd_warn: Option<u64>,
+    fd_crit: Option<u64>,
+    fd_limit_warn: Option<f64>,
+    fd_limit_crit: Option<f64>,
+    threads_warn: Option<u64>,
+    threads_crit: Option<u64>,
+    leak_detect: Option<bool>,
+    leak_horizon: Option<f64>,
+    alert_sustained: Option<u32>,
+    alert_window: Option<u64>,
+    fail_on_alert: Option<String>,
+    fail_exit_code: Option<i32>,
+
+    // Display
+    cgroup_primary: Option<bool>,
+    theme: Option<String>,
+    history: Option<u64>,
+    net_bits: Option<bool>,
+    ethtool: Option<bool>,
+    top_talkers: Option<bool>,
+    gpu: Option<bool>,
+
+    // Export and control
+    control_port: Option<u16>,
+    statsd: Option<String>,
+    dogstatsd: Option<bool>,
+    prometheus_port: Option<u16>,
+    prometheus_bind: Option<IpAddr>,
+    snapshot: Option<PathBuf>,
+    alert_webhook: Option<String>,
+}
+
+impl ConfigFile {
+    /// Read and parse a TOML config file
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config file {}", path.display()))?;
+        toml::from_str(&text).with_context(|| format!("Invalid config file {}", path.display()))
+    }
+
+    /// The settings of `args` that differ from the defaults, for saving as a profile
+    ///
+    /// Log and snapshot destinations, --duration and --no-tui describe a single run rather than a
+    /// kind of host, so they are left out.
+    pub fn from_args(args: &Args) -> Self {
+        let defaults = Args::try_parse_from(["monperf"]).expect("defaults parse without arguments");
+        let mut file = Self::default();
+
+        macro_rules! value {
+            ($($field:ident),* $(,)?) => {$(
+                if args.$field != defaults.$field {
+                    file.$field = Some(args.$field.clone());
+                }
+            )*};
+        }
+        macro_rules! optional {
+            ($($field:ident),* $(,)?) => {$(
+                file.$field = args.$field.clone();
+            )*};
+        }
+        macro_rules! choice {
+            ($($field:ident),* $(,)?) => {$(
+                if args.$field != defaults.$field {
+                    file.$field = args.$field.to_possible_value().map(|v| v.get_name().to_string());
+                }
+            )*};
+        }
+
+        value!(
+            no_process_filter, tree, interval, auto_interval, min_interval, max_interval, summary,
+            csv_preamble, append, skip_first, flush_interval, compress_logs, split_on_process, compact_on_exit, delete_segments,
+            disk_include_partitions, disk_device, watch_fs, fs_warn, inode_warn,
+            cpu_warn, cpu_crit, mem_warn, mem_crit, cgroup_warn, cgroup_crit,
+            psi_mem_warn, psi_mem_crit, psi_io_warn, psi_io_crit,
+            net_error_warn, net_drop_warn, retransmit_warn, dstate_warn, dstate_crit, fd_limit_warn, fd_limit_crit,
+            leak_detect, leak_horizon,
+            alert_sustained, alert_window, fail_exit_code,
+            cgroup_primary, history, net_bits, ethtool, top_talkers, gpu, dogstatsd, prometheus_bind,
+        );
+        optional!(
+            process_name, threads, csv_precision, log_max_size, log_rotate_interval, spill_dir,
+            fd_warn, fd_crit, threads_warn, threads_crit, control_port, statsd, prometheus_port, alert_webhook,
+        );
+        choice!(process_match, theme, format);
+        file.fail_on_alert = args.fail_on_alert
+            .and_then(|level| level.to_possible_value())
+            .map(|v| v.get_name().to_string());
+        file
+    }
+
+    /// Record the network unit chosen in the TUI ('b') instead of the starting one
+    pub fn set_net_bits(&mut self, enabled: bool) {
+        self.net_bits = enabled.then_some(true);
+    }
+
+    /// Write the settings as TOML, creating the profile directory if needed
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        let text = toml::to_string(self).context("Failed to encode profile")?;
+        std::fs::write(path, text).with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Fill in `args` from the file, leaving options given on the command line untouched
+    pub fn apply(self, args: &mut Args, matches: &ArgMatches) -> Result<()> {
+        let from_cli = |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+
+        // Flags with a default (or plain switches): the file value replaces it
+        macro_rules! value {
+            ($($field:ident),* $(,)?) => {$(
+                if let Some(v) = self.$field {
+                    if !from_cli(stringify!($field)) {
+                        args.$field = v;
+                    }
+                }
+            )*};
+        }
+        // Optional flags: the file value turns them on
+        macro_rules! optional {
+            ($($field:ident),* $(,)?) => {$(
+                if let Some(v) = self.$field {
+                    if !from_cli(stringify!($field)) {
+                        args.$field = Some(v);
+                    }
+                }
+            )*};
+        }
+        // Value enums are written as their command-line names (e.g., theme = "mono")
+        macro_rules! choice {
+            ($($field:ident: $ty:ty),* $(,)?) => {$(
+                if let Some(ref v) = self.$field {
+                    if !from_cli(stringify!($field)) {
+                        let parsed = <$ty as ValueEnum>::from_str(v, true).map_err(|_| {
+                            anyhow::anyhow!("Invalid {} '{}' in config file", stringify!($field).replace('_', "-"), v)
+                        })?;
+                        args.$field = parsed.into();
+                    }
+                }
+            )*};
+        }
+
+        value!(
+            no_process_filter, tree, interval, auto_interval, min_interval, max_interval, no_tui, summary,
+            csv_preamble, append, skip_first, flush_interval, compress_logs, split_on_process, compact_on_exit, delete_segments,
+            disk_include_partitions, disk_device, watch_fs, fs_warn, inode_warn,
+            cpu_warn, cpu_crit, mem_warn, mem_crit, cgroup_warn, cgroup_crit,
+            psi_mem_warn, psi_mem_crit, psi_io_warn, psi_io_crit,
+            net_error_warn, net_drop_warn, retransmit_warn, dstate_warn, dstate_crit, fd_limit_warn, fd_limit_crit,
+            leak_detect, leak_horizon,
+            alert_sustained, alert_window, fail_exit_code,
+            cgroup_primary, history, net_bits, ethtool, top_talkers, gpu, dogstatsd, prometheus_bind,
+        );
+        optional!(
+            process_name, threads, duration, log, csv_precision, text_log, per_core_log, capture, json_log, influx_file,
+            log_max_size, log_rotate_interval, spill_dir, fd_warn, fd_crit, threads_warn, threads_crit, control_port, statsd, prometheus_port, snapshot,
+            alert_webhook,
+        );
+        choice!(process_match: MatchChoice, fail_on_alert: FailOn, theme: Theme, format: OutputFormat);
+
+        // Constraints clap enforces for command-line values
+        for (name, secs) in [("interval", args.interval), ("min-interval", args.min_interval), ("max-interval", args.max_interval)] {
+            if !secs.is_finite() || secs <= 0.0 {
+                anyhow::bail!("{} in config file must be a positive number of seconds", name);
+            }
+        }
+        if args.flush_interval == 0 {
+            anyhow::bail!("flush-interval in config file must be at least 1");
+        }
+        if args.log_max_size == Some(0) || args.log_rotate_interval == Some(0) {
+            anyhow::bail!("log-max-size and log-rotate-interval in config file must be at least 1");
+        }
+        if args.history == 0 {
+            anyhow::bail!("history in config file must be at least 1");
+        }
+        if args.dogstatsd && args.statsd.is_none() {
+            anyhow::bail!("dogstatsd in config file requires statsd");
+        }
+        if args.delete_segments && !args.compact_on_exit {
+            anyhow::bail!("delete-segments in config file requires compact-on-exit");
+        }
+        Ok(())
+    }
+}
+
+/// Location of a named profile: `$XDG_CONFIG_HOME/monperf/profiles/<name>.toml`,
+/// falling back to `~/.config`
+pub fn profile_path(name: &str) -> Result<PathBuf> {
+    let valid = !name.is_empty()
+        && !name.starts_with('.')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if !valid {
+        anyhow::bail!("Invalid profile name '{}' (use letters, digits, '-', '_' and '.')", name);
+    }
+    let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .context("Neither XDG_CONFIG_HOME nor HOME is set, so there is no profile directory")?;
+    Ok(config_dir.join("monperf").join("profiles").join(format!("{}.toml", name)))
+}
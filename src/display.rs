@@ -1,25 +1,142 @@
 //! Terminal UI display using ratatui.
 
 use crate::alert::Alert;
-use crate::metrics::{CpuMetrics, DiskMetrics, MemoryMetrics, NetworkMetrics};
-use crate::process::ProcessMetrics;
+use crate::environment::EnvironmentInfo;
+use crate::metrics::disk::DiskStats;
+use crate::metrics::memory::CgroupMemStat;
+use crate::metrics::network::{NicSettings, RemotePeer};
+use crate::metrics::{CpuMetrics, DiskMetrics, GpuMetrics, IoStallSource, MemoryMetrics, NetworkMetrics, PsiMetrics};
+use crate::process::{OpenFd, ProcessMetrics};
+use chrono::{DateTime, Local};
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, List, ListItem, Paragraph, Sparkline},
+    widgets::{Block, Borders, Clear, List, ListItem, Paragraph, Sparkline},
     Frame,
 };
+use std::collections::VecDeque;
+
+/// Samples kept for the sparklines unless --history says otherwise
+pub const DEFAULT_HISTORY: usize = 500;
+
+/// Samples averaged into each sparkline bar at the coarse time scale
+const COARSE_SAMPLES_PER_BAR: usize = 5;
+
+/// How many samples each sparkline bar stands for ('h' cycles)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HistoryScale {
+    /// One sample per bar (scrollable with the arrow keys)
+    #[default]
+    Raw,
+    /// The average of COARSE_SAMPLES_PER_BAR samples per bar
+    Coarse,
+    /// The whole history averaged into the graph width
+    Full,
+}
+
+impl HistoryScale {
+    pub fn next(self) -> Self {
+        match self {
+            HistoryScale::Raw => HistoryScale::Coarse,
+            HistoryScale::Coarse => HistoryScale::Full,
+            HistoryScale::Full => HistoryScale::Raw,
+        }
+    }
 
-/// Get the last N elements from a slice to fit the graph width
-/// The sparkline uses 1 char per data point, so we use area.width - 2 (for borders)
-fn slice_for_width<'a>(data: &'a [u64], area: Rect) -> &'a [u64] {
+    /// Samples shown in a graph `width` bars wide out of a `len`-sample history, and samples per bar
+    fn coverage(self, len: usize, width: usize) -> (usize, f64) {
+        match self {
+            HistoryScale::Raw => (len.min(width), 1.0),
+            HistoryScale::Coarse => (len.min(width * COARSE_SAMPLES_PER_BAR), COARSE_SAMPLES_PER_BAR as f64),
+            HistoryScale::Full => (len, (len as f64 / width.max(1) as f64).max(1.0)),
+        }
+    }
+}
+
+/// Get the N elements of a history that fit the graph width, ending `view.offset` samples before the newest
+/// The sparkline uses 1 char per data point, so we use area.width - 2 (for borders).
+/// Coarser time scales average several samples into each point instead.
+fn slice_for_width(data: &VecDeque<u64>, area: Rect, view: HistoryView) -> Vec<u64> {
     let graph_width = area.width.saturating_sub(2) as usize;
-    if data.len() <= graph_width {
-        data
-    } else {
-        &data[data.len() - graph_width..]
+    match view.scale {
+        HistoryScale::Raw => {
+            let (start, end) = visible_range(data.len(), graph_width, view.offset);
+            data.range(start..end).copied().collect()
+        }
+        HistoryScale::Coarse => {
+            // Buckets end at the newest sample, so only the oldest one can be partial
+            let (shown, _) = view.scale.coverage(data.len(), graph_width);
+            let tail: Vec<u64> = data.range(data.len() - shown..).copied().collect();
+            tail.rchunks(COARSE_SAMPLES_PER_BAR)
+                .rev()
+                .map(|bucket| bucket.iter().sum::<u64>() / bucket.len() as u64)
+                .collect()
+        }
+        HistoryScale::Full if data.len() > graph_width => downsample(data, graph_width),
+        HistoryScale::Full => data.iter().copied().collect(),
+    }
+}
+
+/// Average `data` into `width` consecutive buckets (`data` must be longer than `width`)
+fn downsample(data: &VecDeque<u64>, width: usize) -> Vec<u64> {
+    (0..width)
+        .map(|i| {
+            let (start, end) = (i * data.len() / width, (i + 1) * data.len() / width);
+            data.range(start..end).sum::<u64>() / (end - start) as u64
+        })
+        .collect()
+}
+
+/// Index range of a `len`-sample history shown in `width` points, scrolled back by `offset`
+/// (clamped so the window stays full once there is enough history)
+fn visible_range(len: usize, width: usize, offset: usize) -> (usize, usize) {
+    let end = len - offset.min(len.saturating_sub(width));
+    (end.saturating_sub(width), end)
+}
+
+/// Which stretch of the sparkline histories is on screen (left/right arrows scroll back)
+#[derive(Clone, Copy)]
+pub struct HistoryView<'a> {
+    /// Samples hidden at the newest end, 0 follows live data
+    pub offset: usize,
+    /// Wall-clock time of each history sample, oldest first
+    pub times: &'a VecDeque<DateTime<Local>>,
+    /// Samples per sparkline bar ('h')
+    pub scale: HistoryScale,
+}
+
+impl HistoryView<'_> {
+    /// Panel title suffix with the viewed time window, e.g. "◀ 14:02:10-14:03:05 " when scrolled back
+    /// or "▣ last 8m00s, 5/bar " at a coarser time scale (None while live at one sample per bar)
+    fn window_title(&self, panel_area: Rect) -> Option<String> {
+        // Sparklines span the panel's inner width, minus their own borders
+        let width = panel_area.width.saturating_sub(4) as usize;
+        if self.scale != HistoryScale::Raw {
+            let (shown, per_bar) = self.scale.coverage(self.times.len(), width);
+            let first = self.times.get(self.times.len().checked_sub(shown)?)?;
+            let span = (*self.times.back()? - *first).num_seconds().max(0) as u64;
+            // A whole history rarely divides evenly into the bars
+            let (what, about) = if self.scale == HistoryScale::Full { ("all", "~") } else { ("last", "") };
+            return Some(format!("▣ {} {}, {}{:.0}/bar ", what, format_age(span), about, per_bar));
+        }
+        if self.offset == 0 {
+            return None;
+        }
+        let (start, end) = visible_range(self.times.len(), width, self.offset);
+        let first = self.times.get(start)?;
+        let last = self.times.get(end.checked_sub(1)?)?;
+        Some(format!("◀ {}-{} ", first.format("%H:%M:%S"), last.format("%H:%M:%S")))
+    }
+}
+
+/// Panel title, followed by the scrolled-back time window if any
+fn panel_title(name: &str, view: HistoryView, area: Rect) -> Line<'static> {
+    let mut spans = vec![Span::raw(name.to_string())];
+    if let Some(window) = view.window_title(area) {
+        spans.push(Span::styled(window, Style::default().fg(Color::Black).bg(Color::Cyan)));
     }
+    Line::from(spans)
 }
 
 /// Format bytes to human readable string
@@ -59,6 +176,43 @@ pub fn format_throughput(bytes_per_sec: f64) -> String {
     }
 }
 
+/// Format bytes per second as bits per second (decimal units, as NIC speeds are quoted)
+pub fn format_throughput_bits(bytes_per_sec: f64) -> String {
+    let bits = bytes_per_sec * 8.0;
+    if bits >= 1e9 {
+        format!("{:.2} Gbit/s", bits / 1e9)
+    } else if bits >= 1e6 {
+        format!("{:.2} Mbit/s", bits / 1e6)
+    } else if bits >= 1e3 {
+        format!("{:.2} Kbit/s", bits / 1e3)
+    } else {
+        format!("{:.0} bit/s", bits)
+    }
+}
+
+/// Format a duration in seconds with its two largest units (e.g., "45s", "12m03s", "1h23m", "3d04h")
+pub fn format_age(secs: u64) -> String {
+    let (days, hours, mins) = (secs / 86400, secs % 86400 / 3600, secs % 3600 / 60);
+    if days > 0 {
+        format!("{}d{:02}h", days, hours)
+    } else if hours > 0 {
+        format!("{}h{:02}m", hours, mins)
+    } else if mins > 0 {
+        format!("{}m{:02}s", mins, secs % 60)
+    } else {
+        format!("{}s", secs)
+    }
+}
+
+/// Suffix saying when a summary peak happened (" at t=142s"), empty for a series that stayed at zero
+pub fn format_peak_at(value: f64, offset_secs: f64) -> String {
+    if value > 0.0 {
+        format!(" at t={:.0}s", offset_secs)
+    } else {
+        String::new()
+    }
+}
+
 /// Format bytes to shorter human readable string (no space, for compact output)
 pub fn format_bytes_short(bytes: u64) -> String {
     const KB: u64 = 1024;
@@ -79,43 +233,105 @@ pub fn format_bytes_short(bytes: u64) -> String {
     }
 }
 
-/// Truncate a string to max length, adding ".." if truncated
+/// Truncate a string to max length in bytes, adding ".." if truncated
+/// (cuts never split a multi-byte character)
 pub fn truncate_str(s: &str, max_len: usize) -> String {
     if s.len() <= max_len {
         s.to_string()
     } else if max_len <= 2 {
-        s[..max_len].to_string()
+        s[..s.floor_char_boundary(max_len)].to_string()
     } else {
-        format!("{}..", &s[..max_len - 2])
+        format!("{}..", &s[..s.floor_char_boundary(max_len - 2)])
     }
 }
 
-/// Get color based on percentage value
-fn percentage_color(value: f64, warn_threshold: f64, crit_threshold: f64) -> Color {
-    if value >= crit_threshold {
-        Color::Red
-    } else if value >= warn_threshold {
-        Color::Yellow
-    } else {
-        Color::Green
-    }
+/// TUI color theme (--theme)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Theme {
+    /// Green/yellow/red
+    Default,
+    /// Blue/yellow/orange, readable with red-green color blindness
+    Colorblind,
+    /// No hue: brightness and bold only
+    Mono,
+}
+
+/// Styles for healthy, warning and critical values, selected by the theme
+#[derive(Debug, Clone, Copy)]
+pub struct Palette {
+    pub ok: Style,
+    pub warn: Style,
+    pub crit: Style,
+    /// Highlighted badge for urgent conditions in panel titles (e.g., THROTTLED)
+    pub badge: Style,
 }
 
-/// Format a percentage with color based on value
-fn percentage_style(value: f64, warn_threshold: f64, crit_threshold: f64) -> Style {
-    Style::default()
-        .fg(percentage_color(value, warn_threshold, crit_threshold))
-        .add_modifier(if value >= crit_threshold {
-            Modifier::BOLD
+impl Palette {
+    pub fn new(theme: Theme) -> Self {
+        match theme {
+            Theme::Default => Self {
+                ok: Style::default().fg(Color::Green),
+                warn: Style::default().fg(Color::Yellow),
+                crit: Style::default().fg(Color::Red),
+                badge: Style::default().fg(Color::White).bg(Color::Red).add_modifier(Modifier::BOLD),
+            },
+            // Okabe-Ito sky blue, yellow and orange
+            Theme::Colorblind => Self {
+                ok: Style::default().fg(Color::Rgb(86, 180, 233)),
+                warn: Style::default().fg(Color::Rgb(240, 228, 66)),
+                crit: Style::default().fg(Color::Rgb(230, 159, 0)).add_modifier(Modifier::BOLD),
+                badge: Style::default().fg(Color::Black).bg(Color::Rgb(230, 159, 0)).add_modifier(Modifier::BOLD),
+            },
+            Theme::Mono => Self {
+                ok: Style::default().fg(Color::Gray),
+                warn: Style::default().fg(Color::White),
+                crit: Style::default().fg(Color::White).add_modifier(Modifier::BOLD),
+                badge: Style::default().fg(Color::Black).bg(Color::White).add_modifier(Modifier::BOLD),
+            },
+        }
+    }
+
+    /// Style of a value against its warning and critical thresholds
+    fn level(&self, value: f64, warn_threshold: f64, crit_threshold: f64) -> Style {
+        if value >= crit_threshold {
+            self.crit
+        } else if value >= warn_threshold {
+            self.warn
         } else {
-            Modifier::empty()
-        })
+            self.ok
+        }
+    }
+
+    /// Like `level`, but bold once the value is critical
+    fn level_bold(&self, value: f64, warn_threshold: f64, crit_threshold: f64) -> Style {
+        let style = self.level(value, warn_threshold, crit_threshold);
+        if value >= crit_threshold {
+            style.add_modifier(Modifier::BOLD)
+        } else {
+            style
+        }
+    }
 }
 
+/// Per-core softirq share at which a core is highlighted in the CPU panel
+const SOFTIRQ_HOT_PERCENT: f64 = 25.0;
+
 /// Render CPU metrics widget with per-core overview
-pub fn render_cpu(f: &mut Frame, area: Rect, cpu: &CpuMetrics, history: Option<&CpuHistory>) {
+pub fn render_cpu(f: &mut Frame, area: Rect, cpu: &CpuMetrics, history: Option<&CpuHistory>, view: HistoryView, palette: &Palette) {
+    let mut title = vec![Span::raw(" CPU ")];
+    if let Some(throttling) = cpu.throttling.as_ref().filter(|t| t.is_throttled()) {
+        title.push(Span::styled(
+            format!(" THROTTLED {:.0}% ", throttling.throttled_percent()),
+            palette.badge,
+        ));
+        title.push(Span::raw(" "));
+    }
+    if let Some(window) = view.window_title(area) {
+        title.push(Span::styled(window, Style::default().fg(Color::Black).bg(Color::Cyan)));
+    }
+
     let block = Block::default()
-        .title(" CPU ")
+        .title(Line::from(title))
         .borders(Borders::ALL)
         .border_style(Style::default().fg(Color::Cyan));
 
@@ -146,18 +362,24 @@ pub fn render_cpu(f: &mut Frame, area: Rect, cpu: &CpuMetrics, history: Option<&
     let filled = ((cpu_pct / 100.0) * bar_width as f64) as usize;
     let empty = bar_width.saturating_sub(filled);
     
-    let bar_color = percentage_color(cpu_pct, 70.0, 90.0);
-    let overall_line = Line::from(vec![
+    let bar_style = palette.level(cpu_pct, 70.0, 90.0);
+    let mut overall_spans = vec![
         Span::raw("Total: "),
         Span::styled(
             format!("{:>5.1}%", cpu_pct),
-            Style::default().fg(bar_color).add_modifier(Modifier::BOLD),
+            bar_style.add_modifier(Modifier::BOLD),
         ),
         Span::raw(" ["),
-        Span::styled("█".repeat(filled), Style::default().fg(bar_color)),
+        Span::styled("█".repeat(filled), bar_style),
         Span::styled("░".repeat(empty), Style::default().fg(Color::DarkGray)),
         Span::raw("]"),
-    ]);
+    ];
+    // Inside a CPU-limited container the quota, not the host, is what runs out
+    if let (Some(label), Some(quota_pct)) = (cpu.cgroup_cpu_label(), cpu.cgroup_cpu_quota_percent) {
+        overall_spans.push(Span::raw("  Container: "));
+        overall_spans.push(Span::styled(label, palette.level(quota_pct, 70.0, 90.0)));
+    }
+    let overall_line = Line::from(overall_spans);
     f.render_widget(Paragraph::new(overall_line), chunks[0]);
 
     // Per-core compact visualization
@@ -172,21 +394,21 @@ pub fn render_cpu(f: &mut Frame, area: Rect, cpu: &CpuMetrics, history: Option<&
         for core_idx in start_core..end_core {
             if let Some(core) = cpu.per_core.get(core_idx) {
                 let pct = core.utilization_percent.clamp(0.0, 100.0);
-                let color = percentage_color(pct, 70.0, 90.0);
+                let bar_style = palette.level(pct, 70.0, 90.0);
                 
                 // Create a mini bar for each core: [##  ] format
                 let mini_bar_width = 4;
                 let mini_filled = ((pct / 100.0) * mini_bar_width as f64).round() as usize;
                 let mini_empty = mini_bar_width - mini_filled;
                 
-                spans.push(Span::styled(
-                    format!("{:>2}:", core.core_id),
-                    Style::default().fg(Color::DarkGray),
-                ));
-                spans.push(Span::styled(
-                    "█".repeat(mini_filled),
-                    Style::default().fg(color),
-                ));
+                // Highlight the label of a core busy servicing softirqs
+                let label_style = if core.softirq_percent >= SOFTIRQ_HOT_PERCENT {
+                    Style::default().fg(Color::Magenta).add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default().fg(Color::DarkGray)
+                };
+                spans.push(Span::styled(format!("{:>2}:", core.core_id), label_style));
+                spans.push(Span::styled("█".repeat(mini_filled), bar_style));
                 spans.push(Span::styled(
                     "░".repeat(mini_empty),
                     Style::default().fg(Color::DarkGray),
@@ -202,20 +424,58 @@ pub fn render_cpu(f: &mut Frame, area: Rect, cpu: &CpuMetrics, history: Option<&
 
     // CPU details
     let iowait_style = if cpu.iowait_percent > 30.0 {
-        Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)
+        palette.crit.add_modifier(Modifier::BOLD)
     } else if cpu.iowait_percent > 10.0 {
-        Style::default().fg(Color::Yellow)
+        palette.warn
+    } else {
+        Style::default().fg(Color::White)
+    };
+
+    let steal_style = if cpu.steal_percent > 20.0 {
+        palette.crit.add_modifier(Modifier::BOLD)
+    } else if cpu.steal_percent > 5.0 {
+        palette.warn
     } else {
         Style::default().fg(Color::White)
     };
 
+    let softirq_style = if cpu.per_core.iter().any(|c| c.softirq_percent >= SOFTIRQ_HOT_PERCENT) {
+        Style::default().fg(Color::Magenta).add_modifier(Modifier::BOLD)
+    } else {
+        Style::default().fg(Color::White)
+    };
+
+    // Clock speed range across cores (a low minimum on busy cores suggests throttling)
+    let freqs: Vec<f64> = cpu.per_core.iter().filter_map(|c| c.frequency_mhz).collect();
+    let freq_range = if freqs.is_empty() {
+        String::new()
+    } else {
+        let min = freqs.iter().cloned().fold(f64::INFINITY, f64::min);
+        let max = freqs.iter().cloned().fold(0.0_f64, f64::max);
+        format!("  MHz: {:.0}-{:.0}", min, max)
+    };
+
+    // Runnable tasks beyond the core count are queueing; any blocked task is waiting on I/O
+    let running_style = if cpu.procs_running > cpu.core_count as u64 {
+        palette.crit
+    } else {
+        Style::default().fg(Color::Gray)
+    };
+    let blocked_style = if cpu.procs_blocked >= 4 {
+        palette.crit
+    } else if cpu.procs_blocked > 0 {
+        palette.warn
+    } else {
+        Style::default().fg(Color::Gray)
+    };
+
     // Color load average based on core count
-    let load_color = if cpu.load_avg.0 > cpu.core_count as f64 {
-        Color::Red
+    let load_style = if cpu.load_avg.0 > cpu.core_count as f64 {
+        palette.crit
     } else if cpu.load_avg.0 > cpu.core_count as f64 * 0.7 {
-        Color::Yellow
+        palette.warn
     } else {
-        Color::Green
+        palette.ok
     };
 
     let details = vec![
@@ -223,14 +483,20 @@ pub fn render_cpu(f: &mut Frame, area: Rect, cpu: &CpuMetrics, history: Option<&
             Span::raw("Load: "),
             Span::styled(
                 format!("{:.2} {:.2} {:.2}", cpu.load_avg.0, cpu.load_avg.1, cpu.load_avg.2),
-                Style::default().fg(load_color),
+                load_style,
             ),
             Span::raw("  User: "),
             Span::styled(format!("{:.1}%", cpu.user_percent), Style::default().fg(Color::Cyan)),
             Span::raw("  Sys: "),
             Span::styled(format!("{:.1}%", cpu.system_percent), Style::default().fg(Color::Magenta)),
+            Span::raw("  IRQ: "),
+            Span::styled(format!("{:.1}%", cpu.irq_percent), Style::default().fg(Color::White)),
+            Span::raw("  SIRQ: "),
+            Span::styled(format!("{:.1}%", cpu.softirq_percent), softirq_style),
             Span::raw("  IOW: "),
             Span::styled(format!("{:.1}%", cpu.iowait_percent), iowait_style),
+            Span::raw("  St: "),
+            Span::styled(format!("{:.1}%", cpu.steal_percent), steal_style),
         ]),
         Line::from(vec![
             Span::raw("Ctx/s: "),
@@ -243,6 +509,11 @@ pub fn render_cpu(f: &mut Frame, area: Rect, cpu: &CpuMetrics, history: Option<&
                 format!("{}", cpu.interrupts_delta.unwrap_or(0)),
                 Style::default().fg(Color::Gray),
             ),
+            Span::raw("  Run: "),
+            Span::styled(format!("{}", cpu.procs_running), running_style),
+            Span::raw("  Blk: "),
+            Span::styled(format!("{}", cpu.procs_blocked), blocked_style),
+            Span::raw(freq_range),
         ]),
     ];
 
@@ -251,14 +522,14 @@ pub fn render_cpu(f: &mut Frame, area: Rect, cpu: &CpuMetrics, history: Option<&
     // CPU history sparkline at bottom (sized to graph width)
     if let Some(hist) = history {
         if !hist.utilization.is_empty() {
-            let data = slice_for_width(&hist.utilization, chunks[3]);
+            let data = slice_for_width(&hist.utilization, chunks[3], view);
             let max_val = data.iter().max().copied().unwrap_or(100).max(100);
             let cpu_sparkline = Sparkline::default()
                 .block(Block::default()
                     .borders(Borders::ALL)
                     .border_style(Style::default().fg(Color::DarkGray))
                     .title(format!(" CPU % (max {}%) ", max_val)))
-                .data(data)
+                .data(&data)
                 .max(max_val)
                 .style(Style::default().fg(Color::Cyan));
             f.render_widget(cpu_sparkline, chunks[3]);
@@ -268,6 +539,7 @@ pub fn render_cpu(f: &mut Frame, area: Rect, cpu: &CpuMetrics, history: Option<&
 
 /// Helper to render a labeled progress bar with readable text
 fn render_progress_bar(
+    palette: &Palette,
     label: &str,
     value: &str,
     percent: f64,
@@ -279,34 +551,71 @@ fn render_progress_bar(
     let pct = percent.clamp(0.0, 100.0);
     let filled = ((pct / 100.0) * bar_width as f64) as usize;
     let empty = bar_width.saturating_sub(filled);
-    let color = percentage_color(pct, warn, crit);
+    let style = palette.level(pct, warn, crit);
 
     Line::from(vec![
         Span::raw(label.to_string()),
         Span::raw(" ["),
-        Span::styled("█".repeat(filled), Style::default().fg(color)),
+        Span::styled("█".repeat(filled), style),
         Span::styled("░".repeat(empty), Style::default().fg(Color::DarkGray)),
         Span::raw("] "),
-        Span::styled(value.to_string(), Style::default().fg(color).add_modifier(Modifier::BOLD)),
+        Span::styled(value.to_string(), style.add_modifier(Modifier::BOLD)),
     ])
 }
 
+/// Dirty/writeback pages with their growth rate and distance to the throttling limit
+fn dirty_line(mem: &MemoryMetrics, palette: &Palette) -> Line<'static> {
+    let mut spans = vec![
+        Span::raw("Dirty: "),
+        Span::styled(format_bytes(mem.dirty), Style::default().fg(Color::Gray)),
+    ];
+    if let Some(growth) = mem.dirty_growth_bytes_per_sec {
+        let sign = if growth < 0.0 { "-" } else { "+" };
+        spans.push(Span::styled(
+            format!(" ({}{})", sign, format_throughput(growth.abs())),
+            Style::default().fg(Color::DarkGray),
+        ));
+    }
+    spans.push(Span::raw(" WB: "));
+    spans.push(Span::styled(format_bytes(mem.writeback), Style::default().fg(Color::Gray)));
+    if let Some(pct) = mem.dirty_limit_percent() {
+        spans.push(Span::raw(" Limit: "));
+        spans.push(Span::styled(format!("{:.0}%", pct), palette.level(pct, 50.0, 80.0)));
+    }
+    Line::from(spans)
+}
+
 /// Render memory metrics widget
-pub fn render_memory(f: &mut Frame, area: Rect, mem: &MemoryMetrics, history: Option<&MemoryHistory>) {
+#[allow(clippy::too_many_arguments)]
+pub fn render_memory(
+    f: &mut Frame,
+    area: Rect,
+    mem: &MemoryMetrics,
+    history: Option<&MemoryHistory>,
+    cgroup_primary: bool,
+    graph: GraphMode,
+    view: HistoryView,
+    palette: &Palette,
+) {
     let block = Block::default()
-        .title(" Memory ")
+        .title(panel_title(" Memory ", view, area))
         .borders(Borders::ALL)
         .border_style(Style::default().fg(Color::Magenta));
 
     let inner = block.inner(area);
     f.render_widget(block, area);
 
+    // Extra details lines for the cgroup breakdown and per-node usage on NUMA systems
+    let details_height = 3
+        + u16::from(mem.cgroup_stat.is_some())
+        + u16::from(!mem.numa_nodes.is_empty());
+
     // Layout: text at top, sparkline fills remaining space at bottom
     let main_chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
-            Constraint::Length(4), // Bars + details (fixed)
-            Constraint::Min(6),    // Sparkline graph at bottom (fills remaining)
+            Constraint::Length(2 + details_height), // Bars + details (fixed)
+            Constraint::Min(6),                     // Sparkline graph at bottom (fills remaining)
         ])
         .split(inner);
     
@@ -314,9 +623,9 @@ pub fn render_memory(f: &mut Frame, area: Rect, mem: &MemoryMetrics, history: Op
     let text_chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
-            Constraint::Length(1), // System memory bar
-            Constraint::Length(1), // Cgroup memory bar
-            Constraint::Length(2), // Details
+            Constraint::Length(1),              // Primary memory bar
+            Constraint::Length(1),              // Secondary memory bar
+            Constraint::Length(details_height), // Details
         ])
         .split(main_chunks[0]);
 
@@ -329,37 +638,89 @@ pub fn render_memory(f: &mut Frame, area: Rect, mem: &MemoryMetrics, history: Op
         format_bytes(mem.total),
         mem.used_percent
     );
-    let mem_bar = render_progress_bar("RAM:", &mem_label, mem.used_percent, bar_width, 70.0, 90.0);
-    f.render_widget(Paragraph::new(mem_bar), text_chunks[0]);
+    let mem_bar = render_progress_bar(palette, "RAM:", &mem_label, mem.used_percent, bar_width, 70.0, 90.0);
 
     // Cgroup memory bar (if available)
-    if let (Some(limit), Some(current), Some(percent)) =
+    let cgroup_bar = if let (Some(limit), Some(current), Some(percent)) =
         (mem.cgroup_limit, mem.cgroup_current, mem.cgroup_usage_percent)
     {
+        // Cgroup swap beside the RAM usage, when swap is allowed or in use
+        let swap = match (mem.cgroup_swap_current, mem.cgroup_swap_limit) {
+            (Some(0), Some(0)) => String::new(),
+            (Some(used), Some(swap_limit)) => {
+                format!(" Swp {}/{}", format_bytes_short(used), format_bytes_short(swap_limit))
+            }
+            (Some(used), None) => format!(" Swp {}", format_bytes_short(used)),
+            _ => String::new(),
+        };
         let cgroup_label = format!(
-            "{} / {} ({:.1}%)",
+            "{} / {} ({:.1}%){}",
             format_bytes(current),
             format_bytes(limit),
-            percent
+            percent,
+            swap
         );
-        let cgroup_bar = render_progress_bar("Cgroup:", &cgroup_label, percent, bar_width, 80.0, 95.0);
-        f.render_widget(Paragraph::new(cgroup_bar), text_chunks[1]);
+        Some(render_progress_bar(palette, "Cgroup:", &cgroup_label, percent, bar_width, 80.0, 95.0))
     } else {
-        let no_cgroup = Line::from(vec![
-            Span::raw("Cgroup: "),
-            Span::styled("N/A", Style::default().fg(Color::DarkGray)),
-        ]);
-        f.render_widget(Paragraph::new(no_cgroup), text_chunks[1]);
+        None
+    };
+
+    match cgroup_bar {
+        // Inside a container the cgroup limit is what triggers OOM, so lead with it
+        Some(cgroup_bar) if cgroup_primary => {
+            f.render_widget(Paragraph::new(cgroup_bar), text_chunks[0]);
+            f.render_widget(Paragraph::new(mem_bar), text_chunks[1]);
+        }
+        Some(cgroup_bar) => {
+            f.render_widget(Paragraph::new(mem_bar), text_chunks[0]);
+            f.render_widget(Paragraph::new(cgroup_bar), text_chunks[1]);
+        }
+        None => {
+            f.render_widget(Paragraph::new(mem_bar), text_chunks[0]);
+            let no_cgroup = Line::from(vec![
+                Span::raw("Cgroup: "),
+                Span::styled("N/A", Style::default().fg(Color::DarkGray)),
+            ]);
+            f.render_widget(Paragraph::new(no_cgroup), text_chunks[1]);
+        }
     }
 
     // Memory details
-    let swap_color = if mem.swap_used > 0 {
-        Color::Yellow
+    let swap_style = if mem.swap_used > 0 {
+        palette.warn
     } else {
-        Color::White
+        Style::default().fg(Color::White)
     };
 
-    let details = vec![
+    let mut swap_line = vec![
+        Span::raw("Swap: "),
+        Span::styled(
+            format!("{}/{}", format_bytes(mem.swap_used), format_bytes(mem.swap_total)),
+            swap_style,
+        ),
+        Span::raw(" PgFlt: "),
+        Span::styled(
+            format!("Maj:{} Min:{}", 
+                mem.major_faults_delta.unwrap_or(0),
+                mem.minor_faults_delta.unwrap_or(0)
+            ),
+            Style::default().fg(Color::Gray),
+        ),
+    ];
+    // Huge pages are only worth a glance when some are reserved
+    if mem.hugepages_total > 0 {
+        swap_line.push(Span::raw(" Huge: "));
+        swap_line.push(Span::styled(
+            format!(
+                "{}/{}",
+                mem.hugepages_total - mem.hugepages_free.min(mem.hugepages_total),
+                mem.hugepages_total
+            ),
+            Style::default().fg(Color::Gray),
+        ));
+    }
+
+    let mut details = vec![
         Line::from(vec![
             Span::raw("Avail: "),
             Span::styled(format_bytes(mem.available), Style::default().fg(Color::Green)),
@@ -367,91 +728,178 @@ pub fn render_memory(f: &mut Frame, area: Rect, mem: &MemoryMetrics, history: Op
             Span::styled(format_bytes(mem.buffers), Style::default().fg(Color::Gray)),
             Span::raw(" Cache: "),
             Span::styled(format_bytes(mem.cached), Style::default().fg(Color::Gray)),
-        ]),
-        Line::from(vec![
-            Span::raw("Swap: "),
+            Span::raw(" Slab: "),
             Span::styled(
-                format!("{}/{}", format_bytes(mem.swap_used), format_bytes(mem.swap_total)),
-                Style::default().fg(swap_color),
-            ),
-            Span::raw(" PgFlt: "),
-            Span::styled(
-                format!("Maj:{} Min:{}", 
-                    mem.major_faults_delta.unwrap_or(0),
-                    mem.minor_faults_delta.unwrap_or(0)
+                format!(
+                    "R:{} U:{}",
+                    format_bytes_short(mem.slab_reclaimable),
+                    format_bytes_short(mem.slab_unreclaimable)
                 ),
                 Style::default().fg(Color::Gray),
             ),
         ]),
+        Line::from(swap_line),
+        dirty_line(mem, palette),
     ];
+    if let Some(ref stat) = mem.cgroup_stat {
+        details.push(cgroup_stat_line(stat, palette));
+    }
+    if !mem.numa_nodes.is_empty() {
+        details.push(numa_line(mem, palette));
+    }
     f.render_widget(Paragraph::new(details), text_chunks[2]);
 
     // Memory history sparklines at bottom (fills remaining space)
-    // Show CGroup and RAM as separate graphs if CGroup is available
+    // By default show CGroup and RAM as separate graphs if CGroup is available
     if let Some(hist) = history {
         if !hist.used_percent.is_empty() {
             let has_cgroup = hist.cgroup_percent.iter().any(|&v| v > 0);
             
-            if has_cgroup {
-                // Split into two graphs: CGroup (top) and RAM (bottom)
-                let graph_chunks = Layout::default()
-                    .direction(Direction::Vertical)
-                    .constraints([
-                        Constraint::Ratio(1, 2),  // CGroup sparkline
-                        Constraint::Ratio(1, 2),  // RAM sparkline
-                    ])
-                    .split(main_chunks[1]);
-                
-                // CGroup sparkline (red)
-                let cgroup_data = slice_for_width(&hist.cgroup_percent, graph_chunks[0]);
-                let cgroup_max = cgroup_data.iter().max().copied().unwrap_or(100);
-                let cgroup_title = format!(" CGroup % (max {}%) ", cgroup_max);
-                let cgroup_sparkline = Sparkline::default()
-                    .block(Block::default()
-                        .borders(Borders::ALL)
-                        .border_style(Style::default().fg(Color::DarkGray))
-                        .title(cgroup_title))
-                    .data(cgroup_data)
-                    .max(100)
-                    .style(Style::default().fg(Color::Red));
-                f.render_widget(cgroup_sparkline, graph_chunks[0]);
-                
-                // RAM sparkline (magenta)
-                let ram_data = slice_for_width(&hist.used_percent, graph_chunks[1]);
-                let ram_max = ram_data.iter().max().copied().unwrap_or(100);
-                let ram_title = format!(" RAM % (max {}%) ", ram_max);
-                let ram_sparkline = Sparkline::default()
-                    .block(Block::default()
-                        .borders(Borders::ALL)
-                        .border_style(Style::default().fg(Color::DarkGray))
-                        .title(ram_title))
-                    .data(ram_data)
-                    .max(100)
-                    .style(Style::default().fg(Color::Magenta));
-                f.render_widget(ram_sparkline, graph_chunks[1]);
-            } else {
-                // No CGroup - just show RAM in full area
-                let ram_data = slice_for_width(&hist.used_percent, main_chunks[1]);
-                let ram_max = ram_data.iter().max().copied().unwrap_or(100);
-                let title = format!(" RAM % (max {}%) ", ram_max);
-                let sparkline = Sparkline::default()
-                    .block(Block::default()
-                        .borders(Borders::ALL)
-                        .border_style(Style::default().fg(Color::DarkGray))
-                        .title(title))
-                    .data(ram_data)
-                    .max(100)
-                    .style(Style::default().fg(Color::Magenta));
-                f.render_widget(sparkline, main_chunks[1]);
+            match graph {
+                GraphMode::Second if has_cgroup => {
+                    render_percent_sparkline(f, main_chunks[1], &hist.cgroup_percent, "CGroup", Color::Red, view);
+                }
+                GraphMode::Both | GraphMode::Combined if has_cgroup => {
+                    // Split into two graphs: CGroup (top) and RAM (bottom)
+                    let graph_chunks = Layout::default()
+                        .direction(Direction::Vertical)
+                        .constraints([
+                            Constraint::Ratio(1, 2),  // CGroup sparkline
+                            Constraint::Ratio(1, 2),  // RAM sparkline
+                        ])
+                        .split(main_chunks[1]);
+                    render_percent_sparkline(f, graph_chunks[0], &hist.cgroup_percent, "CGroup", Color::Red, view);
+                    render_percent_sparkline(f, graph_chunks[1], &hist.used_percent, "RAM", Color::Magenta, view);
+                }
+                _ => {
+                    // RAM only (or no CGroup to show)
+                    render_percent_sparkline(f, main_chunks[1], &hist.used_percent, "RAM", Color::Magenta, view);
+                }
             }
         }
     }
 }
 
+/// Cgroup anon vs file split: heap near the limit means OOM risk, cache just gets reclaimed
+fn cgroup_stat_line(stat: &CgroupMemStat, palette: &Palette) -> Line<'static> {
+    let scan = stat.pgscan_delta.unwrap_or(0);
+    let scan_style = if scan > 0 { palette.warn } else { Style::default().fg(Color::Gray) };
+    Line::from(vec![
+        Span::raw("CG Anon: "),
+        Span::styled(format_bytes_short(stat.anon), Style::default().fg(Color::Cyan)),
+        Span::raw(" File: "),
+        Span::styled(format_bytes_short(stat.file), Style::default().fg(Color::Gray)),
+        Span::raw(" Kern: "),
+        Span::styled(format_bytes_short(stat.kernel), Style::default().fg(Color::Gray)),
+        Span::raw(" Scan: "),
+        Span::styled(scan.to_string(), scan_style),
+        Span::raw(" MajFlt: "),
+        Span::styled(
+            stat.pgmajfault_delta.unwrap_or(0).to_string(),
+            Style::default().fg(Color::Gray),
+        ),
+    ])
+}
+
+/// Per-node used memory, e.g. "NUMA: N0 42% 13.2G  N1 91% 28.9G"
+fn numa_line(mem: &MemoryMetrics, palette: &Palette) -> Line<'static> {
+    let mut spans = vec![Span::raw("NUMA:")];
+    for node in &mem.numa_nodes {
+        let pct = node.used_percent();
+        spans.push(Span::raw(format!(" N{} ", node.node_id)));
+        spans.push(Span::styled(
+            format!("{:.0}%", pct),
+            palette.level(pct, 80.0, 95.0),
+        ));
+        spans.push(Span::styled(
+            format!(" {} ", format_bytes_short(node.used())),
+            Style::default().fg(Color::Gray),
+        ));
+    }
+    Line::from(spans)
+}
+
+/// Render a 0-100% history as a bordered sparkline titled with its peak
+fn render_percent_sparkline(f: &mut Frame, area: Rect, history: &VecDeque<u64>, label: &str, color: Color, view: HistoryView) {
+    let data = slice_for_width(history, area, view);
+    let max = data.iter().max().copied().unwrap_or(100);
+    let title = format!(" {} % (max {}%) ", label, max);
+    let sparkline = Sparkline::default()
+        .block(Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::DarkGray))
+            .title(title))
+        .data(&data)
+        .max(100)
+        .style(Style::default().fg(color));
+    f.render_widget(sparkline, area);
+}
+
+/// Render a KB/s history as a bordered sparkline scaled to its peak (labeled with `format_rate`)
+fn render_rate_sparkline(
+    f: &mut Frame,
+    area: Rect,
+    history: &VecDeque<u64>,
+    label: &str,
+    color: Color,
+    view: HistoryView,
+    format_rate: fn(f64) -> String,
+) {
+    let data = slice_for_width(history, area, view);
+    let max = data.iter().max().copied().unwrap_or(1).max(1);
+    let title = format!(" {} max:{} ", label, format_rate(max as f64 * 1024.0));
+    let sparkline = Sparkline::default()
+        .block(Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::DarkGray))
+            .title(title))
+        .data(&data)
+        .max(max)
+        .style(Style::default().fg(color));
+    f.render_widget(sparkline, area);
+}
+
+/// Render a pair of KB/s histories according to the panel's graph mode
+fn render_rate_pair(
+    f: &mut Frame,
+    area: Rect,
+    graph: GraphMode,
+    first: (&VecDeque<u64>, &str, Color),
+    second: (&VecDeque<u64>, &str, Color),
+    view: HistoryView,
+    format_rate: fn(f64) -> String,
+) {
+    match graph {
+        GraphMode::Both => {
+            let graph_chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Ratio(1, 2), Constraint::Ratio(1, 2)])
+                .split(area);
+            render_rate_sparkline(f, graph_chunks[0], first.0, first.1, first.2, view, format_rate);
+            render_rate_sparkline(f, graph_chunks[1], second.0, second.1, second.2, view, format_rate);
+        }
+        GraphMode::First => render_rate_sparkline(f, area, first.0, first.1, first.2, view, format_rate),
+        GraphMode::Second => render_rate_sparkline(f, area, second.0, second.1, second.2, view, format_rate),
+        GraphMode::Combined => {
+            let combined: VecDeque<u64> = first.0.iter().zip(second.0).map(|(a, b)| a + b).collect();
+            let label = format!("{}+{}", first.1, second.1);
+            render_rate_sparkline(f, area, &combined, &label, Color::White, view, format_rate);
+        }
+    }
+}
+
 /// Render disk metrics widget
-pub fn render_disk(f: &mut Frame, area: Rect, disk: &DiskMetrics, history: Option<&DiskHistory>) {
+pub fn render_disk(
+    f: &mut Frame,
+    area: Rect,
+    disk: &DiskMetrics,
+    history: Option<&DiskHistory>,
+    graph: GraphMode,
+    view: HistoryView,
+    palette: &Palette,
+) {
     let block = Block::default()
-        .title(" Disk I/O ")
+        .title(panel_title(" Disk I/O ", view, area))
         .borders(Borders::ALL)
         .border_style(Style::default().fg(Color::Yellow));
 
@@ -467,12 +915,15 @@ pub fn render_disk(f: &mut Frame, area: Rect, disk: &DiskMetrics, history: Optio
     };
     let disk_display_height = disk_rows.max(1) as u16;
 
+    // One usage bar per --watch-fs filesystem
+    let fs_height = disk.watched_fs.len() as u16;
+
     // Layout: text at top, sparklines fill remaining space at bottom
-    let text_height = 1 + disk_display_height;
+    let text_height = 1 + disk_display_height + fs_height;
     let main_chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
-            Constraint::Length(text_height),  // Total + per-disk bars
+            Constraint::Length(text_height),  // Total + per-disk bars + filesystems
             Constraint::Min(6),               // Sparklines area (fills remaining)
         ])
         .split(inner);
@@ -483,18 +934,10 @@ pub fn render_disk(f: &mut Frame, area: Rect, disk: &DiskMetrics, history: Optio
         .constraints([
             Constraint::Length(1),                   // Total throughput
             Constraint::Length(disk_display_height), // Per-disk utilization bars
+            Constraint::Length(fs_height),           // Watched filesystem usage
         ])
         .split(main_chunks[0]);
     
-    // Split sparklines area evenly
-    let graph_chunks = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([
-            Constraint::Ratio(1, 2),  // Read sparkline
-            Constraint::Ratio(1, 2),  // Write sparkline
-        ])
-        .split(main_chunks[1]);
-
     // Total throughput line with colored R/W values
     let mut total_spans = vec![
         Span::raw("Total: "),
@@ -504,6 +947,12 @@ pub fn render_disk(f: &mut Frame, area: Rect, disk: &DiskMetrics, history: Optio
     ];
     if let Some(ref spill) = disk.spill_dir_info {
         total_spans.push(Span::raw(format!("  Spill: {}", format_bytes(spill.used_bytes))));
+        if spill.inodes_total > 0 {
+            total_spans.push(Span::styled(
+                format!(" (inodes {:.0}%)", spill.inodes_used_percent),
+                palette.level(spill.inodes_used_percent, 80.0, 90.0),
+            ));
+        }
     }
     f.render_widget(Paragraph::new(Line::from(total_spans)), text_chunks[0]);
 
@@ -519,19 +968,9 @@ pub fn render_disk(f: &mut Frame, area: Rect, disk: &DiskMetrics, history: Optio
         for disk_idx in start_idx..end_idx {
             if let Some(d) = disk.disks.get(disk_idx) {
                 let pct = d.utilization_percent.clamp(0.0, 100.0);
-                let color = percentage_color(pct, 50.0, 80.0);
+                let bar_style = palette.level(pct, 50.0, 80.0);
                 
-                // Shorten device name (nvme0n1 -> n0, sda -> sda)
-                let short_name = if d.device.starts_with("nvme") {
-                    // nvme0n1 -> n0, nvme5n1 -> n5
-                    let num = d.device.chars()
-                        .filter(|c| c.is_ascii_digit())
-                        .take(1)
-                        .collect::<String>();
-                    format!("n{}", num)
-                } else {
-                    d.device.chars().take(3).collect()
-                };
+                let short_name = short_disk_name(d);
                 
                 // Create a mini bar for each disk: name:[####  ] R/W format
                 let mini_bar_width: usize = 4;
@@ -546,10 +985,7 @@ pub fn render_disk(f: &mut Frame, area: Rect, disk: &DiskMetrics, history: Optio
                     format!("{:>2}:", short_name),
                     Style::default().fg(Color::DarkGray),
                 ));
-                spans.push(Span::styled(
-                    "█".repeat(mini_filled),
-                    Style::default().fg(color),
-                ));
+                spans.push(Span::styled("█".repeat(mini_filled), bar_style));
                 spans.push(Span::styled(
                     "░".repeat(mini_empty),
                     Style::default().fg(Color::DarkGray),
@@ -558,6 +994,13 @@ pub fn render_disk(f: &mut Frame, area: Rect, disk: &DiskMetrics, history: Optio
                 spans.push(Span::styled(read_short, Style::default().fg(Color::Cyan)));
                 spans.push(Span::raw("/"));
                 spans.push(Span::styled(write_short, Style::default().fg(Color::Yellow)));
+                // TRIM traffic only shows up on SSDs, and only when it's happening
+                if let Some(discard) = d.discard_bytes_per_sec.filter(|&rate| rate > 0.0) {
+                    spans.push(Span::styled(
+                        format!(" T{}", format_throughput_short(discard)),
+                        Style::default().fg(Color::Magenta),
+                    ));
+                }
                 spans.push(Span::raw(" "));
             }
         }
@@ -571,36 +1014,47 @@ pub fn render_disk(f: &mut Frame, area: Rect, disk: &DiskMetrics, history: Optio
     
     f.render_widget(Paragraph::new(disk_lines), text_chunks[1]);
 
+    // Watched filesystem usage bars
+    let label_width = disk.watched_fs.iter().map(|fs| fs.path.len()).max().unwrap_or(0);
+    let fs_lines: Vec<Line> = disk.watched_fs
+        .iter()
+        .map(|fs| {
+            let value = format!(
+                "{} / {} ({:.1}%)",
+                format_bytes_short(fs.used_bytes),
+                format_bytes_short(fs.total_bytes),
+                fs.used_percent
+            );
+            // Inode usage follows the byte bar, colored on its own scale
+            let inodes = if fs.inodes_total > 0 {
+                format!(" ino {:.0}%", fs.inodes_used_percent)
+            } else {
+                String::new()
+            };
+            let label = format!("{:<width$}", fs.path, width = label_width);
+            let width = (text_chunks[2].width as usize).saturating_sub(inodes.len());
+            let mut line = render_progress_bar(palette, &label, &value, fs.used_percent, width, 80.0, 90.0);
+            line.spans.push(Span::styled(
+                inodes,
+                palette.level(fs.inodes_used_percent, 80.0, 90.0),
+            ));
+            line
+        })
+        .collect();
+    f.render_widget(Paragraph::new(fs_lines), text_chunks[2]);
+
     // Sparklines for disk history at bottom (sized to graph width)
     if let Some(hist) = history {
         if !hist.read_history.is_empty() {
-            // Read sparkline (cyan)
-            let read_data = slice_for_width(&hist.read_history, graph_chunks[0]);
-            let read_max = read_data.iter().max().copied().unwrap_or(1).max(1);
-            let read_title = format!(" Read max:{} ", format_throughput(read_max as f64 * 1024.0));
-            let read_sparkline = Sparkline::default()
-                .block(Block::default()
-                    .borders(Borders::ALL)
-                    .border_style(Style::default().fg(Color::DarkGray))
-                    .title(read_title))
-                .data(read_data)
-                .max(read_max)
-                .style(Style::default().fg(Color::Cyan));
-            f.render_widget(read_sparkline, graph_chunks[0]);
-
-            // Write sparkline (yellow)
-            let write_data = slice_for_width(&hist.write_history, graph_chunks[1]);
-            let write_max = write_data.iter().max().copied().unwrap_or(1).max(1);
-            let write_title = format!(" Write max:{} ", format_throughput(write_max as f64 * 1024.0));
-            let write_sparkline = Sparkline::default()
-                .block(Block::default()
-                    .borders(Borders::ALL)
-                    .border_style(Style::default().fg(Color::DarkGray))
-                    .title(write_title))
-                .data(write_data)
-                .max(write_max)
-                .style(Style::default().fg(Color::Yellow));
-            f.render_widget(write_sparkline, graph_chunks[1]);
+            render_rate_pair(
+                f,
+                main_chunks[1],
+                graph,
+                (&hist.read_history, "Read", Color::Cyan),
+                (&hist.write_history, "Write", Color::Yellow),
+                view,
+                format_throughput,
+            );
         }
     }
 }
@@ -620,78 +1074,197 @@ fn format_throughput_short(bytes_per_sec: f64) -> String {
     }
 }
 
+/// Format bytes per second as compact bits per second (5 chars, e.g., "   0b", " 12Kb", "940Mb")
+fn format_throughput_bits_short(bytes_per_sec: f64) -> String {
+    let bits = bytes_per_sec * 8.0;
+    if bits < 1.0 {
+        "   0b".to_string()
+    } else if bits < 1e3 {
+        format!("{:>4}b", bits as u64)
+    } else if bits < 1e6 {
+        format!("{:>3}Kb", (bits / 1e3) as u64)
+    } else if bits < 1e9 {
+        format!("{:>3}Mb", (bits / 1e6) as u64)
+    } else {
+        format!("{:>3}Gb", (bits / 1e9) as u64)
+    }
+}
+
+/// Which history series a panel's sparkline area shows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GraphMode {
+    /// Both series in separate graphs (memory: CGroup and RAM when a cgroup exists)
+    #[default]
+    Both,
+    /// First series only (RAM, read, RX)
+    First,
+    /// Second series only (CGroup, write, TX)
+    Second,
+    /// Sum of both series in one graph (disk and network)
+    Combined,
+}
+
+impl GraphMode {
+    /// Next mode in the cycle; `combinable` panels also offer the summed graph
+    pub fn next(self, combinable: bool) -> Self {
+        match self {
+            GraphMode::Both => GraphMode::First,
+            GraphMode::First => GraphMode::Second,
+            GraphMode::Second if combinable => GraphMode::Combined,
+            GraphMode::Second | GraphMode::Combined => GraphMode::Both,
+        }
+    }
+}
+
+/// Short device label for the per-disk mini bars (nvme0n1 -> n0, sda -> sda,
+/// vg0-home -> home, cryptroot -> cryp; dm-N stays whole)
+fn short_disk_name(d: &DiskStats) -> String {
+    if d.mapper_name {
+        // LVM names are "<vg>-<lv>" with hyphens inside either part doubled
+        let name = &d.device;
+        let mut chars = name.char_indices().peekable();
+        let mut volume = None;
+        while let Some((i, c)) = chars.next() {
+            if c == '-' {
+                if chars.peek().is_some_and(|&(_, next)| next == '-') {
+                    chars.next();
+                } else {
+                    volume = Some(name[i + 1..].replace("--", "-"));
+                    break;
+                }
+            }
+        }
+        volume.unwrap_or_else(|| name.clone()).chars().take(4).collect()
+    } else if d.device.starts_with("dm-") {
+        d.device.clone()
+    } else if d.device.starts_with("nvme") {
+        // nvme0n1 -> n0, nvme5n1 -> n5
+        let num = d.device.chars()
+            .filter(|c| c.is_ascii_digit())
+            .take(1)
+            .collect::<String>();
+        format!("n{}", num)
+    } else {
+        d.device.chars().take(3).collect()
+    }
+}
+
 /// CPU history for sparkline display
 pub struct CpuHistory {
-    pub utilization: Vec<u64>,  // CPU % history (0-100)
+    pub utilization: VecDeque<u64>,  // CPU % history (0-100)
     pub max_samples: usize,
 }
 
 impl CpuHistory {
     pub fn new(max_samples: usize) -> Self {
         Self {
-            utilization: Vec::with_capacity(max_samples),
+            utilization: VecDeque::with_capacity(max_samples),
             max_samples,
         }
     }
 
     pub fn push(&mut self, cpu_percent: f64) {
         if self.utilization.len() >= self.max_samples {
-            self.utilization.remove(0);
+            self.utilization.pop_front();
         }
-        self.utilization.push(cpu_percent as u64);
+        self.utilization.push_back(cpu_percent as u64);
+    }
+
+    /// Drop all history so the graph restarts from now
+    pub fn clear(&mut self) {
+        self.utilization.clear();
     }
 }
 
 impl Default for CpuHistory {
     fn default() -> Self {
-        Self::new(500)  // Large buffer, display will use graph width
+        Self::new(DEFAULT_HISTORY)  // Large buffer, display will use graph width
+    }
+}
+
+/// Wall-clock time of each sparkline sample (labels the scrolled-back window)
+pub struct SampleTimes {
+    pub times: VecDeque<DateTime<Local>>,
+    pub max_samples: usize,
+}
+
+impl SampleTimes {
+    pub fn new(max_samples: usize) -> Self {
+        Self {
+            times: VecDeque::with_capacity(max_samples),
+            max_samples,
+        }
+    }
+
+    pub fn push(&mut self, time: DateTime<Local>) {
+        if self.times.len() >= self.max_samples {
+            self.times.pop_front();
+        }
+        self.times.push_back(time);
+    }
+
+    /// Drop all history so the graph restarts from now
+    pub fn clear(&mut self) {
+        self.times.clear();
+    }
+}
+
+impl Default for SampleTimes {
+    fn default() -> Self {
+        Self::new(DEFAULT_HISTORY)  // Same depth as the metric histories
     }
 }
 
 /// Memory history for sparkline display
 pub struct MemoryHistory {
-    pub used_percent: Vec<u64>,    // System memory % history
-    pub cgroup_percent: Vec<u64>,  // Cgroup memory % history (if available)
+    pub used_percent: VecDeque<u64>,    // System memory % history
+    pub cgroup_percent: VecDeque<u64>,  // Cgroup memory % history (if available)
     pub max_samples: usize,
 }
 
 impl MemoryHistory {
     pub fn new(max_samples: usize) -> Self {
         Self {
-            used_percent: Vec::with_capacity(max_samples),
-            cgroup_percent: Vec::with_capacity(max_samples),
+            used_percent: VecDeque::with_capacity(max_samples),
+            cgroup_percent: VecDeque::with_capacity(max_samples),
             max_samples,
         }
     }
 
     pub fn push(&mut self, used_pct: f64, cgroup_pct: Option<f64>) {
         if self.used_percent.len() >= self.max_samples {
-            self.used_percent.remove(0);
-            self.cgroup_percent.remove(0);
+            self.used_percent.pop_front();
+            self.cgroup_percent.pop_front();
         }
-        self.used_percent.push(used_pct as u64);
-        self.cgroup_percent.push(cgroup_pct.unwrap_or(0.0) as u64);
+        self.used_percent.push_back(used_pct as u64);
+        self.cgroup_percent.push_back(cgroup_pct.unwrap_or(0.0) as u64);
+    }
+
+    /// Drop all history so the graph restarts from now
+    pub fn clear(&mut self) {
+        self.used_percent.clear();
+        self.cgroup_percent.clear();
     }
 }
 
 impl Default for MemoryHistory {
     fn default() -> Self {
-        Self::new(500)  // Large buffer, display will use graph width
+        Self::new(DEFAULT_HISTORY)  // Large buffer, display will use graph width
     }
 }
 
 /// Disk history for sparkline display
 pub struct DiskHistory {
-    pub read_history: Vec<u64>,   // Read KB/s history
-    pub write_history: Vec<u64>,  // Write KB/s history
+    pub read_history: VecDeque<u64>,   // Read KB/s history
+    pub write_history: VecDeque<u64>,  // Write KB/s history
     pub max_samples: usize,
 }
 
 impl DiskHistory {
     pub fn new(max_samples: usize) -> Self {
         Self {
-            read_history: Vec::with_capacity(max_samples),
-            write_history: Vec::with_capacity(max_samples),
+            read_history: VecDeque::with_capacity(max_samples),
+            write_history: VecDeque::with_capacity(max_samples),
             max_samples,
         }
     }
@@ -701,32 +1274,38 @@ impl DiskHistory {
         let write_kb = (write_bytes_per_sec / 1024.0).max(0.0) as u64;
         
         if self.read_history.len() >= self.max_samples {
-            self.read_history.remove(0);
-            self.write_history.remove(0);
+            self.read_history.pop_front();
+            self.write_history.pop_front();
         }
-        self.read_history.push(read_kb);
-        self.write_history.push(write_kb);
+        self.read_history.push_back(read_kb);
+        self.write_history.push_back(write_kb);
+    }
+
+    /// Drop all history so the graph restarts from now
+    pub fn clear(&mut self) {
+        self.read_history.clear();
+        self.write_history.clear();
     }
 }
 
 impl Default for DiskHistory {
     fn default() -> Self {
-        Self::new(500)  // Large buffer, display will use graph width
+        Self::new(DEFAULT_HISTORY)  // Large buffer, display will use graph width
     }
 }
 
 /// Network history for sparkline display
 pub struct NetworkHistory {
-    pub rx_history: Vec<u64>,  // RX KB/s history
-    pub tx_history: Vec<u64>,  // TX KB/s history
+    pub rx_history: VecDeque<u64>,  // RX KB/s history
+    pub tx_history: VecDeque<u64>,  // TX KB/s history
     pub max_samples: usize,
 }
 
 impl NetworkHistory {
     pub fn new(max_samples: usize) -> Self {
         Self {
-            rx_history: Vec::with_capacity(max_samples),
-            tx_history: Vec::with_capacity(max_samples),
+            rx_history: VecDeque::with_capacity(max_samples),
+            tx_history: VecDeque::with_capacity(max_samples),
             max_samples,
         }
     }
@@ -736,24 +1315,61 @@ impl NetworkHistory {
         let tx_kb = (tx_bytes_per_sec / 1024.0).max(0.0) as u64;
         
         if self.rx_history.len() >= self.max_samples {
-            self.rx_history.remove(0);
-            self.tx_history.remove(0);
+            self.rx_history.pop_front();
+            self.tx_history.pop_front();
         }
-        self.rx_history.push(rx_kb);
-        self.tx_history.push(tx_kb);
+        self.rx_history.push_back(rx_kb);
+        self.tx_history.push_back(tx_kb);
+    }
+
+    /// Drop all history so the graph restarts from now
+    pub fn clear(&mut self) {
+        self.rx_history.clear();
+        self.tx_history.clear();
     }
 }
 
 impl Default for NetworkHistory {
     fn default() -> Self {
-        Self::new(500)  // Large buffer, display will use graph width
+        Self::new(DEFAULT_HISTORY)  // Large buffer, display will use graph width
     }
 }
 
-/// Render network metrics widget with sparkline graphs
-pub fn render_network(f: &mut Frame, area: Rect, net: &NetworkMetrics, history: Option<&NetworkHistory>) {
+/// Compact NIC queue summary, e.g. " Ring:512/4096 Q:8"
+fn format_nic_settings(nic: &NicSettings) -> String {
+    let mut out = String::new();
+    match (nic.rx_ring, nic.rx_ring_max) {
+        (Some(cur), Some(max)) => out.push_str(&format!(" Ring:{}/{}", cur, max)),
+        (Some(cur), None) => out.push_str(&format!(" Ring:{}", cur)),
+        _ => {
+            if let Some(len) = nic.tx_queue_len {
+                out.push_str(&format!(" TxQLen:{}", len));
+            }
+        }
+    }
+    if nic.rx_queues > 1 {
+        out.push_str(&format!(" Q:{}", nic.rx_queues));
+    }
+    if let Some(usecs) = nic.rx_usecs {
+        out.push_str(&format!(" Coal:{}us", usecs));
+    }
+    out
+}
+
+/// Render network metrics widget with sparkline graphs (throughput in bits per second when `bits` is set)
+#[allow(clippy::too_many_arguments)]
+pub fn render_network(
+    f: &mut Frame,
+    area: Rect,
+    net: &NetworkMetrics,
+    history: Option<&NetworkHistory>,
+    graph: GraphMode,
+    view: HistoryView,
+    palette: &Palette,
+    bits: bool,
+) {
     let block = Block::default()
-        .title(" Network I/O ")
+        .title(panel_title(" Network I/O ", view, area))
         .borders(Borders::ALL)
         .border_style(Style::default().fg(Color::Blue));
 
@@ -769,20 +1385,21 @@ pub fn render_network(f: &mut Frame, area: Rect, net: &NetworkMetrics, history:
         ])
         .split(inner);
     
-    // Split sparklines into RX (top) and TX (bottom)
-    let graph_chunks = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([Constraint::Ratio(1, 2), Constraint::Ratio(1, 2)])
-        .split(main_chunks[1]);
-
-    // Get utilization and link speed from first interface if available
-    let (rx_util, tx_util, link_speed) = net.interfaces.first()
+    // Get utilization and link speed from the busiest interface with a known speed
+    // (virtual interfaces report none, and may be listed before the physical NIC)
+    let (rx_util, tx_util, link_speed) = net.interfaces.iter()
+        .filter(|i| i.link_speed_mbps.is_some())
+        .max_by(|a, b| {
+            (a.rx_bytes_per_sec + a.tx_bytes_per_sec).total_cmp(&(b.rx_bytes_per_sec + b.tx_bytes_per_sec))
+        })
         .map(|i| (i.rx_util_pct, i.tx_util_pct, i.link_speed_mbps))
         .unwrap_or((None, None, None));
     
     // Text information with colored RX/TX values and utilization bars
-    let rx_str = format_throughput_short(net.total_rx_bytes_per_sec);
-    let tx_str = format_throughput_short(net.total_tx_bytes_per_sec);
+    let format_rate: fn(f64) -> String = if bits { format_throughput_bits } else { format_throughput };
+    let format_short: fn(f64) -> String = if bits { format_throughput_bits_short } else { format_throughput_short };
+    let rx_str = format_short(net.total_rx_bytes_per_sec);
+    let tx_str = format_short(net.total_tx_bytes_per_sec);
     
     // Helper to create a utilization bar
     let make_util_bar = |pct: Option<f64>, bar_width: usize| -> Vec<Span> {
@@ -791,13 +1408,13 @@ pub fn render_network(f: &mut Frame, area: Rect, net: &NetworkMetrics, history:
                 let clamped = p.clamp(0.0, 100.0);
                 let filled = ((clamped / 100.0) * bar_width as f64).round() as usize;
                 let empty = bar_width.saturating_sub(filled);
-                let color = percentage_color(clamped, 50.0, 80.0);
+                let style = palette.level(clamped, 50.0, 80.0);
                 vec![
                     Span::raw("["),
-                    Span::styled("█".repeat(filled), Style::default().fg(color)),
+                    Span::styled("█".repeat(filled), style),
                     Span::styled("░".repeat(empty), Style::default().fg(Color::DarkGray)),
                     Span::raw("]"),
-                    Span::styled(format!("{:>5.1}%", clamped), Style::default().fg(color)),
+                    Span::styled(format!("{:>5.1}%", clamped), style),
                 ]
             }
             None => vec![
@@ -808,6 +1425,7 @@ pub fn render_network(f: &mut Frame, area: Rect, net: &NetworkMetrics, history:
     
     // Link speed string
     let link_str = match link_speed {
+        Some(speed) if speed >= 1000 && speed % 1000 != 0 => format!(" @{:.1}G", speed as f64 / 1000.0),
         Some(speed) if speed >= 1000 => format!(" @{}G", speed / 1000),
         Some(speed) => format!(" @{}M", speed),
         None => String::new(),
@@ -836,99 +1454,135 @@ pub fn render_network(f: &mut Frame, area: Rect, net: &NetworkMetrics, history:
         Line::from(tx_spans),
     ];
 
-    // Show interface details on third line
-    if let Some(iface) = net.interfaces.first() {
-        lines.push(Line::from(vec![
-            Span::styled(format!("{}: ", iface.interface), Style::default().fg(Color::DarkGray)),
-            Span::raw("TCP:"),
-            Span::styled(format!("{}", net.tcp.connections_established), Style::default().fg(Color::White)),
-            Span::raw(" Retx:"),
-            Span::styled(
-                format!("{}", net.tcp.retransmits_delta.unwrap_or(0)),
-                if net.tcp.retransmits_delta.unwrap_or(0) > 0 { 
-                    Style::default().fg(Color::Yellow) 
-                } else { 
-                    Style::default().fg(Color::White) 
-                }
-            ),
-            if iface.rx_errors > 0 || iface.tx_errors > 0 || iface.rx_drops > 0 || iface.tx_drops > 0 {
-                Span::styled(
-                    format!(" Err:{}/{} Drop:{}/{}",
-                        iface.rx_errors, iface.tx_errors, iface.rx_drops, iface.tx_drops
-                    ),
-                    Style::default().fg(Color::Red),
-                )
-            } else {
-                Span::raw("")
+    // Socket states on the third line, after the first interface's name and followed by its
+    // error counters when there is one (interfaces need two reads; the sockets do not)
+    let iface = net.interfaces.first();
+    let mut details = Vec::new();
+    if let Some(iface) = iface {
+        details.push(Span::styled(format!("{}: ", iface.interface), Style::default().fg(Color::DarkGray)));
+    }
+    details.extend([
+        Span::raw("TCP:"),
+        Span::styled(format!("{}", net.tcp.connections_established), Style::default().fg(Color::White)),
+        Span::raw(" Retx:"),
+        Span::styled(
+            match net.tcp.retransmit_percent() {
+                Some(pct) if pct > 0.0 => format!("{} ({:.1}%)", net.tcp.retransmits_delta.unwrap_or(0), pct),
+                _ => format!("{}", net.tcp.retransmits_delta.unwrap_or(0)),
             },
-        ]));
+            if net.tcp.retransmits_delta.unwrap_or(0) > 0 { 
+                palette.warn
+            } else { 
+                Style::default().fg(Color::White) 
+            }
+        ),
+        Span::raw(" TW:"),
+        Span::styled(format!("{}", net.socket_state("TIME_WAIT")), Style::default().fg(Color::White)),
+        Span::raw(" CW:"),
+        Span::styled(
+            format!("{}", net.socket_state("CLOSE_WAIT")),
+            // Sockets the application never closed; a growing count is a leak
+            if net.socket_state("CLOSE_WAIT") > 0 {
+                palette.warn
+            } else {
+                Style::default().fg(Color::White)
+            }
+        ),
+        Span::raw(" UDP:"),
+        Span::styled(format!("{}", net.udp_sockets), Style::default().fg(Color::White)),
+    ]);
+    if let Some(iface) = iface {
+        if iface.rx_errors > 0 || iface.tx_errors > 0 || iface.rx_drops > 0 || iface.tx_drops > 0 {
+            details.push(Span::styled(
+                format!(" Err:{}/{} Drop:{}/{}",
+                    iface.rx_errors, iface.tx_errors, iface.rx_drops, iface.tx_drops
+                ),
+                palette.crit,
+            ));
+        }
+        // Ring size next to the drops: a small ring under bursty traffic causes rx_drops
+        details.push(Span::styled(
+            iface.nic.as_ref().map(format_nic_settings).unwrap_or_default(),
+            Style::default().fg(Color::DarkGray),
+        ));
     }
+    lines.push(Line::from(details));
 
     f.render_widget(Paragraph::new(lines), main_chunks[0]);
 
     // Sparklines for network history (sized to graph width)
     if let Some(hist) = history {
-        // RX bytes sparkline (cyan)
         if !hist.rx_history.is_empty() {
-            let rx_data = slice_for_width(&hist.rx_history, graph_chunks[0]);
-            let rx_max = rx_data.iter().max().copied().unwrap_or(1).max(1);
-            let rx_title = format!(" RX ▼ max:{} ", format_throughput(rx_max as f64 * 1024.0));
-            let rx_sparkline = Sparkline::default()
-                .block(Block::default()
-                    .borders(Borders::ALL)
-                    .border_style(Style::default().fg(Color::DarkGray))
-                    .title(rx_title))
-                .data(rx_data)
-                .max(rx_max)
-                .style(Style::default().fg(Color::Cyan));
-            f.render_widget(rx_sparkline, graph_chunks[0]);
-        }
-
-        // TX bytes sparkline (green)
-        if !hist.tx_history.is_empty() {
-            let tx_data = slice_for_width(&hist.tx_history, graph_chunks[1]);
-            let tx_max = tx_data.iter().max().copied().unwrap_or(1).max(1);
-            let tx_title = format!(" TX ▲ max:{} ", format_throughput(tx_max as f64 * 1024.0));
-            let tx_sparkline = Sparkline::default()
-                .block(Block::default()
-                    .borders(Borders::ALL)
-                    .border_style(Style::default().fg(Color::DarkGray))
-                    .title(tx_title))
-                .data(tx_data)
-                .max(tx_max)
-                .style(Style::default().fg(Color::Green));
-            f.render_widget(tx_sparkline, graph_chunks[1]);
+            render_rate_pair(
+                f,
+                main_chunks[1],
+                graph,
+                (&hist.rx_history, "RX ▼", Color::Cyan),
+                (&hist.tx_history, "TX ▲", Color::Green),
+                view,
+                format_rate,
+            );
         }
     }
 }
 
 /// Render process metrics widget
-pub fn render_process(f: &mut Frame, area: Rect, proc: Option<&ProcessMetrics>) {
+pub fn render_process(f: &mut Frame, area: Rect, proc: Option<&ProcessMetrics>, palette: &Palette) {
     let block = Block::default()
         .title(" Process ")
         .borders(Borders::ALL)
         .border_style(Style::default().fg(Color::Green));
 
-    let inner = block.inner(area);
+    let mut inner = block.inner(area);
     f.render_widget(block, area);
 
     if let Some(p) = proc {
-        let state_color = match p.state {
-            crate::process::ProcessState::Running => Color::Green,
-            crate::process::ProcessState::DiskSleep => Color::Yellow,
-            crate::process::ProcessState::Zombie => Color::Red,
-            _ => Color::White,
+        // Busiest threads (--threads) get a column on the right
+        if !p.top_threads.is_empty() {
+            let columns = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Min(0), Constraint::Length(32)])
+                .split(inner);
+            inner = columns[0];
+            let mut lines = vec![Line::from(Span::styled("Top threads", Style::default().add_modifier(Modifier::BOLD)))];
+            lines.extend(p.top_threads.iter().map(|t| {
+                let style = if t.cpu_percent >= 90.0 { palette.crit } else { Style::default() };
+                Line::from(Span::styled(format!("{:>7} {:<15} {:>5.1}%", t.tid, t.name, t.cpu_percent), style))
+            }));
+            f.render_widget(Paragraph::new(lines), columns[1]);
+        }
+
+        let state_style = match p.state {
+            crate::process::ProcessState::Running => palette.ok,
+            crate::process::ProcessState::DiskSleep => palette.warn,
+            crate::process::ProcessState::Zombie => palette.crit,
+            _ => Style::default().fg(Color::White),
         };
 
         let lines = vec![
-            Line::from(format!("PID: {}  Name: {}", p.pid, p.name)),
+            Line::from(format!(
+                "PID: {}{}  Name: {}  Age: {}  Nice: {}",
+                p.pid,
+                p.children_label(),
+                p.name,
+                format_age(p.age_secs),
+                p.nice
+            )),
             Line::from(vec![
                 Span::raw("State: "),
-                Span::styled(format!("{}", p.state), Style::default().fg(state_color)),
+                Span::styled(format!("{}", p.state), state_style),
+                // Voluntary switches mean waiting on I/O or locks, involuntary ones mean CPU contention
+                Span::raw(format!(
+                    "  Ctx/s: {:.0} vol, {:.0} invol",
+                    p.voluntary_ctxt_per_sec, p.nonvoluntary_ctxt_per_sec
+                )),
             ]),
             Line::from(format!(
-                "CPU: {:.1}%  Threads: {}  FDs: {}",
-                p.cpu_percent, p.num_threads, p.num_fds
+                "CPU: {:.1}%  Threads: {}  FDs: {}  TCP: {}",
+                p.cpu_percent,
+                p.num_threads,
+                p.fds_label(),
+                p.tcp_connections.map(|n| n.to_string()).unwrap_or_else(|| "-".to_string())
             )),
             Line::from(format!(
                 "RSS: {}  VSZ: {}",
@@ -939,7 +1593,7 @@ pub fn render_process(f: &mut Frame, area: Rect, proc: Option<&ProcessMetrics>)
             Line::from(format!(
                 "Cmd: {}",
                 if p.cmdline.len() > 60 {
-                    format!("{}...", &p.cmdline[..57])
+                    format!("{}...", &p.cmdline[..p.cmdline.floor_char_boundary(57)])
                 } else {
                     p.cmdline.clone()
                 }
@@ -952,8 +1606,122 @@ pub fn render_process(f: &mut Frame, area: Rect, proc: Option<&ProcessMetrics>)
     }
 }
 
+/// Render GPU metrics widget (--gpu), one line per GPU
+pub fn render_gpu(f: &mut Frame, area: Rect, gpu: &GpuMetrics, palette: &Palette) {
+    let block = Block::default()
+        .title(" GPU ")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Magenta));
+
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    // Utilization and memory bars share what the name and readings leave
+    let bar_width = (inner.width as usize).saturating_sub(76) / 2;
+    let bar = |label: &str, value: &str, percent: f64, warn: f64, crit: f64| {
+        render_progress_bar(palette, label, value, percent, bar_width + label.len() + value.len() + 5, warn, crit).spans
+    };
+    let lines: Vec<Line> = gpu
+        .gpus
+        .iter()
+        .map(|g| {
+            let mut spans = vec![Span::raw(format!("{} {:<24.24} ", g.index, g.name))];
+            spans.extend(bar("Util", &format!("{:>3.0}%", g.utilization_percent), g.utilization_percent, 70.0, 90.0));
+            spans.push(Span::raw("  "));
+            let memory = format!("{} / {}", format_bytes_short(g.memory_used_bytes), format_bytes_short(g.memory_total_bytes));
+            spans.extend(bar("Mem", &memory, g.memory_percent, 80.0, 95.0));
+            if let Some(temp) = g.temperature_c {
+                spans.push(Span::styled(format!("  {:.0}°C", temp), palette.level(temp, 80.0, 90.0)));
+            }
+            if let Some(watts) = g.power_watts {
+                spans.push(Span::raw(format!("  {:.0} W", watts)));
+            }
+            Line::from(spans)
+        })
+        .collect();
+    f.render_widget(Paragraph::new(lines), inner);
+}
+
+/// Popup over the dashboard listing open files grouped by kind ('f'), scrolled down by `scroll` lines
+pub fn render_open_files(f: &mut Frame, area: Rect, title: &str, fds: &[OpenFd], scroll: usize) {
+    let popup = Rect {
+        x: area.x + area.width / 10,
+        y: area.y + area.height / 10,
+        width: area.width - area.width / 5,
+        height: area.height - area.height / 5,
+    };
+    let block = Block::default()
+        .title(format!(" Open files: {} ({} fds) ", title, fds.len()))
+        .title_bottom(" ↑/↓: Scroll | f/Esc: Close ")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan));
+
+    let mut lines = Vec::new();
+    for (i, fd) in fds.iter().enumerate() {
+        if i == 0 || fds[i - 1].kind != fd.kind {
+            if i > 0 {
+                lines.push(Line::from(""));
+            }
+            let count = fds.iter().filter(|other| other.kind == fd.kind).count();
+            lines.push(Line::from(Span::styled(
+                format!("{} ({})", fd.kind, count),
+                Style::default().add_modifier(Modifier::BOLD),
+            )));
+        }
+        lines.push(Line::from(format!("  {:>5}  {}", fd.fd, fd.target)));
+    }
+    if lines.is_empty() {
+        lines.push(Line::from("No open files"));
+    }
+
+    let visible = popup.height.saturating_sub(2) as usize;
+    let scroll = scroll.min(lines.len().saturating_sub(visible));
+    f.render_widget(Clear, popup);
+    f.render_widget(Paragraph::new(lines).block(block).scroll((scroll as u16, 0)), popup);
+}
+
+/// Popup over the dashboard ranking remote addresses by established TCP connections ('t')
+pub fn render_top_talkers(f: &mut Frame, area: Rect, peers: &[RemotePeer]) {
+    let width = area.width.min(60);
+    let height = area.height.min(peers.len().max(1) as u16 + 4);
+    let popup = Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    };
+    let block = Block::default()
+        .title(" Top talkers (established TCP) ")
+        .title_bottom(" t/Esc: Close ")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan));
+
+    let mut lines = vec![Line::from(Span::styled(
+        format!("{:<40} {:>6} {:>8}", "Remote address", "Now", "Avg"),
+        Style::default().add_modifier(Modifier::BOLD),
+    ))];
+    for peer in peers {
+        // Gone now but busy earlier in the run
+        let style = if peer.connections == 0 {
+            Style::default().fg(Color::DarkGray)
+        } else {
+            Style::default()
+        };
+        lines.push(Line::from(Span::styled(
+            format!("{:<40} {:>6} {:>8.1}", peer.address, peer.connections, peer.avg_connections),
+            style,
+        )));
+    }
+    if peers.is_empty() {
+        lines.push(Line::from("No established connections to remote hosts"));
+    }
+
+    f.render_widget(Clear, popup);
+    f.render_widget(Paragraph::new(lines).block(block), popup);
+}
+
 /// Render alerts widget
-pub fn render_alerts(f: &mut Frame, area: Rect, alerts: &[Alert]) {
+pub fn render_alerts(f: &mut Frame, area: Rect, alerts: &[Alert], palette: &Palette) {
     let block = Block::default()
         .title(" Alerts ")
         .borders(Borders::ALL)
@@ -971,15 +1739,22 @@ pub fn render_alerts(f: &mut Frame, area: Rect, alerts: &[Alert]) {
         return;
     }
 
+    // Newest first, as many as fit
     let items: Vec<ListItem> = alerts
         .iter()
-        .take(5) // Show only last 5 alerts
+        .rev()
+        .take(inner.height as usize)
         .map(|alert| {
             let style = match alert.severity {
-                crate::alert::Severity::Warning => Style::default().fg(Color::Yellow),
-                crate::alert::Severity::Critical => Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+                crate::alert::Severity::Warning => palette.warn,
+                crate::alert::Severity::Critical => palette.crit.add_modifier(Modifier::BOLD),
+                crate::alert::Severity::Resolved => palette.ok,
             };
-            ListItem::new(Span::styled(&alert.message, style))
+            let time = alert.timestamp.with_timezone(&Local).format("%H:%M:%S ").to_string();
+            ListItem::new(Line::from(vec![
+                Span::styled(time, Style::default().fg(Color::DarkGray)),
+                Span::styled(&alert.message, style),
+            ]))
         })
         .collect();
 
@@ -988,9 +1763,16 @@ pub fn render_alerts(f: &mut Frame, area: Rect, alerts: &[Alert]) {
 }
 
 /// Render system info widget
-pub fn render_system_info(f: &mut Frame, area: Rect, uptime_secs: u64) {
+pub fn render_system_info(
+    f: &mut Frame,
+    area: Rect,
+    uptime_secs: u64,
+    env: &EnvironmentInfo,
+    psi: Option<&PsiMetrics>,
+    palette: &Palette,
+) {
     let block = Block::default()
-        .title(" System ")
+        .title(" System / PSI ")
         .borders(Borders::ALL)
         .border_style(Style::default().fg(Color::Gray));
 
@@ -1008,14 +1790,60 @@ pub fn render_system_info(f: &mut Frame, area: Rect, uptime_secs: u64) {
         format!("{}h {}m {}s", hours, mins, secs)
     };
 
-    let lines = vec![
-        Line::from(format!("Uptime: {}", uptime_str)),
+    let mut header = vec![Span::raw(format!("Uptime: {}", uptime_str))];
+    if let Some(ref kernel) = env.kernel_release {
+        header.push(Span::styled(format!("  Kernel: {}", kernel), Style::default().fg(Color::Gray)));
+    }
+    if let Some(ref model) = env.cpu_model {
+        header.push(Span::styled(
+            format!("  CPU: {} ({} CPUs)", model, env.cpu_count),
+            Style::default().fg(Color::Gray),
+        ));
+    }
+    if let Some(ref clocksource) = env.clocksource {
+        // Slow clocksources (hpet, acpi_pm) inflate time-read cost on VMs
+        let style = if env.slow_clocksource() { palette.crit } else { Style::default().fg(Color::Gray) };
+        header.push(Span::styled(format!("  Clock: {}", clocksource), style));
+    }
+
+    let mut lines = vec![
+        Line::from(header),
+        Line::from(Span::styled(env.sysctl_summary(), Style::default().fg(Color::DarkGray))),
     ];
+
+    if let Some(psi) = psi {
+        let mut spans = vec![
+            Span::raw("PSI some10 (system): "),
+            Span::raw("CPU "),
+            Span::styled(format!("{:.1}%", psi.cpu.some_avg10), palette.level_bold(psi.cpu.some_avg10, 10.0, 25.0)),
+            Span::raw(" Mem "),
+            Span::styled(format!("{:.1}%", psi.memory.some_avg10), palette.level_bold(psi.memory.some_avg10, 10.0, 25.0)),
+            Span::raw(" IO "),
+            Span::styled(format!("{:.1}%", psi.io.some_avg10), palette.level_bold(psi.io.some_avg10, 10.0, 25.0)),
+        ];
+
+        // Cgroup vs system I/O pressure: is the container causing the stall or suffering it?
+        if let (Some(cgroup_io), Some(source)) = (psi.cgroup_io.as_ref(), psi.io_stall_source()) {
+            let (verdict, style) = match source {
+                IoStallSource::None => ("no I/O stall", palette.ok),
+                IoStallSource::Cgroup => ("stall is in this cgroup", palette.warn),
+                IoStallSource::Neighbor => ("stall is outside this cgroup (noisy neighbor?)", palette.crit),
+            };
+            spans.extend([
+                Span::raw("  |  IO cgroup: "),
+                Span::styled(format!("{:.1}%", cgroup_io.some_avg10), palette.level_bold(cgroup_io.some_avg10, 10.0, 25.0)),
+                Span::raw(format!(" vs system {:.1}% → ", psi.io.some_avg10)),
+                Span::styled(verdict, style),
+            ]);
+        }
+        lines.push(Line::from(spans));
+    }
+
     f.render_widget(Paragraph::new(lines), inner);
 }
 
 /// Render help bar at the bottom
-pub fn render_help_bar(f: &mut Frame, area: Rect, pending_split: bool, status: Option<&str>, current_log: Option<&str>) {
+pub fn render_help_bar(f: &mut Frame, area: Rect, pending_split: bool, paused: bool, replay: Option<&str>, status: Option<&str>, current_log: Option<&str>) {
     let (text, style) = if pending_split {
         (
             " Split logs? Press Y to confirm, any other key to cancel ".to_string(),
@@ -1026,12 +1854,32 @@ pub fn render_help_bar(f: &mut Frame, area: Rect, pending_split: bool, status: O
             format!(" {} ", msg),
             Style::default().fg(Color::Black).bg(Color::Green),
         )
+    } else if let Some(position) = replay {
+        let style = if paused {
+            Style::default().fg(Color::Black).bg(Color::Cyan).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(Color::Black).bg(Color::Magenta)
+        };
+        (
+            format!(
+                " {} {} | space: {} | [/]: Seek | +/-: Speed | q: Quit | 1-4/z: Zoom | g: Graph | ←/→: Scroll | h: Time scale ",
+                if paused { "PAUSED" } else { "REPLAY" },
+                position,
+                if paused { "Resume" } else { "Pause" },
+            ),
+            style,
+        )
+    } else if paused {
+        (
+            " PAUSED | space: Resume | q: Quit | 1-4/z: Zoom | g: Graph ".to_string(),
+            Style::default().fg(Color::Black).bg(Color::Cyan).add_modifier(Modifier::BOLD),
+        )
     } else {
         let log_info = current_log
             .map(|name| format!(" ({})", name))
             .unwrap_or_default();
         (
-            format!(" q: Quit | p: Toggle process | l: Toggle logging | r: Reset | s: Split logs{} ", log_info),
+            format!(" q: Quit | space: Pause | p: Toggle process | a: Alerts | l: Toggle logging | r: Reset | c: Clear graphs | 1-4/z: Zoom | g: Graph | ←/→: Scroll | h: Time scale | f: Open files | t: Talkers | w: Save profile | s: Split logs{} ", log_info),
             Style::default().fg(Color::Black).bg(Color::Gray),
         )
     };
@@ -1039,3 +1887,75 @@ pub fn render_help_bar(f: &mut Frame, area: Rect, pending_split: bool, status: O
     let paragraph = Paragraph::new(text).style(style);
     f.render_widget(paragraph, area);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn truncate_str_cuts_on_char_boundaries() {
+        let s = "résumé-server —flag";
+        for max_len in 0..=s.len() + 1 {
+            let truncated = truncate_str(s, max_len);
+            assert!(truncated.len() <= max_len.max(2), "{:?} longer than {}", truncated, max_len);
+        }
+        assert_eq!(truncate_str(s, s.len()), s);
+        // "é" spans bytes 1..3, so a cut inside it falls back to before it
+        assert_eq!(truncate_str(s, 4), "r..");
+        assert_eq!(truncate_str(s, 5), "ré..");
+        assert_eq!(truncate_str(s, 2), "r");
+    }
+
+    fn disk(device: &str, mapper_name: bool) -> DiskStats {
+        DiskStats { device: device.to_string(), mapper_name, ..Default::default() }
+    }
+
+    #[test]
+    fn short_disk_names() {
+        assert_eq!(short_disk_name(&disk("nvme0n1", false)), "n0");
+        assert_eq!(short_disk_name(&disk("sda", false)), "sda");
+        assert_eq!(short_disk_name(&disk("dm-3", false)), "dm-3");
+        assert_eq!(short_disk_name(&disk("dm-12", false)), "dm-12");
+        assert_eq!(short_disk_name(&disk("vg0-home", true)), "home");
+        assert_eq!(short_disk_name(&disk("cryptroot", true)), "cryp");
+        // Hyphens inside the volume group or logical volume name are doubled
+        assert_eq!(short_disk_name(&disk("vg--data-lv--root", true)), "lv-r");
+        assert_eq!(short_disk_name(&disk("vg-lv-root", true)), "lv-r");
+    }
+
+    #[test]
+    fn history_scale_coverage() {
+        assert_eq!(HistoryScale::Raw.coverage(3, 10), (3, 1.0));
+        assert_eq!(HistoryScale::Raw.coverage(100, 10), (10, 1.0));
+        assert_eq!(HistoryScale::Coarse.coverage(100, 10), (50, 5.0));
+        // A history shorter than the width covers fewer bars
+        assert_eq!(HistoryScale::Coarse.coverage(12, 10), (12, 5.0));
+        assert_eq!(HistoryScale::Full.coverage(1000, 10), (1000, 100.0));
+        assert_eq!(HistoryScale::Full.coverage(3, 10), (3, 1.0));
+        assert_eq!(HistoryScale::Full.coverage(5, 0), (5, 5.0));
+    }
+
+    /// The sparkline data of `data` in a graph `graph_width` bars wide at the coarse scale
+    fn coarse_slice(data: impl IntoIterator<Item = u64>, graph_width: u16) -> Vec<u64> {
+        let data: VecDeque<u64> = data.into_iter().collect();
+        let times = VecDeque::new();
+        let view = HistoryView { offset: 0, times: &times, scale: HistoryScale::Coarse };
+        slice_for_width(&data, Rect::new(0, 0, graph_width + 2, 5), view)
+    }
+
+    #[test]
+    fn coarse_slice_averages_from_the_newest_sample() {
+        // 2 bars of 5 cover the newest 10 of 30 samples
+        assert_eq!(coarse_slice(0..30, 2), vec![22, 27]);
+        // Exactly the covered length: no partial bucket
+        assert_eq!(coarse_slice(0..10, 2), vec![2, 7]);
+    }
+
+    #[test]
+    fn coarse_slice_keeps_a_partial_oldest_bucket() {
+        // 12 samples in a 10-bar graph: the oldest bar averages only 2 of them
+        assert_eq!(coarse_slice(1..=12, 10), vec![1, 5, 10]);
+        assert_eq!(coarse_slice([7], 10), vec![7]);
+        assert!(coarse_slice([], 10).is_empty());
+    }
+}
@@ -0,0 +1,108 @@
+//! One-time probe of the host environment (kernel, CPU model, clocksource, key sysctls).
+//!
+//! Recorded with each capture so runs on different machines or kernels can be
+//! compared with the settings that explain their behavior.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+/// Sysctls that commonly explain memory, writeback and network behavior
+const SYSCTLS: &[&str] = &[
+    "vm.swappiness",
+    "vm.dirty_ratio",
+    "vm.dirty_background_ratio",
+    "vm.overcommit_memory",
+    "net.core.somaxconn",
+];
+
+/// Clocksources with expensive reads, often a VM fallback when the TSC is unstable
+const SLOW_CLOCKSOURCES: &[&str] = &["hpet", "acpi_pm", "jiffies", "refined-jiffies"];
+
+/// Host environment captured once at startup
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnvironmentInfo {
+    /// Kernel release (e.g., "6.8.0-45-generic")
+    pub kernel_release: Option<String>,
+    /// Full kernel version string from /proc/version
+    pub kernel_version: Option<String>,
+    /// CPU model name from /proc/cpuinfo
+    pub cpu_model: Option<String>,
+    /// Number of online logical CPUs
+    pub cpu_count: usize,
+    /// Current kernel clocksource (e.g., "tsc", "kvm-clock", "hpet")
+    pub clocksource: Option<String>,
+    /// Sysctl name/value pairs (only those readable on this host)
+    pub sysctls: Vec<(String, String)>,
+}
+
+impl EnvironmentInfo {
+    /// Read the environment from /proc
+    pub fn probe() -> Self {
+        let read_trimmed = |path: &str| {
+            fs::read_to_string(path)
+                .ok()
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+        };
+
+        let cpuinfo = fs::read_to_string("/proc/cpuinfo").unwrap_or_default();
+        let cpu_model = cpuinfo
+            .lines()
+            .find(|l| l.starts_with("model name") || l.starts_with("Model"))
+            .and_then(|l| l.split_once(':'))
+            .map(|(_, v)| v.trim().to_string());
+        let cpu_count = cpuinfo.lines().filter(|l| l.starts_with("processor")).count();
+
+        let sysctls = SYSCTLS
+            .iter()
+            .filter_map(|name| {
+                let path = format!("/proc/sys/{}", name.replace('.', "/"));
+                read_trimmed(&path).map(|v| (name.to_string(), v))
+            })
+            .collect();
+
+        Self {
+            kernel_release: read_trimmed("/proc/sys/kernel/osrelease"),
+            kernel_version: read_trimmed("/proc/version"),
+            cpu_model,
+            cpu_count,
+            clocksource: read_trimmed("/sys/devices/system/clocksource/clocksource0/current_clocksource"),
+            sysctls,
+        }
+    }
+
+    /// Whether the current clocksource is known to make time reads expensive
+    pub fn slow_clocksource(&self) -> bool {
+        self.clocksource
+            .as_deref()
+            .map(|c| SLOW_CLOCKSOURCES.contains(&c))
+            .unwrap_or(false)
+    }
+
+    /// The environment as "Label: value" lines for the CSV log preamble
+    pub fn header_lines(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        if let Some(ref kernel) = self.kernel_version {
+            lines.push(format!("Kernel: {}", kernel));
+        }
+        if let Some(ref model) = self.cpu_model {
+            lines.push(format!("CPU: {} ({} CPUs)", model, self.cpu_count));
+        }
+        if let Some(ref clocksource) = self.clocksource {
+            lines.push(format!("Clock: {}", clocksource));
+        }
+        if !self.sysctls.is_empty() {
+            lines.push(format!("Sysctls: {}", self.sysctl_summary()));
+        }
+        lines
+    }
+
+    /// Sysctls as a compact "name=value" list
+    pub fn sysctl_summary(&self) -> String {
+        self.sysctls
+            .iter()
+            .map(|(n, v)| format!("{}={}", n, v))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
@@ -0,0 +1,647 @@
+//! Metric and alert export to external monitoring systems (StatsD, Prometheus,
+//! InfluxDB line protocol, webhooks, snapshot files).
+//!
+//! The key metrics of a sample are enumerated once by `key_metrics`, and each
+//! exporter formats the resulting points in its own wire format. StatsD gauges
+//! are pushed each interval, while a small HTTP server serves the latest sample
+//! to Prometheus scrapes. Alerts are POSTed as JSON to a webhook from a background task.
+//! A snapshot file holds the latest full sample as JSON for local pollers.
+
+use crate::alert::Alert;
+use crate::logging::MetricsSample;
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::ffi::OsString;
+use std::fs;
+use std::net::{IpAddr, TcpListener, UdpSocket};
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+/// Maximum StatsD payload per UDP packet (stays under a typical 1500 byte MTU)
+const STATSD_MAX_PACKET: usize = 1400;
+/// Alerts waiting for webhook delivery before new ones are dropped
+const WEBHOOK_QUEUE_LEN: usize = 64;
+/// Give up on a webhook request after this long
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+/// Longest wait at exit for queued alerts to go out
+const WEBHOOK_DRAIN_TIMEOUT: Duration = Duration::from_secs(5);
+/// Drop a scrape connection that hasn't sent its request within this long
+const SCRAPE_READ_TIMEOUT: Duration = Duration::from_secs(5);
+/// Longest scrape request head (request line and headers) read before answering
+const SCRAPE_MAX_REQUEST: usize = 8192;
+/// Wait after a failed accept (e.g. EMFILE) instead of retrying in a tight loop
+const ACCEPT_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+/// A single exported metric value
+#[derive(Debug, Clone)]
+pub struct MetricPoint {
+    /// Metric group (e.g., "cpu", "disk")
+    pub group: &'static str,
+    /// Field name within the group (e.g., "total", "read_bytes_per_sec")
+    pub field: &'static str,
+    /// Identifying labels (e.g., device, interface, pid)
+    pub labels: Vec<(&'static str, String)>,
+    /// Gauge value
+    pub value: f64,
+}
+
+impl MetricPoint {
+    fn new(group: &'static str, field: &'static str, value: f64) -> Self {
+        Self {
+            group,
+            field,
+            labels: Vec::new(),
+            value,
+        }
+    }
+
+    fn with_label(mut self, key: &'static str, value: impl Into<String>) -> Self {
+        self.labels.push((key, value.into()));
+        self
+    }
+}
+
+/// Enumerate the key metrics of a sample as exportable gauges
+pub fn key_metrics(sample: &MetricsSample) -> Vec<MetricPoint> {
+    let mut points = Vec::new();
+
+    // CPU
+    let cpu = &sample.cpu;
+    points.push(MetricPoint::new("cpu", "total", cpu.total_utilization));
+    points.push(MetricPoint::new("cpu", "user", cpu.user_percent));
+    points.push(MetricPoint::new("cpu", "system", cpu.system_percent));
+    points.push(MetricPoint::new("cpu", "iowait", cpu.iowait_percent));
+    points.push(MetricPoint::new("cpu", "steal", cpu.steal_percent));
+    points.push(MetricPoint::new("cpu", "load_1m", cpu.load_avg.0));
+
+    // Memory
+    let mem = &sample.memory;
+    points.push(MetricPoint::new("mem", "used_pct", mem.used_percent));
+    points.push(MetricPoint::new("mem", "used_bytes", mem.used as f64));
+    points.push(MetricPoint::new("mem", "available_bytes", mem.available as f64));
+    points.push(MetricPoint::new("mem", "cached_bytes", mem.cached as f64));
+    points.push(MetricPoint::new("mem", "dirty_bytes", mem.dirty as f64));
+    points.push(MetricPoint::new("mem", "swap_used_bytes", mem.swap_used as f64));
+    if let Some(pct) = mem.cgroup_usage_percent {
+        points.push(MetricPoint::new("mem", "cgroup_used_pct", pct));
+    }
+
+    // Disk
+    let disk = &sample.disk;
+    points.push(MetricPoint::new("disk", "total_read_bytes_per_sec", disk.total_read_bytes_per_sec));
+    points.push(MetricPoint::new("disk", "total_write_bytes_per_sec", disk.total_write_bytes_per_sec));
+    for d in &disk.disks {
+        points.push(MetricPoint::new("disk", "read_bytes_per_sec", d.read_bytes_per_sec).with_label("device", &d.device));
+        points.push(MetricPoint::new("disk", "write_bytes_per_sec", d.write_bytes_per_sec).with_label("device", &d.device));
+        points.push(MetricPoint::new("disk", "util_pct", d.utilization_percent).with_label("device", &d.device));
+        points.push(MetricPoint::new("disk", "in_flight", d.in_flight as f64).with_label("device", &d.device));
+    }
+
+    // Network
+    let net = &sample.network;
+    points.push(MetricPoint::new("net", "total_rx_bytes_per_sec", net.total_rx_bytes_per_sec));
+    points.push(MetricPoint::new("net", "total_tx_bytes_per_sec", net.total_tx_bytes_per_sec));
+    points.push(MetricPoint::new("net", "tcp_connections", net.tcp.connections_established as f64));
+    for iface in &net.interfaces {
+        points.push(MetricPoint::new("net", "rx_bytes_per_sec", iface.rx_bytes_per_sec).with_label("interface", &iface.interface));
+        points.push(MetricPoint::new("net", "tx_bytes_per_sec", iface.tx_bytes_per_sec).with_label("interface", &iface.interface));
+    }
+
+    // PSI
+    if let Some(ref psi) = sample.psi {
+        points.push(MetricPoint::new("psi", "cpu_some_avg10", psi.cpu.some_avg10));
+        points.push(MetricPoint::new("psi", "mem_some_avg10", psi.memory.some_avg10));
+        points.push(MetricPoint::new("psi", "io_some_avg10", psi.io.some_avg10));
+    }
+
+    // Process
+    if let Some(ref proc) = sample.process {
+        let pid = proc.pid.to_string();
+        let proc_points = [
+            ("cpu_pct", proc.cpu_percent),
+            ("rss_bytes", proc.rss_bytes as f64),
+            ("threads", proc.num_threads as f64),
+            ("fds", proc.num_fds as f64),
+            ("io_read_bytes_per_sec", proc.io_read_bytes_per_sec),
+            ("io_write_bytes_per_sec", proc.io_write_bytes_per_sec),
+        ];
+        for (field, value) in proc_points {
+            points.push(
+                MetricPoint::new("process", field, value)
+                    .with_label("pid", pid.as_str())
+                    .with_label("name", proc.name.as_str()),
+            );
+        }
+    }
+
+    points
+}
+
+/// Replace characters that are not safe in a StatsD metric name
+fn sanitize_statsd(s: &str) -> String {
+    s.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '_' || c == '-' { c } else { '_' })
+        .collect()
+}
+
+/// Format a metric point as a StatsD gauge line. Plain StatsD has no tags, so labels
+/// become name segments ("monperf.disk.sda.util_pct:12.5|g"); DogStatsD keeps the name
+/// fixed and appends them as tags ("monperf.disk.util_pct:12.5|g|#device:sda").
+fn format_statsd(prefix: &str, point: &MetricPoint, dogstatsd: bool) -> String {
+    let mut name = format!("{}.{}", prefix, point.group);
+    if !dogstatsd {
+        for (_, value) in &point.labels {
+            name.push('.');
+            name.push_str(&sanitize_statsd(value));
+        }
+    }
+    name.push('.');
+    name.push_str(point.field);
+
+    // Sums over empty collections yield -0.0, which collectors may reject
+    let value = if point.value == 0.0 { 0.0 } else { point.value };
+    let mut line = format!("{}:{}|g", name, value);
+    if dogstatsd && !point.labels.is_empty() {
+        let tags: Vec<String> = point.labels.iter()
+            .map(|(key, value)| format!("{}:{}", key, sanitize_statsd(value)))
+            .collect();
+        line.push_str("|#");
+        line.push_str(&tags.join(","));
+    }
+    line
+}
+
+/// StatsD exporter that pushes gauges over UDP (fire-and-forget)
+pub struct StatsdExporter {
+    socket: UdpSocket,
+    prefix: String,
+    dogstatsd: bool,
+}
+
+impl StatsdExporter {
+    /// Create an exporter sending to the given "host:port" address
+    pub fn new(addr: &str) -> Result<Self> {
+        let socket = UdpSocket::bind("0.0.0.0:0").context("Failed to bind StatsD socket")?;
+        socket
+            .connect(addr)
+            .with_context(|| format!("Failed to resolve StatsD address {}", addr))?;
+        // Never block the sampling loop on a slow or missing collector
+        socket.set_nonblocking(true)?;
+
+        Ok(Self {
+            socket,
+            prefix: "monperf".to_string(),
+            dogstatsd: false,
+        })
+    }
+
+    /// Send device, interface and process labels as DogStatsD tags instead of name segments
+    pub fn set_dogstatsd(&mut self, enabled: bool) {
+        self.dogstatsd = enabled;
+    }
+
+    /// Send the key metrics of a sample, batching lines into MTU-sized packets
+    pub fn send(&self, sample: &MetricsSample) {
+        let mut packet = String::new();
+
+        for point in key_metrics(sample) {
+            let line = format_statsd(&self.prefix, &point, self.dogstatsd);
+            if !packet.is_empty() && packet.len() + line.len() + 1 > STATSD_MAX_PACKET {
+                let _ = self.socket.send(packet.as_bytes());
+                packet.clear();
+            }
+            if !packet.is_empty() {
+                packet.push('\n');
+            }
+            packet.push_str(&line);
+        }
+
+        if !packet.is_empty() {
+            // Errors (e.g., ECONNREFUSED when nothing listens) are ignored by design
+            let _ = self.socket.send(packet.as_bytes());
+        }
+    }
+}
+
+/// Prometheus metric name of a point (e.g., "monperf_disk_read_bytes_per_sec")
+fn prometheus_name(point: &MetricPoint) -> String {
+    let group = match point.group {
+        "mem" => "memory",
+        group => group,
+    };
+    let field = match (point.group, point.field) {
+        ("cpu", "total") => "utilization".to_string(),
+        (_, field) => match field.strip_suffix("_pct") {
+            Some(base) => format!("{}_percent", base),
+            None => field.to_string(),
+        },
+    };
+    format!("monperf_{}_{}", group, field)
+}
+
+/// Escape a label value for the Prometheus text format
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// Format a gauge value for the Prometheus text format
+fn prometheus_value(value: f64) -> String {
+    if value.is_nan() {
+        "NaN".to_string()
+    } else if value.is_infinite() {
+        if value > 0.0 { "+Inf" } else { "-Inf" }.to_string()
+    } else if value == 0.0 {
+        // Sums over empty collections yield -0.0
+        "0".to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+/// Render the key metrics of a sample in the Prometheus text exposition format.
+/// Series of the same metric are grouped under a single TYPE line, as the format requires.
+pub fn format_prometheus(sample: &MetricsSample) -> String {
+    let mut families: Vec<(String, Vec<String>)> = Vec::new();
+
+    for point in key_metrics(sample) {
+        let name = prometheus_name(&point);
+        let labels = if point.labels.is_empty() {
+            String::new()
+        } else {
+            let pairs: Vec<String> = point.labels.iter()
+                .map(|(key, value)| format!("{}=\"{}\"", key, escape_label(value)))
+                .collect();
+            format!("{{{}}}", pairs.join(","))
+        };
+        let line = format!("{}{} {}", name, labels, prometheus_value(point.value));
+
+        match families.iter_mut().find(|(family, _)| *family == name) {
+            Some((_, lines)) => lines.push(line),
+            None => families.push((name, vec![line])),
+        }
+    }
+
+    let mut out = String::new();
+    for (name, lines) in families {
+        out.push_str(&format!("# TYPE {} gauge\n", name));
+        for line in lines {
+            out.push_str(&line);
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// Escape a measurement name, tag key/value or field key for InfluxDB line protocol
+fn escape_influx(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        if matches!(c, ',' | '=' | ' ' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Render the key metrics of a sample as InfluxDB line protocol: one line per
+/// measurement (cpu, memory, disk, network, psi, process) and tag set, timestamped
+/// in nanoseconds (e.g., "disk,device=sda read_bytes_per_sec=1024,util_pct=3.5 1700000000000000000")
+pub fn format_influx(sample: &MetricsSample) -> String {
+    let mut series: Vec<(String, Vec<String>)> = Vec::new();
+
+    for point in key_metrics(sample) {
+        let measurement = match point.group {
+            "mem" => "memory",
+            "net" => "network",
+            group => group,
+        };
+        let mut key = escape_influx(measurement);
+        for (tag, value) in &point.labels {
+            key.push_str(&format!(",{}={}", escape_influx(tag), escape_influx(value)));
+        }
+        // Influx rejects NaN and infinite field values
+        if !point.value.is_finite() {
+            continue;
+        }
+        let value = if point.value == 0.0 { 0.0 } else { point.value };
+        let field = format!("{}={}", escape_influx(point.field), value);
+
+        match series.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, fields)) => fields.push(field),
+            None => series.push((key, vec![field])),
+        }
+    }
+
+    let timestamp = sample.timestamp.timestamp_nanos_opt().unwrap_or_default();
+    let mut out = String::new();
+    for (key, fields) in series {
+        out.push_str(&format!("{} {} {}\n", key, fields.join(","), timestamp));
+    }
+    out
+}
+
+/// Keeps one JSON file holding the latest sample (--snapshot)
+///
+/// Each sample is written to a hidden sibling file and renamed over the target,
+/// so a reader opening the path always gets a complete sample. The file is left
+/// in place on exit; readers can tell a stale one by its timestamp.
+pub struct SnapshotWriter {
+    path: PathBuf,
+    tmp_path: PathBuf,
+}
+
+impl SnapshotWriter {
+    /// Check that the snapshot's directory exists; nothing is written until the first sample
+    pub fn new(path: &Path) -> Result<Self> {
+        let file_name = path.file_name()
+            .with_context(|| format!("Snapshot path {} has no file name", path.display()))?;
+        let dir = path.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+        if !dir.is_dir() {
+            anyhow::bail!("Snapshot directory {} does not exist", dir.display());
+        }
+        // Same directory, so the rename never crosses filesystems
+        let mut tmp_name = OsString::from(".");
+        tmp_name.push(file_name);
+        tmp_name.push(".tmp");
+        Ok(Self {
+            path: path.to_path_buf(),
+            tmp_path: path.with_file_name(tmp_name),
+        })
+    }
+
+    /// Replace the snapshot with `sample`
+    pub fn write(&self, sample: &MetricsSample) -> Result<()> {
+        let mut json = serde_json::to_vec(sample).context("Failed to encode sample")?;
+        json.push(b'\n');
+        fs::write(&self.tmp_path, json)
+            .with_context(|| format!("Failed to write {}", self.tmp_path.display()))?;
+        fs::rename(&self.tmp_path, &self.path)
+            .with_context(|| format!("Failed to replace {}", self.path.display()))
+    }
+}
+
+/// Serves the latest sample at /metrics for Prometheus to scrape
+pub struct PrometheusExporter {
+    latest: Arc<Mutex<Option<MetricsSample>>>,
+    errors: mpsc::Receiver<String>,
+}
+
+impl PrometheusExporter {
+    /// Bind the HTTP server on `addr` (must be called from within the tokio runtime)
+    pub fn new(addr: IpAddr, port: u16) -> Result<Self> {
+        let listener = TcpListener::bind((addr, port))
+            .with_context(|| format!("Failed to bind Prometheus endpoint on {}:{}", addr, port))?;
+        listener.set_nonblocking(true)?;
+        let listener = tokio::net::TcpListener::from_std(listener)?;
+
+        let latest: Arc<Mutex<Option<MetricsSample>>> = Arc::new(Mutex::new(None));
+        let shared = Arc::clone(&latest);
+        let (error_tx, errors) = mpsc::channel();
+        tokio::spawn(async move {
+            // Report a persistent failure once, not on every retry
+            let mut last_error = None;
+            loop {
+                let stream = match listener.accept().await {
+                    Ok((stream, _)) => {
+                        last_error = None;
+                        stream
+                    }
+                    Err(e) => {
+                        let error = format!("Prometheus endpoint accept failed: {}", e);
+                        if last_error.as_ref() != Some(&error) {
+                            let _ = error_tx.send(error.clone());
+                            last_error = Some(error);
+                        }
+                        tokio::time::sleep(ACCEPT_RETRY_DELAY).await;
+                        continue;
+                    }
+                };
+                let shared = Arc::clone(&shared);
+                tokio::spawn(async move {
+                    // A failed scrape only affects that client
+                    let _ = serve_scrape(stream, shared).await;
+                });
+            }
+        });
+
+        Ok(Self { latest, errors })
+    }
+
+    /// Publish a new sample to subsequent scrapes
+    pub fn update(&self, sample: &MetricsSample) {
+        if let Ok(mut latest) = self.latest.lock() {
+            *latest = Some(sample.clone());
+        }
+    }
+
+    /// Accept errors reported since the last call
+    pub fn take_errors(&self) -> Vec<String> {
+        self.errors.try_iter().collect()
+    }
+}
+
+/// Answer one HTTP request: the metrics at /metrics, 404 elsewhere
+async fn serve_scrape(
+    mut stream: tokio::net::TcpStream,
+    latest: Arc<Mutex<Option<MetricsSample>>>,
+) -> std::io::Result<()> {
+    let request = tokio::time::timeout(SCRAPE_READ_TIMEOUT, read_request_head(&mut stream))
+        .await
+        .map_err(|_| std::io::Error::from(std::io::ErrorKind::TimedOut))??;
+    let request = String::from_utf8_lossy(&request);
+    let path = request.split_whitespace().nth(1).unwrap_or("");
+
+    let (status, body) = if path == "/metrics" || path.starts_with("/metrics?") {
+        // Nothing is exposed until the first sample has been collected
+        let body = latest.lock()
+            .ok()
+            .and_then(|latest| latest.as_ref().map(format_prometheus))
+            .unwrap_or_default();
+        ("200 OK", body)
+    } else {
+        ("404 Not Found", "Metrics are served at /metrics\n".to_string())
+    };
+
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+/// Read an HTTP request up to the blank line ending its headers, which may arrive over
+/// several reads (stops early at SCRAPE_MAX_REQUEST bytes or when the client closes)
+async fn read_request_head(stream: &mut tokio::net::TcpStream) -> std::io::Result<Vec<u8>> {
+    let mut request = Vec::new();
+    let mut buf = [0u8; 1024];
+    while request.len() < SCRAPE_MAX_REQUEST && !request.windows(4).any(|w| w == b"\r\n\r\n") {
+        let len = stream.read(&mut buf).await?;
+        if len == 0 {
+            break;
+        }
+        request.extend_from_slice(&buf[..len]);
+    }
+    Ok(request)
+}
+
+/// JSON body of a webhook request: the alert plus the host it fired on
+#[derive(Serialize)]
+struct WebhookPayload<'a> {
+    hostname: &'a str,
+    #[serde(flatten)]
+    alert: &'a Alert,
+}
+
+/// POSTs alerts as JSON to a webhook URL without blocking the sampling loop
+pub struct AlertWebhook {
+    queue: tokio::sync::mpsc::Sender<Alert>,
+    errors: mpsc::Receiver<String>,
+    delivery: tokio::task::JoinHandle<()>,
+}
+
+impl AlertWebhook {
+    /// Start the delivery task (must be called from within the tokio runtime)
+    pub fn new(url: &str) -> Result<Self> {
+        let url = reqwest::Url::parse(url).with_context(|| format!("Invalid webhook URL {}", url))?;
+        let client = reqwest::Client::builder()
+            .timeout(WEBHOOK_TIMEOUT)
+            .build()
+            .context("Failed to create HTTP client")?;
+        let hostname = std::fs::read_to_string("/proc/sys/kernel/hostname")
+            .map(|h| h.trim().to_string())
+            .unwrap_or_default();
+
+        let (queue, mut pending) = tokio::sync::mpsc::channel::<Alert>(WEBHOOK_QUEUE_LEN);
+        let (error_tx, errors) = mpsc::channel();
+        let delivery = tokio::spawn(async move {
+            while let Some(alert) = pending.recv().await {
+                let payload = WebhookPayload { hostname: &hostname, alert: &alert };
+                let error = match client.post(url.clone()).json(&payload).send().await {
+                    Ok(response) if !response.status().is_success() => {
+                        Some(format!("Alert webhook returned {}", response.status()))
+                    }
+                    Ok(_) => None,
+                    Err(e) => Some(format!("Alert webhook delivery failed: {}", e)),
+                };
+                if let Some(error) = error {
+                    let _ = error_tx.send(error);
+                }
+            }
+        });
+
+        Ok(Self { queue, errors, delivery })
+    }
+
+    /// Queue an alert for delivery; false if it was dropped because the queue is full
+    pub fn send(&self, alert: &Alert) -> bool {
+        self.queue.try_send(alert.clone()).is_ok()
+    }
+
+    /// Delivery errors reported since the last call
+    pub fn take_errors(&self) -> Vec<String> {
+        self.errors.try_iter().collect()
+    }
+
+    /// Deliver the alerts still queued (giving up after WEBHOOK_DRAIN_TIMEOUT)
+    /// and return the delivery errors not yet taken
+    pub async fn finish(self) -> Vec<String> {
+        let Self { queue, errors, delivery } = self;
+        // Closing the queue ends the delivery task once it has sent what is left
+        drop(queue);
+        if tokio::time::timeout(WEBHOOK_DRAIN_TIMEOUT, delivery).await.is_err() {
+            return vec!["Alert webhook still delivering at exit, remaining alerts dropped".to_string()];
+        }
+        errors.try_iter().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::disk::DiskStats;
+    use crate::metrics::{CpuMetrics, DiskMetrics, MemoryMetrics, NetworkMetrics};
+    use chrono::{TimeZone, Utc};
+
+    #[test]
+    fn escape_influx_special_characters() {
+        assert_eq!(escape_influx("sda"), "sda");
+        assert_eq!(escape_influx("a,b"), "a\\,b");
+        assert_eq!(escape_influx("my disk"), "my\\ disk");
+        assert_eq!(escape_influx("k=v"), "k\\=v");
+        assert_eq!(escape_influx("C:\\data"), "C:\\\\data");
+        assert_eq!(escape_influx("a, b=c\\"), "a\\,\\ b\\=c\\\\");
+    }
+
+    #[test]
+    fn influx_tags_are_escaped() {
+        let mut disk = DiskMetrics::default();
+        disk.disks.push(DiskStats { device: "vg0 data,1".to_string(), read_bytes_per_sec: 1024.0, ..Default::default() });
+        let sample = MetricsSample {
+            timestamp: Utc.timestamp_opt(1_700_000_000, 0).unwrap(),
+            interval_secs: 1.0,
+            cpu: CpuMetrics::default(),
+            memory: MemoryMetrics::default(),
+            disk,
+            network: NetworkMetrics::default(),
+            process: None,
+            psi: None,
+            cgroup_psi: None,
+            gpu: None,
+        };
+
+        let out = format_influx(&sample);
+        let line = out.lines().find(|l| l.starts_with("disk,")).expect("per-disk line");
+        assert!(line.starts_with("disk,device=vg0\\ data\\,1 "), "{}", line);
+        assert!(line.contains("read_bytes_per_sec=1024"), "{}", line);
+        assert!(line.ends_with(" 1700000000000000000"), "{}", line);
+    }
+
+    #[tokio::test]
+    async fn finish_delivers_queued_alerts() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 4096];
+            while !String::from_utf8_lossy(&request).contains("process_oom_kill") {
+                let n = stream.read(&mut buf).await.unwrap();
+                assert!(n > 0, "connection closed before the alert arrived");
+                request.extend_from_slice(&buf[..n]);
+            }
+            stream.write_all(b"HTTP/1.1 204 No Content\r\ncontent-length: 0\r\n\r\n").await.unwrap();
+            String::from_utf8_lossy(&request).into_owned()
+        });
+
+        let webhook = AlertWebhook::new(&url).unwrap();
+        assert!(webhook.send(&Alert::oom_kill(42, "worker")));
+        assert_eq!(webhook.finish().await, Vec::<String>::new());
+        assert!(server.await.unwrap().starts_with("POST /hook "));
+    }
+
+    #[tokio::test]
+    async fn scrape_request_split_across_reads() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let client = tokio::spawn(async move {
+            let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+            stream.write_all(b"GET /metr").await.unwrap();
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            stream.write_all(b"ics HTTP/1.1\r\nHost: localhost\r\n\r\n").await.unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).await.unwrap();
+            response
+        });
+
+        let (stream, _) = listener.accept().await.unwrap();
+        serve_scrape(stream, Arc::new(Mutex::new(None))).await.unwrap();
+        let response = client.await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK"), "{}", response);
+    }
+}
@@ -0,0 +1,169 @@
+//! Sampling interval control: adaptive tuning and timing drift tracking.
+//!
+//! The tuner shortens the interval when metrics change sharply between samples
+//! (so spikes are not missed) and lengthens it after a run of stable samples
+//! (so long captures don't produce huge logs), always within the given bounds.
+//! The drift tracker compares intended and actual sample spacing, which exposes
+//! overloaded hosts and slow clocksources.
+
+use crate::metrics::{CpuMetrics, DiskMetrics, MemoryMetrics, NetworkMetrics};
+use std::time::Duration;
+
+/// Change score (percentage points) at or above which the interval is halved
+const VOLATILE_SCORE: f64 = 15.0;
+/// Change score at or below which a sample counts as stable
+const STABLE_SCORE: f64 = 3.0;
+/// Consecutive stable samples required before the interval is lengthened
+const STABLE_SAMPLES_TO_GROW: u32 = 5;
+/// Factor applied to the interval after a stable run
+const GROWTH_FACTOR: f64 = 1.5;
+/// Throughput floor for relative change, so idle devices don't look volatile
+const THROUGHPUT_FLOOR: f64 = 1024.0 * 1024.0;
+
+/// Values compared between consecutive samples
+#[derive(Debug, Clone, Copy)]
+struct Snapshot {
+    cpu_pct: f64,
+    mem_pct: f64,
+    disk_bytes_per_sec: f64,
+    net_bytes_per_sec: f64,
+}
+
+/// Adapts the sampling interval to how volatile the system currently is
+pub struct IntervalTuner {
+    min: Duration,
+    max: Duration,
+    prev: Option<Snapshot>,
+    stable_samples: u32,
+}
+
+impl IntervalTuner {
+    pub fn new(min: Duration, max: Duration) -> Self {
+        Self {
+            min,
+            max,
+            prev: None,
+            stable_samples: 0,
+        }
+    }
+
+    /// Clamp an interval to the configured bounds
+    pub fn clamp(&self, interval: Duration) -> Duration {
+        interval.clamp(self.min, self.max)
+    }
+
+    /// Observe the latest metrics and return the interval to use for the next sample
+    pub fn observe(
+        &mut self,
+        current: Duration,
+        cpu: &CpuMetrics,
+        memory: &MemoryMetrics,
+        disk: &DiskMetrics,
+        network: &NetworkMetrics,
+    ) -> Duration {
+        let snapshot = Snapshot {
+            cpu_pct: cpu.total_utilization,
+            mem_pct: memory.used_percent,
+            disk_bytes_per_sec: disk.total_read_bytes_per_sec + disk.total_write_bytes_per_sec,
+            net_bytes_per_sec: network.total_rx_bytes_per_sec + network.total_tx_bytes_per_sec,
+        };
+
+        let prev = match self.prev.replace(snapshot) {
+            Some(prev) => prev,
+            None => return self.clamp(current),
+        };
+
+        let score = change_score(&prev, &snapshot);
+
+        let next = if score >= VOLATILE_SCORE {
+            self.stable_samples = 0;
+            current / 2
+        } else if score <= STABLE_SCORE {
+            self.stable_samples += 1;
+            if self.stable_samples >= STABLE_SAMPLES_TO_GROW {
+                self.stable_samples = 0;
+                current.mul_f64(GROWTH_FACTOR)
+            } else {
+                current
+            }
+        } else {
+            self.stable_samples = 0;
+            current
+        };
+
+        self.clamp(next)
+    }
+}
+
+/// Largest change between two snapshots, in percentage points
+/// (throughput changes are expressed relative to the larger of the two rates)
+fn change_score(prev: &Snapshot, curr: &Snapshot) -> f64 {
+    let relative = |a: f64, b: f64| -> f64 {
+        let base = a.max(b).max(THROUGHPUT_FLOOR);
+        100.0 * (a - b).abs() / base
+    };
+
+    (curr.cpu_pct - prev.cpu_pct)
+        .abs()
+        .max((curr.mem_pct - prev.mem_pct).abs())
+        .max(relative(prev.disk_bytes_per_sec, curr.disk_bytes_per_sec))
+        .max(relative(prev.net_bytes_per_sec, curr.net_bytes_per_sec))
+}
+
+/// A sample counts as late when it arrives this fraction of the interval late...
+const LATE_FRACTION: f64 = 0.25;
+/// ...and at least this many seconds late (ignores jitter on short intervals)
+const LATE_MIN_SECS: f64 = 0.05;
+
+/// Tracks how far actual sample spacing strays from the intended interval
+#[derive(Debug, Default)]
+pub struct TimingDrift {
+    samples: u64,
+    late_samples: u64,
+    total_intended_secs: f64,
+    total_actual_secs: f64,
+    max_late_secs: f64,
+}
+
+impl TimingDrift {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record one sample's intended and actual spacing
+    pub fn record(&mut self, intended: Duration, actual_secs: f64) {
+        let intended_secs = intended.as_secs_f64();
+        let late_secs = actual_secs - intended_secs;
+
+        self.samples += 1;
+        self.total_intended_secs += intended_secs;
+        self.total_actual_secs += actual_secs;
+        self.max_late_secs = self.max_late_secs.max(late_secs);
+        if late_secs > (intended_secs * LATE_FRACTION).max(LATE_MIN_SECS) {
+            self.late_samples += 1;
+        }
+    }
+
+    pub fn samples(&self) -> u64 {
+        self.samples
+    }
+
+    pub fn late_samples(&self) -> u64 {
+        self.late_samples
+    }
+
+    /// Mean intended interval in seconds
+    pub fn mean_intended_secs(&self) -> f64 {
+        if self.samples == 0 { 0.0 } else { self.total_intended_secs / self.samples as f64 }
+    }
+
+    /// Mean actual interval in seconds
+    pub fn mean_actual_secs(&self) -> f64 {
+        if self.samples == 0 { 0.0 } else { self.total_actual_secs / self.samples as f64 }
+    }
+
+    /// Largest lateness of a single sample in seconds
+    pub fn max_late_secs(&self) -> f64 {
+        self.max_late_secs
+    }
+}
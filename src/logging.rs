@@ -3,52 +3,153 @@
 //! The canonical log format is CSV (CsvLogger), containing all detailed metrics.
 //! The human-readable text format (TextLogger) derives simpler metrics from the same data.
 
+use crate::alert::{Alert, AlertThresholds, Severity, OOM_KILL_KEY};
 use crate::display::{format_bytes_short, format_throughput};
-use crate::metrics::{CpuMetrics, DiskMetrics, MemoryMetrics, NetworkMetrics, PsiMetrics};
-use crate::process::ProcessMetrics;
+use crate::environment::EnvironmentInfo;
+use crate::metrics::{CpuMetrics, DiskMetrics, GpuMetrics, MemoryMetrics, NetworkMetrics, PsiMetrics};
+use crate::process::{ProcessMetrics, ProcessState};
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
 use std::fs::{File, OpenOptions};
-use std::io::{BufWriter, Write};
-use std::path::Path;
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+/// How --no-tui mode writes samples to stdout (--format)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// A readable block per sample
+    Text,
+    /// One JSON object per line, for piping into jq
+    Json,
+}
 
 /// A single metrics sample with timestamp
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MetricsSample {
     pub timestamp: DateTime<Utc>,
+    /// Actual time elapsed since the previous sample in seconds (0 for the first sample)
+    pub interval_secs: f64,
     pub cpu: CpuMetrics,
     pub memory: MemoryMetrics,
     pub disk: DiskMetrics,
     pub network: NetworkMetrics,
     pub process: Option<ProcessMetrics>,
     pub psi: Option<PsiMetrics>,
+    /// Pressure of the monitored process's cgroup (None without a process or on cgroup v1)
+    #[serde(default)]
+    pub cgroup_psi: Option<PsiMetrics>,
+    /// Per-GPU utilization and memory (None without --gpu or NVIDIA GPUs)
+    #[serde(default)]
+    pub gpu: Option<GpuMetrics>,
+}
+
+/// Options shared by every file logger
+#[derive(Debug, Clone, Copy)]
+pub struct LogOptions {
+    /// Add to existing files instead of truncating them
+    pub append: bool,
+    /// Flush buffered output every N samples. 1 means a crash loses
+    /// at most the sample in progress; larger values save syscalls at short
+    /// intervals at the cost of losing up to N samples. Drop always flushes.
+    pub flush_every: u64,
+}
+
+/// Open a log file, truncating it or (with `append`) adding to its end.
+/// Also returns whether the file already had content, so headers aren't repeated.
+fn open_log_file(path: &Path, append: bool, what: &str) -> Result<(File, bool)> {
+    let mut options = OpenOptions::new();
+    options.create(true);
+    if append {
+        options.append(true);
+    } else {
+        options.write(true).truncate(true);
+    }
+    let file = options
+        .open(path)
+        .with_context(|| format!("Failed to create {}", what))?;
+    let has_content = append && file.metadata().map(|m| m.len() > 0).unwrap_or(false);
+    Ok((file, has_content))
+}
+
+/// Buffered log file writer that counts the bytes written through it (starting from
+/// the file's size when appending), so --log-max-size sees rows still in the buffer
+/// without a stat call per sample
+pub struct LogWriter {
+    inner: BufWriter<File>,
+    bytes: u64,
+}
+
+impl LogWriter {
+    fn new(file: File) -> Self {
+        let bytes = file.metadata().map(|m| m.len()).unwrap_or(0);
+        Self { inner: BufWriter::new(file), bytes }
+    }
+
+    /// Size of the file once the buffer is flushed
+    pub fn bytes(&self) -> u64 {
+        self.bytes
+    }
+}
+
+impl Write for LogWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.bytes += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
 }
 
 /// Logger for writing human-readable text observations to a file
 pub struct TextLogger {
-    writer: BufWriter<File>,
+    writer: LogWriter,
     samples_written: u64,
+    flush_every: u64,
 }
 
 impl TextLogger {
     /// Create a new text logger writing to the specified file
-    pub fn new<P: AsRef<Path>>(path: P) -> Result<Self> {
-        let file = OpenOptions::new()
-            .create(true)
-            .write(true)
-            .truncate(true)
-            .open(path.as_ref())
-            .context("Failed to create text log file")?;
+    pub fn new<P: AsRef<Path>>(path: P, env: &EnvironmentInfo, options: LogOptions) -> Result<Self> {
+        let (file, has_content) = open_log_file(path.as_ref(), options.append, "text log file")?;
 
         let mut logger = Self {
-            writer: BufWriter::new(file),
+            writer: LogWriter::new(file),
             samples_written: 0,
+            flush_every: options.flush_every.max(1),
         };
 
+        // Appending to an earlier run: mark the restart instead of repeating the header
+        if has_content {
+            writeln!(logger.writer, "# Resumed: {}", Utc::now().format("%Y-%m-%d %H:%M:%S UTC"))?;
+            return Ok(logger);
+        }
+
         // Write header - aligned with the extended output format
         writeln!(logger.writer, "# Performance Monitor Log")?;
         writeln!(logger.writer, "# Started: {}", Utc::now().format("%Y-%m-%d %H:%M:%S UTC"))?;
         writeln!(logger.writer, "#")?;
+        writeln!(logger.writer, "# Environment:")?;
+        if let Some(ref kernel) = env.kernel_version {
+            writeln!(logger.writer, "#   Kernel:  {}", kernel)?;
+        }
+        if let Some(ref model) = env.cpu_model {
+            writeln!(logger.writer, "#   CPU:     {} ({} CPUs)", model, env.cpu_count)?;
+        }
+        if let Some(ref clocksource) = env.clocksource {
+            writeln!(logger.writer, "#   Clock:   {}", clocksource)?;
+        }
+        if !env.sysctls.is_empty() {
+            writeln!(logger.writer, "#   Sysctls: {}", env.sysctl_summary())?;
+        }
+        writeln!(logger.writer, "#")?;
         writeln!(logger.writer, "# Column Definitions:")?;
         writeln!(logger.writer, "#")?;
         writeln!(logger.writer, "# CPU Section:")?;
@@ -142,8 +243,10 @@ impl TextLogger {
 
         self.samples_written += 1;
 
-        // Flush every sample for real-time logging
-        self.writer.flush()?;
+        // Flush every `flush_every` samples (every sample by default) for real-time logging
+        if self.samples_written.is_multiple_of(self.flush_every) {
+            self.writer.flush()?;
+        }
 
         Ok(())
     }
@@ -153,6 +256,11 @@ impl TextLogger {
         self.writer.flush()?;
         Ok(())
     }
+
+    /// Bytes in the file, counting buffered output (for --log-max-size)
+    pub fn bytes_written(&self) -> u64 {
+        self.writer.bytes()
+    }
 }
 
 impl Drop for TextLogger {
@@ -161,65 +269,216 @@ impl Drop for TextLogger {
     }
 }
 
+/// Numeric formatting for CSV output
+#[derive(Debug, Clone, Copy)]
+pub struct CsvFormat {
+    /// Decimal places for percentages, load averages, IOPS and packet rates
+    decimals: usize,
+    /// Decimal places for byte rates
+    byte_rate_decimals: usize,
+    /// Decimal places for latencies and intervals
+    latency_decimals: usize,
+    /// Decimal places for small percentages (link utilization)
+    fine_decimals: usize,
+    /// Strip trailing zeros ("12.50" -> "12.5", "0.00" -> "0")
+    trim: bool,
+}
+
+impl CsvFormat {
+    /// Per-field precision: `decimals` places for percentages and rates,
+    /// whole numbers for byte rates, extra places for (sub-millisecond) latencies,
+    /// and no trailing zeros
+    pub fn with_precision(decimals: usize) -> Self {
+        Self {
+            decimals,
+            byte_rate_decimals: 0,
+            latency_decimals: (decimals + 2).max(3),
+            fine_decimals: decimals + 2,
+            trim: true,
+        }
+    }
+
+    fn fmt(&self, value: f64, decimals: usize) -> String {
+        let s = format!("{:.*}", decimals, value);
+        if !self.trim || !s.contains('.') {
+            return s;
+        }
+        let s = s.trim_end_matches('0').trim_end_matches('.');
+        // Avoid "-0" for tiny negative values rounded away
+        if s == "-0" { "0".to_string() } else { s.to_string() }
+    }
+
+    fn decimal(&self, value: f64) -> String {
+        self.fmt(value, self.decimals)
+    }
+
+    fn byte_rate(&self, value: f64) -> String {
+        self.fmt(value, self.byte_rate_decimals)
+    }
+
+    fn latency(&self, value: f64) -> String {
+        self.fmt(value, self.latency_decimals)
+    }
+
+    fn fine_percent(&self, value: f64) -> String {
+        self.fmt(value, self.fine_decimals)
+    }
+}
+
+impl Default for CsvFormat {
+    /// Fixed precision (two decimals, three for latencies, four for link utilization)
+    fn default() -> Self {
+        Self {
+            decimals: 2,
+            byte_rate_decimals: 2,
+            latency_decimals: 3,
+            fine_decimals: 4,
+            trim: false,
+        }
+    }
+}
+
+fn header_mismatch() -> anyhow::Error {
+    anyhow::anyhow!("existing CSV header doesn't match this run's columns (different disks, interfaces or version); log to a new file")
+}
+
 /// Canonical CSV logger for writing comprehensive metrics to a CSV file.
 /// This is the primary log format, containing all detailed metrics:
 /// per-core CPU, per-disk I/O, per-interface network, and full PSI breakdown.
 pub struct CsvLogger {
-    writer: BufWriter<File>,
+    writer: LogWriter,
     samples_written: u64,
     header_written: bool,
+    flush_every: u64,
+    /// Header line of the file being appended to (it must match this run's columns)
+    existing_header: Option<String>,
+    /// `#` comment lines describing the host, written above a new header
+    preamble: Vec<String>,
+    format: CsvFormat,
     // Track device names from first sample for consistent columns
     core_ids: Vec<usize>,
+    numa_node_ids: Vec<usize>,
     disk_devices: Vec<String>,
+    /// Watched filesystems readable at the first sample; later changes only
+    /// show up in a new segment (split or rotation)
+    watched_fs: Vec<String>,
     interface_names: Vec<String>,
+    gpu_indices: Vec<u32>,
 }
 
 impl CsvLogger {
-    /// Create a new CSV logger writing to the specified file
-    pub fn new<P: AsRef<Path>>(path: P) -> Result<Self> {
-        let file = OpenOptions::new()
-            .create(true)
-            .write(true)
-            .truncate(true)
-            .open(path.as_ref())
-            .context("Failed to create CSV log file")?;
+    /// Create a new CSV logger writing to the specified file. With `env` (--csv-preamble),
+    /// the environment is written as `#` comment lines above the header; without it the
+    /// file is plain CSV that any reader takes as is.
+    pub fn new<P: AsRef<Path>>(path: P, format: CsvFormat, env: Option<&EnvironmentInfo>, options: LogOptions) -> Result<Self> {
+        let (file, has_content) = open_log_file(path.as_ref(), options.append, "CSV log file")?;
+        let existing_header = if has_content {
+            csv_lines(BufReader::new(File::open(path.as_ref())?)).next().transpose()?
+        } else {
+            None
+        };
+
+        let preamble = env.map_or_else(Vec::new, |env| {
+            let started = format!(
+                "monperf {} log started {}",
+                env!("CARGO_PKG_VERSION"),
+                Utc::now().format("%Y-%m-%d %H:%M:%S UTC")
+            );
+            std::iter::once(started).chain(env.header_lines()).collect()
+        });
 
         Ok(Self {
-            writer: BufWriter::new(file),
+            writer: LogWriter::new(file),
             samples_written: 0,
             header_written: false,
+            flush_every: options.flush_every.max(1),
+            existing_header,
+            preamble,
+            format,
             core_ids: Vec::new(),
+            numa_node_ids: Vec::new(),
             disk_devices: Vec::new(),
+            watched_fs: Vec::new(),
             interface_names: Vec::new(),
+            gpu_indices: Vec::new(),
         })
     }
 
+    /// Whether rows are appended below an existing header (--append)
+    pub fn is_appending(&self) -> bool {
+        self.existing_header.is_some()
+    }
+
+    /// Check that the header this sample would produce matches the file being appended to,
+    /// so a mismatch fails at startup instead of at the first row (a no-op for new files)
+    pub fn check_existing_header(&mut self, sample: &MetricsSample) -> Result<()> {
+        let header = self.build_header(sample);
+        match self.existing_header {
+            Some(ref existing) if *existing != header => Err(header_mismatch()),
+            _ => Ok(()),
+        }
+    }
+
     /// Write CSV header based on the first sample's structure
     fn write_header(&mut self, sample: &MetricsSample) -> Result<()> {
+        let header = self.build_header(sample);
+        match self.existing_header {
+            // Appending rows under a different header would misalign every column
+            Some(ref existing) if *existing != header => return Err(header_mismatch()),
+            Some(_) => {}
+            None => {
+                for line in &self.preamble {
+                    writeln!(self.writer, "# {}", line)?;
+                }
+                writeln!(self.writer, "{}", header)?;
+            }
+        }
+        self.header_written = true;
+        Ok(())
+    }
+
+    /// Header line for the sample's devices, which fix the columns of every later row
+    fn build_header(&mut self, sample: &MetricsSample) -> String {
         // Capture device names from first sample
         self.core_ids = sample.cpu.per_core.iter().map(|c| c.core_id).collect();
+        self.numa_node_ids = sample.memory.numa_nodes.iter().map(|n| n.node_id).collect();
         self.disk_devices = sample.disk.disks.iter().map(|d| d.device.clone()).collect();
+        self.watched_fs = sample.disk.watched_fs.iter().map(|fs| fs.path.clone()).collect();
         self.interface_names = sample.network.interfaces.iter().map(|i| i.interface.clone()).collect();
+        self.gpu_indices = sample.gpu.iter().flat_map(|g| g.gpus.iter().map(|gpu| gpu.index)).collect();
 
         let mut headers = vec![
             // Timestamp
             "timestamp".to_string(),
+            "sample_interval_secs".to_string(),
             // CPU aggregate
             "cpu_total_pct".to_string(),
             "cpu_user_pct".to_string(),
             "cpu_system_pct".to_string(),
             "cpu_iowait_pct".to_string(),
+            "cpu_steal_pct".to_string(),
+            "cpu_irq_pct".to_string(),
+            "cpu_softirq_pct".to_string(),
             "cpu_load_1m".to_string(),
             "cpu_load_5m".to_string(),
             "cpu_load_15m".to_string(),
             "cpu_context_switches".to_string(),
             "cpu_interrupts".to_string(),
+            "cpu_procs_running".to_string(),
+            "cpu_procs_blocked".to_string(),
+            "cpu_throttled_periods".to_string(),
+            "cpu_throttled_usec".to_string(),
+            "cpu_cgroup_limit_cores".to_string(),
+            "cpu_cgroup_quota_pct".to_string(),
         ];
 
         // Per-core CPU columns
         for core_id in &self.core_ids {
             headers.push(format!("cpu_core{}_pct", core_id));
         }
+        for core_id in &self.core_ids {
+            headers.push(format!("cpu_core{}_mhz", core_id));
+        }
 
         // Memory columns
         headers.extend(vec![
@@ -231,8 +490,16 @@ impl CsvLogger {
             "mem_cached_bytes".to_string(),
             "mem_dirty_bytes".to_string(),
             "mem_writeback_bytes".to_string(),
+            "mem_dirty_growth_bytes_per_sec".to_string(),
+            "mem_dirty_limit_bytes".to_string(),
             "mem_active_file_bytes".to_string(),
             "mem_inactive_file_bytes".to_string(),
+            "mem_hugepages_total".to_string(),
+            "mem_hugepages_free".to_string(),
+            "mem_hugepage_size_bytes".to_string(),
+            "mem_slab_bytes".to_string(),
+            "mem_slab_reclaimable_bytes".to_string(),
+            "mem_slab_unreclaimable_bytes".to_string(),
             "mem_swap_total_bytes".to_string(),
             "mem_swap_used_bytes".to_string(),
             "mem_swap_pct".to_string(),
@@ -241,8 +508,20 @@ impl CsvLogger {
             "cgroup_limit_bytes".to_string(),
             "cgroup_current_bytes".to_string(),
             "cgroup_usage_pct".to_string(),
+            "cgroup_swap_current_bytes".to_string(),
+            "cgroup_swap_limit_bytes".to_string(),
+            "cgroup_anon_bytes".to_string(),
+            "cgroup_file_bytes".to_string(),
+            "cgroup_kernel_bytes".to_string(),
+            "cgroup_pgmajfault".to_string(),
+            "cgroup_pgscan".to_string(),
         ]);
 
+        // Per-NUMA-node columns (none on single-node systems)
+        for node_id in &self.numa_node_ids {
+            headers.push(format!("numa_node{}_used_bytes", node_id));
+        }
+
         // Disk aggregate columns
         headers.extend(vec![
             "disk_total_read_bytes_per_sec".to_string(),
@@ -260,6 +539,14 @@ impl CsvLogger {
             headers.push(format!("disk_{}_write_latency_ms", dev));
             headers.push(format!("disk_{}_util_pct", dev));
             headers.push(format!("disk_{}_in_flight", dev));
+            headers.push(format!("disk_{}_discard_bytes_per_sec", dev));
+            headers.push(format!("disk_{}_flush_per_sec", dev));
+        }
+
+        // Watched filesystem columns
+        for name in fs_column_names(&self.watched_fs) {
+            headers.push(format!("fs_{}_used_pct", name));
+            headers.push(format!("fs_{}_inodes_used_pct", name));
         }
 
         // Network aggregate columns
@@ -268,6 +555,10 @@ impl CsvLogger {
             "net_total_tx_bytes_per_sec".to_string(),
             "net_tcp_connections".to_string(),
             "net_tcp_retransmits".to_string(),
+            "net_tcp_listen".to_string(),
+            "net_tcp_time_wait".to_string(),
+            "net_tcp_close_wait".to_string(),
+            "net_udp_sockets".to_string(),
         ]);
 
         // Per-interface columns
@@ -300,6 +591,13 @@ impl CsvLogger {
             "psi_io_full_avg10".to_string(),
             "psi_io_full_avg60".to_string(),
             "psi_io_full_avg300".to_string(),
+            "psi_cgroup_io_some_avg10".to_string(),
+            "psi_cgroup_io_full_avg10".to_string(),
+            "psi_proc_cgroup_cpu_some_avg10".to_string(),
+            "psi_proc_cgroup_mem_some_avg10".to_string(),
+            "psi_proc_cgroup_mem_full_avg10".to_string(),
+            "psi_proc_cgroup_io_some_avg10".to_string(),
+            "psi_proc_cgroup_io_full_avg10".to_string(),
         ]);
 
         // Process columns (always included, may be empty)
@@ -324,11 +622,17 @@ impl CsvLogger {
             "proc_io_rchar".to_string(),
             "proc_io_wchar".to_string(),
             "proc_io_cancelled_write_bytes".to_string(),
+            "proc_children".to_string(),
+            "proc_tcp_connections".to_string(),
         ]);
 
-        writeln!(self.writer, "{}", headers.join(","))?;
-        self.header_written = true;
-        Ok(())
+        // Per-GPU columns (--gpu)
+        for index in &self.gpu_indices {
+            headers.push(format!("gpu_{}_util_pct", index));
+            headers.push(format!("gpu_{}_mem_used_bytes", index));
+        }
+
+        headers.join(",")
     }
 
     /// Log a sample as a CSV row
@@ -344,20 +648,32 @@ impl CsvLogger {
         }
 
         let mut values: Vec<String> = Vec::new();
+        let f = self.format;
 
         // Timestamp
         values.push(sample.timestamp.format("%Y-%m-%d %H:%M:%S%.3f").to_string());
+        values.push(f.latency(sample.interval_secs));
 
         // CPU aggregate
-        values.push(format!("{:.2}", sample.cpu.total_utilization));
-        values.push(format!("{:.2}", sample.cpu.user_percent));
-        values.push(format!("{:.2}", sample.cpu.system_percent));
-        values.push(format!("{:.2}", sample.cpu.iowait_percent));
-        values.push(format!("{:.2}", sample.cpu.load_avg.0));
-        values.push(format!("{:.2}", sample.cpu.load_avg.1));
-        values.push(format!("{:.2}", sample.cpu.load_avg.2));
+        values.push(f.decimal(sample.cpu.total_utilization));
+        values.push(f.decimal(sample.cpu.user_percent));
+        values.push(f.decimal(sample.cpu.system_percent));
+        values.push(f.decimal(sample.cpu.iowait_percent));
+        values.push(f.decimal(sample.cpu.steal_percent));
+        values.push(f.decimal(sample.cpu.irq_percent));
+        values.push(f.decimal(sample.cpu.softirq_percent));
+        values.push(f.decimal(sample.cpu.load_avg.0));
+        values.push(f.decimal(sample.cpu.load_avg.1));
+        values.push(f.decimal(sample.cpu.load_avg.2));
         values.push(sample.cpu.context_switches_delta.map(|v| v.to_string()).unwrap_or_default());
         values.push(sample.cpu.interrupts_delta.map(|v| v.to_string()).unwrap_or_default());
+        values.push(sample.cpu.procs_running.to_string());
+        values.push(sample.cpu.procs_blocked.to_string());
+        let throttling = sample.cpu.throttling.as_ref();
+        values.push(throttling.map(|t| t.throttled_periods.to_string()).unwrap_or_default());
+        values.push(throttling.map(|t| t.throttled_usec.to_string()).unwrap_or_default());
+        values.push(sample.cpu.cgroup_cpu_limit_cores.map(|v| f.decimal(v)).unwrap_or_default());
+        values.push(sample.cpu.cgroup_cpu_quota_percent.map(|v| f.decimal(v)).unwrap_or_default());
 
         // Per-core CPU values (match the order from header)
         for core_id in &self.core_ids {
@@ -366,71 +682,117 @@ impl CsvLogger {
                 .find(|c| c.core_id == *core_id)
                 .map(|c| c.utilization_percent)
                 .unwrap_or(0.0);
-            values.push(format!("{:.2}", util));
+            values.push(f.decimal(util));
+        }
+        for core_id in &self.core_ids {
+            let mhz = sample.cpu.per_core
+                .iter()
+                .find(|c| c.core_id == *core_id)
+                .and_then(|c| c.frequency_mhz);
+            values.push(mhz.map(|v| format!("{:.0}", v)).unwrap_or_default());
         }
 
         // Memory values
         values.push(sample.memory.total.to_string());
         values.push(sample.memory.used.to_string());
         values.push(sample.memory.available.to_string());
-        values.push(format!("{:.2}", sample.memory.used_percent));
+        values.push(f.decimal(sample.memory.used_percent));
         values.push(sample.memory.buffers.to_string());
         values.push(sample.memory.cached.to_string());
         values.push(sample.memory.dirty.to_string());
         values.push(sample.memory.writeback.to_string());
+        values.push(sample.memory.dirty_growth_bytes_per_sec.map(|v| f.byte_rate(v)).unwrap_or_default());
+        values.push(sample.memory.dirty_limit.map(|v| v.to_string()).unwrap_or_default());
         values.push(sample.memory.active_file.to_string());
         values.push(sample.memory.inactive_file.to_string());
+        values.push(sample.memory.hugepages_total.to_string());
+        values.push(sample.memory.hugepages_free.to_string());
+        values.push(sample.memory.hugepage_size.to_string());
+        values.push(sample.memory.slab.to_string());
+        values.push(sample.memory.slab_reclaimable.to_string());
+        values.push(sample.memory.slab_unreclaimable.to_string());
         values.push(sample.memory.swap_total.to_string());
         values.push(sample.memory.swap_used.to_string());
-        values.push(format!("{:.2}", sample.memory.swap_percent));
+        values.push(f.decimal(sample.memory.swap_percent));
         values.push(sample.memory.major_faults_delta.map(|v| v.to_string()).unwrap_or_default());
         values.push(sample.memory.minor_faults_delta.map(|v| v.to_string()).unwrap_or_default());
         values.push(sample.memory.cgroup_limit.map(|v| v.to_string()).unwrap_or_default());
         values.push(sample.memory.cgroup_current.map(|v| v.to_string()).unwrap_or_default());
-        values.push(sample.memory.cgroup_usage_percent.map(|v| format!("{:.2}", v)).unwrap_or_default());
+        values.push(sample.memory.cgroup_usage_percent.map(|v| f.decimal(v)).unwrap_or_default());
+        values.push(sample.memory.cgroup_swap_current.map(|v| v.to_string()).unwrap_or_default());
+        values.push(sample.memory.cgroup_swap_limit.map(|v| v.to_string()).unwrap_or_default());
+        let cgroup_stat = sample.memory.cgroup_stat.as_ref();
+        values.push(cgroup_stat.map(|s| s.anon.to_string()).unwrap_or_default());
+        values.push(cgroup_stat.map(|s| s.file.to_string()).unwrap_or_default());
+        values.push(cgroup_stat.map(|s| s.kernel.to_string()).unwrap_or_default());
+        values.push(cgroup_stat.and_then(|s| s.pgmajfault_delta).map(|v| v.to_string()).unwrap_or_default());
+        values.push(cgroup_stat.and_then(|s| s.pgscan_delta).map(|v| v.to_string()).unwrap_or_default());
+
+        // Per-NUMA-node values (match the order from header)
+        for node_id in &self.numa_node_ids {
+            let used = sample.memory.numa_nodes
+                .iter()
+                .find(|n| n.node_id == *node_id)
+                .map(|n| n.used().to_string())
+                .unwrap_or_default();
+            values.push(used);
+        }
 
         // Disk aggregate
-        values.push(format!("{:.2}", sample.disk.total_read_bytes_per_sec));
-        values.push(format!("{:.2}", sample.disk.total_write_bytes_per_sec));
+        values.push(f.byte_rate(sample.disk.total_read_bytes_per_sec));
+        values.push(f.byte_rate(sample.disk.total_write_bytes_per_sec));
         values.push(sample.disk.total_in_flight.to_string());
 
         // Per-disk values (match the order from header)
         for dev in &self.disk_devices {
             if let Some(disk) = sample.disk.disks.iter().find(|d| &d.device == dev) {
-                values.push(format!("{:.2}", disk.read_bytes_per_sec));
-                values.push(format!("{:.2}", disk.write_bytes_per_sec));
-                values.push(format!("{:.2}", disk.read_iops));
-                values.push(format!("{:.2}", disk.write_iops));
-                values.push(format!("{:.3}", disk.read_latency_ms));
-                values.push(format!("{:.3}", disk.write_latency_ms));
-                values.push(format!("{:.2}", disk.utilization_percent));
+                values.push(f.byte_rate(disk.read_bytes_per_sec));
+                values.push(f.byte_rate(disk.write_bytes_per_sec));
+                values.push(f.decimal(disk.read_iops));
+                values.push(f.decimal(disk.write_iops));
+                values.push(f.latency(disk.read_latency_ms));
+                values.push(f.latency(disk.write_latency_ms));
+                values.push(f.decimal(disk.utilization_percent));
                 values.push(disk.in_flight.to_string());
+                values.push(disk.discard_bytes_per_sec.map(|v| f.byte_rate(v)).unwrap_or_default());
+                values.push(disk.flush_per_sec.map(|v| f.decimal(v)).unwrap_or_default());
             } else {
                 // Device not found in this sample, add empty values
-                for _ in 0..8 {
+                for _ in 0..10 {
                     values.push(String::new());
                 }
             }
         }
 
+        // Watched filesystem values (match the order from header)
+        for path in &self.watched_fs {
+            let fs = sample.disk.watched_fs.iter().find(|fs| &fs.path == path);
+            values.push(fs.map(|fs| f.decimal(fs.used_percent)).unwrap_or_default());
+            values.push(fs.map(|fs| f.decimal(fs.inodes_used_percent)).unwrap_or_default());
+        }
+
         // Network aggregate
-        values.push(format!("{:.2}", sample.network.total_rx_bytes_per_sec));
-        values.push(format!("{:.2}", sample.network.total_tx_bytes_per_sec));
+        values.push(f.byte_rate(sample.network.total_rx_bytes_per_sec));
+        values.push(f.byte_rate(sample.network.total_tx_bytes_per_sec));
         values.push(sample.network.tcp.connections_established.to_string());
         values.push(sample.network.tcp.retransmits_delta.map(|v| v.to_string()).unwrap_or_default());
+        values.push(sample.network.socket_state("LISTEN").to_string());
+        values.push(sample.network.socket_state("TIME_WAIT").to_string());
+        values.push(sample.network.socket_state("CLOSE_WAIT").to_string());
+        values.push(sample.network.udp_sockets.to_string());
 
         // Per-interface values (match the order from header)
         for iface_name in &self.interface_names {
             if let Some(iface) = sample.network.interfaces.iter().find(|i| &i.interface == iface_name) {
-                values.push(format!("{:.2}", iface.rx_bytes_per_sec));
-                values.push(format!("{:.2}", iface.tx_bytes_per_sec));
-                values.push(format!("{:.2}", iface.rx_packets_per_sec));
-                values.push(format!("{:.2}", iface.tx_packets_per_sec));
+                values.push(f.byte_rate(iface.rx_bytes_per_sec));
+                values.push(f.byte_rate(iface.tx_bytes_per_sec));
+                values.push(f.decimal(iface.rx_packets_per_sec));
+                values.push(f.decimal(iface.tx_packets_per_sec));
                 values.push(iface.rx_errors.to_string());
                 values.push(iface.tx_errors.to_string());
                 values.push(iface.link_speed_mbps.map(|v| v.to_string()).unwrap_or_default());
-                values.push(iface.rx_util_pct.map(|v| format!("{:.4}", v)).unwrap_or_default());
-                values.push(iface.tx_util_pct.map(|v| format!("{:.4}", v)).unwrap_or_default());
+                values.push(iface.rx_util_pct.map(|v| f.fine_percent(v)).unwrap_or_default());
+                values.push(iface.tx_util_pct.map(|v| f.fine_percent(v)).unwrap_or_default());
             } else {
                 // Interface not found in this sample, add empty values
                 for _ in 0..9 {
@@ -441,24 +803,37 @@ impl CsvLogger {
 
         // PSI values
         if let Some(psi) = &sample.psi {
-            values.push(format!("{:.2}", psi.cpu.some_avg10));
-            values.push(format!("{:.2}", psi.cpu.some_avg60));
-            values.push(format!("{:.2}", psi.cpu.some_avg300));
-            values.push(format!("{:.2}", psi.memory.some_avg10));
-            values.push(format!("{:.2}", psi.memory.some_avg60));
-            values.push(format!("{:.2}", psi.memory.some_avg300));
-            values.push(psi.memory.full_avg10.map(|v| format!("{:.2}", v)).unwrap_or_default());
-            values.push(psi.memory.full_avg60.map(|v| format!("{:.2}", v)).unwrap_or_default());
-            values.push(psi.memory.full_avg300.map(|v| format!("{:.2}", v)).unwrap_or_default());
-            values.push(format!("{:.2}", psi.io.some_avg10));
-            values.push(format!("{:.2}", psi.io.some_avg60));
-            values.push(format!("{:.2}", psi.io.some_avg300));
-            values.push(psi.io.full_avg10.map(|v| format!("{:.2}", v)).unwrap_or_default());
-            values.push(psi.io.full_avg60.map(|v| format!("{:.2}", v)).unwrap_or_default());
-            values.push(psi.io.full_avg300.map(|v| format!("{:.2}", v)).unwrap_or_default());
+            values.push(f.decimal(psi.cpu.some_avg10));
+            values.push(f.decimal(psi.cpu.some_avg60));
+            values.push(f.decimal(psi.cpu.some_avg300));
+            values.push(f.decimal(psi.memory.some_avg10));
+            values.push(f.decimal(psi.memory.some_avg60));
+            values.push(f.decimal(psi.memory.some_avg300));
+            values.push(psi.memory.full_avg10.map(|v| f.decimal(v)).unwrap_or_default());
+            values.push(psi.memory.full_avg60.map(|v| f.decimal(v)).unwrap_or_default());
+            values.push(psi.memory.full_avg300.map(|v| f.decimal(v)).unwrap_or_default());
+            values.push(f.decimal(psi.io.some_avg10));
+            values.push(f.decimal(psi.io.some_avg60));
+            values.push(f.decimal(psi.io.some_avg300));
+            values.push(psi.io.full_avg10.map(|v| f.decimal(v)).unwrap_or_default());
+            values.push(psi.io.full_avg60.map(|v| f.decimal(v)).unwrap_or_default());
+            values.push(psi.io.full_avg300.map(|v| f.decimal(v)).unwrap_or_default());
+            values.push(psi.cgroup_io.as_ref().map(|c| f.decimal(c.some_avg10)).unwrap_or_default());
+            values.push(psi.cgroup_io.as_ref().and_then(|c| c.full_avg10).map(|v| f.decimal(v)).unwrap_or_default());
         } else {
             // No PSI data, add empty values
-            for _ in 0..15 {
+            for _ in 0..17 {
+                values.push(String::new());
+            }
+        }
+        if let Some(psi) = &sample.cgroup_psi {
+            values.push(f.decimal(psi.cpu.some_avg10));
+            values.push(f.decimal(psi.memory.some_avg10));
+            values.push(psi.memory.full_avg10.map(|v| f.decimal(v)).unwrap_or_default());
+            values.push(f.decimal(psi.io.some_avg10));
+            values.push(psi.io.full_avg10.map(|v| f.decimal(v)).unwrap_or_default());
+        } else {
+            for _ in 0..5 {
                 values.push(String::new());
             }
         }
@@ -469,7 +844,7 @@ impl CsvLogger {
             // Escape commas and quotes in process name
             values.push(format!("\"{}\"", proc.name.replace('"', "\"\"")));
             values.push(proc.state.to_string());
-            values.push(format!("{:.2}", proc.cpu_percent));
+            values.push(f.decimal(proc.cpu_percent));
             values.push(proc.num_threads.to_string());
             values.push(proc.num_fds.to_string());
             values.push(proc.rss_bytes.to_string());
@@ -479,25 +854,36 @@ impl CsvLogger {
             values.push(proc.rss_file.to_string());
             values.push(proc.rss_shmem.to_string());
             values.push(proc.vm_swap.to_string());
-            values.push(format!("{:.2}", proc.io_read_bytes_per_sec));
-            values.push(format!("{:.2}", proc.io_write_bytes_per_sec));
+            values.push(f.byte_rate(proc.io_read_bytes_per_sec));
+            values.push(f.byte_rate(proc.io_write_bytes_per_sec));
             values.push(proc.io_read_bytes.to_string());
             values.push(proc.io_write_bytes.to_string());
             values.push(proc.io_rchar.to_string());
             values.push(proc.io_wchar.to_string());
             values.push(proc.io_cancelled_write_bytes.to_string());
+            values.push(proc.children.to_string());
+            values.push(proc.tcp_connections.map(|n| n.to_string()).unwrap_or_default());
         } else {
             // No process data, add empty values
-            for _ in 0..20 {
+            for _ in 0..22 {
                 values.push(String::new());
             }
         }
 
+        // Per-GPU values (match the order from header)
+        for index in &self.gpu_indices {
+            let gpu = sample.gpu.iter().flat_map(|g| g.gpus.iter()).find(|gpu| gpu.index == *index);
+            values.push(gpu.map(|gpu| f.decimal(gpu.utilization_percent)).unwrap_or_default());
+            values.push(gpu.map(|gpu| gpu.memory_used_bytes.to_string()).unwrap_or_default());
+        }
+
         writeln!(self.writer, "{}", values.join(","))?;
         self.samples_written += 1;
 
-        // Flush every sample for real-time logging
-        self.writer.flush()?;
+        // Flush every `flush_every` samples (every sample by default) for real-time logging
+        if self.samples_written.is_multiple_of(self.flush_every) {
+            self.writer.flush()?;
+        }
 
         Ok(())
     }
@@ -508,6 +894,11 @@ impl CsvLogger {
         Ok(())
     }
 
+    /// Bytes in the file, counting buffered output (for --log-max-size)
+    pub fn bytes_written(&self) -> u64 {
+        self.writer.bytes()
+    }
+
     /// Get the number of samples written
     pub fn samples_written(&self) -> u64 {
         self.samples_written
@@ -520,6 +911,545 @@ impl Drop for CsvLogger {
     }
 }
 
+/// Tidy (long format) CSV logger for per-core CPU utilization.
+/// Writes one row per core per sample, which analysis tools like pandas and
+/// ggplot consume directly without reshaping the wide cpu_coreN_pct columns.
+pub struct PerCoreCsvLogger {
+    writer: LogWriter,
+    samples_written: u64,
+    flush_every: u64,
+}
+
+impl PerCoreCsvLogger {
+    /// Create a new per-core logger writing to the specified file
+    pub fn new<P: AsRef<Path>>(path: P, options: LogOptions) -> Result<Self> {
+        let (file, has_content) = open_log_file(path.as_ref(), options.append, "per-core CSV log file")?;
+
+        let mut logger = Self {
+            writer: LogWriter::new(file),
+            samples_written: 0,
+            flush_every: options.flush_every.max(1),
+        };
+
+        if !has_content {
+            writeln!(logger.writer, "timestamp,core_id,utilization_pct,user_pct,system_pct,iowait_pct")?;
+        }
+
+        Ok(logger)
+    }
+
+    /// Log one row per core for a sample
+    pub fn log(&mut self, sample: &MetricsSample) -> Result<()> {
+        // The first sample has no CPU deltas yet (all cores read 0%)
+        if sample.interval_secs == 0.0 {
+            return Ok(());
+        }
+
+        let timestamp = sample.timestamp.format("%Y-%m-%d %H:%M:%S%.3f");
+        for core in &sample.cpu.per_core {
+            writeln!(
+                self.writer,
+                "{},{},{:.2},{:.2},{:.2},{:.2}",
+                timestamp,
+                core.core_id,
+                core.utilization_percent,
+                core.user_percent,
+                core.system_percent,
+                core.iowait_percent,
+            )?;
+        }
+        self.samples_written += 1;
+
+        // Flush every `flush_every` samples (every sample by default) for real-time logging
+        if self.samples_written.is_multiple_of(self.flush_every) {
+            self.writer.flush()?;
+        }
+
+        Ok(())
+    }
+
+    /// Flush any buffered data
+    pub fn flush(&mut self) -> Result<()> {
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    /// Bytes in the file, counting buffered output (for --log-max-size)
+    pub fn bytes_written(&self) -> u64 {
+        self.writer.bytes()
+    }
+}
+
+impl Drop for PerCoreCsvLogger {
+    fn drop(&mut self) {
+        let _ = self.writer.flush();
+    }
+}
+
+/// Logger writing every MetricsSample as one JSON object per line (JSON Lines)
+pub struct JsonLogger {
+    writer: LogWriter,
+    samples_written: u64,
+    flush_every: u64,
+}
+
+impl JsonLogger {
+    /// Create a new JSON Lines logger writing to the specified file
+    pub fn new<P: AsRef<Path>>(path: P, options: LogOptions) -> Result<Self> {
+        let (file, _) = open_log_file(path.as_ref(), options.append, "JSON log file")?;
+
+        Ok(Self {
+            writer: LogWriter::new(file),
+            samples_written: 0,
+            flush_every: options.flush_every.max(1),
+        })
+    }
+
+    /// Append one sample as a JSON line
+    pub fn log(&mut self, sample: &MetricsSample) -> Result<()> {
+        serde_json::to_writer(&mut self.writer, sample).context("Failed to encode sample")?;
+        self.writer.write_all(b"\n")?;
+        self.samples_written += 1;
+        if self.samples_written.is_multiple_of(self.flush_every) {
+            self.writer.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Flush any buffered data
+    pub fn flush(&mut self) -> Result<()> {
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    /// Bytes in the file, counting buffered output (for --log-max-size)
+    pub fn bytes_written(&self) -> u64 {
+        self.writer.bytes()
+    }
+}
+
+impl Drop for JsonLogger {
+    fn drop(&mut self) {
+        let _ = self.writer.flush();
+    }
+}
+
+/// Logger writing every MetricsSample as InfluxDB line protocol (one line per measurement and tag set)
+pub struct InfluxLogger {
+    writer: LogWriter,
+    samples_written: u64,
+    flush_every: u64,
+}
+
+impl InfluxLogger {
+    /// Create a new line protocol logger writing to the specified file
+    pub fn new<P: AsRef<Path>>(path: P, options: LogOptions) -> Result<Self> {
+        let (file, _) = open_log_file(path.as_ref(), options.append, "InfluxDB line protocol file")?;
+
+        Ok(Self {
+            writer: LogWriter::new(file),
+            samples_written: 0,
+            flush_every: options.flush_every.max(1),
+        })
+    }
+
+    /// Append the lines for one sample
+    pub fn log(&mut self, sample: &MetricsSample) -> Result<()> {
+        self.writer.write_all(crate::export::format_influx(sample).as_bytes())?;
+        self.samples_written += 1;
+        if self.samples_written.is_multiple_of(self.flush_every) {
+            self.writer.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Flush any buffered data
+    pub fn flush(&mut self) -> Result<()> {
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    /// Bytes in the file, counting buffered output (for --log-max-size)
+    pub fn bytes_written(&self) -> u64 {
+        self.writer.bytes()
+    }
+}
+
+impl Drop for InfluxLogger {
+    fn drop(&mut self) {
+        let _ = self.writer.flush();
+    }
+}
+
+/// Magic bytes at the start of a binary capture file (format version 2)
+const CAPTURE_MAGIC: &[u8; 8] = b"MONPCAP2";
+
+/// Magic bytes of version 1 captures, which have no header record
+const CAPTURE_MAGIC_V1: &[u8; 8] = b"MONPCAP1";
+
+/// First record of a version 2 capture: where and when it was recorded
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaptureHeader {
+    /// monperf version that wrote the capture
+    pub version: String,
+    pub started: DateTime<Utc>,
+    pub environment: EnvironmentInfo,
+}
+
+/// Logger for writing a lossless binary capture of the MetricsSample stream.
+/// Each record is a little-endian u32 length followed by the sample encoded
+/// as MessagePack (with field names, so optional fields survive schema growth).
+/// The first record after the magic bytes is the CaptureHeader.
+pub struct CaptureLogger {
+    writer: LogWriter,
+    samples_written: u64,
+    flush_every: u64,
+}
+
+impl CaptureLogger {
+    /// Create a new capture logger writing to the specified file
+    pub fn new<P: AsRef<Path>>(path: P, env: &EnvironmentInfo, options: LogOptions) -> Result<Self> {
+        let (file, has_content) = open_log_file(path.as_ref(), options.append, "capture file")?;
+        if has_content && !is_capture_file(path.as_ref()) {
+            anyhow::bail!("{} exists and is not a monperf capture", path.as_ref().display());
+        }
+
+        let mut logger = Self {
+            writer: LogWriter::new(file),
+            samples_written: 0,
+            flush_every: options.flush_every.max(1),
+        };
+
+        // Records appended to an existing capture follow its magic bytes and header
+        if !has_content {
+            let header = CaptureHeader {
+                version: env!("CARGO_PKG_VERSION").to_string(),
+                started: Utc::now(),
+                environment: env.clone(),
+            };
+            logger.writer.write_all(CAPTURE_MAGIC)?;
+            logger.write_record(&header).context("Failed to encode capture header")?;
+            logger.writer.flush()?;
+        }
+
+        Ok(logger)
+    }
+
+    /// Append one sample record
+    pub fn log(&mut self, sample: &MetricsSample) -> Result<()> {
+        self.write_record(sample).context("Failed to encode sample")?;
+
+        // Flushing every sample (the default) means a crash loses at most the record in progress
+        self.samples_written += 1;
+        if self.samples_written.is_multiple_of(self.flush_every) {
+            self.writer.flush()?;
+        }
+
+        Ok(())
+    }
+
+    fn write_record<T: Serialize>(&mut self, value: &T) -> Result<()> {
+        let record = rmp_serde::to_vec_named(value)?;
+        let len = u32::try_from(record.len()).context("Record too large")?;
+        self.writer.write_all(&len.to_le_bytes())?;
+        self.writer.write_all(&record)?;
+        Ok(())
+    }
+
+    /// Flush any buffered data
+    pub fn flush(&mut self) -> Result<()> {
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    /// Bytes in the file, counting buffered output (for --log-max-size)
+    pub fn bytes_written(&self) -> u64 {
+        self.writer.bytes()
+    }
+}
+
+impl Drop for CaptureLogger {
+    fn drop(&mut self) {
+        let _ = self.writer.flush();
+    }
+}
+
+/// Capture format version from the magic bytes (None for other files)
+fn capture_version(magic: &[u8; 8]) -> Option<u8> {
+    match magic {
+        m if m == CAPTURE_MAGIC => Some(2),
+        m if m == CAPTURE_MAGIC_V1 => Some(1),
+        _ => None,
+    }
+}
+
+/// Whether a file starts with the binary capture magic bytes
+pub fn is_capture_file<P: AsRef<Path>>(path: P) -> bool {
+    let mut magic = [0u8; 8];
+    open_log_reader(path.as_ref())
+        .and_then(|mut r| r.read_exact(&mut magic))
+        .map(|_| capture_version(&magic).is_some())
+        .unwrap_or(false)
+}
+
+/// Largest capture record accepted on read; samples are a few KiB, so a bigger
+/// length prefix means a corrupt file rather than a record worth allocating for
+const MAX_CAPTURE_RECORD: usize = 64 * 1024 * 1024;
+
+/// Read the next length-prefixed record into `record` (false at the end or on a truncated record)
+fn read_capture_record(reader: &mut impl Read, record: &mut Vec<u8>) -> Result<bool> {
+    let mut len = [0u8; 4];
+    if reader.read_exact(&mut len).is_err() {
+        return Ok(false);
+    }
+    let len = u32::from_le_bytes(len) as usize;
+    if len > MAX_CAPTURE_RECORD {
+        anyhow::bail!("capture record of {} bytes exceeds the {} MiB limit (corrupt file?)", len, MAX_CAPTURE_RECORD >> 20);
+    }
+    record.resize(len, 0);
+    Ok(reader.read_exact(record).is_ok())
+}
+
+/// Open a capture and read past its magic bytes and header
+fn open_capture(path: &Path) -> Result<(Box<dyn BufRead>, Option<CaptureHeader>)> {
+    let mut reader = open_log_reader(path)
+        .with_context(|| format!("Failed to open capture file: {}", path.display()))?;
+
+    let mut magic = [0u8; 8];
+    reader.read_exact(&mut magic).context("Failed to read capture header")?;
+    let header = match capture_version(&magic) {
+        Some(1) => None,
+        Some(_) => {
+            let mut record = Vec::new();
+            if !read_capture_record(&mut reader, &mut record)? {
+                anyhow::bail!("Truncated capture header: {}", path.display());
+            }
+            Some(rmp_serde::from_slice(&record).context("Failed to decode capture header")?)
+        }
+        None => anyhow::bail!("Not a monperf capture file: {}", path.display()),
+    };
+
+    Ok((reader, header))
+}
+
+/// Header of a capture file (None for version 1 captures, which predate it)
+pub fn read_capture_header<P: AsRef<Path>>(path: P) -> Result<Option<CaptureHeader>> {
+    Ok(open_capture(path.as_ref())?.1)
+}
+
+/// Read every sample from a binary capture file.
+/// A truncated final record (e.g. the monitor was killed mid-write) is ignored.
+pub fn read_capture<P: AsRef<Path>>(path: P) -> Result<Vec<MetricsSample>> {
+    let (mut reader, _) = open_capture(path.as_ref())?;
+
+    let mut samples = Vec::new();
+    let mut record = Vec::new();
+    while read_capture_record(&mut reader, &mut record)
+        .with_context(|| format!("Failed to read capture record {}", samples.len() + 1))?
+    {
+        let sample = rmp_serde::from_slice(&record)
+            .with_context(|| format!("Failed to decode capture record {}", samples.len() + 1))?;
+        samples.push(sample);
+    }
+
+    Ok(samples)
+}
+
+/// Outcome of concatenating CSV log segments into one file
+#[derive(Debug, Default)]
+pub struct CompactionResult {
+    /// Segments whose rows were copied (or that held no data)
+    pub merged: Vec<PathBuf>,
+    /// Segments left out, with the reason
+    pub skipped: Vec<(PathBuf, String)>,
+    /// Data rows written to the consolidated file
+    pub rows: u64,
+}
+
+/// Whether a file is a JSON Lines log, judged by its extension (`.jsonl` or `.json`, optionally gzipped)
+pub fn is_json_log<P: AsRef<Path>>(path: P) -> bool {
+    let path = path.as_ref();
+    let path = if path.extension().is_some_and(|ext| ext == "gz") {
+        Path::new(path.file_stem().unwrap_or_default())
+    } else {
+        path
+    };
+    path.extension().is_some_and(|ext| ext == "jsonl" || ext == "json")
+}
+
+/// Read every sample from a JSON Lines log.
+/// A truncated final line (e.g. the monitor was killed mid-write) is ignored.
+pub fn read_json_log<P: AsRef<Path>>(path: P) -> Result<Vec<MetricsSample>> {
+    let reader = open_log_reader(path.as_ref())
+        .with_context(|| format!("Failed to open JSON log: {}", path.as_ref().display()))?;
+
+    let mut samples = Vec::new();
+    let mut lines = reader.lines().enumerate().peekable();
+    while let Some((index, line)) = lines.next() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str(&line) {
+            Ok(sample) => samples.push(sample),
+            Err(_) if lines.peek().is_none() => break,
+            Err(e) => {
+                return Err(e).with_context(|| {
+                    format!("Invalid sample on line {} of {}", index + 1, path.as_ref().display())
+                });
+            }
+        }
+    }
+    Ok(samples)
+}
+
+/// Lines of a CSV log without its `#` preamble (the environment it was recorded in)
+pub fn csv_lines<R: BufRead>(reader: R) -> impl Iterator<Item = std::io::Result<String>> {
+    reader.lines().filter(|line| !line.as_ref().is_ok_and(|l| l.starts_with('#')))
+}
+
+/// Open a log, CSV or capture file for reading, decompressing `.gz` files transparently
+pub fn open_log_reader(path: &Path) -> std::io::Result<Box<dyn BufRead>> {
+    let file = File::open(path)?;
+    if path.extension().is_some_and(|ext| ext == "gz") {
+        Ok(Box::new(BufReader::new(GzDecoder::new(file))))
+    } else {
+        Ok(Box::new(BufReader::new(file)))
+    }
+}
+
+/// Path of the gzip-compressed copy of a log file (`foo_1.csv` -> `foo_1.csv.gz`)
+pub fn gzip_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".gz");
+    path.with_file_name(name)
+}
+
+/// Gzip a closed log file, replacing it with `<file>.gz`. Returns the compressed path.
+/// On failure the original is kept and any partial output removed.
+pub fn gzip_file(path: &Path) -> Result<PathBuf> {
+    let output = gzip_path(path);
+    let result = (|| -> Result<()> {
+        let mut input = File::open(path)?;
+        let mut encoder = GzEncoder::new(BufWriter::new(File::create(&output)?), Compression::default());
+        std::io::copy(&mut input, &mut encoder)?;
+        encoder.finish()?.flush()?;
+        Ok(())
+    })();
+
+    if let Err(e) = result {
+        let _ = std::fs::remove_file(&output);
+        return Err(e.context(format!("Failed to compress {}", path.display())));
+    }
+    std::fs::remove_file(path)
+        .with_context(|| format!("Failed to remove {} after compressing it", path.display()))?;
+    Ok(output)
+}
+
+/// Concatenate CSV log segments into a single file.
+/// The first segment with a header defines the columns; segments with a
+/// different header (e.g. a disk appeared mid-run) are skipped rather than
+/// producing misaligned rows.
+pub fn compact_csv_segments(segments: &[PathBuf], output: &Path) -> Result<CompactionResult> {
+    let file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(true)
+        .open(output)
+        .context("Failed to create consolidated CSV file")?;
+    let mut writer = BufWriter::new(file);
+    let mut header: Option<String> = None;
+    let mut result = CompactionResult::default();
+
+    for segment in segments {
+        let reader = match open_log_reader(segment) {
+            Ok(r) => r,
+            Err(e) => {
+                result.skipped.push((segment.clone(), e.to_string()));
+                continue;
+            }
+        };
+        let mut lines = reader.lines();
+
+        // The `#` preamble above the header; the first segment's is kept
+        let mut preamble = Vec::new();
+        let mut seg_header = None;
+        for line in lines.by_ref() {
+            let line = line?;
+            if line.starts_with('#') {
+                preamble.push(line);
+            } else {
+                seg_header = Some(line);
+                break;
+            }
+        }
+
+        // Segments that never received a populated sample have no header
+        let Some(seg_header) = seg_header else {
+            result.merged.push(segment.clone());
+            continue;
+        };
+
+        match header {
+            Some(ref h) if *h != seg_header => {
+                result.skipped.push((segment.clone(), "header differs from first segment".to_string()));
+                continue;
+            }
+            Some(_) => {}
+            None => {
+                for line in &preamble {
+                    writeln!(writer, "{}", line)?;
+                }
+                writeln!(writer, "{}", seg_header)?;
+                header = Some(seg_header);
+            }
+        }
+
+        for line in lines {
+            let line = line?;
+            if !line.is_empty() && !line.starts_with('#') {
+                writeln!(writer, "{}", line)?;
+                result.rows += 1;
+            }
+        }
+        result.merged.push(segment.clone());
+    }
+
+    writer.flush()?;
+    Ok(result)
+}
+
+/// Column-safe name for a watched path: "/" -> "root", "/var/tmp" -> "var_tmp"
+fn fs_column_name(path: &str) -> String {
+    let name: String = path
+        .trim_matches('/')
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if name.is_empty() {
+        "root".to_string()
+    } else {
+        name
+    }
+}
+
+/// Column names for the watched paths, numbering repeats ("/var/tmp" and "/var_tmp" ->
+/// "var_tmp", "var_tmp_2") so every path keeps its own columns
+fn fs_column_names(paths: &[String]) -> Vec<String> {
+    let mut names: Vec<String> = Vec::with_capacity(paths.len());
+    for path in paths {
+        let base = fs_column_name(path);
+        let mut name = base.clone();
+        let mut n = 1;
+        while names.contains(&name) {
+            n += 1;
+            name = format!("{}_{}", base, n);
+        }
+        names.push(name);
+    }
+    names
+}
+
 /// Summary statistics calculated from metrics history
 #[derive(Debug, Clone)]
 pub struct MetricsSummary {
@@ -529,39 +1459,209 @@ pub struct MetricsSummary {
     // CPU summary
     pub cpu_avg_utilization: f64,
     pub cpu_max_utilization: f64,
+    /// Seconds from the start of the run to the CPU utilization peak
+    pub cpu_max_at_secs: f64,
     pub cpu_avg_iowait: f64,
     pub cpu_max_iowait: f64,
+    pub cpu_avg_steal: f64,
+    pub cpu_max_steal: f64,
 
     // Memory summary
     pub memory_avg_used_percent: f64,
     pub memory_max_used_percent: f64,
+    /// Seconds from the start of the run to the memory usage peak
+    pub memory_max_at_secs: f64,
     pub memory_max_used_bytes: u64,
     pub cgroup_max_usage_percent: Option<f64>,
     pub swap_max_used: u64,
 
     // Disk summary
     pub disk_max_read_throughput: f64,
+    pub disk_max_read_at_secs: f64,
     pub disk_max_write_throughput: f64,
+    pub disk_max_write_at_secs: f64,
     pub disk_max_utilization: f64,
+    /// Highest average queue depth (requests in flight) of any disk in one interval
+    pub disk_max_queue_depth: f64,
+    /// Highest average write latency of any disk in one interval
+    pub disk_max_write_latency_ms: f64,
+    /// Per-disk latency percentiles, in order of first appearance
+    pub disk_latency: Vec<DiskLatency>,
 
     // Network summary
     pub network_total_rx_bytes: u64,
     pub network_total_tx_bytes: u64,
     pub network_max_rx_throughput: f64,
+    pub network_max_rx_at_secs: f64,
     pub network_max_tx_throughput: f64,
+    pub network_max_tx_at_secs: f64,
+    /// Groups of two or more peaks that happened close together
+    pub peak_clusters: Vec<PeakCluster>,
 
     // Process summary (if monitored)
     pub process_max_cpu: Option<f64>,
     pub process_max_rss: Option<u64>,
+    /// Most descendants aggregated in one sample (--tree)
+    pub process_max_children: Option<u32>,
     pub process_max_fds: Option<u64>,
+    pub process_activity: Option<ProcessActivity>,
+    /// OOM kills of the monitored process, as alert messages
+    pub process_oom_kills: Vec<String>,
 
     // Bottleneck analysis
     pub bottleneck_indicators: Vec<String>,
+
+    // Alerts fired during the run
+    pub alert_profile: AlertProfile,
+}
+
+/// Quantitative profile of the alerts fired during a run
+#[derive(Debug, Clone, Default)]
+pub struct AlertProfile {
+    pub total: u64,
+    pub warnings: u64,
+    pub criticals: u64,
+    /// (category, warnings, criticals), most alerts first
+    pub by_category: Vec<(String, u64, u64)>,
+    /// Most frequent alert key and its count
+    pub most_frequent: Option<(String, u64)>,
+    /// Seconds from the start of the run to the first critical alert
+    pub first_critical_offset_secs: Option<f64>,
+    /// Aggregation window length in seconds
+    pub window_secs: u64,
+    /// Start offset (seconds) and alert count of the window with the most alerts
+    pub busiest_window: Option<(f64, u64)>,
+}
+
+/// Peaks this close together (seconds) are reported as correlated
+const PEAK_CLUSTER_SECS: f64 = 5.0;
+
+/// Peaks of different metrics that happened within a few seconds of each other
+#[derive(Debug, Clone)]
+pub struct PeakCluster {
+    /// Seconds from the start of the run to the earliest peak in the group
+    pub start_secs: f64,
+    /// Seconds from the start of the run to the latest peak in the group
+    pub end_secs: f64,
+    /// Metrics that peaked, in the order they did
+    pub metrics: Vec<&'static str>,
+}
+
+impl PeakCluster {
+    /// E.g. "CPU, disk write and network TX peaked within t=140-143s"
+    pub fn describe(&self) -> String {
+        let (last, rest) = self.metrics.split_last().expect("clusters hold at least two peaks");
+        let when = if self.end_secs - self.start_secs < 1.0 {
+            format!("together at t={:.0}s", self.start_secs)
+        } else {
+            format!("within t={:.0}-{:.0}s", self.start_secs, self.end_secs)
+        };
+        format!("{} and {} peaked {}", rest.join(", "), last, when)
+    }
+}
+
+/// Process CPU% at or above which a sample counts as active even if not caught running
+const ACTIVE_CPU_PERCENT: f64 = 5.0;
+
+/// How the monitored process spent the samples it was present for
+#[derive(Debug, Clone, Default)]
+pub struct ProcessActivity {
+    /// Samples in which the process existed
+    pub samples: u64,
+    /// Samples where it was running or consuming CPU
+    pub active: u64,
+    /// Inactive samples spent in uninterruptible (disk) sleep
+    pub blocked: u64,
+    /// Remaining inactive samples (sleeping, stopped, ...)
+    pub idle: u64,
+    /// Start offset (seconds) and length (seconds) of the longest inactive stretch
+    pub longest_idle: Option<(f64, f64)>,
+}
+
+impl ProcessActivity {
+    fn percent(&self, count: u64) -> f64 {
+        if self.samples == 0 { 0.0 } else { 100.0 * count as f64 / self.samples as f64 }
+    }
+
+    pub fn active_percent(&self) -> f64 {
+        self.percent(self.active)
+    }
+
+    pub fn blocked_percent(&self) -> f64 {
+        self.percent(self.blocked)
+    }
+
+    pub fn idle_percent(&self) -> f64 {
+        self.percent(self.idle)
+    }
+}
+
+/// p50/p95/p99 of a latency series in milliseconds
+#[derive(Debug, Clone, Copy)]
+pub struct LatencyPercentiles {
+    pub p50: f64,
+    pub p95: f64,
+    pub p99: f64,
+}
+
+impl LatencyPercentiles {
+    /// None if the series is empty
+    fn of(mut values: Vec<f64>) -> Option<Self> {
+        if values.is_empty() {
+            return None;
+        }
+        values.sort_by(|a, b| a.total_cmp(b));
+        Some(Self {
+            p50: percentile(&values, 50.0),
+            p95: percentile(&values, 95.0),
+            p99: percentile(&values, 99.0),
+        })
+    }
+}
+
+/// Read and write latency distribution of one disk over its last LATENCY_WINDOW
+/// samples with I/O in each direction. Built from per-sample average latencies,
+/// so it shows how latency varied between intervals rather than the latency of
+/// individual requests.
+#[derive(Debug, Clone)]
+pub struct DiskLatency {
+    pub device: String,
+    pub read: Option<LatencyPercentiles>,
+    pub write: Option<LatencyPercentiles>,
+}
+
+/// Per-sample latencies kept per disk and direction for the percentiles (an hour at
+/// the default 1 s interval)
+const LATENCY_WINDOW: usize = 3600;
+
+/// Most recent per-sample average latencies of one disk, in milliseconds
+#[derive(Debug, Default)]
+struct LatencyWindow {
+    reads: VecDeque<f64>,
+    writes: VecDeque<f64>,
+}
+
+impl LatencyWindow {
+    fn push(values: &mut VecDeque<f64>, latency_ms: f64) {
+        if values.len() == LATENCY_WINDOW {
+            values.pop_front();
+        }
+        values.push_back(latency_ms);
+    }
 }
 
 /// Accumulator for building summary statistics
 pub struct SummaryAccumulator {
     samples: Vec<MetricsSample>,
+    /// Latency ring buffers per disk, in the order the disks were first seen
+    disk_latencies: Vec<(String, LatencyWindow)>,
+    alerts: Vec<Alert>,
+    alert_window_secs: u64,
+    /// Queue depth the disk queue alert warns at, for the queueing bottleneck indicator
+    disk_queue_warn: f64,
+    /// Memory and I/O PSI some avg10 the pressure alerts warn at, for the sustained pressure indicators
+    psi_mem_warn: f64,
+    psi_io_warn: f64,
     start_time: Option<DateTime<Utc>>,
 }
 
@@ -569,15 +1669,206 @@ impl SummaryAccumulator {
     pub fn new() -> Self {
         Self {
             samples: Vec::new(),
+            disk_latencies: Vec::new(),
+            alerts: Vec::new(),
+            alert_window_secs: 60,
+            disk_queue_warn: AlertThresholds::default().disk_queue_warn,
+            psi_mem_warn: AlertThresholds::default().psi_mem_warn,
+            psi_io_warn: AlertThresholds::default().psi_io_warn,
             start_time: None,
         }
     }
 
+    /// Set the window length used to find the busiest alert period
+    pub fn set_alert_window(&mut self, secs: u64) {
+        self.alert_window_secs = secs.max(1);
+    }
+
+    /// Queue depth that counts as queueing for the bottleneck analysis (the disk queue warning level)
+    pub fn set_disk_queue_warn(&mut self, depth: f64) {
+        self.disk_queue_warn = depth;
+    }
+
+    /// PSI averages that count as sustained memory and I/O pressure (the PSI warning levels)
+    pub fn set_psi_warn(&mut self, memory: f64, io: f64) {
+        self.psi_mem_warn = memory;
+        self.psi_io_warn = io;
+    }
+
+    /// Record alerts fired during the run (recoveries are not counted as alerts)
+    pub fn add_alerts(&mut self, alerts: &[Alert]) {
+        self.alerts.extend(alerts.iter().filter(|a| a.severity != Severity::Resolved).cloned());
+    }
+
+    /// Build the alert profile relative to the start of the run
+    fn alert_profile(&self, start: DateTime<Utc>) -> AlertProfile {
+        let mut profile = AlertProfile {
+            window_secs: self.alert_window_secs,
+            ..Default::default()
+        };
+        if self.alerts.is_empty() {
+            return profile;
+        }
+
+        let offset = |a: &Alert| ((a.timestamp - start).num_milliseconds() as f64 / 1000.0).max(0.0);
+
+        let mut by_category: std::collections::HashMap<&str, (u64, u64)> = std::collections::HashMap::new();
+        let mut by_key: std::collections::HashMap<&str, u64> = std::collections::HashMap::new();
+        let mut by_window: std::collections::HashMap<u64, u64> = std::collections::HashMap::new();
+
+        for alert in &self.alerts {
+            let entry = by_category.entry(alert.category.as_str()).or_default();
+            match alert.severity {
+                Severity::Warning => {
+                    profile.warnings += 1;
+                    entry.0 += 1;
+                }
+                Severity::Critical => {
+                    profile.criticals += 1;
+                    entry.1 += 1;
+                    if profile.first_critical_offset_secs.is_none() {
+                        profile.first_critical_offset_secs = Some(offset(alert));
+                    }
+                }
+                Severity::Resolved => {}
+            }
+            *by_key.entry(alert.key.as_str()).or_default() += 1;
+            *by_window.entry(offset(alert) as u64 / self.alert_window_secs).or_default() += 1;
+        }
+
+        profile.total = self.alerts.len() as u64;
+        profile.by_category = by_category
+            .into_iter()
+            .map(|(cat, (w, c))| (cat.to_string(), w, c))
+            .collect();
+        profile.by_category.sort_by(|a, b| (b.1 + b.2).cmp(&(a.1 + a.2)).then(a.0.cmp(&b.0)));
+        // Ties go to the alphabetically first key so the report is stable
+        profile.most_frequent = by_key
+            .into_iter()
+            .max_by(|a, b| a.1.cmp(&b.1).then(b.0.cmp(a.0)))
+            .map(|(key, count)| (key.to_string(), count));
+        profile.busiest_window = by_window
+            .into_iter()
+            .max_by(|a, b| a.1.cmp(&b.1).then(b.0.cmp(&a.0)))
+            .map(|(window, count)| ((window * self.alert_window_secs) as f64, count));
+
+        profile
+    }
+
+    /// Classify each process sample as active, blocked or idle (None if never monitored)
+    fn process_activity(&self, start: DateTime<Utc>) -> Option<ProcessActivity> {
+        let mut activity = ProcessActivity::default();
+        let mut stretch: Option<(f64, f64)> = None;
+
+        for sample in &self.samples {
+            let proc = match sample.process {
+                Some(ref proc) => proc,
+                None => {
+                    stretch = None; // The process was gone, not idle
+                    continue;
+                }
+            };
+            activity.samples += 1;
+
+            if proc.state == ProcessState::Running || proc.cpu_percent >= ACTIVE_CPU_PERCENT {
+                activity.active += 1;
+                stretch = None;
+                continue;
+            }
+            if proc.state == ProcessState::DiskSleep {
+                activity.blocked += 1;
+            } else {
+                activity.idle += 1;
+            }
+
+            let offset = (sample.timestamp - start).num_milliseconds() as f64 / 1000.0;
+            let (from, len) = stretch.unwrap_or((offset, 0.0));
+            let current = (from, len + sample.interval_secs);
+            stretch = Some(current);
+            if activity.longest_idle.is_none_or(|(_, longest)| current.1 > longest) {
+                activity.longest_idle = Some(current);
+            }
+        }
+
+        (activity.samples > 0).then_some(activity)
+    }
+
+    /// Latency percentiles per disk (disks that never did I/O are left out)
+    fn disk_latency(&self) -> Vec<DiskLatency> {
+        self.disk_latencies
+            .iter()
+            .filter_map(|(device, window)| {
+                let latency = DiskLatency {
+                    device: device.clone(),
+                    read: LatencyPercentiles::of(window.reads.iter().copied().collect()),
+                    write: LatencyPercentiles::of(window.writes.iter().copied().collect()),
+                };
+                (latency.read.is_some() || latency.write.is_some()).then_some(latency)
+            })
+            .collect()
+    }
+
+    /// Add each disk's latencies of `sample` to its ring buffers (only directions that saw I/O)
+    fn add_disk_latencies(&mut self, sample: &MetricsSample) {
+        for disk in &sample.disk.disks {
+            let index = match self.disk_latencies.iter().position(|(device, _)| *device == disk.device) {
+                Some(index) => index,
+                None => {
+                    self.disk_latencies.push((disk.device.clone(), LatencyWindow::default()));
+                    self.disk_latencies.len() - 1
+                }
+            };
+            let window = &mut self.disk_latencies[index].1;
+            if disk.read_iops > 0.0 {
+                LatencyWindow::push(&mut window.reads, disk.read_latency_ms);
+            }
+            if disk.write_iops > 0.0 {
+                LatencyWindow::push(&mut window.writes, disk.write_latency_ms);
+            }
+        }
+    }
+
+    /// Bottleneck line for the disk that most often had a queue at or above the warning
+    /// depth, if that was at least a quarter of the samples
+    ///
+    /// A long queue at moderate utilization means requests wait on a device that is
+    /// saturated without looking busy (utilization only counts time with I/O in flight).
+    fn disk_queueing(&self) -> Option<String> {
+        // Per disk: samples seen, samples at or above the warning depth, max queue and utilization
+        let mut disks: Vec<(&str, usize, usize, f64, f64)> = Vec::new();
+        for disk in self.samples.iter().flat_map(|s| &s.disk.disks) {
+            let index = match disks.iter().position(|d| d.0 == disk.device) {
+                Some(index) => index,
+                None => {
+                    disks.push((&disk.device, 0, 0, 0.0, 0.0));
+                    disks.len() - 1
+                }
+            };
+            let entry = &mut disks[index];
+            entry.1 += 1;
+            if disk.queue_depth >= self.disk_queue_warn {
+                entry.2 += 1;
+            }
+            entry.3 = entry.3.max(disk.queue_depth);
+            entry.4 = entry.4.max(disk.utilization_percent);
+        }
+
+        let (device, seen, queued, max_queue, max_util) = disks.into_iter().max_by_key(|d| d.2)?;
+        let queued_percent = 100.0 * queued as f64 / seen as f64;
+        (queued > 0 && queued_percent >= 25.0).then(|| {
+            format!(
+                "Disk queueing: {} queue depth at or above {} in {:.0}% of samples (max {:.1}, utilization max {:.0}%)",
+                device, self.disk_queue_warn, queued_percent, max_queue, max_util
+            )
+        })
+    }
+
     /// Add a sample to the accumulator
     pub fn add_sample(&mut self, sample: MetricsSample) {
         if self.start_time.is_none() {
             self.start_time = Some(sample.timestamp);
         }
+        self.add_disk_latencies(&sample);
         self.samples.push(sample);
     }
 
@@ -590,10 +1881,16 @@ impl SummaryAccumulator {
         let first = self.samples.first()?;
         let last = self.samples.last()?;
         let duration_secs = (last.timestamp - first.timestamp).num_milliseconds() as f64 / 1000.0;
+        let start = self.start_time.unwrap_or(first.timestamp);
+        let offsets: Vec<f64> = self.samples.iter()
+            .map(|s| (s.timestamp - start).num_milliseconds() as f64 / 1000.0)
+            .collect();
 
         // CPU stats
         let cpu_utils: Vec<f64> = self.samples.iter().map(|s| s.cpu.total_utilization).collect();
         let cpu_iowaits: Vec<f64> = self.samples.iter().map(|s| s.cpu.iowait_percent).collect();
+        let cpu_steals: Vec<f64> = self.samples.iter().map(|s| s.cpu.steal_percent).collect();
+        let procs_blocked: Vec<f64> = self.samples.iter().map(|s| s.cpu.procs_blocked as f64).collect();
 
         // Memory stats
         let mem_used_pcts: Vec<f64> = self.samples.iter().map(|s| s.memory.used_percent).collect();
@@ -609,6 +1906,12 @@ impl SummaryAccumulator {
         let disk_utils: Vec<f64> = self.samples.iter()
             .flat_map(|s| s.disk.disks.iter().map(|d| d.utilization_percent))
             .collect();
+        let disk_queues: Vec<f64> = self.samples.iter()
+            .flat_map(|s| s.disk.disks.iter().map(|d| d.queue_depth))
+            .collect();
+        let disk_write_latencies: Vec<f64> = self.samples.iter()
+            .flat_map(|s| s.disk.disks.iter().map(|d| d.write_latency_ms))
+            .collect();
 
         // Network stats
         let net_rx: Vec<f64> = self.samples.iter().map(|s| s.network.total_rx_bytes_per_sec).collect();
@@ -640,48 +1943,161 @@ impl SummaryAccumulator {
         if avg_cpu > 90.0 {
             bottlenecks.push("CPU-bound: High average CPU utilization (>90%)".to_string());
         }
+        let avg_steal = avg(&cpu_steals);
+        if avg_steal > 10.0 {
+            bottlenecks.push(format!("Oversubscribed host: CPU steal averaged {:.1}% (>10%)", avg_steal));
+        }
+        let throttled_periods: u64 = self.samples.iter()
+            .filter_map(|s| s.cpu.throttling.as_ref().map(|t| t.throttled_periods))
+            .sum();
+        if throttled_periods > 0 {
+            bottlenecks.push(format!("CPU throttled by cgroup quota ({} periods)", throttled_periods));
+        }
         if max_iowait > 50.0 {
             bottlenecks.push("I/O-bound: High CPU iowait observed (>50%)".to_string());
         }
+        let avg_blocked = avg(&procs_blocked);
+        let avg_iowait = avg(&cpu_iowaits);
+        if avg_blocked >= 1.0 && avg_iowait > 20.0 {
+            bottlenecks.push(format!(
+                "Disk saturation: {:.1} tasks blocked on I/O on average with {:.1}% iowait",
+                avg_blocked, avg_iowait
+            ));
+        }
         if max_cgroup > 90.0 {
             bottlenecks.push("Memory-bound: Cgroup memory near limit (>90%)".to_string());
         }
+        // Unreclaimable slab only shrinks when the kernel frees objects, so steady growth is a leak signal
+        let slab_growth = last.memory.slab_unreclaimable.saturating_sub(first.memory.slab_unreclaimable);
+        if last.memory.total > 0 && slab_growth > last.memory.total / 20 {
+            bottlenecks.push(format!(
+                "Kernel memory growth: unreclaimable slab grew by {} (>5% of RAM, possible leak)",
+                format_bytes_short(slab_growth)
+            ));
+        }
         if *swap_used.iter().max().unwrap_or(&0) > 0 {
             bottlenecks.push("Memory pressure: Swap usage detected".to_string());
         }
         if max_disk_util > 80.0 {
             bottlenecks.push("Disk I/O-bound: High disk utilization (>80%)".to_string());
         }
+        if let Some(queueing) = self.disk_queueing() {
+            bottlenecks.push(queueing);
+        }
+        // PSI measures time actually lost waiting, so a high average means sustained stalls
+        let psi_samples: Vec<&PsiMetrics> = self.samples.iter().filter_map(|s| s.psi.as_ref()).collect();
+        if !psi_samples.is_empty() {
+            let avg_mem_psi = avg(&psi_samples.iter().map(|p| p.memory.some_avg10).collect::<Vec<_>>());
+            let avg_io_psi = avg(&psi_samples.iter().map(|p| p.io.some_avg10).collect::<Vec<_>>());
+            if avg_mem_psi > self.psi_mem_warn {
+                bottlenecks.push(format!(
+                    "Sustained memory pressure: PSI some avg10 averaged {:.1}% (>{}%)",
+                    avg_mem_psi, self.psi_mem_warn
+                ));
+            }
+            if avg_io_psi > self.psi_io_warn {
+                bottlenecks.push(format!(
+                    "Sustained I/O pressure: PSI some avg10 averaged {:.1}% (>{}%)",
+                    avg_io_psi, self.psi_io_warn
+                ));
+            }
+        }
+
+        let process_oom_kills: Vec<String> = self.alerts.iter()
+            .filter(|a| a.key == OOM_KILL_KEY)
+            .map(|a| a.message.clone())
+            .collect();
+        if !process_oom_kills.is_empty() {
+            bottlenecks.push(format!(
+                "Memory-bound: monitored process OOM-killed ({}x)",
+                process_oom_kills.len()
+            ));
+        }
+
+        let process_activity = self.process_activity(start);
+        if let Some(ref activity) = process_activity {
+            if activity.active_percent() > 90.0 {
+                bottlenecks.push("Process compute-bound: active in >90% of samples".to_string());
+            } else if activity.blocked_percent() > 30.0 {
+                bottlenecks.push(format!(
+                    "Process I/O-blocked: in disk sleep for {:.0}% of samples",
+                    activity.blocked_percent()
+                ));
+            }
+        }
+
+        // When each per-sample series peaked, for correlating spikes across subsystems
+        let cpu_max_at_secs = peak_offset(&cpu_utils, &offsets);
+        let memory_max_at_secs = peak_offset(&mem_used_pcts, &offsets);
+        let disk_max_read_at_secs = peak_offset(&disk_reads, &offsets);
+        let disk_max_write_at_secs = peak_offset(&disk_writes, &offsets);
+        let network_max_rx_at_secs = peak_offset(&net_rx, &offsets);
+        let network_max_tx_at_secs = peak_offset(&net_tx, &offsets);
+        let peak_clusters = if duration_secs >= 4.0 * PEAK_CLUSTER_SECS {
+            // Idle series (no disk reads, no traffic) have no meaningful peak
+            let peaks = [
+                ("CPU", max_cpu, cpu_max_at_secs),
+                ("memory", max_f64(&mem_used_pcts), memory_max_at_secs),
+                ("disk read", max_f64(&disk_reads), disk_max_read_at_secs),
+                ("disk write", max_f64(&disk_writes), disk_max_write_at_secs),
+                ("network RX", max_f64(&net_rx), network_max_rx_at_secs),
+                ("network TX", max_f64(&net_tx), network_max_tx_at_secs),
+            ];
+            peak_clusters(peaks.iter().filter(|p| p.1 > 0.0).map(|p| (p.0, p.2)).collect())
+        } else {
+            // In a short run every peak is within a few seconds of the others
+            Vec::new()
+        };
 
         Some(MetricsSummary {
             duration_secs,
             samples_count: self.samples.len() as u64,
             cpu_avg_utilization: avg_cpu,
             cpu_max_utilization: max_cpu,
-            cpu_avg_iowait: avg(&cpu_iowaits),
+            cpu_max_at_secs,
+            cpu_avg_iowait: avg_iowait,
             cpu_max_iowait: max_iowait,
+            cpu_avg_steal: avg_steal,
+            cpu_max_steal: max_f64(&cpu_steals),
             memory_avg_used_percent: avg(&mem_used_pcts),
             memory_max_used_percent: max_f64(&mem_used_pcts),
+            memory_max_at_secs,
             memory_max_used_bytes: *mem_used_bytes.iter().max().unwrap_or(&0),
             cgroup_max_usage_percent: if cgroup_usages.is_empty() { None } else { Some(max_f64(&cgroup_usages)) },
             swap_max_used: *swap_used.iter().max().unwrap_or(&0),
             disk_max_read_throughput: max_f64(&disk_reads),
+            disk_max_read_at_secs,
             disk_max_write_throughput: max_f64(&disk_writes),
+            disk_max_write_at_secs,
             disk_max_utilization: max_disk_util,
+            disk_max_queue_depth: max_f64(&disk_queues),
+            disk_max_write_latency_ms: max_f64(&disk_write_latencies),
+            disk_latency: self.disk_latency(),
             network_total_rx_bytes: network_total_rx,
             network_total_tx_bytes: network_total_tx,
             network_max_rx_throughput: max_f64(&net_rx),
+            network_max_rx_at_secs,
             network_max_tx_throughput: max_f64(&net_tx),
+            network_max_tx_at_secs,
+            peak_clusters,
             process_max_cpu: if proc_cpus.is_empty() { None } else { Some(max_f64(&proc_cpus)) },
             process_max_rss: proc_rss.iter().max().copied(),
+            process_max_children: self.samples.iter()
+                .filter_map(|s| s.process.as_ref().map(|p| p.children))
+                .max(),
             process_max_fds: proc_fds.iter().max().copied(),
+            process_activity,
+            process_oom_kills,
             bottleneck_indicators: bottlenecks,
+            alert_profile: self.alert_profile(start),
         })
     }
 
     /// Clear accumulated samples
     pub fn clear(&mut self) {
         self.samples.clear();
+        self.disk_latencies.clear();
+        self.alerts.clear();
         self.start_time = None;
     }
 }
@@ -702,3 +2118,294 @@ fn avg(values: &[f64]) -> f64 {
 fn max_f64(values: &[f64]) -> f64 {
     values.iter().cloned().fold(f64::NEG_INFINITY, f64::max)
 }
+
+/// Offset of the first sample holding the maximum of `values` (parallel to `offsets`)
+fn peak_offset(values: &[f64], offsets: &[f64]) -> f64 {
+    let max = max_f64(values);
+    values.iter().position(|&v| v == max).map_or(0.0, |i| offsets[i])
+}
+
+/// Group (metric, offset) peaks that fall within PEAK_CLUSTER_SECS of the first
+/// peak of their group, keeping groups of two or more
+fn peak_clusters(mut peaks: Vec<(&'static str, f64)>) -> Vec<PeakCluster> {
+    peaks.sort_by(|a, b| a.1.total_cmp(&b.1));
+    let mut clusters: Vec<PeakCluster> = Vec::new();
+    for (metric, offset) in peaks {
+        match clusters.last_mut() {
+            Some(cluster) if offset - cluster.start_secs <= PEAK_CLUSTER_SECS => {
+                cluster.end_secs = offset;
+                cluster.metrics.push(metric);
+            }
+            _ => clusters.push(PeakCluster { start_secs: offset, end_secs: offset, metrics: vec![metric] }),
+        }
+    }
+    clusters.retain(|c| c.metrics.len() > 1);
+    clusters
+}
+
+/// Nearest-rank percentile of an ascending-sorted, non-empty slice
+pub(crate) fn percentile(sorted: &[f64], pct: f64) -> f64 {
+    let rank = (pct / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("monperf-test-{}-{}", std::process::id(), name))
+    }
+
+    fn test_environment() -> EnvironmentInfo {
+        EnvironmentInfo {
+            kernel_release: Some("6.8.0".to_string()),
+            kernel_version: Some("Linux version 6.8.0".to_string()),
+            cpu_model: Some("Test CPU".to_string()),
+            cpu_count: 4,
+            clocksource: Some("tsc".to_string()),
+            sysctls: vec![("vm.swappiness".to_string(), "60".to_string())],
+        }
+    }
+
+    fn sample_with_disk(device: &str) -> MetricsSample {
+        let mut disk = DiskMetrics::default();
+        disk.disks.push(crate::metrics::disk::DiskStats { device: device.to_string(), ..Default::default() });
+        MetricsSample {
+            timestamp: Utc::now(),
+            interval_secs: 1.0,
+            cpu: CpuMetrics::default(),
+            memory: MemoryMetrics::default(),
+            disk,
+            network: NetworkMetrics::default(),
+            process: None,
+            psi: None,
+            cgroup_psi: None,
+            gpu: None,
+        }
+    }
+
+    #[test]
+    fn append_checks_existing_header_before_writing() {
+        let path = temp_path("append.csv");
+        let env = test_environment();
+        {
+            let mut logger = CsvLogger::new(&path, CsvFormat::default(), Some(&env), LogOptions { append: false, flush_every: 1 }).unwrap();
+            assert!(!logger.is_appending());
+            logger.log(&sample_with_disk("sda")).unwrap();
+        }
+
+        let append = LogOptions { append: true, flush_every: 1 };
+        let mut logger = CsvLogger::new(&path, CsvFormat::default(), Some(&env), append).unwrap();
+        assert!(logger.is_appending());
+        logger.check_existing_header(&sample_with_disk("sda")).unwrap();
+        assert!(logger.check_existing_header(&sample_with_disk("nvme0n1")).is_err());
+        drop(logger);
+
+        // The failed check wrote nothing: one preamble, one header, one row
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(content.lines().filter(|l| l.starts_with("timestamp,")).count(), 1);
+        assert_eq!(csv_lines(content.as_bytes()).count(), 2);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn log_writer_counts_buffered_and_existing_bytes() {
+        let path = temp_path("counted.log");
+        std::fs::write(&path, "earlier run\n").unwrap();
+
+        let (file, has_content) = open_log_file(&path, true, "test log").unwrap();
+        assert!(has_content);
+        let mut writer = LogWriter::new(file);
+        assert_eq!(writer.bytes(), 12);
+        writer.write_all(b"0123456789").unwrap();
+        // Still buffered, but already counted
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), 12);
+        assert_eq!(writer.bytes(), 22);
+        writer.flush().unwrap();
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), 22);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn csv_preamble_is_opt_in() {
+        let plain = temp_path("plain.csv");
+        let mut logger = CsvLogger::new(&plain, CsvFormat::default(), None, LogOptions { append: false, flush_every: 1 }).unwrap();
+        logger.log(&sample_with_disk("sda")).unwrap();
+        drop(logger);
+        let content = std::fs::read_to_string(&plain).unwrap();
+        assert!(content.starts_with("timestamp,"), "{}", content);
+        assert!(!content.contains('#'));
+        std::fs::remove_file(&plain).unwrap();
+
+        let annotated = temp_path("preamble.csv");
+        let env = test_environment();
+        let mut logger = CsvLogger::new(&annotated, CsvFormat::default(), Some(&env), LogOptions { append: false, flush_every: 1 }).unwrap();
+        logger.log(&sample_with_disk("sda")).unwrap();
+        drop(logger);
+        let content = std::fs::read_to_string(&annotated).unwrap();
+        assert!(content.starts_with("# monperf "), "{}", content);
+        assert!(content.contains("\n# Kernel: Linux version 6.8.0\n"), "{}", content);
+        assert!(csv_lines(content.as_bytes()).next().unwrap().unwrap().starts_with("timestamp,"));
+        std::fs::remove_file(&annotated).unwrap();
+    }
+
+    #[test]
+    fn csv_lines_skip_preamble() {
+        let log = "# monperf 0.1.0 log started 2026-01-01 00:00:00 UTC\n# Clock: tsc\ntimestamp,cpu\n1,2\n";
+        let lines: Vec<String> = csv_lines(log.as_bytes()).collect::<Result<_, _>>().unwrap();
+        assert_eq!(lines, ["timestamp,cpu", "1,2"]);
+    }
+
+    #[test]
+    fn capture_header_records_environment() {
+        let path = temp_path("header.mpc");
+        let options = LogOptions { append: false, flush_every: 1 };
+        drop(CaptureLogger::new(&path, &test_environment(), options).unwrap());
+
+        assert!(is_capture_file(&path));
+        let header = read_capture_header(&path).unwrap().expect("version 2 capture has a header");
+        assert_eq!(header.environment.clocksource.as_deref(), Some("tsc"));
+        assert_eq!(header.environment.cpu_count, 4);
+        assert!(read_capture(&path).unwrap().is_empty());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn version_1_capture_has_no_header() {
+        let path = temp_path("v1.mpc");
+        std::fs::write(&path, CAPTURE_MAGIC_V1).unwrap();
+
+        assert!(is_capture_file(&path));
+        assert!(read_capture_header(&path).unwrap().is_none());
+        assert!(read_capture(&path).unwrap().is_empty());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn oversized_capture_record_is_rejected() {
+        let path = temp_path("oversized.mpc");
+        let mut data = CAPTURE_MAGIC_V1.to_vec();
+        data.extend_from_slice(&u32::MAX.to_le_bytes());
+        std::fs::write(&path, data).unwrap();
+
+        let err = read_capture(&path).unwrap_err();
+        assert!(format!("{:#}", err).contains("exceeds the 64 MiB limit"));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn truncated_capture_record_is_ignored() {
+        let path = temp_path("truncated.mpc");
+        let mut data = CAPTURE_MAGIC_V1.to_vec();
+        data.extend_from_slice(&100u32.to_le_bytes());
+        data.extend_from_slice(&[0u8; 10]);
+        std::fs::write(&path, data).unwrap();
+
+        assert!(read_capture(&path).unwrap().is_empty());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    /// A sample with `device` at the given queue depth and utilization
+    fn sample_with_queue(device: &str, queue_depth: f64, utilization_percent: f64) -> MetricsSample {
+        let mut sample = sample_with_disk(device);
+        sample.disk.disks[0].queue_depth = queue_depth;
+        sample.disk.disks[0].utilization_percent = utilization_percent;
+        sample
+    }
+
+    #[test]
+    fn disk_queueing_reports_a_disk_queued_in_a_quarter_of_samples() {
+        let mut summary = SummaryAccumulator::new();
+        summary.set_disk_queue_warn(4.0);
+        for queue_depth in [6.0, 1.0, 1.0, 1.0] {
+            summary.add_sample(sample_with_queue("sda", queue_depth, 40.0));
+        }
+
+        assert_eq!(
+            summary.disk_queueing().as_deref(),
+            Some("Disk queueing: sda queue depth at or above 4 in 25% of samples (max 6.0, utilization max 40%)")
+        );
+    }
+
+    #[test]
+    fn disk_queueing_ignores_rare_queues() {
+        let mut summary = SummaryAccumulator::new();
+        summary.set_disk_queue_warn(4.0);
+        for queue_depth in [6.0, 1.0, 1.0, 1.0, 1.0] {
+            summary.add_sample(sample_with_queue("sda", queue_depth, 40.0));
+        }
+
+        assert_eq!(summary.disk_queueing(), None);
+    }
+
+    /// A sample where `device` read with the given per-sample average latency
+    fn sample_with_read_latency(device: &str, latency_ms: f64) -> MetricsSample {
+        let mut sample = sample_with_disk(device);
+        sample.disk.disks[0].read_iops = 10.0;
+        sample.disk.disks[0].read_latency_ms = latency_ms;
+        sample
+    }
+
+    #[test]
+    fn disk_latency_percentiles_of_evenly_spread_latencies() {
+        let mut summary = SummaryAccumulator::new();
+        for latency in 1..=100 {
+            summary.add_sample(sample_with_read_latency("sda", latency as f64));
+        }
+        // A disk that never did I/O has no row
+        summary.add_sample(sample_with_disk("sdb"));
+
+        let latency = summary.disk_latency();
+        assert_eq!(latency.len(), 1);
+        assert_eq!(latency[0].device, "sda");
+        let read = latency[0].read.unwrap();
+        assert_eq!((read.p50, read.p95, read.p99), (50.0, 95.0, 99.0));
+        assert!(latency[0].write.is_none());
+    }
+
+    #[test]
+    fn disk_latency_keeps_the_last_window_of_samples() {
+        let mut summary = SummaryAccumulator::new();
+        for _ in 0..100 {
+            summary.add_sample(sample_with_read_latency("sda", 500.0));
+        }
+        for _ in 0..LATENCY_WINDOW {
+            summary.add_sample(sample_with_read_latency("sda", 2.0));
+        }
+
+        let read = summary.disk_latency()[0].read.unwrap();
+        assert_eq!(read.p99, 2.0);
+        assert_eq!(summary.disk_latencies[0].1.reads.len(), LATENCY_WINDOW);
+    }
+
+    #[test]
+    fn fs_column_names_stay_unique() {
+        let paths = ["/", "/var/tmp", "/var_tmp", "/var/tmp/"].map(String::from);
+        assert_eq!(fs_column_names(&paths), ["root", "var_tmp", "var_tmp_2", "var_tmp_3"]);
+    }
+
+    /// Bottleneck lines of a run whose memory PSI some avg10 was always `memory_psi`
+    fn psi_bottlenecks(summary: &mut SummaryAccumulator, memory_psi: f64) -> Vec<String> {
+        for _ in 0..3 {
+            let mut sample = sample_with_disk("sda");
+            let mut psi = PsiMetrics::default();
+            psi.memory.some_avg10 = memory_psi;
+            sample.psi = Some(psi);
+            summary.add_sample(sample);
+        }
+        summary.generate_summary().unwrap().bottleneck_indicators
+    }
+
+    #[test]
+    fn psi_bottlenecks_follow_the_warning_levels() {
+        let lines = psi_bottlenecks(&mut SummaryAccumulator::new(), 8.0);
+        assert!(!lines.iter().any(|l| l.starts_with("Sustained memory pressure")), "{:?}", lines);
+
+        let mut summary = SummaryAccumulator::new();
+        summary.set_psi_warn(5.0, 20.0);
+        let lines = psi_bottlenecks(&mut summary, 8.0);
+        let expected = "Sustained memory pressure: PSI some avg10 averaged 8.0% (>5%)";
+        assert!(lines.iter().any(|l| l == expected), "{:?}", lines);
+    }
+}
@@ -4,33 +4,48 @@
 //! with real-time TUI display, historical logging, and alerting.
 
 mod alert;
+mod compare;
+mod config;
 mod display;
+mod environment;
+mod export;
+mod interval;
 mod logging;
 mod metrics;
 mod plot;
 mod process;
+mod replay;
+mod report;
 
-use alert::{AlertChecker, AlertThresholds};
-use anyhow::Result;
+use alert::{AlertChecker, AlertThresholds, FailOn};
+use anyhow::{Context, Result};
 use chrono::Utc;
-use clap::Parser;
+use clap::{CommandFactory, FromArgMatches, Parser};
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind},
+    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind, MouseButton, MouseEventKind},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
-use display::{format_bytes, format_throughput, CpuHistory, DiskHistory, MemoryHistory, NetworkHistory};
-use logging::{CsvLogger, MetricsSample, SummaryAccumulator, TextLogger};
+use display::{format_bytes, format_peak_at, format_throughput, format_throughput_bits, CpuHistory, DiskHistory, GraphMode, HistoryScale, HistoryView, MemoryHistory, NetworkHistory, Palette, SampleTimes};
+use environment::EnvironmentInfo;
+use export::{AlertWebhook, PrometheusExporter, SnapshotWriter, StatsdExporter};
+use interval::{IntervalTuner, TimingDrift};
+use logging::{CaptureLogger, CsvFormat, CsvLogger, InfluxLogger, JsonLogger, LatencyPercentiles, LogOptions, MetricsSample, OutputFormat, PerCoreCsvLogger, SummaryAccumulator, TextLogger};
 use metrics::{CpuMetrics, DiskMetrics, MemoryMetrics, NetworkMetrics};
-use process::{ProcessCollector, ProcessMetrics};
+use process::{MatchChoice, MatchOptions, ProcessCollector, ProcessMetrics};
+use replay::Replay;
 use ratatui::{
-    layout::{Constraint, Direction, Layout},
+    layout::{Constraint, Direction, Layout, Rect},
     prelude::CrosstermBackend,
-    Terminal,
+    Frame, Terminal,
 };
-use std::io;
+use std::collections::HashSet;
+use std::io::{self, Write as _};
 use std::net::UdpSocket;
+use std::os::unix::process::ExitStatusExt;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use std::time::Duration;
 
 /// Performance monitoring CLI for identifying system bottlenecks
@@ -38,6 +53,18 @@ use std::time::Duration;
 #[command(name = "monperf")]
 #[command(author, version, about, long_about = None)]
 struct Args {
+    /// TOML file with default option values (keys are long flag names); command-line flags override it
+    #[arg(long, value_name = "FILE")]
+    config: Option<PathBuf>,
+
+    /// Load option values from a named profile in ~/.config/monperf/profiles (like --config)
+    #[arg(long, value_name = "NAME", conflicts_with = "config")]
+    profile: Option<String>,
+
+    /// Save the effective thresholds and settings as a named profile ('w' in the TUI saves again)
+    #[arg(long, value_name = "NAME")]
+    save_profile: Option<String>,
+
     /// Process ID to monitor (optional)
     #[arg(short, long)]
     pid: Option<u32>,
@@ -46,22 +73,104 @@ struct Args {
     #[arg(short = 'n', long)]
     process_name: Option<String>,
 
+    /// Don't skip shells and other monperf instances when matching --process-name
+    #[arg(long)]
+    no_process_filter: bool,
+
+    /// Which process to pick when several match --process-name
+    #[arg(long, value_enum, default_value_t = MatchChoice::Best)]
+    process_match: MatchChoice,
+
+    /// Sum CPU, memory, threads, FDs and I/O of all descendants into the monitored process
+    #[arg(long)]
+    tree: bool,
+
+    /// Show the N busiest threads of the monitored process (default 5) from per-thread CPU time
+    #[arg(long, value_name = "N", num_args = 0..=1, default_missing_value = "5")]
+    threads: Option<usize>,
+
+    /// Print the open files of the --pid or --process-name process, grouped by type, and exit
+    #[arg(long, conflicts_with_all = ["command", "replay", "compare", "plot"])]
+    dump_fds: bool,
+
     /// Sampling interval in seconds
-    #[arg(short = 'i', long, default_value = "1")]
+    #[arg(short = 'i', long, default_value = "1", value_parser = parse_seconds)]
     interval: f64,
 
     /// Log all metrics to CSV file (canonical detailed format)
     #[arg(short, long)]
     log: Option<PathBuf>,
 
+    /// Decimal places for CSV percentages; byte rates become integers,
+    /// latencies keep extra places and trailing zeros are trimmed
+    #[arg(long)]
+    csv_precision: Option<usize>,
+
+    /// Write the environment (kernel, CPU, clocksource, sysctls) as `#` comment lines above
+    /// the CSV header; readers then need to skip comments (e.g. pandas comment='#')
+    #[arg(long)]
+    csv_preamble: bool,
+
     /// Log metrics to file (human-readable summary format)
     #[arg(short = 'o', long)]
     text_log: Option<PathBuf>,
 
+    /// Also log per-core CPU utilization as tidy CSV (timestamp, core_id, utilization)
+    #[arg(long)]
+    per_core_log: Option<PathBuf>,
+
+    /// Write a lossless binary capture of every sample (reload with --plot)
+    #[arg(long)]
+    capture: Option<PathBuf>,
+
+    /// Write every sample as one JSON object per line (JSON Lines)
+    #[arg(long)]
+    json_log: Option<PathBuf>,
+
+    /// Write every sample as InfluxDB line protocol (measurements cpu, memory, disk, network, psi, process)
+    #[arg(long)]
+    influx_file: Option<PathBuf>,
+
+    /// Append to existing log files instead of truncating them (headers are not repeated)
+    #[arg(long)]
+    append: bool,
+
+    /// Keep the first sample (all rates zero, collectors need two reads) out of logs, exports and the summary
+    #[arg(long)]
+    skip_first: bool,
+
+    /// Flush log files every N samples (1 = every sample; higher values cut syscalls at short intervals but a crash can lose up to N samples)
+    #[arg(long, default_value = "1", value_parser = clap::value_parser!(u64).range(1..))]
+    flush_interval: u64,
+
+    /// Gzip each log segment in the background once it is rotated out (foo_1.csv -> foo_1.csv.gz)
+    #[arg(long)]
+    compress_logs: bool,
+
     /// Spill directory to monitor for size
     #[arg(short, long)]
     spill_dir: Option<PathBuf>,
 
+    /// Also show partitions (e.g. sda1, nvme0n1p2), not just whole disks
+    #[arg(long)]
+    disk_include_partitions: bool,
+
+    /// Only show this block device, e.g. dm-0, vg0-root or sda1 (repeatable)
+    #[arg(long, value_name = "NAME")]
+    disk_device: Vec<String>,
+
+    /// Filesystem to watch for space usage, by any path on it (repeatable)
+    #[arg(long, value_name = "PATH")]
+    watch_fs: Vec<PathBuf>,
+
+    /// Filesystem usage warning threshold (%) for --watch-fs
+    #[arg(long, default_value = "90")]
+    fs_warn: f64,
+
+    /// Inode usage warning threshold (%) for --spill-dir and --watch-fs
+    #[arg(long, default_value = "90")]
+    inode_warn: f64,
+
     /// Run for specified duration (seconds), then exit with summary
     #[arg(short, long)]
     duration: Option<u64>,
@@ -70,6 +179,10 @@ struct Args {
     #[arg(long)]
     no_tui: bool,
 
+    /// How --no-tui prints samples: text blocks, or one JSON object per line (summary then goes to stderr)
+    #[arg(long, value_enum, default_value = "text")]
+    format: OutputFormat,
+
     /// Generate summary report at end
     #[arg(long)]
     summary: bool,
@@ -98,7 +211,107 @@ struct Args {
     #[arg(long, default_value = "95")]
     cgroup_crit: f64,
 
-    /// Generate plots from a CSV log file (use with --plot-output)
+    /// Memory pressure (PSI some avg10) warning threshold (%)
+    #[arg(long, default_value = "10")]
+    psi_mem_warn: f64,
+
+    /// Memory pressure (PSI some avg10) critical threshold (%)
+    #[arg(long, default_value = "30")]
+    psi_mem_crit: f64,
+
+    /// I/O pressure (PSI some avg10) warning threshold (%)
+    #[arg(long, default_value = "20")]
+    psi_io_warn: f64,
+
+    /// I/O pressure (PSI some avg10) critical threshold (%)
+    #[arg(long, default_value = "50")]
+    psi_io_crit: f64,
+
+    /// Network interface errors per second (RX+TX) warning threshold
+    #[arg(long, default_value = "1")]
+    net_error_warn: f64,
+
+    /// Network interface drops per second (RX+TX) warning threshold
+    #[arg(long, default_value = "100")]
+    net_drop_warn: f64,
+
+    /// TCP retransmitted share of sent segments warning threshold (%)
+    #[arg(long, default_value = "2")]
+    retransmit_warn: f64,
+
+    /// Show cgroup memory as the primary memory gauge (host RAM becomes secondary)
+    #[arg(long)]
+    cgroup_primary: bool,
+
+    /// TUI color theme: default, colorblind (blue/yellow/orange) or mono (brightness and bold only)
+    #[arg(long, value_enum, default_value = "default")]
+    theme: display::Theme,
+
+    /// Samples kept for the TUI graphs; 'h' fits all of them into the graph width
+    #[arg(long, default_value_t = display::DEFAULT_HISTORY as u64, value_parser = clap::value_parser!(u64).range(1..))]
+    history: u64,
+
+    /// Show network throughput in bits per second (Kbit/Mbit/Gbit) in the TUI, stdout and summary ('b' toggles)
+    #[arg(long)]
+    net_bits: bool,
+
+    /// Only raise an alert after its condition holds for this many consecutive samples
+    #[arg(long, default_value = "1")]
+    alert_sustained: u32,
+
+    /// Exit with --fail-exit-code if any alert of at least this severity fired during the run
+    #[arg(long, value_enum)]
+    fail_on_alert: Option<FailOn>,
+
+    /// Exit status used by --fail-on-alert
+    #[arg(long, default_value = "2")]
+    fail_exit_code: i32,
+
+    /// Window length in seconds for the busiest-alert-period line of the summary
+    #[arg(long, default_value = "60")]
+    alert_window: u64,
+
+    /// Seconds the monitored process may stay in uninterruptible sleep (D state) before a warning
+    #[arg(long, default_value = "10")]
+    dstate_warn: f64,
+
+    /// Seconds the monitored process may stay in uninterruptible sleep (D state) before a critical alert
+    #[arg(long, default_value = "30")]
+    dstate_crit: f64,
+
+    /// Open file descriptors of the monitored process (or tree) that trigger a warning
+    #[arg(long, value_name = "N")]
+    fd_warn: Option<u64>,
+
+    /// Open file descriptors of the monitored process (or tree) that trigger a critical alert
+    #[arg(long, value_name = "N")]
+    fd_crit: Option<u64>,
+
+    /// Open fds as a share (%) of the process's soft open file limit that trigger a warning
+    #[arg(long, default_value = "80")]
+    fd_limit_warn: f64,
+
+    /// Open fds as a share (%) of the process's soft open file limit that trigger a critical alert
+    #[arg(long, default_value = "95")]
+    fd_limit_crit: f64,
+
+    /// Threads of the monitored process (or tree) that trigger a warning
+    #[arg(long, value_name = "N")]
+    threads_warn: Option<u64>,
+
+    /// Threads of the monitored process (or tree) that trigger a critical alert
+    #[arg(long, value_name = "N")]
+    threads_crit: Option<u64>,
+
+    /// Warn when process RSS (or cgroup memory without a process) grows steadily toward its limit
+    #[arg(long)]
+    leak_detect: bool,
+
+    /// With --leak-detect, warn when the projected time to reach the limit is under this many seconds
+    #[arg(long, default_value = "600")]
+    leak_horizon: f64,
+
+    /// Generate plots from a CSV log or binary capture file (use with --plot-output)
     #[arg(long)]
     plot: Option<PathBuf>,
 
@@ -106,13 +319,144 @@ struct Args {
     #[arg(long, default_value = "plots")]
     plot_output: PathBuf,
 
+    /// Annotate each plot with avg/max/p95 of its metrics (use with --plot)
+    #[arg(long)]
+    plot_stats: bool,
+
+    /// Maximum time columns in the CPU core heatmap; longer runs are averaged per column
+    #[arg(long, default_value_t = plot::DEFAULT_HEATMAP_COLUMNS)]
+    heatmap_columns: usize,
+
+    /// Width of single-chart plots in pixels (multi-panel plots scale proportionally)
+    #[arg(long, default_value_t = plot::DEFAULT_PLOT_WIDTH, value_parser = clap::value_parser!(u32).range(200..))]
+    plot_width: u32,
+
+    /// Height of single-chart plots in pixels (multi-panel plots scale proportionally)
+    #[arg(long, default_value_t = plot::DEFAULT_PLOT_HEIGHT, value_parser = clap::value_parser!(u32).range(150..))]
+    plot_height: u32,
+
+    /// Downsample logs longer than N samples before plotting, keeping each bucket's min and max so spikes survive
+    #[arg(long, value_parser = clap::value_parser!(u64).range(2..))]
+    plot_max_points: Option<u64>,
+
+    /// With --plot, also write a self-contained report.html with every chart inlined and summary tables
+    #[arg(long, requires = "plot")]
+    plot_html: bool,
+
+    /// Compare two recorded runs: overlay plots in --plot-output and a summary diff on stdout
+    #[arg(long, num_args = 2, value_names = ["BASELINE", "CANDIDATE"], conflicts_with = "plot")]
+    compare: Option<Vec<PathBuf>>,
+
+    /// Play a CSV log, JSON log or binary capture back through the dashboard instead of sampling live
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["pid", "process_name", "command", "auto_interval", "plot", "compare"])]
+    replay: Option<PathBuf>,
+
+    /// Playback speed for --replay (2 plays twice as fast as recorded)
+    #[arg(long, default_value = "1", requires = "replay")]
+    replay_speed: f64,
+
+    /// Image format for --plot output
+    #[arg(long, value_enum, default_value = "svg")]
+    plot_format: plot::PlotFormat,
+
+    /// TrueType/OpenType font file for PNG plot text (default: DejaVu or Liberation from the usual system paths)
+    #[arg(long, value_name = "FILE")]
+    plot_font: Option<PathBuf>,
+
     /// Automatically split logs when monitored process starts or ends
     #[arg(long)]
     split_on_process: bool,
 
+    /// Start a new log segment once any active log file reaches this size in MB
+    #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+    log_max_size: Option<u64>,
+
+    /// Start a new log segment every N seconds
+    #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+    log_rotate_interval: Option<u64>,
+
     /// UDP port to listen for control messages (split logs on message, rename if filename provided)
     #[arg(long)]
     control_port: Option<u16>,
+
+    /// On exit, concatenate CSV log segments into <log>_all.csv
+    #[arg(long)]
+    compact_on_exit: bool,
+
+    /// Delete the individual segments after a successful --compact-on-exit
+    #[arg(long, requires = "compact_on_exit")]
+    delete_segments: bool,
+
+    /// Query NIC ring sizes and interrupt coalescing with the external ethtool command
+    #[arg(long)]
+    ethtool: bool,
+
+    /// Count established TCP connections per remote address and list the top talkers ('t' in the TUI)
+    #[arg(long)]
+    top_talkers: bool,
+
+    /// Collect per-GPU utilization and memory with nvidia-smi (no GPU panel without NVIDIA GPUs)
+    #[arg(long)]
+    gpu: bool,
+
+    /// Push key metrics as StatsD gauges over UDP each interval (host:port)
+    #[arg(long)]
+    statsd: Option<String>,
+
+    /// With --statsd, send labels as DogStatsD tags (|#device:sda) instead of metric name segments
+    #[arg(long, requires = "statsd")]
+    dogstatsd: bool,
+
+    /// Keep the latest sample as JSON in this file, replaced atomically every interval
+    #[arg(long)]
+    snapshot: Option<PathBuf>,
+
+    /// Serve the latest metrics in Prometheus text format at http://<host>:<port>/metrics
+    #[arg(long)]
+    prometheus_port: Option<u16>,
+
+    /// Address the --prometheus-port endpoint listens on (0.0.0.0 to accept scrapes from other hosts)
+    #[arg(long, default_value = "127.0.0.1", requires = "prometheus_port")]
+    prometheus_bind: std::net::IpAddr,
+
+    /// POST each new alert as JSON to this URL (e.g. a Slack or PagerDuty relay)
+    #[arg(long)]
+    alert_webhook: Option<String>,
+
+    /// Adapt the sampling interval to metric volatility (shorter when busy, longer when stable)
+    #[arg(long)]
+    auto_interval: bool,
+
+    /// Lower bound for --auto-interval in seconds
+    #[arg(long, default_value = "0.25", value_parser = parse_seconds)]
+    min_interval: f64,
+
+    /// Upper bound for --auto-interval in seconds
+    #[arg(long, default_value = "10", value_parser = parse_seconds)]
+    max_interval: f64,
+
+    /// Command to run and monitor (after --); monperf exits with its status when it finishes
+    #[arg(last = true, value_name = "COMMAND", conflicts_with_all = ["pid", "process_name"])]
+    command: Vec<String>,
+}
+
+/// Parse a time in seconds, rejecting zero, negative and non-finite values
+/// that `Duration::from_secs_f64` would panic on
+fn parse_seconds(value: &str) -> Result<f64, String> {
+    let secs: f64 = value.parse().map_err(|_| format!("'{}' is not a number", value))?;
+    if !secs.is_finite() || secs <= 0.0 {
+        return Err(format!("'{}' must be a positive number of seconds", value));
+    }
+    Ok(secs)
+}
+
+/// Main-area panels that can be zoomed to fill the grid
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Panel {
+    Cpu,
+    Memory,
+    Disk,
+    Network,
 }
 
 /// Application state
@@ -122,65 +466,180 @@ struct App {
     disk_collector: metrics::disk::DiskCollector,
     net_collector: metrics::network::NetworkCollector,
     psi_collector: metrics::psi::PsiCollector,
+    gpu_collector: Option<metrics::gpu::GpuCollector>,
     proc_collector: Option<ProcessCollector>,
+    /// Collect categories on separate threads (off on single-CPU hosts, where it only adds overhead)
+    parallel_collection: bool,
 
     cpu_metrics: Option<CpuMetrics>,
     mem_metrics: Option<MemoryMetrics>,
     disk_metrics: Option<DiskMetrics>,
     net_metrics: Option<NetworkMetrics>,
     psi_metrics: Option<metrics::PsiMetrics>,
+    gpu_metrics: Option<metrics::GpuMetrics>,
+    /// Pressure of the monitored process's cgroup
+    cgroup_psi_metrics: Option<metrics::PsiMetrics>,
     proc_metrics: Option<ProcessMetrics>,
 
     alert_checker: AlertChecker,
     alerts: Vec<alert::Alert>,
+    /// Alerts that fail the run under --fail-on-alert (survives statistics resets)
+    failing_alerts: u64,
+    fail_on_alert: Option<FailOn>,
 
     csv_logger: Option<CsvLogger>,
     text_logger: Option<TextLogger>,
+    per_core_logger: Option<PerCoreCsvLogger>,
+    capture_logger: Option<CaptureLogger>,
+    json_logger: Option<JsonLogger>,
+    influx_logger: Option<InfluxLogger>,
     accumulator: SummaryAccumulator,
+    environment: EnvironmentInfo,
 
-    uptime_secs: u64,
+    uptime: Duration,
     samples_collected: u64,
     show_process: bool,
+    show_alerts: bool,             // Recent alerts beside the bottom row ('a')
     logging_enabled: bool,
+    skip_first: bool,
+    paused: bool,                  // Sampling frozen for reading ('space')
+    paused_at: std::time::Instant, // When the current pause started
+    focused_panel: Option<Panel>,  // Zoomed panel, None shows the grid
+    last_focus: Panel,             // Panel that 'z' zooms
+    panel_areas: Vec<(Panel, Rect)>, // Where panels were last drawn (for mouse clicks)
+    memory_graph: GraphMode,       // Sparkline series per panel ('g' cycles)
+    disk_graph: GraphMode,
+    network_graph: GraphMode,
+    cgroup_primary: bool,
+    palette: Palette,
+    net_bits: bool,  // Network throughput in bits per second ('b' toggles)
+    profile: Option<(String, config::ConfigFile)>,  // Profile name and settings that 'w' saves
 
     // Process discovery settings
     process_name_pattern: Option<String>,
     process_rescan_interval: u64,  // Rescan every N samples
+    process_match: MatchOptions,
+    process_tree: bool,
+    process_threads: usize,  // Busiest threads to show (--threads), 0 when off
     current_monitored_pid: Option<u32>,
 
     // Log rotation settings
     csv_log_base: Option<PathBuf>,
+    csv_format: CsvFormat,
+    csv_preamble: bool,
     text_log_base: Option<PathBuf>,
+    per_core_log_base: Option<PathBuf>,
+    capture_base: Option<PathBuf>,
+    json_log_base: Option<PathBuf>,
+    influx_log_base: Option<PathBuf>,
+    log_options: LogOptions,
+    compress_logs: bool,
+    compression_jobs: Vec<std::thread::JoinHandle<Result<PathBuf>>>,
     log_segment: u32,
+    closed_csv_segments: Vec<PathBuf>,  // Under their final names, for --compact-on-exit
     pending_log_split: bool,  // Confirmation state for log split
     status_message: Option<(String, std::time::Instant)>,  // Temporary status message
     tui_mode: bool,  // Whether running in TUI mode (suppress eprintln)
+    json_output: bool,  // --no-tui --format json: one sample per stdout line
+
+    // Recorded samples played back instead of live collection (--replay)
+    replay: Option<Replay>,
+
+    // Open-files popup ('f'): process label and its fds when opened, scrolled with the arrow keys
+    open_files: Option<(String, Vec<process::OpenFd>)>,
+    open_files_scroll: usize,
+
+    // Top-talkers popup ('t'), redrawn from each new sample while open
+    show_talkers: bool,
+    top_talkers: bool,
+
+    // Command spawned with `monperf -- cmd` and its exit status once reaped
+    child: Option<std::process::Child>,
+    child_status: Option<std::process::ExitStatus>,
+
+    // OOM kill detection for the monitored process
+    oom_watcher: Option<metrics::memory::OomWatcher>,
+    oom_checked_at: std::time::Instant,
+    oom_target: Option<(u32, String)>,  // Last seen PID and name of the monitored process
+    oom_tree: HashSet<u32>,             // --tree descendants seen since the last OOM check
 
     // Auto-split on process state change
     split_on_process: bool,
     prev_process_running: bool,
 
+    // Auto-split by size (bytes) or age of the current segment
+    log_max_bytes: Option<u64>,
+    log_rotate_interval: Option<std::time::Duration>,
+    segment_started: std::time::Instant,
+
     // History for sparkline graphs
     cpu_history: CpuHistory,
     memory_history: MemoryHistory,
     disk_history: DiskHistory,
     network_history: NetworkHistory,
+    history_times: SampleTimes,
+    history_offset: usize,  // Samples scrolled back from the newest (arrow keys), 0 is live
+    history_scale: HistoryScale,  // Samples per sparkline bar ('h' cycles)
 
     // Control socket for external log split commands
     control_socket: Option<UdpSocket>,
+
+    // StatsD push exporter
+    statsd_exporter: Option<StatsdExporter>,
+
+    // Prometheus scrape endpoint (--prometheus-port)
+    prometheus_exporter: Option<PrometheusExporter>,
+    // Latest-sample JSON file (--snapshot)
+    snapshot_writer: Option<SnapshotWriter>,
+
+    // Alert webhook (--alert-webhook)
+    alert_webhook: Option<AlertWebhook>,
+
+    // Adaptive sampling interval (--auto-interval)
+    interval_tuner: Option<IntervalTuner>,
+    last_sample_at: Option<std::time::Instant>,
+
+    // Intended vs actual sample spacing
+    current_interval: Duration,
+    timing_drift: TimingDrift,
 }
 
 impl App {
     fn new(args: &Args) -> Result<Self> {
+        let process_match = MatchOptions {
+            no_filter: args.no_process_filter,
+            choice: args.process_match,
+        };
+
+        // Spawn the command to monitor, if one was given after --
+        let child = if let Some((program, rest)) = args.command.split_first() {
+            let mut command = std::process::Command::new(program);
+            command.args(rest);
+            if !args.no_tui {
+                // Output from the command would corrupt the TUI
+                command
+                    .stdin(std::process::Stdio::null())
+                    .stdout(std::process::Stdio::null())
+                    .stderr(std::process::Stdio::null());
+            }
+            let child = command
+                .spawn()
+                .with_context(|| format!("Failed to run '{}'", program))?;
+            eprintln!("Started '{}' with PID {}", program, child.id());
+            Some(child)
+        } else {
+            None
+        };
+
         // Determine process to monitor
-        let (proc_collector, current_pid, pattern) = if let Some(pid) = args.pid {
+        let (proc_collector, current_pid, pattern) = if let Some(pid) = args.pid.or(child.as_ref().map(|c| c.id())) {
             // Explicit PID - no pattern matching needed
-            (Some(ProcessCollector::new(pid)), Some(pid), None)
+            (Some(new_process_collector(pid, args.tree, args.threads.unwrap_or(0))), Some(pid), None)
         } else if let Some(ref name) = args.process_name {
             // Pattern matching - will be rescanned periodically
-            if let Some(pid) = process::find_process_by_name(name) {
+            if let Some(pid) = find_named_process(name, process_match) {
                 eprintln!("Found process '{}' with PID {}", name, pid);
-                (Some(ProcessCollector::new(pid)), Some(pid), Some(name.clone()))
+                (Some(new_process_collector(pid, args.tree, args.threads.unwrap_or(0))), Some(pid), Some(name.clone()))
             } else {
                 eprintln!("Process '{}' not found yet, will keep searching...", name);
                 (None, None, Some(name.clone()))
@@ -189,22 +648,107 @@ impl App {
             (None, None, None)
         };
 
+        // Watch the kernel log for OOM kills of the monitored process
+        let oom_watcher = if current_pid.is_some() || pattern.is_some() {
+            let watcher = metrics::memory::OomWatcher::new();
+            if !watcher.is_available() {
+                eprintln!("Note: kernel log is not readable (needs root or dmesg access), OOM kills won't be detected");
+            }
+            watcher.is_available().then_some(watcher)
+        } else {
+            None
+        };
+
         // Setup disk collector with spill dir
         let mut disk_collector = metrics::disk::DiskCollector::new();
         if let Some(ref spill_dir) = args.spill_dir {
             disk_collector.set_spill_dir(&spill_dir.to_string_lossy());
         }
+        disk_collector.set_include_partitions(args.disk_include_partitions);
+        for name in &args.disk_device {
+            disk_collector.add_device(name);
+        }
+        for path in &args.watch_fs {
+            if !path.exists() {
+                eprintln!("Warning: --watch-fs path {} does not exist, skipping until it appears", path.display());
+            }
+            disk_collector.add_watched_fs(&path.to_string_lossy());
+        }
+
+        let mut net_collector = metrics::network::NetworkCollector::new();
+        net_collector.set_use_ethtool(args.ethtool);
+        net_collector.set_remote_peers(args.top_talkers);
+
+        let gpu_collector = if args.gpu {
+            let collector = metrics::gpu::GpuCollector::detect();
+            if collector.is_none() {
+                eprintln!("Note: nvidia-smi found no NVIDIA GPU, GPU monitoring disabled");
+            }
+            collector
+        } else {
+            None
+        };
+
+        let log_options = LogOptions {
+            append: args.append,
+            flush_every: args.flush_interval,
+        };
+
+        let replay = match args.replay {
+            Some(ref path) => Some(Replay::load(path, args.replay_speed)?),
+            None => None,
+        };
+
+        // Probe the environment once; the log headers and the system info panel share it.
+        // A replayed capture brings the environment it was recorded in.
+        let environment = replay.as_ref()
+            .and_then(|r| r.environment.clone())
+            .unwrap_or_else(EnvironmentInfo::probe);
 
         // Setup CSV logger (canonical detailed format)
+        let csv_format = args.csv_precision
+            .map(CsvFormat::with_precision)
+            .unwrap_or_default();
         let csv_logger = if let Some(ref log_path) = args.log {
-            Some(CsvLogger::new(log_path)?)
+            Some(CsvLogger::new(log_path, csv_format, args.csv_preamble.then_some(&environment), log_options)?)
         } else {
             None
         };
 
+        let mut accumulator = SummaryAccumulator::new();
+        accumulator.set_alert_window(args.alert_window);
+
         // Setup text logger (human-readable summary)
         let text_logger = if let Some(ref log_path) = args.text_log {
-            Some(TextLogger::new(log_path)?)
+            Some(TextLogger::new(log_path, &environment, log_options)?)
+        } else {
+            None
+        };
+
+        // Setup tidy per-core CPU logger
+        let per_core_logger = if let Some(ref log_path) = args.per_core_log {
+            Some(PerCoreCsvLogger::new(log_path, log_options)?)
+        } else {
+            None
+        };
+
+        // Setup binary capture (lossless, fast to reload)
+        let capture_logger = if let Some(ref capture_path) = args.capture {
+            Some(CaptureLogger::new(capture_path, &environment, log_options)?)
+        } else {
+            None
+        };
+
+        // Setup JSON Lines log
+        let json_logger = if let Some(ref json_path) = args.json_log {
+            Some(JsonLogger::new(json_path, log_options)?)
+        } else {
+            None
+        };
+
+        // Setup InfluxDB line protocol log
+        let influx_logger = if let Some(ref influx_path) = args.influx_file {
+            Some(InfluxLogger::new(influx_path, log_options)?)
         } else {
             None
         };
@@ -217,12 +761,40 @@ impl App {
             memory_crit: args.mem_crit,
             cgroup_warn: args.cgroup_warn,
             cgroup_crit: args.cgroup_crit,
+            fs_used_warn: args.fs_warn,
+            inodes_used_warn: args.inode_warn,
+            process_fd_warn: args.fd_warn,
+            process_fd_crit: args.fd_crit,
+            process_fd_limit_warn: args.fd_limit_warn,
+            process_fd_limit_crit: args.fd_limit_crit,
+            process_threads_warn: args.threads_warn,
+            process_threads_crit: args.threads_crit,
+            process_dstate_warn_secs: args.dstate_warn,
+            process_dstate_crit_secs: args.dstate_crit,
+            psi_mem_warn: args.psi_mem_warn,
+            psi_mem_crit: args.psi_mem_crit,
+            psi_io_warn: args.psi_io_warn,
+            psi_io_crit: args.psi_io_crit,
+            net_error_rate_warn: args.net_error_warn,
+            net_drop_rate_warn: args.net_drop_warn,
+            tcp_retransmit_warn: args.retransmit_warn,
+            leak_horizon_secs: args.leak_detect.then_some(args.leak_horizon),
             ..Default::default()
         };
+        accumulator.set_disk_queue_warn(thresholds.disk_queue_warn);
+        accumulator.set_psi_warn(thresholds.psi_mem_warn, thresholds.psi_io_warn);
+        let mut alert_checker = AlertChecker::new(thresholds);
+        alert_checker.set_sustained_samples(args.alert_sustained);
 
         // Determine initial process running state
         let initial_process_running = proc_collector.is_some();
 
+        // Unreadable I/O counters would silently show zeros all session, so say so upfront
+        let io_warning = current_pid.and_then(process::io_access_warning);
+        if let Some(ref warning) = io_warning {
+            eprintln!("Warning: {}", warning);
+        }
+
         // Setup control socket if port specified
         let control_socket = if let Some(port) = args.control_port {
             match UdpSocket::bind(format!("127.0.0.1:{}", port)) {
@@ -241,47 +813,208 @@ impl App {
             None
         };
 
+        // Setup StatsD exporter if address specified
+        let statsd_exporter = if let Some(ref addr) = args.statsd {
+            match StatsdExporter::new(addr) {
+                Ok(mut exporter) => {
+                    exporter.set_dogstatsd(args.dogstatsd);
+                    eprintln!("Pushing StatsD metrics to {}", addr);
+                    Some(exporter)
+                }
+                Err(e) => {
+                    eprintln!("Warning: Failed to set up StatsD export to {}: {}", addr, e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        // Setup Prometheus endpoint if port specified
+        let prometheus_exporter = if let Some(port) = args.prometheus_port {
+            match PrometheusExporter::new(args.prometheus_bind, port) {
+                Ok(exporter) => {
+                    let addr = std::net::SocketAddr::new(args.prometheus_bind, port);
+                    eprintln!("Serving Prometheus metrics on http://{}/metrics", addr);
+                    Some(exporter)
+                }
+                Err(e) => {
+                    eprintln!("Warning: Failed to set up Prometheus endpoint: {:#}", e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        // Setup the snapshot file if a path was given
+        let snapshot_writer = match args.snapshot {
+            Some(ref path) => Some(SnapshotWriter::new(path)?),
+            None => None,
+        };
+
+        // Setup alert webhook if URL specified
+        let alert_webhook = if let Some(ref url) = args.alert_webhook {
+            match AlertWebhook::new(url) {
+                Ok(webhook) => {
+                    eprintln!("Posting alerts to {}", url);
+                    Some(webhook)
+                }
+                Err(e) => {
+                    eprintln!("Warning: Failed to set up alert webhook: {:#}", e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        let interval_tuner = if args.auto_interval {
+            Some(IntervalTuner::new(
+                Duration::from_secs_f64(args.min_interval),
+                Duration::from_secs_f64(args.max_interval),
+            ))
+        } else {
+            None
+        };
+
         Ok(Self {
             cpu_collector: metrics::cpu::CpuCollector::new(),
             mem_collector: metrics::memory::MemoryCollector::new(),
             disk_collector,
-            net_collector: metrics::network::NetworkCollector::new(),
+            net_collector,
             psi_collector: metrics::psi::PsiCollector::new(),
+            gpu_collector,
+            parallel_collection: std::thread::available_parallelism().is_ok_and(|n| n.get() > 1),
             proc_collector,
             cpu_metrics: None,
             mem_metrics: None,
             disk_metrics: None,
             net_metrics: None,
             psi_metrics: None,
+            gpu_metrics: None,
+            cgroup_psi_metrics: None,
             proc_metrics: None,
-            alert_checker: AlertChecker::new(thresholds),
+            alert_checker,
             alerts: Vec::new(),
+            failing_alerts: 0,
+            fail_on_alert: args.fail_on_alert,
             csv_logger,
             text_logger,
-            accumulator: SummaryAccumulator::new(),
-            uptime_secs: 0,
+            per_core_logger,
+            capture_logger,
+            json_logger,
+            influx_logger,
+            accumulator,
+            environment,
+            uptime: Duration::ZERO,
             samples_collected: 0,
             show_process: true,
+            show_alerts: false,
             logging_enabled: true,
+            skip_first: args.skip_first,
+            paused: false,
+            paused_at: std::time::Instant::now(),
+            focused_panel: None,
+            last_focus: Panel::Cpu,
+            panel_areas: Vec::new(),
+            memory_graph: GraphMode::default(),
+            disk_graph: GraphMode::default(),
+            network_graph: GraphMode::default(),
+            cgroup_primary: args.cgroup_primary,
+            palette: Palette::new(args.theme),
+            net_bits: args.net_bits,
+            profile: args.save_profile.clone().or_else(|| args.profile.clone())
+                .map(|name| (name, config::ConfigFile::from_args(args))),
             process_name_pattern: pattern,
             process_rescan_interval: 10, // Rescan for process every 10 samples
+            process_match,
+            process_tree: args.tree,
+            process_threads: args.threads.unwrap_or(0),
             current_monitored_pid: current_pid,
             csv_log_base: args.log.clone(),
+            csv_format,
+            csv_preamble: args.csv_preamble,
             text_log_base: args.text_log.clone(),
+            per_core_log_base: args.per_core_log.clone(),
+            capture_base: args.capture.clone(),
+            json_log_base: args.json_log.clone(),
+            influx_log_base: args.influx_file.clone(),
+            log_options,
+            compress_logs: args.compress_logs,
+            compression_jobs: Vec::new(),
+            closed_csv_segments: Vec::new(),
             log_segment: 0,
             pending_log_split: false,
-            status_message: None,
+            status_message: io_warning.map(|w| (w, std::time::Instant::now())),
             tui_mode: false,  // Set by run_tui
+            json_output: args.no_tui && args.format == OutputFormat::Json,
+            replay,
+            open_files: None,
+            open_files_scroll: 0,
+            show_talkers: false,
+            top_talkers: args.top_talkers,
+            child,
+            child_status: None,
+            oom_watcher,
+            oom_checked_at: std::time::Instant::now(),
+            oom_target: None,
+            oom_tree: HashSet::new(),
             split_on_process: args.split_on_process,
             prev_process_running: initial_process_running,
-            cpu_history: CpuHistory::default(),
-            memory_history: MemoryHistory::default(),
-            disk_history: DiskHistory::default(),
-            network_history: NetworkHistory::default(),
+            log_max_bytes: args.log_max_size.map(|mb| mb * 1024 * 1024),
+            log_rotate_interval: args.log_rotate_interval.map(std::time::Duration::from_secs),
+            segment_started: std::time::Instant::now(),
+            cpu_history: CpuHistory::new(args.history as usize),
+            memory_history: MemoryHistory::new(args.history as usize),
+            disk_history: DiskHistory::new(args.history as usize),
+            network_history: NetworkHistory::new(args.history as usize),
+            history_times: SampleTimes::new(args.history as usize),
+            history_offset: 0,
+            history_scale: HistoryScale::default(),
             control_socket,
+            statsd_exporter,
+            prometheus_exporter,
+            snapshot_writer,
+            alert_webhook,
+            interval_tuner,
+            last_sample_at: None,
+            current_interval: Duration::from_secs_f64(args.interval),
+            timing_drift: TimingDrift::new(),
         })
     }
 
+    /// With --append, compare this run's CSV columns with the existing header before
+    /// sampling starts. The device lists fill in once the collectors have read twice,
+    /// which also leaves the first logged sample with real rates (so --skip-first keeps it).
+    fn check_csv_append(&mut self) -> Result<()> {
+        if self.replay.is_some() || !self.csv_logger.as_ref().is_some_and(CsvLogger::is_appending) {
+            return Ok(());
+        }
+        self.collect_live()?;
+        std::thread::sleep(Duration::from_millis(100));
+        self.collect_live()?;
+        // The collectors are primed, so --skip-first has no zero-rate sample left to drop
+        self.skip_first = false;
+
+        let sample = MetricsSample {
+            timestamp: Utc::now(),
+            interval_secs: 0.0,
+            cpu: self.cpu_metrics.clone().unwrap_or_default(),
+            memory: self.mem_metrics.clone().unwrap_or_default(),
+            disk: self.disk_metrics.clone().unwrap_or_default(),
+            network: self.net_metrics.clone().unwrap_or_default(),
+            process: self.proc_metrics.clone(),
+            psi: self.psi_metrics.clone(),
+            cgroup_psi: self.cgroup_psi_metrics.clone(),
+            gpu: self.gpu_metrics.clone(),
+        };
+        if let Some(ref mut csv_logger) = self.csv_logger {
+            csv_logger.check_existing_header(&sample)?;
+        }
+        Ok(())
+    }
+
     /// Rescan for matching process if using pattern matching
     fn refresh_process_collector(&mut self) {
         // Only rescan if we have a pattern (not explicit PID)
@@ -302,7 +1035,7 @@ impl App {
         }
 
         // Try to find a new matching process
-        if let Some(pid) = process::find_process_by_name(&pattern) {
+        if let Some(pid) = process::find_process_by_name(&pattern, self.process_match) {
             // Found a (potentially new) process
             if self.current_monitored_pid != Some(pid) {
                 let msg = format!("Found process '{}' with PID {}", pattern, pid);
@@ -311,8 +1044,15 @@ impl App {
                 } else {
                     eprintln!("{}", msg);
                 }
-                self.proc_collector = Some(ProcessCollector::new(pid));
+                self.proc_collector = Some(new_process_collector(pid, self.process_tree, self.process_threads));
                 self.current_monitored_pid = Some(pid);
+                if let Some(warning) = process::io_access_warning(pid) {
+                    if self.tui_mode {
+                        self.set_status(&warning);
+                    } else {
+                        eprintln!("Warning: {}", warning);
+                    }
+                }
             }
         } else if self.current_monitored_pid.is_some() {
             // Process disappeared
@@ -329,7 +1069,8 @@ impl App {
         }
     }
 
-    fn collect_metrics(&mut self) -> Result<()> {
+    /// Read every collector and the monitored process
+    fn collect_live(&mut self) -> Result<()> {
         // Periodically rescan for matching process (every N samples)
         if self.process_name_pattern.is_some() 
             && (self.samples_collected == 0 
@@ -339,13 +1080,86 @@ impl App {
             self.refresh_process_collector();
         }
 
-        self.cpu_metrics = Some(self.cpu_collector.collect()?);
-        self.mem_metrics = Some(self.mem_collector.collect()?);
-        self.disk_metrics = Some(self.disk_collector.collect()?);
-        self.net_metrics = Some(self.net_collector.collect()?);
-        self.psi_metrics = self.psi_collector.collect().ok();
+        // A finished command stays a zombie until reaped, so reap it to let it read as ended
+        if let (Some(child), None) = (self.child.as_mut(), self.child_status) {
+            self.child_status = child.try_wait()?;
+        }
 
-        // Update history for sparklines
+        // Network (sysfs per interface) and GPU (nvidia-smi) take milliseconds, so they run on
+        // their own threads while this one reads the rest; CPU, memory, disk and PSI together
+        // finish well before the network thread does, so threads of their own would not
+        // shorten a sample
+        let parallel = self.parallel_collection;
+        let gpu_parallel = parallel && self.gpu_collector.is_some();
+        let (cpu, mem, disk, net, psi, gpu) = std::thread::scope(|s| {
+            let net = Collected::start(s, parallel, || self.net_collector.collect());
+            let gpu = Collected::start(s, gpu_parallel, || self.gpu_collector.as_mut().and_then(|gpu| gpu.collect().ok()));
+            let cpu = self.cpu_collector.collect();
+            let mem = self.mem_collector.collect();
+            let disk = self.disk_collector.collect();
+            let psi = self.psi_collector.collect().ok();
+
+            if let Some(ref mut proc) = self.proc_collector {
+                // Pick up children forked since the last walk of the process tree
+                if self.samples_collected > 0 && self.samples_collected.is_multiple_of(self.process_rescan_interval) {
+                    proc.refresh_descendants();
+                }
+                if proc.exists() {
+                    self.proc_metrics = proc.collect().ok();
+                } else {
+                    // Process ended, trigger rescan on next sample
+                    self.proc_metrics = None;
+                    self.proc_collector = None;
+                    self.current_monitored_pid = None;
+                }
+            }
+
+            (cpu, mem, disk, net.join(), psi, gpu.join())
+        });
+        self.cpu_metrics = Some(cpu?);
+        self.mem_metrics = Some(mem?);
+        self.disk_metrics = Some(disk?);
+        self.net_metrics = Some(net?);
+        self.psi_metrics = psi;
+        self.gpu_metrics = gpu;
+        self.cgroup_psi_metrics = self.proc_metrics
+            .as_ref()
+            .and_then(|p| metrics::psi::read_process_cgroup_psi(p.pid));
+        Ok(())
+    }
+
+    /// Show the next recorded sample and return its timestamp and spacing (None once the replay is over)
+    fn play_next_sample(&mut self) -> Option<(chrono::DateTime<Utc>, f64)> {
+        let replay = self.replay.as_mut()?;
+        let sample = replay.advance()?.clone();
+        let finished = replay.is_finished().then(|| replay.played().len());
+        let recorded = (sample.timestamp, sample.interval_secs);
+        self.show_sample(sample);
+        if let Some(count) = finished {
+            let msg = format!("Replay finished after {} samples", count);
+            if self.tui_mode {
+                self.set_status(&msg);
+            } else {
+                eprintln!("{}", msg);
+            }
+        }
+        Some(recorded)
+    }
+
+    /// Make a recorded sample the one the panels display
+    fn show_sample(&mut self, sample: MetricsSample) {
+        self.cpu_metrics = Some(sample.cpu);
+        self.mem_metrics = Some(sample.memory);
+        self.disk_metrics = Some(sample.disk);
+        self.net_metrics = Some(sample.network);
+        self.psi_metrics = sample.psi;
+        self.proc_metrics = sample.process;
+        self.cgroup_psi_metrics = sample.cgroup_psi;
+        self.gpu_metrics = sample.gpu;
+    }
+
+    /// Append the displayed metrics to the sparkline histories
+    fn push_history(&mut self, at: chrono::DateTime<chrono::Local>) {
         if let Some(ref cpu) = self.cpu_metrics {
             self.cpu_history.push(cpu.total_utilization);
         }
@@ -358,20 +1172,90 @@ impl App {
         if let Some(ref net) = self.net_metrics {
             self.network_history.push(net.total_rx_bytes_per_sec, net.total_tx_bytes_per_sec);
         }
+        self.history_times.push(at);
+    }
 
-        if let Some(ref mut proc) = self.proc_collector {
-            if proc.exists() {
-                self.proc_metrics = proc.collect().ok();
-            } else {
-                // Process ended, trigger rescan on next sample
-                self.proc_metrics = None;
-                self.proc_collector = None;
-                self.current_monitored_pid = None;
+    /// Jump back or forward in the replay and rebuild the graphs up to the new position
+    fn seek_replay(&mut self, back: bool) {
+        const STEP: isize = 10;
+        let Some(ref mut replay) = self.replay else {
+            return;
+        };
+        replay.seek(if back { -STEP } else { STEP });
+        let played = replay.played();
+        let recent = played[played.len().saturating_sub(self.history_times.max_samples)..].to_vec();
+        let label = replay.label();
+
+        self.cpu_history.clear();
+        self.memory_history.clear();
+        self.disk_history.clear();
+        self.network_history.clear();
+        self.history_times.clear();
+        self.history_offset = 0;
+        for sample in recent {
+            let at = sample.timestamp.with_timezone(&chrono::Local);
+            self.show_sample(sample);
+            self.push_history(at);
+        }
+        self.set_status(&format!("Replay at {}", label));
+    }
+
+    /// Critical alerts for OOM kills of the monitored process (or, with --tree, its
+    /// descendants) logged since the last check
+    ///
+    /// The kernel log is read every few seconds, and right away when the process
+    /// disappears so a kill is reported before the run ends.
+    fn check_oom_kills(&mut self, process_vanished: bool) -> Vec<alert::Alert> {
+        let Some(ref mut watcher) = self.oom_watcher else {
+            return Vec::new();
+        };
+        if let Some(ref proc) = self.proc_metrics {
+            self.oom_target = Some((proc.pid, proc.name.clone()));
+        }
+        // Workers forked under --tree are often the ones killed; remember every descendant
+        // seen until the next check, since a killed one drops out of the tree
+        if let Some(ref collector) = self.proc_collector {
+            self.oom_tree.extend(collector.descendants());
+        }
+        if !process_vanished && self.oom_checked_at.elapsed() < Duration::from_secs(5) {
+            return Vec::new();
+        }
+        self.oom_checked_at = std::time::Instant::now();
+
+        let kills = watcher.poll();
+        let tree = std::mem::take(&mut self.oom_tree);
+        let Some((pid, ref name)) = self.oom_target else {
+            return Vec::new();
+        };
+        kills
+            .into_iter()
+            .filter(|kill| kill.pid == pid || kill.name == *name || tree.contains(&kill.pid))
+            .map(|kill| alert::Alert::oom_kill(kill.pid, &kill.name))
+            .collect()
+    }
+
+    fn collect_metrics(&mut self) -> Result<()> {
+        // Recorded timestamp and spacing when replaying, None when sampling live
+        let recorded = if self.replay.is_some() {
+            match self.play_next_sample() {
+                Some(recorded) => Some(recorded),
+                None => return Ok(()),  // Every sample was played
             }
+        } else {
+            self.collect_live()?;
+            None
+        };
+
+        // Update history for sparklines
+        let at = recorded.map_or_else(chrono::Local::now, |(t, _)| t.with_timezone(&chrono::Local));
+        self.push_history(at);
+        if self.history_offset > 0 {
+            // Keep a scrolled-back view on the same samples while new ones arrive
+            self.history_offset = (self.history_offset + 1).min(self.max_history_offset());
         }
 
         // Check for process state change and auto-split logs if enabled
-        let current_process_running = self.proc_collector.is_some() && self.proc_metrics.is_some();
+        let current_process_running = (self.proc_collector.is_some() || self.replay.is_some()) && self.proc_metrics.is_some();
         if self.split_on_process && self.samples_collected > 0 {
             if current_process_running != self.prev_process_running {
                 // Process state changed - split logs
@@ -382,8 +1266,8 @@ impl App {
                 };
                 
                 // Only split if logging is configured
-                if self.csv_log_base.is_some() || self.text_log_base.is_some() {
-                    if let Err(e) = self.rotate_logs() {
+                if self.has_file_logs() {
+                    if let Err(e) = self.split_logs() {
                         let msg = format!("Auto-split failed on {}: {}", event, e);
                         if self.tui_mode {
                             self.set_status(&msg);
@@ -401,6 +1285,7 @@ impl App {
                 }
             }
         }
+        let oom_alerts = self.check_oom_kills(self.prev_process_running && !current_process_running);
         self.prev_process_running = current_process_running;
 
         self.samples_collected += 1;
@@ -412,10 +1297,24 @@ impl App {
             &self.disk_metrics,
             &self.net_metrics,
         ) {
-            let new_alerts = self
+            let mut new_alerts = self
                 .alert_checker
-                .check(cpu, mem, disk, net, self.proc_metrics.as_ref());
-
+                .check(cpu, mem, disk, net, self.psi_metrics.as_ref(), self.proc_metrics.as_ref());
+            new_alerts.extend(oom_alerts);
+
+            self.accumulator.add_alerts(&new_alerts);
+            let mut export_errors = Vec::new();
+            if let Some(ref webhook) = self.alert_webhook {
+                export_errors = webhook.take_errors();
+                for alert in &new_alerts {
+                    if !webhook.send(alert) {
+                        export_errors.push(format!("Alert webhook queue full, dropped: {}", alert.message));
+                    }
+                }
+            }
+            if let Some(fail_on) = self.fail_on_alert {
+                self.failing_alerts += new_alerts.iter().filter(|a| fail_on.matches(a.severity)).count() as u64;
+            }
             for alert in new_alerts {
                 self.alerts.push(alert);
             }
@@ -425,18 +1324,46 @@ impl App {
                 self.alerts.drain(0..self.alerts.len() - 20);
             }
 
-            // Log and accumulate
+            // Log and accumulate (a replayed sample keeps its recorded time and spacing)
+            let (timestamp, interval_secs) = match recorded {
+                Some(recorded) => recorded,
+                None => {
+                    let now = std::time::Instant::now();
+                    let interval_secs = self.last_sample_at
+                        .map(|prev| now.duration_since(prev).as_secs_f64())
+                        .unwrap_or(0.0);
+                    if self.last_sample_at.is_some() {
+                        self.timing_drift.record(self.current_interval, interval_secs);
+                    }
+                    self.last_sample_at = Some(now);
+                    (Utc::now(), interval_secs)
+                }
+            };
+
             let sample = MetricsSample {
-                timestamp: Utc::now(),
+                timestamp,
+                interval_secs,
                 cpu: cpu.clone(),
                 memory: mem.clone(),
                 disk: disk.clone(),
                 network: net.clone(),
                 process: self.proc_metrics.clone(),
                 psi: self.psi_metrics.clone(),
+                cgroup_psi: self.cgroup_psi_metrics.clone(),
+                gpu: self.gpu_metrics.clone(),
             };
 
-            if self.logging_enabled {
+            // Rates need two reads, so the first sample only primes the collectors
+            let priming = self.skip_first && self.samples_collected == 1;
+
+            if self.json_output && !priming {
+                let line = serde_json::to_string(&sample).context("Failed to encode sample")?;
+                if let Err(e) = writeln!(io::stdout(), "{}", line) {
+                    eprintln!("Stdout write error: {}", e);
+                }
+            }
+
+            if self.logging_enabled && !priming {
                 if let Some(ref mut csv_logger) = self.csv_logger {
                     if let Err(e) = csv_logger.log(&sample) {
                         if self.tui_mode {
@@ -455,59 +1382,601 @@ impl App {
                         }
                     }
                 }
+                if let Some(ref mut per_core_logger) = self.per_core_logger {
+                    if let Err(e) = per_core_logger.log(&sample) {
+                        if self.tui_mode {
+                            self.set_status(&format!("Per-core log error: {}", e));
+                        } else {
+                            eprintln!("Per-core log error: {}", e);
+                        }
+                    }
+                }
+                if let Some(ref mut capture_logger) = self.capture_logger {
+                    if let Err(e) = capture_logger.log(&sample) {
+                        if self.tui_mode {
+                            self.set_status(&format!("Capture error: {}", e));
+                        } else {
+                            eprintln!("Capture error: {}", e);
+                        }
+                    }
+                }
+                if let Some(ref mut json_logger) = self.json_logger {
+                    if let Err(e) = json_logger.log(&sample) {
+                        if self.tui_mode {
+                            self.set_status(&format!("JSON log error: {}", e));
+                        } else {
+                            eprintln!("JSON log error: {}", e);
+                        }
+                    }
+                }
+                if let Some(ref mut influx_logger) = self.influx_logger {
+                    if let Err(e) = influx_logger.log(&sample) {
+                        if self.tui_mode {
+                            self.set_status(&format!("Influx log error: {}", e));
+                        } else {
+                            eprintln!("Influx log error: {}", e);
+                        }
+                    }
+                }
+                self.check_log_limits();
+            }
+
+            if !priming {
+                if let Some(ref exporter) = self.statsd_exporter {
+                    exporter.send(&sample);
+                }
+                if let Some(ref exporter) = self.prometheus_exporter {
+                    exporter.update(&sample);
+                    export_errors.extend(exporter.take_errors());
+                }
+                if let Some(ref writer) = self.snapshot_writer {
+                    if let Err(e) = writer.write(&sample) {
+                        if self.tui_mode {
+                            self.set_status(&format!("Snapshot error: {:#}", e));
+                        } else {
+                            eprintln!("Snapshot error: {:#}", e);
+                        }
+                    }
+                }
+                self.accumulator.add_sample(sample);
+            }
+
+            for error in export_errors {
+                if self.tui_mode {
+                    self.set_status(&error);
+                } else {
+                    eprintln!("{}", error);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Whether any file log is configured, so there is something to split or rename
+    fn has_file_logs(&self) -> bool {
+        self.csv_log_base.is_some()
+            || self.text_log_base.is_some()
+            || self.per_core_log_base.is_some()
+            || self.capture_base.is_some()
+            || self.json_log_base.is_some()
+            || self.influx_log_base.is_some()
+    }
+
+    /// Split the logs to start a new run: rotate to a new segment and restart the summary
+    /// (automatic --log-max-size/--log-rotate-interval rotation keeps the whole run's summary)
+    fn split_logs(&mut self) -> Result<()> {
+        self.rotate_logs()?;
+        self.accumulator.clear();
+        Ok(())
+    }
+
+    /// Rotate log files to start a new segment
+    fn rotate_logs(&mut self) -> Result<()> {
+        self.reap_compression_jobs();
+        let closed_segment = self.log_segment;
+        self.log_segment += 1;
+        let segment = self.log_segment;
+        let mut closed = Vec::new();
+        
+        // Rotate CSV log (canonical format)
+        if let Some(ref base_path) = self.csv_log_base {
+            let new_path = Self::segment_path(base_path, segment);
+            self.csv_logger = Some(CsvLogger::new(&new_path, self.csv_format, self.csv_preamble.then_some(&self.environment), self.log_options)?);
+            // A segment renamed via the control port is already recorded under its new name
+            let closed_path = Self::active_segment_path(base_path, closed_segment);
+            if closed_path.exists() {
+                self.closed_csv_segments.push(closed_path.clone());
+            }
+            closed.push(closed_path);
+            if !self.tui_mode {
+                eprintln!("Started new CSV log: {}", new_path.display());
+            }
+        }
+        
+        // Rotate text log (human-readable summary)
+        if let Some(ref base_path) = self.text_log_base {
+            let new_path = Self::segment_path(base_path, segment);
+            self.text_logger = Some(TextLogger::new(&new_path, &self.environment, self.log_options)?);
+            closed.push(Self::active_segment_path(base_path, closed_segment));
+            if !self.tui_mode {
+                eprintln!("Started new text log: {}", new_path.display());
+            }
+        }
+        
+        // Rotate per-core CPU log
+        if let Some(ref base_path) = self.per_core_log_base {
+            let new_path = Self::segment_path(base_path, segment);
+            self.per_core_logger = Some(PerCoreCsvLogger::new(&new_path, self.log_options)?);
+            closed.push(Self::active_segment_path(base_path, closed_segment));
+            if !self.tui_mode {
+                eprintln!("Started new per-core log: {}", new_path.display());
+            }
+        }
+        
+        // Rotate binary capture
+        if let Some(ref base_path) = self.capture_base {
+            let new_path = Self::segment_path(base_path, segment);
+            self.capture_logger = Some(CaptureLogger::new(&new_path, &self.environment, self.log_options)?);
+            closed.push(Self::active_segment_path(base_path, closed_segment));
+            if !self.tui_mode {
+                eprintln!("Started new capture: {}", new_path.display());
+            }
+        }
+
+        // Rotate JSON Lines log
+        if let Some(ref base_path) = self.json_log_base {
+            let new_path = Self::segment_path(base_path, segment);
+            self.json_logger = Some(JsonLogger::new(&new_path, self.log_options)?);
+            closed.push(Self::active_segment_path(base_path, closed_segment));
+            if !self.tui_mode {
+                eprintln!("Started new JSON log: {}", new_path.display());
+            }
+        }
+
+        // Rotate InfluxDB line protocol log
+        if let Some(ref base_path) = self.influx_log_base {
+            let new_path = Self::segment_path(base_path, segment);
+            self.influx_logger = Some(InfluxLogger::new(&new_path, self.log_options)?);
+            closed.push(Self::active_segment_path(base_path, closed_segment));
+            if !self.tui_mode {
+                eprintln!("Started new Influx log: {}", new_path.display());
+            }
+        }
+        
+        // Compress the segments just closed (a segment renamed via the control port was
+        // already moved away and is compressed under its new name)
+        if self.compress_logs {
+            for path in closed.into_iter().filter(|p| p.exists()) {
+                self.compress_segment(path);
+            }
+        }
+
+        self.segment_started = std::time::Instant::now();
+        
+        Ok(())
+    }
+
+    /// Size of the largest log file in the current segment, including buffered output
+    /// (None when no file log is written)
+    fn largest_log_bytes(&self) -> Option<u64> {
+        [
+            self.csv_logger.as_ref().map(CsvLogger::bytes_written),
+            self.text_logger.as_ref().map(TextLogger::bytes_written),
+            self.per_core_logger.as_ref().map(PerCoreCsvLogger::bytes_written),
+            self.capture_logger.as_ref().map(CaptureLogger::bytes_written),
+            self.json_logger.as_ref().map(JsonLogger::bytes_written),
+            self.influx_logger.as_ref().map(InfluxLogger::bytes_written),
+        ]
+        .into_iter()
+        .flatten()
+        .max()
+    }
+
+    /// Start a new segment once a log file reaches --log-max-size or the segment
+    /// is --log-rotate-interval old
+    fn check_log_limits(&mut self) {
+        let Some(largest) = self.largest_log_bytes() else {
+            return;
+        };
+        let reason = match (self.log_max_bytes, self.log_rotate_interval) {
+            (Some(max), _) if largest >= max => format!("reaching {}", format_bytes(max)),
+            (_, Some(interval)) if self.segment_started.elapsed() >= interval => {
+                format!("{}s", interval.as_secs())
+            }
+            _ => return,
+        };
+
+        let msg = match self.rotate_logs() {
+            Ok(()) => format!("Logs split after {} → segment {}", reason, self.log_segment),
+            Err(e) => format!("Auto-split failed after {}: {}", reason, e),
+        };
+        if self.tui_mode {
+            self.set_status(&msg);
+        } else {
+            eprintln!("{}", msg);
+        }
+    }
+
+    /// Concatenate all CSV segments of this run into a single file
+    fn compact_csv_logs(&mut self, delete_segments: bool) -> Result<()> {
+        let base = match self.csv_log_base.clone() {
+            Some(base) => base,
+            None => return Ok(()),
+        };
+        if self.closed_csv_segments.is_empty() {
+            return Ok(()); // Never rotated, the log is already a single file
+        }
+
+        // Close the active logger so its buffered rows are on disk
+        self.csv_logger = None;
+
+        // Closed segments may have been replaced by their --compress-logs .gz copy
+        let segments: Vec<PathBuf> = self.closed_csv_segments.iter()
+            .cloned()
+            .chain(std::iter::once(Self::active_segment_path(&base, self.log_segment)))
+            .map(|p| if p.exists() { p } else { logging::gzip_path(&p) })
+            .filter(|p| p.exists())
+            .collect();
+
+        // Never overwrite an existing file, such as a log the user named <log>_all.csv
+        let stem = base.file_stem().and_then(|s| s.to_str()).unwrap_or("log");
+        let mut output = base.with_file_name(format!("{}_all.csv", stem));
+        let mut n = 1;
+        while output.exists() {
+            output = base.with_file_name(format!("{}_all_{}.csv", stem, n));
+            n += 1;
+        }
+        let result = logging::compact_csv_segments(&segments, &output)?;
+
+        eprintln!(
+            "Compacted {} CSV segments ({} rows) into: {}",
+            result.merged.len(),
+            result.rows,
+            output.display()
+        );
+        for (path, reason) in &result.skipped {
+            eprintln!("  Skipped {}: {}", path.display(), reason);
+        }
+
+        if delete_segments {
+            for path in &result.merged {
+                if let Err(e) = std::fs::remove_file(path) {
+                    eprintln!("  Failed to delete {}: {}", path.display(), e);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Path of a log segment, where segment 0 is the base path itself
+    fn active_segment_path(base: &PathBuf, segment: u32) -> PathBuf {
+        if segment == 0 {
+            base.clone()
+        } else {
+            Self::segment_path(base, segment)
+        }
+    }
+
+    /// Gzip a closed log segment on a background thread
+    fn compress_segment(&mut self, path: PathBuf) {
+        self.compression_jobs.push(std::thread::spawn(move || logging::gzip_file(&path)));
+    }
+
+    /// Report compression jobs that have finished since the last check
+    fn reap_compression_jobs(&mut self) {
+        let (finished, running) = std::mem::take(&mut self.compression_jobs)
+            .into_iter()
+            .partition(|job| job.is_finished());
+        self.compression_jobs = running;
+        for job in finished {
+            self.report_compression(job);
+        }
+    }
+
+    /// Write out everything the loggers still buffer (called before exit)
+    fn flush_logs(&mut self) -> Result<()> {
+        if let Some(ref mut logger) = self.csv_logger {
+            logger.flush()?;
+        }
+        if let Some(ref mut logger) = self.text_logger {
+            logger.flush()?;
+        }
+        if let Some(ref mut logger) = self.per_core_logger {
+            logger.flush()?;
+        }
+        if let Some(ref mut logger) = self.capture_logger {
+            logger.flush()?;
+        }
+        if let Some(ref mut logger) = self.json_logger {
+            logger.flush()?;
+        }
+        if let Some(ref mut logger) = self.influx_logger {
+            logger.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Whether the command given after -- has exited
+    fn command_finished(&self) -> bool {
+        self.child_status.is_some()
+    }
+
+    /// Stop the command if monitoring ended first and return its exit code
+    /// (128 + signal number when it was killed by a signal, as shells report it)
+    fn finish_command(&mut self) -> Option<i32> {
+        let child = self.child.as_mut()?;
+        if self.child_status.is_none() {
+            // Quit or duration limit before the command finished: ask it to stop, then force it
+            eprintln!("Stopping command (PID {})", child.id());
+            unsafe {
+                libc::kill(child.id() as libc::pid_t, libc::SIGTERM);
+            }
+            let deadline = std::time::Instant::now() + Duration::from_secs(2);
+            while self.child_status.is_none() && std::time::Instant::now() < deadline {
+                self.child_status = child.try_wait().ok().flatten();
+                std::thread::sleep(Duration::from_millis(50));
+            }
+            if self.child_status.is_none() {
+                let _ = child.kill();
+                self.child_status = child.wait().ok();
+            }
+        }
+        let status = self.child_status?;
+        status.code().or_else(|| status.signal().map(|sig| 128 + sig))
+    }
+
+    /// Wait for all background compression to complete (called before exit)
+    fn finish_compression(&mut self) {
+        for job in std::mem::take(&mut self.compression_jobs) {
+            self.report_compression(job);
+        }
+    }
+
+    fn report_compression(&mut self, job: std::thread::JoinHandle<Result<PathBuf>>) {
+        let result = job
+            .join()
+            .unwrap_or_else(|_| Err(anyhow::anyhow!("compression thread panicked")));
+        match result {
+            Ok(path) => {
+                if !self.tui_mode {
+                    eprintln!("Compressed log segment: {}", path.display());
+                }
+            }
+            Err(e) => {
+                if self.tui_mode {
+                    self.set_status(&format!("Log compression failed: {}", e));
+                } else {
+                    eprintln!("Warning: Log compression failed: {:#}", e);
+                }
+            }
+        }
+    }
+
+    /// Generate a numbered segment path from base path (`metrics.csv` -> `metrics_2.csv`)
+    fn segment_path(base: &PathBuf, segment: u32) -> PathBuf {
+        let stem = base.file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("log");
+        let ext = base.extension()
+            .and_then(|s| s.to_str())
+            .unwrap_or("");
+        
+        let new_name = if ext.is_empty() {
+            format!("{}_{}", stem, segment)
+        } else {
+            format!("{}_{}.{}", stem, segment, ext)
+        };
+        
+        base.with_file_name(new_name)
+    }
+
+    /// Render one of the main-area panels
+    fn draw_panel(&self, f: &mut Frame, area: Rect, panel: Panel) {
+        let view = HistoryView {
+            offset: self.history_offset,
+            times: &self.history_times.times,
+            scale: self.history_scale,
+        };
+        match panel {
+            Panel::Cpu => {
+                if let Some(ref cpu) = self.cpu_metrics {
+                    display::render_cpu(f, area, cpu, Some(&self.cpu_history), view, &self.palette);
+                }
+            }
+            Panel::Memory => {
+                if let Some(ref mem) = self.mem_metrics {
+                    display::render_memory(f, area, mem, Some(&self.memory_history), self.cgroup_primary, self.memory_graph, view, &self.palette);
+                }
+            }
+            Panel::Disk => {
+                if let Some(ref disk) = self.disk_metrics {
+                    display::render_disk(f, area, disk, Some(&self.disk_history), self.disk_graph, view, &self.palette);
+                }
+            }
+            Panel::Network => {
+                if let Some(ref net) = self.net_metrics {
+                    display::render_network(f, area, net, Some(&self.network_history), self.network_graph, view, &self.palette, self.net_bits);
+                }
+            }
+        }
+    }
+
+    /// Freeze or resume sampling; the display keeps showing the last frame while paused
+    fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+        if self.paused {
+            self.paused_at = std::time::Instant::now();
+        } else if let Some(ref mut last) = self.last_sample_at {
+            // The pause is not a late sample: keep it out of the timing drift
+            *last += self.paused_at.elapsed();
+        }
+    }
+
+    /// Format a network rate in the selected unit (bytes or bits per second)
+    fn format_net_rate(&self, bytes_per_sec: f64) -> String {
+        if self.net_bits {
+            format_throughput_bits(bytes_per_sec)
+        } else {
+            format_throughput(bytes_per_sec)
+        }
+    }
+
+    /// Scroll the sparklines back (older) or forward (newer) through the history
+    fn scroll_history(&mut self, back: bool) {
+        const STEP: usize = 10;
+        if self.history_scale != HistoryScale::Raw {
+            self.set_status("Scrolling needs one sample per bar (h cycles the time scale)");
+            return;
+        }
+        self.history_offset = if back {
+            (self.history_offset + STEP).min(self.max_history_offset())
+        } else {
+            self.history_offset.saturating_sub(STEP)
+        };
+        if self.history_offset == 0 {
+            self.set_status("Graphs following live data");
+        }
+    }
+
+    /// Cycle the graphs between one sample per bar, several per bar and the whole history
+    fn cycle_history_scale(&mut self) {
+        self.history_scale = self.history_scale.next();
+        self.history_offset = 0;
+        self.set_status(match self.history_scale {
+            HistoryScale::Raw => "Graphs: one sample per bar",
+            HistoryScale::Coarse => "Graphs: 5 samples per bar",
+            HistoryScale::Full => "Graphs: whole history",
+        });
+    }
+
+    /// Furthest scroll-back that still fills the narrowest sparkline on screen
+    fn max_history_offset(&self) -> usize {
+        let graph_width = self.panel_areas.iter()
+            .map(|(_, area)| area.width.saturating_sub(4) as usize)
+            .min()
+            .unwrap_or(0);
+        self.history_times.times.len().saturating_sub(graph_width)
+    }
+
+    /// Zoom a panel, or return to the grid if it is already zoomed
+    fn toggle_focus(&mut self, panel: Panel) {
+        self.last_focus = panel;
+        self.focused_panel = if self.focused_panel == Some(panel) {
+            None
+        } else {
+            Some(panel)
+        };
+    }
+
+    /// Zoom (or return from) the panel whose title bar was clicked
+    fn handle_click(&mut self, column: u16, row: u16) {
+        let clicked = self.panel_areas.iter()
+            .find(|(_, area)| row == area.y && column >= area.x && column < area.x + area.width)
+            .map(|(panel, _)| *panel);
+        if let Some(panel) = clicked {
+            self.toggle_focus(panel);
+        }
+    }
+
+    /// Cycle the sparkline series of the zoomed panel (or the last zoomed one)
+    fn cycle_graph(&mut self) {
+        let panel = self.focused_panel.unwrap_or(self.last_focus);
+        let message = match panel {
+            Panel::Cpu => "CPU graph has a single series (zoom another panel with 2-4)".to_string(),
+            Panel::Memory => {
+                if self.memory_history.cgroup_percent.iter().any(|&v| v > 0) {
+                    self.memory_graph = self.memory_graph.next(false);
+                    format!("Memory graph: {}", graph_name(self.memory_graph, "RAM", "CGroup"))
+                } else {
+                    "Memory graph: RAM (no cgroup detected)".to_string()
+                }
             }
+            Panel::Disk => {
+                self.disk_graph = self.disk_graph.next(true);
+                format!("Disk graph: {}", graph_name(self.disk_graph, "Read", "Write"))
+            }
+            Panel::Network => {
+                self.network_graph = self.network_graph.next(true);
+                format!("Network graph: {}", graph_name(self.network_graph, "RX", "TX"))
+            }
+        };
+        self.set_status(&message);
+    }
 
-            self.accumulator.add_sample(sample);
+    /// Interval to wait before the next sample (adapted when --auto-interval is set)
+    fn next_interval(&mut self, current: Duration) -> Duration {
+        // Replays wait out the recorded gap to the next sample
+        if let Some(ref replay) = self.replay {
+            self.current_interval = replay.delay();
+            return self.current_interval;
         }
+        let tuner = match self.interval_tuner {
+            Some(ref mut tuner) => tuner,
+            None => {
+                self.current_interval = current;
+                return current;
+            }
+        };
 
-        Ok(())
-    }
+        let next = match (&self.cpu_metrics, &self.mem_metrics, &self.disk_metrics, &self.net_metrics) {
+            (Some(cpu), Some(mem), Some(disk), Some(net)) => tuner.observe(current, cpu, mem, disk, net),
+            _ => tuner.clamp(current),
+        };
 
-    /// Rotate log files to start a new segment
-    fn rotate_logs(&mut self) -> Result<()> {
-        self.log_segment += 1;
-        let segment = self.log_segment;
-        
-        // Rotate CSV log (canonical format)
-        if let Some(ref base_path) = self.csv_log_base {
-            let new_path = Self::segment_path(base_path, segment);
-            self.csv_logger = Some(CsvLogger::new(&new_path)?);
-            if !self.tui_mode {
-                eprintln!("Started new CSV log: {}", new_path.display());
+        if next != current {
+            let msg = format!("Sampling interval adjusted to {:.2}s", next.as_secs_f64());
+            if self.tui_mode {
+                self.set_status(&msg);
+            } else {
+                eprintln!("{}", msg);
             }
         }
-        
-        // Rotate text log (human-readable summary)
-        if let Some(ref base_path) = self.text_log_base {
-            let new_path = Self::segment_path(base_path, segment);
-            self.text_logger = Some(TextLogger::new(&new_path)?);
-            if !self.tui_mode {
-                eprintln!("Started new text log: {}", new_path.display());
+        self.current_interval = next;
+        next
+    }
+
+    /// Save the settings, with the network unit as currently toggled, to the active profile
+    fn save_profile(&mut self) {
+        let Some((name, settings)) = &mut self.profile else {
+            self.set_status("No profile to save to (start with --profile or --save-profile)");
+            return;
+        };
+        settings.set_net_bits(self.net_bits);
+        let msg = match config::profile_path(name).and_then(|path| settings.save(&path).map(|()| path)) {
+            Ok(path) => format!("Saved profile '{}' to {}", name, path.display()),
+            Err(e) => format!("Failed to save profile '{}': {:#}", name, e),
+        };
+        self.set_status(&msg);
+    }
+
+    /// Open the open-files popup for the monitored process, or say why it can't
+    fn show_open_files(&mut self) {
+        let Some(ref collector) = self.proc_collector else {
+            self.set_status("No process being monitored");
+            return;
+        };
+        match collector.open_fds() {
+            Ok(fds) => {
+                let label = match self.proc_metrics {
+                    Some(ref p) => format!("{} (PID {})", p.name, p.pid),
+                    None => format!("PID {}", self.current_monitored_pid.unwrap_or_default()),
+                };
+                self.open_files = Some((label, fds));
+                self.open_files_scroll = 0;
             }
+            Err(e) => self.set_status(&format!("{:#}", e)),
         }
-        
-        // Reset accumulator for new segment
-        self.accumulator.clear();
-        
-        Ok(())
     }
 
-    /// Generate a segmented path from base path
-    fn segment_path(base: &PathBuf, segment: u32) -> PathBuf {
-        let stem = base.file_stem()
-            .and_then(|s| s.to_str())
-            .unwrap_or("log");
-        let ext = base.extension()
-            .and_then(|s| s.to_str())
-            .unwrap_or("");
-        
-        let new_name = if ext.is_empty() {
-            format!("{}_{}", stem, segment)
+    /// Open or close the top-talkers popup, which needs --top-talkers
+    fn toggle_talkers(&mut self) {
+        if self.top_talkers || self.show_talkers {
+            self.show_talkers = !self.show_talkers;
         } else {
-            format!("{}_{}.{}", stem, segment, ext)
-        };
-        
-        base.with_file_name(new_name)
+            self.set_status("Start with --top-talkers to count connections per remote address");
+        }
     }
 
     /// Set a temporary status message
@@ -582,60 +2051,59 @@ impl App {
     /// Rename the current log segment to a custom name
     fn rename_current_segment(&mut self, new_name: &str) -> Result<()> {
         // Get current paths
-        let csv_current = self.csv_log_base.as_ref().map(|base| {
-            if self.log_segment == 0 {
-                base.clone()
-            } else {
-                Self::segment_path(base, self.log_segment)
-            }
-        });
-        
-        let text_current = self.text_log_base.as_ref().map(|base| {
-            if self.log_segment == 0 {
-                base.clone()
-            } else {
-                Self::segment_path(base, self.log_segment)
-            }
-        });
+        let current = |base: &Option<PathBuf>| base.as_ref().map(|base| Self::active_segment_path(base, self.log_segment));
+        let csv_current = current(&self.csv_log_base);
+        let text_current = current(&self.text_log_base);
+        let per_core_current = current(&self.per_core_log_base);
+        let capture_current = current(&self.capture_base);
+        let json_current = current(&self.json_log_base);
+        let influx_current = current(&self.influx_log_base);
         
-        // Close current loggers first
+        // Close current loggers first (the following split opens the next segment)
         self.csv_logger = None;
         self.text_logger = None;
+        self.per_core_logger = None;
+        self.capture_logger = None;
+        self.json_logger = None;
+        self.influx_logger = None;
         
-        // Rename CSV log
-        if let Some(current) = csv_current {
-            if current.exists() {
-                let dir = current.parent().unwrap_or_else(|| std::path::Path::new("."));
-                let new_path = dir.join(format!("{}.csv", new_name));
-                if let Err(e) = std::fs::rename(&current, &new_path) {
-                    if !self.tui_mode {
-                        eprintln!("Failed to rename CSV log to {}: {}", new_path.display(), e);
-                    }
-                } else if !self.tui_mode {
-                    eprintln!("Renamed CSV log to: {}", new_path.display());
-                }
-            }
+        if let Some(new_path) = self.rename_segment_file(csv_current, &format!("{}.csv", new_name), "CSV log") {
+            self.closed_csv_segments.push(new_path);
         }
+        self.rename_segment_file(text_current, &format!("{}.txt", new_name), "text log");
+        self.rename_segment_file(per_core_current, &format!("{}_cores.csv", new_name), "per-core log");
+        self.rename_segment_file(capture_current, &format!("{}.mpc", new_name), "capture");
+        self.rename_segment_file(json_current, &format!("{}.jsonl", new_name), "JSON log");
+        self.rename_segment_file(influx_current, &format!("{}.lp", new_name), "Influx log");
         
-        // Rename text log
-        if let Some(current) = text_current {
-            if current.exists() {
-                let dir = current.parent().unwrap_or_else(|| std::path::Path::new("."));
-                let new_path = dir.join(format!("{}.txt", new_name));
-                if let Err(e) = std::fs::rename(&current, &new_path) {
-                    if !self.tui_mode {
-                        eprintln!("Failed to rename text log to {}: {}", new_path.display(), e);
-                    }
-                } else if !self.tui_mode {
-                    eprintln!("Renamed text log to: {}", new_path.display());
-                }
+        Ok(())
+    }
+
+    /// Rename a closed segment file to `file_name` in the same directory (compressing it
+    /// with --compress-logs), returning its new path
+    fn rename_segment_file(&mut self, current: Option<PathBuf>, file_name: &str, label: &str) -> Option<PathBuf> {
+        let current = current.filter(|path| path.exists())?;
+        let dir = current.parent().unwrap_or_else(|| std::path::Path::new("."));
+        let new_path = dir.join(file_name);
+        if let Err(e) = std::fs::rename(&current, &new_path) {
+            if !self.tui_mode {
+                eprintln!("Failed to rename {} to {}: {}", label, new_path.display(), e);
             }
+            return None;
         }
-        
-        Ok(())
+        if !self.tui_mode {
+            eprintln!("Renamed {} to: {}", label, new_path.display());
+        }
+        if self.compress_logs {
+            self.compress_segment(new_path.clone());
+        }
+        Some(new_path)
     }
 
     fn print_metrics(&self) {
+        if self.json_output {
+            return;
+        }
         if let (Some(cpu), Some(mem), Some(disk), Some(net)) = (
             &self.cpu_metrics,
             &self.mem_metrics,
@@ -644,15 +2112,19 @@ impl App {
         ) {
             println!("\n--- Sample {} ---", self.samples_collected);
             println!(
-                "CPU: {:.1}% (user:{:.1}% sys:{:.1}% iowait:{:.1}%) Load: {:.2} {:.2} {:.2}",
+                "CPU: {:.1}% (user:{:.1}% sys:{:.1}% iowait:{:.1}% steal:{:.1}%) Load: {:.2} {:.2} {:.2}",
                 cpu.total_utilization,
                 cpu.user_percent,
                 cpu.system_percent,
                 cpu.iowait_percent,
+                cpu.steal_percent,
                 cpu.load_avg.0,
                 cpu.load_avg.1,
                 cpu.load_avg.2
             );
+            if let Some(label) = cpu.cgroup_cpu_label() {
+                println!("Container CPU: {}", label);
+            }
             println!(
                 "Memory: {} / {} ({:.1}%) Swap: {} / {}",
                 format_bytes(mem.used),
@@ -676,19 +2148,46 @@ impl App {
             );
             println!(
                 "Network: RX {} TX {}",
-                format_throughput(net.total_rx_bytes_per_sec),
-                format_throughput(net.total_tx_bytes_per_sec)
+                self.format_net_rate(net.total_rx_bytes_per_sec),
+                self.format_net_rate(net.total_tx_bytes_per_sec)
             );
+            if !net.remote_peers.is_empty() {
+                let peers: Vec<String> = net.remote_peers
+                    .iter()
+                    .map(|p| format!("{} {}", p.address, p.connections))
+                    .collect();
+                println!("  Top talkers (connections): {}", peers.join(", "));
+            }
+            for gpu in self.gpu_metrics.iter().flat_map(|g| g.gpus.iter()) {
+                println!(
+                    "GPU{}: {:.0}% Mem: {} / {} ({:.1}%)",
+                    gpu.index,
+                    gpu.utilization_percent,
+                    format_bytes(gpu.memory_used_bytes),
+                    format_bytes(gpu.memory_total_bytes),
+                    gpu.memory_percent
+                );
+            }
 
             if let Some(proc) = &self.proc_metrics {
                 println!(
-                    "Process [{}]: CPU:{:.1}% RSS:{} Threads:{} FDs:{}",
+                    "Process [{}{}]: CPU:{:.1}% RSS:{} Threads:{} FDs:{} Ctx/s:{:.0} vol {:.0} invol",
                     proc.name,
+                    proc.children_label(),
                     proc.cpu_percent,
                     format_bytes(proc.rss_bytes),
                     proc.num_threads,
-                    proc.num_fds
+                    proc.fds_label(),
+                    proc.voluntary_ctxt_per_sec,
+                    proc.nonvoluntary_ctxt_per_sec
                 );
+                if !proc.top_threads.is_empty() {
+                    let threads: Vec<String> = proc.top_threads
+                        .iter()
+                        .map(|t| format!("{} {} {:.1}%", t.tid, t.name, t.cpu_percent))
+                        .collect();
+                    println!("  Top threads: {}", threads.join(", "));
+                }
             }
 
             // Print any new alerts
@@ -696,91 +2195,352 @@ impl App {
                 let prefix = match alert.severity {
                     alert::Severity::Warning => "⚠️  WARNING",
                     alert::Severity::Critical => "🚨 CRITICAL",
+                    alert::Severity::Resolved => "✅ RESOLVED",
                 };
                 println!("{}: {}", prefix, alert.message);
             }
         }
     }
 
-    fn print_summary(&self) {
+    /// Final report; goes to stderr with --format json so stdout stays one sample per line
+    fn write_summary(&self, out: &mut dyn io::Write) -> io::Result<()> {
         if let Some(summary) = self.accumulator.generate_summary() {
-            println!("\n{}", "=".repeat(60));
-            println!("                    PERFORMANCE SUMMARY");
-            println!("{}", "=".repeat(60));
-            println!("Duration: {:.1}s  Samples: {}", summary.duration_secs, summary.samples_count);
-            println!();
-            println!("CPU:");
-            println!(
-                "  Utilization: avg {:.1}%, max {:.1}%",
-                summary.cpu_avg_utilization, summary.cpu_max_utilization
-            );
-            println!(
+            writeln!(out, "\n{}", "=".repeat(60))?;
+            writeln!(out, "                    PERFORMANCE SUMMARY")?;
+            writeln!(out, "{}", "=".repeat(60))?;
+            writeln!(out, "Duration: {:.1}s  Samples: {}", summary.duration_secs, summary.samples_count)?;
+            writeln!(out)?;
+            writeln!(out, "Environment:")?;
+            let env = &self.environment;
+            if let Some(ref kernel) = env.kernel_release {
+                writeln!(out, "  Kernel: {}", kernel)?;
+            }
+            if let Some(ref model) = env.cpu_model {
+                writeln!(out, "  CPU: {} ({} CPUs)", model, env.cpu_count)?;
+            }
+            if let Some(ref clocksource) = env.clocksource {
+                writeln!(out, "  Clocksource: {}", clocksource)?;
+            }
+            if !env.sysctls.is_empty() {
+                writeln!(out, "  Sysctls: {}", env.sysctl_summary())?;
+            }
+            writeln!(out)?;
+
+            let drift = &self.timing_drift;
+            if drift.samples() > 0 {
+                writeln!(out, "Sample Timing:")?;
+                writeln!(
+                    out,
+                    "  Interval: intended {:.3}s, actual {:.3}s avg, max {:+.3}s late",
+                    drift.mean_intended_secs(),
+                    drift.mean_actual_secs(),
+                    drift.max_late_secs()
+                )?;
+                if drift.late_samples() > 0 {
+                    writeln!(out, "  Late samples: {} of {}", drift.late_samples(), drift.samples())?;
+                }
+                // Expensive clock reads slow down both the workload and the sampler
+                if env.slow_clocksource() {
+                    writeln!(
+                        out,
+                        "  ⚠ Clocksource '{}' has expensive reads; expect timing overhead (tsc/kvm-clock preferred)",
+                        env.clocksource.as_deref().unwrap_or_default()
+                    )?;
+                }
+                writeln!(out)?;
+            }
+            writeln!(out, "CPU:")?;
+            writeln!(
+                out,
+                "  Utilization: avg {:.1}%, max {:.1}%{}",
+                summary.cpu_avg_utilization,
+                summary.cpu_max_utilization,
+                format_peak_at(summary.cpu_max_utilization, summary.cpu_max_at_secs)
+            )?;
+            writeln!(
+                out,
                 "  IOWait: avg {:.1}%, max {:.1}%",
                 summary.cpu_avg_iowait, summary.cpu_max_iowait
-            );
-            println!();
-            println!("Memory:");
-            println!(
-                "  Usage: avg {:.1}%, max {:.1}% ({})",
+            )?;
+            writeln!(
+                out,
+                "  Steal: avg {:.1}%, max {:.1}%",
+                summary.cpu_avg_steal, summary.cpu_max_steal
+            )?;
+            writeln!(out)?;
+            writeln!(out, "Memory:")?;
+            writeln!(
+                out,
+                "  Usage: avg {:.1}%, max {:.1}% ({}){}",
                 summary.memory_avg_used_percent,
                 summary.memory_max_used_percent,
-                format_bytes(summary.memory_max_used_bytes)
-            );
+                format_bytes(summary.memory_max_used_bytes),
+                format_peak_at(summary.memory_max_used_percent, summary.memory_max_at_secs)
+            )?;
             if let Some(cgroup_max) = summary.cgroup_max_usage_percent {
-                println!("  Cgroup max: {:.1}%", cgroup_max);
+                writeln!(out, "  Cgroup max: {:.1}%", cgroup_max)?;
             }
             if summary.swap_max_used > 0 {
-                println!("  Swap max: {}", format_bytes(summary.swap_max_used));
+                writeln!(out, "  Swap max: {}", format_bytes(summary.swap_max_used))?;
             }
-            println!();
-            println!("Disk I/O:");
-            println!(
-                "  Max read throughput: {}",
-                format_throughput(summary.disk_max_read_throughput)
-            );
-            println!(
-                "  Max write throughput: {}",
-                format_throughput(summary.disk_max_write_throughput)
-            );
-            println!("  Max utilization: {:.1}%", summary.disk_max_utilization);
-            println!();
-            println!("Network:");
-            println!("  Total RX: {}", format_bytes(summary.network_total_rx_bytes));
-            println!("  Total TX: {}", format_bytes(summary.network_total_tx_bytes));
-            println!(
-                "  Max RX throughput: {}",
-                format_throughput(summary.network_max_rx_throughput)
-            );
-            println!(
-                "  Max TX throughput: {}",
-                format_throughput(summary.network_max_tx_throughput)
-            );
+            writeln!(out)?;
+            writeln!(out, "Disk I/O:")?;
+            writeln!(
+                out,
+                "  Max read throughput: {}{}",
+                format_throughput(summary.disk_max_read_throughput),
+                format_peak_at(summary.disk_max_read_throughput, summary.disk_max_read_at_secs)
+            )?;
+            writeln!(
+                out,
+                "  Max write throughput: {}{}",
+                format_throughput(summary.disk_max_write_throughput),
+                format_peak_at(summary.disk_max_write_throughput, summary.disk_max_write_at_secs)
+            )?;
+            writeln!(out, "  Max utilization: {:.1}%", summary.disk_max_utilization)?;
+            let thresholds = self.alert_checker.thresholds();
+            writeln!(
+                out,
+                "  Max queue depth: {:.1} (alerts at {} / {})",
+                summary.disk_max_queue_depth, thresholds.disk_queue_warn, thresholds.disk_queue_crit
+            )?;
+            writeln!(out, "  Max write latency: {:.2} ms", summary.disk_max_write_latency_ms)?;
+            for latency in &summary.disk_latency {
+                let fmt = |p: Option<LatencyPercentiles>| match p {
+                    Some(p) => format!("{:.2}/{:.2}/{:.2} ms", p.p50, p.p95, p.p99),
+                    None => "-".to_string(),
+                };
+                writeln!(
+                    out,
+                    "  {} latency p50/p95/p99: read {}, write {}",
+                    latency.device,
+                    fmt(latency.read),
+                    fmt(latency.write)
+                )?;
+            }
+            if let Some(ref disk) = self.disk_metrics {
+                let sizes: Vec<String> = disk.disks
+                    .iter()
+                    .map(|d| format!("{} {}/{}", d.device, d.logical_block_size, d.physical_block_size))
+                    .collect();
+                if !sizes.is_empty() {
+                    writeln!(out, "  Block size (logical/physical bytes): {}", sizes.join(", "))?;
+                }
+            }
+            writeln!(out)?;
+            writeln!(out, "Network:")?;
+            writeln!(out, "  Total RX: {}", format_bytes(summary.network_total_rx_bytes))?;
+            writeln!(out, "  Total TX: {}", format_bytes(summary.network_total_tx_bytes))?;
+            writeln!(
+                out,
+                "  Max RX throughput: {}{}",
+                self.format_net_rate(summary.network_max_rx_throughput),
+                format_peak_at(summary.network_max_rx_throughput, summary.network_max_rx_at_secs)
+            )?;
+            writeln!(
+                out,
+                "  Max TX throughput: {}{}",
+                self.format_net_rate(summary.network_max_tx_throughput),
+                format_peak_at(summary.network_max_tx_throughput, summary.network_max_tx_at_secs)
+            )?;
 
             if let Some(proc_cpu) = summary.process_max_cpu {
-                println!();
-                println!("Process:");
-                println!("  Max CPU: {:.1}%", proc_cpu);
+                writeln!(out)?;
+                writeln!(out, "Process:")?;
+                writeln!(out, "  Max CPU: {:.1}%", proc_cpu)?;
                 if let Some(rss) = summary.process_max_rss {
-                    println!("  Max RSS: {}", format_bytes(rss));
+                    match summary.process_max_children {
+                        Some(children) if children > 0 => writeln!(
+                            out,
+                            "  Peak aggregate RSS: {} (process tree, up to {} children)",
+                            format_bytes(rss),
+                            children
+                        )?,
+                        _ => writeln!(out, "  Max RSS: {}", format_bytes(rss))?,
+                    }
                 }
                 if let Some(fds) = summary.process_max_fds {
-                    println!("  Max FDs: {}", fds);
+                    writeln!(out, "  Max FDs: {}", fds)?;
+                }
+                for kill in &summary.process_oom_kills {
+                    writeln!(out, "  {}", kill)?;
+                }
+                if let Some(ref activity) = summary.process_activity {
+                    writeln!(
+                        out,
+                        "  Active: {:.0}% of samples, blocked on I/O: {:.0}%, idle: {:.0}%",
+                        activity.active_percent(),
+                        activity.blocked_percent(),
+                        activity.idle_percent()
+                    )?;
+                    if let Some((start, len)) = activity.longest_idle {
+                        writeln!(out, "  Longest inactive stretch: {:.1}s at {}", len, format_offset(start))?;
+                    }
+                }
+            }
+
+            let alerts = &summary.alert_profile;
+            if alerts.total > 0 {
+                writeln!(out)?;
+                writeln!(out, "Alerts:")?;
+                writeln!(
+                    out,
+                    "  Total: {} ({} warning, {} critical)",
+                    alerts.total, alerts.warnings, alerts.criticals
+                )?;
+                for (category, warnings, criticals) in &alerts.by_category {
+                    writeln!(out, "  {}: {} warning, {} critical", category, warnings, criticals)?;
+                }
+                if let Some((ref key, count)) = alerts.most_frequent {
+                    writeln!(out, "  Most frequent: {} ({}x)", key, count)?;
+                }
+                if let Some(offset) = alerts.first_critical_offset_secs {
+                    writeln!(out, "  First critical: {}", format_offset(offset))?;
+                }
+                if let Some((start, count)) = alerts.busiest_window {
+                    writeln!(
+                        out,
+                        "  Busiest {}s window: {} ({} alerts)",
+                        alerts.window_secs,
+                        format_offset(start),
+                        count
+                    )?;
+                }
+            }
+
+            if !summary.peak_clusters.is_empty() {
+                writeln!(out)?;
+                writeln!(out, "Correlated Peaks:")?;
+                for cluster in &summary.peak_clusters {
+                    writeln!(out, "  • {}", cluster.describe())?;
                 }
             }
 
             if !summary.bottleneck_indicators.is_empty() {
-                println!();
-                println!("Bottleneck Analysis:");
+                writeln!(out)?;
+                writeln!(out, "Bottleneck Analysis:")?;
                 for indicator in &summary.bottleneck_indicators {
-                    println!("  • {}", indicator);
+                    writeln!(out, "  • {}", indicator)?;
                 }
             }
-            println!("{}", "=".repeat(60));
+            writeln!(out, "{}", "=".repeat(60))?;
+        }
+        Ok(())
+    }
+}
+
+/// A metric category being collected on a scoped thread, or already collected inline
+enum Collected<'scope, T> {
+    Thread(std::thread::ScopedJoinHandle<'scope, T>),
+    Inline(T),
+}
+
+impl<'scope, T: Send + 'scope> Collected<'scope, T> {
+    /// Run `collect` on its own thread, or right away when threads can't run side by side
+    fn start<'env>(
+        scope: &'scope std::thread::Scope<'scope, 'env>,
+        parallel: bool,
+        collect: impl FnOnce() -> T + Send + 'scope,
+    ) -> Self {
+        if parallel {
+            Self::Thread(scope.spawn(collect))
+        } else {
+            Self::Inline(collect())
+        }
+    }
+
+    /// The result, re-raising a collector thread's panic on the sampling thread
+    fn join(self) -> T {
+        match self {
+            Self::Thread(handle) => handle.join().unwrap_or_else(|panic| std::panic::resume_unwind(panic)),
+            Self::Inline(value) => value,
+        }
+    }
+}
+
+/// Collector for a monitored PID, optionally aggregating its descendants (--tree) and ranking its threads (--threads)
+fn new_process_collector(pid: u32, tree: bool, threads: usize) -> ProcessCollector {
+    let mut collector = ProcessCollector::new(pid);
+    collector.set_tree(tree);
+    collector.set_top_threads(threads);
+    collector
+}
+
+/// Describe a graph mode using the panel's series names
+fn graph_name(mode: GraphMode, first: &str, second: &str) -> String {
+    match mode {
+        GraphMode::Both => format!("{} and {}", first, second),
+        GraphMode::First => format!("{} only", first),
+        GraphMode::Second => format!("{} only", second),
+        GraphMode::Combined => format!("{} + {} combined", first, second),
+    }
+}
+
+/// Format seconds since the start of a run as "+4m12s"
+fn format_offset(secs: f64) -> String {
+    let total = secs.max(0.0) as u64;
+    let (hours, mins, secs) = (total / 3600, (total % 3600) / 60, total % 60);
+    if hours > 0 {
+        format!("+{}h{:02}m{:02}s", hours, mins, secs)
+    } else {
+        format!("+{}m{:02}s", mins, secs)
+    }
+}
+
+/// PID of the process matching a name pattern, asking the user under --process-match interactive
+fn find_named_process(pattern: &str, process_match: MatchOptions) -> Option<u32> {
+    if process_match.choice == MatchChoice::Interactive {
+        pick_process_interactively(pattern, process_match.no_filter)
+    } else {
+        process::find_process_by_name(pattern, process_match)
+    }
+}
+
+/// Print the open files of a process grouped by type (--dump-fds)
+fn dump_open_files(pid: u32) -> Result<()> {
+    let fds = process::list_open_fds(pid)?;
+    println!("Open files of PID {} ({} fds)", pid, fds.len());
+    for (i, fd) in fds.iter().enumerate() {
+        if i == 0 || fds[i - 1].kind != fd.kind {
+            let count = fds.iter().filter(|other| other.kind == fd.kind).count();
+            println!("\n{} ({}):", fd.kind, count);
+        }
+        println!("  {:>5}  {}", fd.fd, fd.target);
+    }
+    Ok(())
+}
+
+/// Let the user choose among several processes matching a pattern (startup only)
+fn pick_process_interactively(pattern: &str, no_filter: bool) -> Option<u32> {
+    let mut matches = process::find_process_matches(pattern, no_filter);
+    if matches.len() <= 1 {
+        return matches.first().map(|m| m.pid);
+    }
+    matches.sort_by_key(|m| m.pid);
+
+    eprintln!("Multiple processes match '{}':", pattern);
+    for (i, m) in matches.iter().enumerate() {
+        let cmdline = if m.cmdline.len() > 70 {
+            format!("{}...", &m.cmdline[..m.cmdline.floor_char_boundary(67)])
+        } else {
+            m.cmdline.clone()
+        };
+        eprintln!("  [{}] PID {:<7} {}", i + 1, m.pid, cmdline.trim());
+    }
+    eprint!("Select process [1-{}] (Enter for best match): ", matches.len());
+
+    let mut input = String::new();
+    if io::stdin().read_line(&mut input).is_ok() {
+        if let Ok(choice) = input.trim().parse::<usize>() {
+            if (1..=matches.len()).contains(&choice) {
+                return Some(matches[choice - 1].pid);
+            }
         }
     }
+    process::select_match(&matches, MatchChoice::Best)
 }
 
-fn run_tui(mut app: App, interval: Duration, duration: Option<Duration>) -> Result<App> {
+fn run_tui(app: &mut App, interval: Duration, duration: Option<Duration>) -> Result<()> {
     // Setup terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
@@ -792,11 +2552,12 @@ fn run_tui(mut app: App, interval: Duration, duration: Option<Duration>) -> Resu
     app.tui_mode = true;
 
     let start_time = std::time::Instant::now();
-    let tick_rate = interval;
+    let mut tick_rate = interval;
     let mut last_tick = std::time::Instant::now();
 
     // Initial collection to populate metrics
     app.collect_metrics()?;
+    tick_rate = app.next_interval(tick_rate);
 
     loop {
         // Check duration limit
@@ -807,75 +2568,141 @@ fn run_tui(mut app: App, interval: Duration, duration: Option<Duration>) -> Resu
         }
 
         // Draw UI
+        let mut panel_areas = Vec::new();
         terminal.draw(|f| {
             // First split off the fixed-height bottom sections
+            // The process row grows to list the busiest threads (--threads)
+            let process_height = if app.show_process && app.process_threads > 0 {
+                (app.process_threads as u16 + 3).clamp(5, 12)
+            } else {
+                5
+            };
+            // One line per GPU (--gpu), no row at all without GPUs
+            let gpu_count = app.gpu_metrics.as_ref().map_or(0, |g| g.gpus.len());
+            let gpu_height = if gpu_count > 0 { (gpu_count as u16 + 2).min(10) } else { 0 };
             let main_chunks = Layout::default()
                 .direction(Direction::Vertical)
                 .constraints([
                     Constraint::Min(10),      // Main area (CPU + Memory + Disk + Network)
-                    Constraint::Length(5),    // Bottom row (Process only) - compact
+                    Constraint::Length(gpu_height),  // GPU row
+                    Constraint::Length(process_height),  // Bottom row (Process only) - compact
                     Constraint::Length(1),    // Help bar
                 ])
                 .split(f.area());
             
-            // Split the main area into top and middle rows (each gets half)
-            let chunks = Layout::default()
-                .direction(Direction::Vertical)
-                .constraints([
-                    Constraint::Percentage(50),  // Top row (CPU + Memory)
-                    Constraint::Percentage(50),  // Middle row (Disk + Network)
-                ])
-                .split(main_chunks[0]);
-
-            // Top row: CPU and Memory
-            let top_chunks = Layout::default()
-                .direction(Direction::Horizontal)
-                .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
-                .split(chunks[0]);
-
-            if let Some(ref cpu) = app.cpu_metrics {
-                display::render_cpu(f, top_chunks[0], cpu, Some(&app.cpu_history));
-            }
-            if let Some(ref mem) = app.mem_metrics {
-                display::render_memory(f, top_chunks[1], mem, Some(&app.memory_history));
+            if let Some(panel) = app.focused_panel {
+                // Focus mode: one panel fills the whole main area
+                app.draw_panel(f, main_chunks[0], panel);
+                panel_areas.push((panel, main_chunks[0]));
+            } else {
+                // Split the main area into top and middle rows (each gets half)
+                let chunks = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([
+                        Constraint::Percentage(50),  // Top row (CPU + Memory)
+                        Constraint::Percentage(50),  // Middle row (Disk + Network)
+                    ])
+                    .split(main_chunks[0]);
+
+                // Top row: CPU and Memory
+                let top_chunks = Layout::default()
+                    .direction(Direction::Horizontal)
+                    .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+                    .split(chunks[0]);
+
+                app.draw_panel(f, top_chunks[0], Panel::Cpu);
+                app.draw_panel(f, top_chunks[1], Panel::Memory);
+
+                // Middle row: Disk and Network
+                let mid_chunks = Layout::default()
+                    .direction(Direction::Horizontal)
+                    .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+                    .split(chunks[1]);
+
+                app.draw_panel(f, mid_chunks[0], Panel::Disk);
+                app.draw_panel(f, mid_chunks[1], Panel::Network);
+
+                panel_areas.extend([
+                    (Panel::Cpu, top_chunks[0]),
+                    (Panel::Memory, top_chunks[1]),
+                    (Panel::Disk, mid_chunks[0]),
+                    (Panel::Network, mid_chunks[1]),
+                ]);
             }
 
-            // Middle row: Disk and Network
-            let mid_chunks = Layout::default()
-                .direction(Direction::Horizontal)
-                .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
-                .split(chunks[1]);
-
-            if let Some(ref disk) = app.disk_metrics {
-                display::render_disk(f, mid_chunks[0], disk, Some(&app.disk_history));
-            }
-            if let Some(ref net) = app.net_metrics {
-                display::render_network(f, mid_chunks[1], net, Some(&app.network_history));
+            if let Some(ref gpu) = app.gpu_metrics {
+                if !gpu.gpus.is_empty() {
+                    display::render_gpu(f, main_chunks[1], gpu, &app.palette);
+                }
             }
 
-            // Bottom row: Process info only (no alerts)
+            // Bottom row: Process or system info, with recent alerts beside it when toggled on
+            let bottom_area = if app.show_alerts {
+                let bottom_chunks = Layout::default()
+                    .direction(Direction::Horizontal)
+                    .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+                    .split(main_chunks[2]);
+                display::render_alerts(f, bottom_chunks[1], &app.alerts, &app.palette);
+                bottom_chunks[0]
+            } else {
+                main_chunks[2]
+            };
             if app.show_process {
-                display::render_process(f, main_chunks[1], app.proc_metrics.as_ref());
+                display::render_process(f, bottom_area, app.proc_metrics.as_ref(), &app.palette);
             } else {
-                display::render_system_info(f, main_chunks[1], app.uptime_secs);
+                display::render_system_info(f, bottom_area, app.uptime.as_secs(), &app.environment, app.psi_metrics.as_ref(), &app.palette);
             }
 
             // Help bar with status and current log name
             let log_name = app.current_log_name();
-            display::render_help_bar(f, main_chunks[2], app.pending_log_split, app.get_status(), log_name.as_deref());
+            let replay_label = app.replay.as_ref().map(Replay::label);
+            display::render_help_bar(f, main_chunks[3], app.pending_log_split, app.paused, replay_label.as_deref(), app.get_status(), log_name.as_deref());
+
+            if let Some((ref label, ref fds)) = app.open_files {
+                display::render_open_files(f, f.area(), label, fds, app.open_files_scroll);
+            } else if app.show_talkers {
+                let peers = app.net_metrics.as_ref().map_or(&[][..], |n| &n.remote_peers[..]);
+                display::render_top_talkers(f, f.area(), peers);
+            }
         })?;
+        app.panel_areas = panel_areas;
 
         // Handle input
         let timeout = tick_rate.saturating_sub(last_tick.elapsed());
         if event::poll(timeout)? {
-            if let Event::Key(key) = event::read()? {
+            let event = event::read()?;
+            if let Event::Mouse(mouse) = event {
+                // Clicking a panel's title bar zooms it, clicking again returns to the grid
+                if mouse.kind == MouseEventKind::Down(MouseButton::Left) {
+                    app.handle_click(mouse.column, mouse.row);
+                }
+            }
+            if let Event::Key(key) = event {
                 if key.kind == KeyEventKind::Press {
-                    if app.pending_log_split {
+                    if let Some((_, ref fds)) = app.open_files {
+                        // The popup takes the keys until it is closed
+                        let last = fds.len() + 8;  // Group headers and gaps
+                        match key.code {
+                            KeyCode::Up => app.open_files_scroll = app.open_files_scroll.saturating_sub(1),
+                            KeyCode::Down => app.open_files_scroll = (app.open_files_scroll + 1).min(last),
+                            KeyCode::PageUp => app.open_files_scroll = app.open_files_scroll.saturating_sub(10),
+                            KeyCode::PageDown => app.open_files_scroll = (app.open_files_scroll + 10).min(last),
+                            KeyCode::Char('f') | KeyCode::Esc => app.open_files = None,
+                            KeyCode::Char('q') => break,
+                            _ => {}
+                        }
+                    } else if app.show_talkers {
+                        match key.code {
+                            KeyCode::Char('t') | KeyCode::Esc => app.show_talkers = false,
+                            KeyCode::Char('q') => break,
+                            _ => {}
+                        }
+                    } else if app.pending_log_split {
                         // Confirmation mode for log split
                         match key.code {
                             KeyCode::Char('y') | KeyCode::Char('Y') => {
                                 app.pending_log_split = false;
-                                if let Err(e) = app.rotate_logs() {
+                                if let Err(e) = app.split_logs() {
                                     app.set_status(&format!("Log split failed: {}", e));
                                 } else {
                                     app.set_status(&format!("Logs split → segment {}", app.log_segment));
@@ -889,18 +2716,70 @@ fn run_tui(mut app: App, interval: Duration, duration: Option<Duration>) -> Resu
                     } else {
                         match key.code {
                             KeyCode::Char('q') => break,
+                            KeyCode::Char(' ') => {
+                                app.toggle_pause();
+                                // Resume with a full interval so the first sample is not rushed
+                                last_tick = std::time::Instant::now();
+                            }
                             KeyCode::Char('p') => app.show_process = !app.show_process,
-                            KeyCode::Char('l') => app.logging_enabled = !app.logging_enabled,
+                            KeyCode::Char('a') => app.show_alerts = !app.show_alerts,
+                            KeyCode::Char('l') => {
+                                app.logging_enabled = !app.logging_enabled;
+                                // Time spent with logging off doesn't count toward --log-rotate-interval
+                                if app.logging_enabled {
+                                    app.segment_started = std::time::Instant::now();
+                                }
+                            }
                             KeyCode::Char('r') => {
                                 app.alerts.clear();
                                 app.accumulator.clear();
                             }
+                            KeyCode::Char('1') => app.toggle_focus(Panel::Cpu),
+                            KeyCode::Char('2') => app.toggle_focus(Panel::Memory),
+                            KeyCode::Char('3') => app.toggle_focus(Panel::Disk),
+                            KeyCode::Char('4') => app.toggle_focus(Panel::Network),
+                            KeyCode::Char('z') => app.toggle_focus(app.last_focus),
+                            KeyCode::Esc => app.focused_panel = None,
+                            KeyCode::Char('g') => app.cycle_graph(),
+                            KeyCode::Char('f') => app.show_open_files(),
+                            KeyCode::Char('t') => app.toggle_talkers(),
+                            KeyCode::Char('w') => app.save_profile(),
+                            KeyCode::Char('b') => {
+                                app.net_bits = !app.net_bits;
+                                app.set_status(if app.net_bits { "Network in bits/s" } else { "Network in bytes/s" });
+                            }
+                            KeyCode::Char('h') => app.cycle_history_scale(),
+                            KeyCode::Left => app.scroll_history(true),
+                            KeyCode::Right => app.scroll_history(false),
+                            KeyCode::Char(key @ ('[' | ']')) if app.replay.is_some() => {
+                                app.seek_replay(key == '[');
+                                tick_rate = app.next_interval(tick_rate);
+                                last_tick = std::time::Instant::now();
+                            }
+                            KeyCode::Char(key @ ('+' | '=' | '-')) => {
+                                if let Some(ref mut replay) = app.replay {
+                                    replay.change_speed(key != '-');
+                                    let msg = format!("Replay speed {}x", replay.speed());
+                                    app.set_status(&msg);
+                                    tick_rate = app.next_interval(tick_rate);
+                                }
+                            }
+                            KeyCode::Char('c') => {
+                                // Restart the graphs only; logging and summary are untouched
+                                app.cpu_history.clear();
+                                app.memory_history.clear();
+                                app.disk_history.clear();
+                                app.network_history.clear();
+                                app.history_times.clear();
+                                app.history_offset = 0;
+                                app.set_status("Graph history cleared");
+                            }
                             KeyCode::Char('s') => {
                                 // Check if logging is configured
-                                if app.csv_log_base.is_some() || app.text_log_base.is_some() {
+                                if app.has_file_logs() {
                                     app.pending_log_split = true;
                                 } else {
-                                    app.set_status("No log files configured (-l, -o, or --detailed-log)");
+                                    app.set_status("No log files configured (-l, -o, --per-core-log, --capture, --json-log or --influx-file)");
                                 }
                             }
                             _ => {}
@@ -917,8 +2796,8 @@ fn run_tui(mut app: App, interval: Duration, duration: Option<Duration>) -> Resu
                 let _ = app.rename_current_segment(&rename_to);
             }
             // Then rotate to a new segment
-            if app.csv_log_base.is_some() || app.text_log_base.is_some() {
-                if let Err(e) = app.rotate_logs() {
+            if app.has_file_logs() {
+                if let Err(e) = app.split_logs() {
                     app.set_status(&format!("Control split failed: {}", e));
                 } else {
                     app.set_status("Log split via control port");
@@ -926,11 +2805,16 @@ fn run_tui(mut app: App, interval: Duration, duration: Option<Duration>) -> Resu
             }
         }
 
-        // Collect metrics on tick
-        if last_tick.elapsed() >= tick_rate {
+        // Collect metrics on tick (history and sparklines hold still while paused)
+        if !app.paused && last_tick.elapsed() >= tick_rate {
             app.collect_metrics()?;
-            app.uptime_secs += interval.as_secs();
+            app.uptime += tick_rate;
+            tick_rate = app.next_interval(tick_rate);
             last_tick = std::time::Instant::now();
+            // The command after -- finished: end the session with its final sample
+            if app.command_finished() {
+                break;
+            }
         }
     }
 
@@ -943,11 +2827,47 @@ fn run_tui(mut app: App, interval: Duration, duration: Option<Duration>) -> Resu
     )?;
     terminal.show_cursor()?;
 
-    Ok(app)
+    Ok(())
+}
+
+/// Watch for SIGINT/SIGTERM; the returned flag is set on the first one, a second one exits at once
+fn install_shutdown_handler() -> Result<Arc<AtomicBool>> {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let shutdown = Arc::new(AtomicBool::new(false));
+    let mut interrupt = signal(SignalKind::interrupt())?;
+    let mut terminate = signal(SignalKind::terminate())?;
+    let flag = Arc::clone(&shutdown);
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                _ = interrupt.recv() => {}
+                _ = terminate.recv() => {}
+            }
+            if flag.swap(true, Ordering::SeqCst) {
+                std::process::exit(130);
+            }
+        }
+    });
+    Ok(shutdown)
+}
+
+/// Sleep for `duration`, waking early once `shutdown` is set
+fn sleep_unless_shutdown(duration: Duration, shutdown: &AtomicBool) {
+    const STEP: Duration = Duration::from_millis(50);
+    let deadline = std::time::Instant::now() + duration;
+    while !shutdown.load(Ordering::SeqCst) {
+        let remaining = deadline.saturating_duration_since(std::time::Instant::now());
+        if remaining.is_zero() {
+            break;
+        }
+        std::thread::sleep(remaining.min(STEP));
+    }
 }
 
-fn run_no_tui(mut app: App, interval: Duration, duration: Option<Duration>) -> Result<App> {
+fn run_no_tui(app: &mut App, interval: Duration, duration: Option<Duration>, shutdown: &AtomicBool) -> Result<()> {
     let start_time = std::time::Instant::now();
+    let mut tick_rate = interval;
 
     loop {
         // Check duration limit
@@ -956,9 +2876,18 @@ fn run_no_tui(mut app: App, interval: Duration, duration: Option<Duration>) -> R
                 break;
             }
         }
+        // Ctrl-C or SIGTERM: stop sampling and let main flush logs and print the summary
+        if shutdown.load(Ordering::SeqCst) {
+            eprintln!("\nShutdown requested, finishing up (signal again to exit immediately)");
+            break;
+        }
 
         app.collect_metrics()?;
         app.print_metrics();
+        tick_rate = app.next_interval(tick_rate);
+        if app.command_finished() || app.replay.as_ref().is_some_and(Replay::is_finished) {
+            break;
+        }
 
         // Check for control messages (log split requests)
         if let Some(rename_to) = app.check_control_messages() {
@@ -967,8 +2896,8 @@ fn run_no_tui(mut app: App, interval: Duration, duration: Option<Duration>) -> R
                 let _ = app.rename_current_segment(&rename_to);
             }
             // Then rotate to a new segment
-            if app.csv_log_base.is_some() || app.text_log_base.is_some() {
-                if let Err(e) = app.rotate_logs() {
+            if app.has_file_logs() {
+                if let Err(e) = app.split_logs() {
                     eprintln!("Control split failed: {}", e);
                 } else {
                     eprintln!("Log split via control port");
@@ -976,54 +2905,146 @@ fn run_no_tui(mut app: App, interval: Duration, duration: Option<Duration>) -> R
             }
         }
 
-        std::thread::sleep(interval);
+        sleep_unless_shutdown(tick_rate, shutdown);
     }
 
-    Ok(app)
+    Ok(())
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let args = Args::parse();
+    let matches = Args::command().get_matches();
+    let mut args = Args::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    if let Some(path) = args.config.clone() {
+        config::ConfigFile::load(&path)?.apply(&mut args, &matches)?;
+    }
+    if let Some(ref name) = args.profile {
+        let path = config::profile_path(name)?;
+        config::ConfigFile::load(&path)?.apply(&mut args, &matches)?;
+    }
+    if let Some(ref name) = args.save_profile {
+        let path = config::profile_path(name)?;
+        config::ConfigFile::from_args(&args).save(&path)?;
+        eprintln!("Saved profile '{}' to {}", name, path.display());
+    }
+
+    let plot_options = plot::PlotOptions {
+        format: args.plot_format,
+        annotate_stats: args.plot_stats,
+        heatmap_columns: args.heatmap_columns,
+        width: args.plot_width,
+        height: args.plot_height,
+        max_points: args.plot_max_points.map(|n| n as usize),
+        font: args.plot_font.clone(),
+    };
+
+    // Compare mode: overlay two recorded runs and diff their summaries
+    if let Some(ref runs) = args.compare {
+        let (baseline, candidate) = (&runs[0], &runs[1]);
+        eprintln!("Comparing {} (baseline) with {} (candidate)", baseline.display(), candidate.display());
+        eprintln!("Generating plots in: {}", args.plot_output.display());
+        let generated = plot::generate_compare_plots(baseline, candidate, &args.plot_output, &plot_options)?;
+        print!("{}", compare::summary_diff(baseline, candidate)?);
+
+        eprintln!("\nGenerated {} plots:", generated.len());
+        for path in &generated {
+            eprintln!("  • {}", path);
+        }
+        return Ok(());
+    }
 
     // Plot mode: generate plots from existing log file
     if let Some(ref log_path) = args.plot {
+        if args.plot_html && args.plot_format != plot::PlotFormat::Svg {
+            anyhow::bail!("--plot-html inlines SVG charts and cannot be combined with --plot-format png");
+        }
         eprintln!("Loading samples from: {}", log_path.display());
         eprintln!("Generating plots in: {}", args.plot_output.display());
-        let generated = plot::generate_all_plots(log_path, &args.plot_output)?;
+        let generated = plot::generate_all_plots(log_path, &args.plot_output, &plot_options)?;
         
         eprintln!("\nGenerated {} plots:", generated.len());
-        for path in generated {
+        for path in &generated {
             eprintln!("  • {}", path);
         }
+        if args.plot_html {
+            let report = report::write_html_report(log_path, &args.plot_output, &generated)?;
+            eprintln!("HTML report: {}", report.display());
+        }
         return Ok(());
     }
 
+    // Open files mode: list the process's open files once and exit
+    if args.dump_fds {
+        let pid = match (args.pid, args.process_name.as_deref()) {
+            (Some(pid), _) => pid,
+            (None, Some(name)) => {
+                let process_match = MatchOptions {
+                    no_filter: args.no_process_filter,
+                    choice: args.process_match,
+                };
+                find_named_process(name, process_match).with_context(|| format!("Process '{}' not found", name))?
+            }
+            (None, None) => anyhow::bail!("--dump-fds needs --pid or --process-name"),
+        };
+        return dump_open_files(pid);
+    }
+
     // Normal monitoring mode
+    if args.auto_interval && args.min_interval > args.max_interval {
+        anyhow::bail!("--min-interval must not be greater than --max-interval");
+    }
     let interval = Duration::from_secs_f64(args.interval);
     let duration = args.duration.map(Duration::from_secs);
-    let summary = args.summary || args.duration.is_some();
+    let summary = args.summary || args.duration.is_some() || !args.command.is_empty();
 
-    let app = App::new(&args)?;
+    let mut app = App::new(&args)?;
+    app.check_csv_append()?;
 
     let result = if args.no_tui {
-        run_no_tui(app, interval, duration)
+        let shutdown = install_shutdown_handler()?;
+        run_no_tui(&mut app, interval, duration, &shutdown)
     } else {
-        run_tui(app, interval, duration)
+        run_tui(&mut app, interval, duration)
     };
 
-    // Handle cleanup and summary
-    match result {
-        Ok(app) => {
-            if summary {
-                app.print_summary();
-            }
+    // Cleanup runs even when monitoring failed: dropping the App would leave the
+    // command after -- running and lose the queued webhook alerts
+
+    // Flush explicitly: a write error in the loggers' Drop would go unreported
+    if let Err(e) = app.flush_logs() {
+        eprintln!("Warning: Failed to flush logs: {}", e);
+    }
+    // The runtime drops spawned tasks when main returns (and process::exit below
+    // stops everything), so send the alerts still queued for the webhook first
+    if let Some(webhook) = app.alert_webhook.take() {
+        for error in webhook.finish().await {
+            eprintln!("Warning: {}", error);
+        }
+    }
+    let command_exit = app.finish_command();
+    if let Err(e) = result {
+        eprintln!("Error: {}", e);
+        return Err(e);
+    }
+
+    // Handle summary and segment cleanup
+    if summary {
+        let written = if app.json_output {
+            app.write_summary(&mut io::stderr())
+        } else {
+            app.write_summary(&mut io::stdout())
+        };
+        if let Err(e) = written {
+            eprintln!("Warning: Failed to write summary: {}", e);
         }
-        Err(e) => {
-            eprintln!("Error: {}", e);
-            return Err(e);
+    }
+    app.finish_compression();
+    if args.compact_on_exit {
+        if let Err(e) = app.compact_csv_logs(args.delete_segments) {
+            eprintln!("Warning: Failed to compact CSV segments: {}", e);
         }
     }
+    let failing_alerts = app.failing_alerts;
 
     // Log file messages
     if let Some(ref log_path) = args.log {
@@ -1032,6 +3053,62 @@ async fn main() -> Result<()> {
     if let Some(ref log_path) = args.text_log {
         eprintln!("Text summary logged to: {}", log_path.display());
     }
+    if let Some(ref log_path) = args.per_core_log {
+        eprintln!("Per-core CPU logged to: {}", log_path.display());
+    }
+    if let Some(ref capture_path) = args.capture {
+        eprintln!("Binary capture written to: {}", capture_path.display());
+    }
+    if let Some(ref json_path) = args.json_log {
+        eprintln!("JSON log written to: {}", json_path.display());
+    }
+    if let Some(ref influx_path) = args.influx_file {
+        eprintln!("InfluxDB line protocol written to: {}", influx_path.display());
+    }
+
+    // A failing command's status wins over --fail-on-alert
+    if let Some(code) = command_exit {
+        eprintln!("Command exited with status {}", code);
+        if code != 0 {
+            std::process::exit(code);
+        }
+    }
+    if failing_alerts > 0 {
+        eprintln!(
+            "{} alert(s) at or above --fail-on-alert severity, exiting with status {}",
+            failing_alerts, args.fail_exit_code
+        );
+        std::process::exit(args.fail_exit_code);
+    }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collected_returns_the_same_value_threaded_or_inline() {
+        let mut counter = 0;
+        let (threaded, inline) = std::thread::scope(|s| {
+            let threaded = Collected::start(s, true, || 41 + 1);
+            let inline = Collected::start(s, false, || {
+                counter += 1;
+                counter
+            });
+            (threaded.join(), inline.join())
+        });
+        assert_eq!(threaded, 42);
+        assert_eq!(inline, 1);
+    }
+
+    #[test]
+    fn collected_reraises_a_collector_panic() {
+        let result = std::panic::catch_unwind(|| {
+            std::thread::scope(|s| Collected::start(s, true, || -> u32 { panic!("collector failed") }).join())
+        });
+        let panic = result.expect_err("the thread's panic should reach the caller");
+        assert_eq!(panic.downcast_ref::<&str>(), Some(&"collector failed"));
+    }
+}
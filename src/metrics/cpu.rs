@@ -1,9 +1,11 @@
 //! CPU metrics collection from /proc/stat and /proc/loadavg.
 
+use super::ProcFile;
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
+use std::time::Instant;
 
 /// Raw CPU time values from /proc/stat
 #[derive(Debug, Clone, Default)]
@@ -35,20 +37,31 @@ impl CpuTimes {
     pub fn active(&self) -> u64 {
         self.total() - self.idle - self.iowait
     }
+
+    /// The counters of time spent running, which only go back when a CPU comes back
+    /// online (idle and iowait can also step back a tick on tickless kernels)
+    fn busy_counters(&self) -> [u64; 6] {
+        [self.user, self.nice, self.system, self.irq, self.softirq, self.steal]
+    }
 }
 
 /// Per-core CPU utilization
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct CoreUtilization {
     pub core_id: usize,
     pub utilization_percent: f64,
     pub user_percent: f64,
     pub system_percent: f64,
     pub iowait_percent: f64,
+    /// Softirq time percentage (network-heavy hosts can saturate single cores with it)
+    #[serde(default)]
+    pub softirq_percent: f64,
+    /// Current clock speed (None without cpufreq, e.g. in containers or some VMs)
+    pub frequency_mhz: Option<f64>,
 }
 
 /// Aggregated CPU metrics
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct CpuMetrics {
     /// Overall CPU utilization percentage
     pub total_utilization: f64,
@@ -58,6 +71,15 @@ pub struct CpuMetrics {
     pub system_percent: f64,
     /// I/O wait percentage
     pub iowait_percent: f64,
+    /// Hardware interrupt handling time percentage
+    #[serde(default)]
+    pub irq_percent: f64,
+    /// Softirq handling time percentage
+    #[serde(default)]
+    pub softirq_percent: f64,
+    /// Time stolen by the hypervisor for other guests, as a percentage
+    #[serde(default)]
+    pub steal_percent: f64,
     /// Per-core utilization
     pub per_core: Vec<CoreUtilization>,
     /// Load averages (1min, 5min, 15min)
@@ -70,8 +92,66 @@ pub struct CpuMetrics {
     pub interrupts: u64,
     /// Interrupts delta (for rate calculation)
     pub interrupts_delta: Option<u64>,
+    /// Tasks runnable at the instant of sampling
+    #[serde(default)]
+    pub procs_running: u64,
+    /// Tasks blocked on I/O at the instant of sampling
+    #[serde(default)]
+    pub procs_blocked: u64,
     /// Number of CPU cores
     pub core_count: usize,
+    /// Cgroup CFS quota throttling since the previous sample (None outside a CPU-limited cgroup)
+    #[serde(default)]
+    pub throttling: Option<CpuThrottling>,
+    /// Cgroup CFS quota in cores (cpu.max or cpu.cfs_quota_us / cpu.cfs_period_us; None without a quota)
+    #[serde(default)]
+    pub cgroup_cpu_limit_cores: Option<f64>,
+    /// Cgroup CPU usage since the previous sample as a percentage of its quota
+    #[serde(default)]
+    pub cgroup_cpu_quota_percent: Option<f64>,
+}
+
+impl CpuMetrics {
+    /// Container usage against its quota in top-style percent, e.g. "180% of 2.0 quota"
+    pub fn cgroup_cpu_label(&self) -> Option<String> {
+        let limit = self.cgroup_cpu_limit_cores?;
+        let percent = self.cgroup_cpu_quota_percent?;
+        Some(format!("{:.0}% of {:.1} quota", percent * limit, limit))
+    }
+}
+
+/// CFS bandwidth throttling counters from cgroup cpu.stat
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CpuThrottling {
+    /// Enforcement periods elapsed
+    pub periods: u64,
+    /// Periods in which the cgroup exhausted its quota and was throttled
+    pub throttled_periods: u64,
+    /// Time spent throttled in microseconds
+    pub throttled_usec: u64,
+}
+
+impl CpuThrottling {
+    pub fn is_throttled(&self) -> bool {
+        self.throttled_periods > 0
+    }
+
+    /// Percentage of enforcement periods that were throttled
+    pub fn throttled_percent(&self) -> f64 {
+        if self.periods == 0 {
+            0.0
+        } else {
+            100.0 * self.throttled_periods as f64 / self.periods as f64
+        }
+    }
+
+    fn delta(&self, prev: &CpuThrottling) -> CpuThrottling {
+        CpuThrottling {
+            periods: self.periods.saturating_sub(prev.periods),
+            throttled_periods: self.throttled_periods.saturating_sub(prev.throttled_periods),
+            throttled_usec: self.throttled_usec.saturating_sub(prev.throttled_usec),
+        }
+    }
 }
 
 /// CPU metrics collector with state for delta calculations
@@ -80,6 +160,13 @@ pub struct CpuCollector {
     prev_core_times: HashMap<usize, CpuTimes>,
     prev_context_switches: Option<u64>,
     prev_interrupts: Option<u64>,
+    prev_throttling: Option<CpuThrottling>,
+    /// Cumulative cgroup CPU usage (microseconds) and when it was read
+    prev_cgroup_usage: Option<(u64, Instant)>,
+    stat_file: ProcFile,
+    loadavg_file: ProcFile,
+    /// Reused across samples for /proc/stat and /proc/loadavg
+    buf: String,
 }
 
 impl CpuCollector {
@@ -89,63 +176,41 @@ impl CpuCollector {
             prev_core_times: HashMap::new(),
             prev_context_switches: None,
             prev_interrupts: None,
+            prev_throttling: None,
+            prev_cgroup_usage: None,
+            stat_file: ProcFile::new("/proc/stat"),
+            loadavg_file: ProcFile::new("/proc/loadavg"),
+            buf: String::new(),
         }
     }
 
     /// Collect current CPU metrics
     pub fn collect(&mut self) -> Result<CpuMetrics> {
-        let stat_content = fs::read_to_string("/proc/stat")
+        self.stat_file.read_into(&mut self.buf)
             .context("Failed to read /proc/stat")?;
 
-        let mut total_times = CpuTimes::default();
-        let mut core_times: HashMap<usize, CpuTimes> = HashMap::new();
-        let mut context_switches: u64 = 0;
-        let mut interrupts: u64 = 0;
-
-        for line in stat_content.lines() {
-            if line.starts_with("cpu ") {
-                total_times = parse_cpu_line(line)?;
-            } else if line.starts_with("cpu") {
-                // Per-core line like "cpu0", "cpu1", etc.
-                let core_id: usize = line[3..].split_whitespace()
-                    .next()
-                    .and_then(|s| s.parse().ok())
-                    .unwrap_or(0);
-                core_times.insert(core_id, parse_cpu_line(line)?);
-            } else if line.starts_with("ctxt ") {
-                context_switches = line.split_whitespace()
-                    .nth(1)
-                    .and_then(|s| s.parse().ok())
-                    .unwrap_or(0);
-            } else if line.starts_with("intr ") {
-                interrupts = line.split_whitespace()
-                    .nth(1)
-                    .and_then(|s| s.parse().ok())
-                    .unwrap_or(0);
-            }
-        }
+        let ProcStat { total_times, core_times, context_switches, interrupts, procs_running, procs_blocked } =
+            parse_proc_stat(&self.buf)?;
 
         // Calculate utilization from deltas
-        let (total_util, user_pct, sys_pct, iowait_pct) = if let Some(ref prev) = self.prev_total_times {
-            calculate_utilization(prev, &total_times)
-        } else {
-            (0.0, 0.0, 0.0, 0.0)
-        };
+        let total = self.prev_total_times.as_ref()
+            .map(|prev| calculate_utilization(prev, &total_times))
+            .unwrap_or_default();
 
         // Per-core utilization
         let mut per_core = Vec::new();
         for (core_id, times) in &core_times {
-            let (util, user, sys, iowait) = if let Some(prev) = self.prev_core_times.get(core_id) {
-                calculate_utilization(prev, times)
-            } else {
-                (0.0, 0.0, 0.0, 0.0)
-            };
+            let core = self.prev_core_times.get(core_id)
+                .map(|prev| calculate_utilization(prev, times))
+                .unwrap_or_default();
             per_core.push(CoreUtilization {
                 core_id: *core_id,
-                utilization_percent: util,
-                user_percent: user,
-                system_percent: sys,
-                iowait_percent: iowait,
+                utilization_percent: core.total,
+                user_percent: core.user,
+                system_percent: core.system,
+                iowait_percent: core.iowait,
+                softirq_percent: core.softirq,
+                frequency_mhz: read_core_frequency(*core_id),
             });
         }
         per_core.sort_by_key(|c| c.core_id);
@@ -155,7 +220,31 @@ impl CpuCollector {
         let intr_delta = self.prev_interrupts.map(|prev| interrupts.saturating_sub(prev));
 
         // Load average
-        let load_avg = read_load_average()?;
+        let load_avg = read_load_average(&mut self.loadavg_file, &mut self.buf)?;
+
+        // Cgroup quota throttling (cumulative counters, so report deltas)
+        let cgroup_stat = read_cgroup_cpu_stat();
+        let throttling = match (&cgroup_stat.throttling, &self.prev_throttling) {
+            (Some(curr), Some(prev)) => Some(curr.delta(prev)),
+            _ => None,
+        };
+        self.prev_throttling = cgroup_stat.throttling;
+
+        // Cgroup usage against its quota: CPU time consumed per wall-clock time, per quota core
+        let now = Instant::now();
+        let cgroup_cpu_limit_cores = read_cgroup_cpu_limit();
+        let cgroup_cpu_quota_percent = match (cgroup_cpu_limit_cores, cgroup_stat.usage_usec, self.prev_cgroup_usage) {
+            (Some(limit), Some(usage), Some((prev_usage, prev_time))) => {
+                let elapsed_usec = now.duration_since(prev_time).as_secs_f64() * 1e6;
+                (elapsed_usec > 0.0)
+                    .then(|| 100.0 * usage.saturating_sub(prev_usage) as f64 / (elapsed_usec * limit))
+            }
+            _ => None,
+        };
+        self.prev_cgroup_usage = cgroup_stat.usage_usec.map(|usage| (usage, now));
+
+        // Count the cores of this sample before its times become the previous state
+        let core_count = core_times.len();
 
         // Update state for next collection
         self.prev_total_times = Some(total_times);
@@ -164,17 +253,25 @@ impl CpuCollector {
         self.prev_interrupts = Some(interrupts);
 
         Ok(CpuMetrics {
-            total_utilization: total_util,
-            user_percent: user_pct,
-            system_percent: sys_pct,
-            iowait_percent: iowait_pct,
+            total_utilization: total.total,
+            user_percent: total.user,
+            system_percent: total.system,
+            iowait_percent: total.iowait,
+            irq_percent: total.irq,
+            softirq_percent: total.softirq,
+            steal_percent: total.steal,
             per_core,
             load_avg,
             context_switches,
             context_switches_delta: ctx_delta,
             interrupts,
             interrupts_delta: intr_delta,
-            core_count: self.prev_core_times.len(),
+            procs_running,
+            procs_blocked,
+            core_count,
+            throttling,
+            cgroup_cpu_limit_cores,
+            cgroup_cpu_quota_percent,
         })
     }
 }
@@ -185,59 +282,268 @@ impl Default for CpuCollector {
     }
 }
 
+/// Counters from one read of /proc/stat
+struct ProcStat {
+    total_times: CpuTimes,
+    /// Times of each online core by id; offline cores have no line
+    core_times: HashMap<usize, CpuTimes>,
+    context_switches: u64,
+    interrupts: u64,
+    procs_running: u64,
+    procs_blocked: u64,
+}
+
+fn parse_proc_stat(content: &str) -> Result<ProcStat> {
+    let mut total_times = CpuTimes::default();
+    let mut core_times: HashMap<usize, CpuTimes> = HashMap::new();
+    let mut context_switches: u64 = 0;
+    let mut interrupts: u64 = 0;
+    let mut procs_running: u64 = 0;
+    let mut procs_blocked: u64 = 0;
+
+    for line in content.lines() {
+        if line.starts_with("cpu ") {
+            total_times = parse_cpu_line(line)?;
+        } else if line.starts_with("cpu") {
+            // Per-core line like "cpu0", "cpu1", etc.
+            let core_id: usize = line[3..].split_whitespace()
+                .next()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(0);
+            core_times.insert(core_id, parse_cpu_line(line)?);
+        } else if line.starts_with("ctxt ") {
+            context_switches = line.split_whitespace()
+                .nth(1)
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(0);
+        } else if line.starts_with("intr ") {
+            interrupts = line.split_whitespace()
+                .nth(1)
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(0);
+        } else if line.starts_with("procs_running ") {
+            procs_running = line.split_whitespace()
+                .nth(1)
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(0);
+        } else if line.starts_with("procs_blocked ") {
+            procs_blocked = line.split_whitespace()
+                .nth(1)
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(0);
+        }
+    }
+
+    Ok(ProcStat { total_times, core_times, context_switches, interrupts, procs_running, procs_blocked })
+}
+
 fn parse_cpu_line(line: &str) -> Result<CpuTimes> {
-    let parts: Vec<u64> = line
+    let mut parts = [0u64; 10];
+    let values = line
         .split_whitespace()
         .skip(1) // Skip "cpu" or "cpuN"
-        .filter_map(|s| s.parse().ok())
-        .collect();
-
-    Ok(CpuTimes {
-        user: *parts.first().unwrap_or(&0),
-        nice: *parts.get(1).unwrap_or(&0),
-        system: *parts.get(2).unwrap_or(&0),
-        idle: *parts.get(3).unwrap_or(&0),
-        iowait: *parts.get(4).unwrap_or(&0),
-        irq: *parts.get(5).unwrap_or(&0),
-        softirq: *parts.get(6).unwrap_or(&0),
-        steal: *parts.get(7).unwrap_or(&0),
-        guest: *parts.get(8).unwrap_or(&0),
-        guest_nice: *parts.get(9).unwrap_or(&0),
-    })
-}
-
-fn calculate_utilization(prev: &CpuTimes, curr: &CpuTimes) -> (f64, f64, f64, f64) {
+        .filter_map(|s| s.parse().ok());
+    for (slot, value) in parts.iter_mut().zip(values) {
+        *slot = value;
+    }
+    let [user, nice, system, idle, iowait, irq, softirq, steal, guest, guest_nice] = parts;
+
+    Ok(CpuTimes { user, nice, system, idle, iowait, irq, softirq, steal, guest, guest_nice })
+}
+
+/// Percentage breakdown of CPU time between two readings
+#[derive(Debug, Clone, Copy, Default)]
+struct Utilization {
+    total: f64,
+    user: f64,
+    system: f64,
+    iowait: f64,
+    irq: f64,
+    softirq: f64,
+    steal: f64,
+}
+
+fn calculate_utilization(prev: &CpuTimes, curr: &CpuTimes) -> Utilization {
+    // A CPU brought back online restarts its counters; mixing them with the old
+    // idle total would read as a 100% spike
+    if super::counters_reset(curr.busy_counters().into_iter().zip(prev.busy_counters())) {
+        return Utilization::default();
+    }
     let total_delta = curr.total().saturating_sub(prev.total());
     if total_delta == 0 {
-        return (0.0, 0.0, 0.0, 0.0);
+        return Utilization::default();
     }
 
     let idle_delta = (curr.idle + curr.iowait).saturating_sub(prev.idle + prev.iowait);
-    let user_delta = curr.user.saturating_sub(prev.user);
-    let system_delta = curr.system.saturating_sub(prev.system);
-    let iowait_delta = curr.iowait.saturating_sub(prev.iowait);
+    let pct = |curr: u64, prev: u64| 100.0 * (curr.saturating_sub(prev) as f64 / total_delta as f64);
+
+    Utilization {
+        total: 100.0 * (1.0 - (idle_delta as f64 / total_delta as f64)),
+        user: pct(curr.user, prev.user),
+        system: pct(curr.system, prev.system),
+        iowait: pct(curr.iowait, prev.iowait),
+        irq: pct(curr.irq, prev.irq),
+        softirq: pct(curr.softirq, prev.softirq),
+        steal: pct(curr.steal, prev.steal),
+    }
+}
+
+/// Cumulative cgroup CPU counters
+#[derive(Default)]
+struct CgroupCpuStat {
+    /// CPU time used by the cgroup in microseconds (cpu.stat usage_usec, or v1 cpuacct.usage)
+    usage_usec: Option<u64>,
+    /// None when CPU bandwidth control is not available
+    throttling: Option<CpuThrottling>,
+}
+
+/// Cumulative usage and throttling counters from cgroup v2 cpu.stat, or v1
+/// cpu.stat and cpuacct.usage (all None when no CPU controller is present)
+fn read_cgroup_cpu_stat() -> CgroupCpuStat {
+    let (content, v1) = match fs::read_to_string("/sys/fs/cgroup/cpu.stat") {
+        Ok(content) => (content, false),
+        Err(_) => match fs::read_to_string("/sys/fs/cgroup/cpu/cpu.stat") {
+            Ok(content) => (content, true),
+            Err(_) => return CgroupCpuStat::default(),
+        },
+    };
 
-    let total_util = 100.0 * (1.0 - (idle_delta as f64 / total_delta as f64));
-    let user_pct = 100.0 * (user_delta as f64 / total_delta as f64);
-    let sys_pct = 100.0 * (system_delta as f64 / total_delta as f64);
-    let iowait_pct = 100.0 * (iowait_delta as f64 / total_delta as f64);
+    let mut usage_usec = None;
+    let mut periods = None;
+    let mut stats = CpuThrottling::default();
+    for line in content.lines() {
+        let mut parts = line.split_whitespace();
+        let (key, value) = match (parts.next(), parts.next().and_then(|v| v.parse::<u64>().ok())) {
+            (Some(key), Some(value)) => (key, value),
+            _ => continue,
+        };
+        match key {
+            "usage_usec" if !v1 => usage_usec = Some(value),
+            "nr_periods" => periods = Some(value),
+            "nr_throttled" => stats.throttled_periods = value,
+            "throttled_usec" if !v1 => stats.throttled_usec = value,
+            "throttled_time" if v1 => stats.throttled_usec = value / 1000, // nanoseconds
+            _ => {}
+        }
+    }
+
+    if v1 {
+        // The v1 cpuacct controller is usually co-mounted with cpu ("cpu,cpuacct")
+        usage_usec = ["/sys/fs/cgroup/cpuacct/cpuacct.usage", "/sys/fs/cgroup/cpu/cpuacct.usage"]
+            .iter()
+            .find_map(|path| fs::read_to_string(path).ok())
+            .and_then(|s| s.trim().parse::<u64>().ok())
+            .map(|nanos| nanos / 1000);
+    }
 
-    (total_util, user_pct, sys_pct, iowait_pct)
+    CgroupCpuStat {
+        usage_usec,
+        // nr_periods is only reported when CPU bandwidth control is available
+        throttling: periods.map(|periods| CpuThrottling { periods, ..stats }),
+    }
 }
 
-fn read_load_average() -> Result<(f64, f64, f64)> {
-    let content = fs::read_to_string("/proc/loadavg")
+/// CFS quota in cores from cgroup v2 cpu.max ("200000 100000") or v1
+/// cpu.cfs_quota_us / cpu.cfs_period_us (None when unlimited: "max" or -1)
+fn read_cgroup_cpu_limit() -> Option<f64> {
+    let (quota, period) = match fs::read_to_string("/sys/fs/cgroup/cpu.max") {
+        Ok(content) => {
+            let mut parts = content.split_whitespace();
+            let quota: i64 = parts.next()?.parse().ok()?;
+            (quota, parts.next()?.parse::<i64>().ok()?)
+        }
+        Err(_) => {
+            let read = |name: &str| -> Option<i64> {
+                fs::read_to_string(format!("/sys/fs/cgroup/cpu/{}", name)).ok()?.trim().parse().ok()
+            };
+            (read("cpu.cfs_quota_us")?, read("cpu.cfs_period_us")?)
+        }
+    };
+    (quota > 0 && period > 0).then(|| quota as f64 / period as f64)
+}
+
+/// Current frequency of a core in MHz from cpufreq (reported in kHz)
+fn read_core_frequency(core_id: usize) -> Option<f64> {
+    fs::read_to_string(format!("/sys/devices/system/cpu/cpu{}/cpufreq/scaling_cur_freq", core_id))
+        .ok()
+        .and_then(|s| s.trim().parse::<f64>().ok())
+        .map(|khz| khz / 1000.0)
+}
+
+fn read_load_average(file: &mut ProcFile, buf: &mut String) -> Result<(f64, f64, f64)> {
+    file.read_into(buf)
         .context("Failed to read /proc/loadavg")?;
 
-    let parts: Vec<f64> = content
-        .split_whitespace()
-        .take(3)
-        .filter_map(|s| s.parse().ok())
-        .collect();
-
-    Ok((
-        *parts.first().unwrap_or(&0.0),
-        *parts.get(1).unwrap_or(&0.0),
-        *parts.get(2).unwrap_or(&0.0),
-    ))
+    let mut parts = buf.split_whitespace().map(|s| s.parse().unwrap_or(0.0));
+    let mut next = || parts.next().unwrap_or(0.0);
+    Ok((next(), next(), next()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// /proc/stat of a 4-CPU host with cpu1 offline
+    const PROC_STAT: &str = "\
+cpu  400 0 200 3000 40 0 10 0 0 0
+cpu0 200 0 100 1000 20 0 5 0 0 0
+cpu2 100 0 50 1000 10 0 3 0 0 0
+cpu3 100 0 50 1000 10 0 2 0 0 0
+intr 12345 0 0
+ctxt 6789
+btime 1700000000
+processes 100
+procs_running 3
+procs_blocked 1
+";
+
+    #[test]
+    fn core_count_skips_offline_cores() {
+        let stat = parse_proc_stat(PROC_STAT).unwrap();
+        assert_eq!(stat.core_times.len(), 3);
+        let mut ids: Vec<usize> = stat.core_times.keys().copied().collect();
+        ids.sort();
+        assert_eq!(ids, [0, 2, 3]);
+        assert_eq!(stat.total_times.user, 400);
+        assert_eq!(stat.core_times[&2].idle, 1000);
+        assert_eq!((stat.context_switches, stat.interrupts), (6789, 12345));
+        assert_eq!((stat.procs_running, stat.procs_blocked), (3, 1));
+    }
+
+    #[test]
+    fn first_sample_counts_cores() {
+        // No previous reading yet: the count must come from this sample, not the last one
+        let metrics = CpuCollector::new().collect().unwrap();
+        assert!(metrics.core_count > 0);
+        assert_eq!(metrics.core_count, metrics.per_core.len());
+    }
+
+    fn times(user: u64, system: u64, idle: u64, iowait: u64) -> CpuTimes {
+        CpuTimes { user, system, idle, iowait, ..Default::default() }
+    }
+
+    #[test]
+    fn utilization_from_deltas() {
+        let u = calculate_utilization(&times(100, 100, 700, 100), &times(130, 120, 740, 110));
+        assert!((u.total - 50.0).abs() < 1e-9);
+        assert!((u.user - 30.0).abs() < 1e-9);
+        assert!((u.iowait - 10.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn hot_plugged_cpu_reports_idle_interval() {
+        // The core went offline and came back, restarting every counter
+        let u = calculate_utilization(&times(5000, 2000, 90000, 300), &times(3, 1, 40, 0));
+        assert_eq!(u.total, 0.0);
+        assert_eq!(u.user, 0.0);
+    }
+
+    #[test]
+    fn iowait_dip_keeps_utilization() {
+        // iowait steps back one tick while the CPU stays busy
+        let u = calculate_utilization(&times(100, 100, 700, 100), &times(160, 120, 721, 99));
+        assert!((u.total - 80.0).abs() < 1e-9, "{:?}", u);
+        assert!((u.user - 60.0).abs() < 1e-9);
+        assert_eq!(u.iowait, 0.0);
+    }
 }
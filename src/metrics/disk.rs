@@ -1,5 +1,6 @@
 //! Disk I/O metrics collection from /proc/diskstats.
 
+use super::ProcFile;
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -7,9 +8,9 @@ use std::fs;
 use std::path::Path;
 
 /// Per-disk I/O statistics
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct DiskStats {
-    /// Device name (e.g., "sda", "nvme0n1")
+    /// Device name (e.g., "sda", "nvme0n1", or "vg0-root" for device-mapper)
     pub device: String,
     /// Read throughput in bytes per second
     pub read_bytes_per_sec: f64,
@@ -37,6 +38,24 @@ pub struct DiskStats {
     pub bytes_read: u64,
     /// Total bytes written
     pub bytes_written: u64,
+    /// Discard (TRIM) throughput in bytes per second (None on kernels before 4.18)
+    #[serde(default)]
+    pub discard_bytes_per_sec: Option<f64>,
+    /// Cache flush requests per second (None on kernels before 5.5)
+    #[serde(default)]
+    pub flush_per_sec: Option<f64>,
+    /// Logical block size in bytes (smallest addressable unit)
+    #[serde(default)]
+    pub logical_block_size: u64,
+    /// Physical block size in bytes (writes smaller than this need read-modify-write)
+    #[serde(default)]
+    pub physical_block_size: u64,
+    /// Whether this is a partition (only collected with --disk-include-partitions or --disk-device)
+    #[serde(default)]
+    pub partition: bool,
+    /// Whether `device` is a /dev/mapper name rather than the kernel's dm-N
+    #[serde(default)]
+    pub mapper_name: bool,
 }
 
 /// Raw disk statistics from /proc/diskstats
@@ -53,10 +72,32 @@ struct RawDiskStats {
     ios_in_progress: u64,
     time_doing_ios_ms: u64,
     weighted_time_ms: u64,
+    // Discard fields 14-17 appeared in Linux 4.18, flush fields 18-19 in 5.5;
+    // older kernels print only the first 11 counters
+    sectors_discarded: Option<u64>,
+    flushes_completed: Option<u64>,
+}
+
+impl RawDiskStats {
+    /// Counters to take this read's deltas from: `prev`, or this read itself when a
+    /// re-created device started its counters over, so the interval reports as idle
+    fn delta_base<'a>(&'a self, prev: &'a RawDiskStats) -> &'a RawDiskStats {
+        if super::counters_reset([
+            (self.reads_completed, prev.reads_completed),
+            (self.writes_completed, prev.writes_completed),
+            (self.sectors_read, prev.sectors_read),
+            (self.sectors_written, prev.sectors_written),
+            (self.time_doing_ios_ms, prev.time_doing_ios_ms),
+        ]) {
+            self
+        } else {
+            prev
+        }
+    }
 }
 
 /// Aggregated disk metrics
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct DiskMetrics {
     /// Per-disk statistics
     pub disks: Vec<DiskStats>,
@@ -68,6 +109,9 @@ pub struct DiskMetrics {
     pub total_in_flight: u64,
     /// Spill directory information (if configured)
     pub spill_dir_info: Option<SpillDirInfo>,
+    /// Usage of each filesystem given with --watch-fs (missing paths are skipped)
+    #[serde(default)]
+    pub watched_fs: Vec<SpillDirInfo>,
 }
 
 /// Information about a spill/temp directory
@@ -78,6 +122,13 @@ pub struct SpillDirInfo {
     pub available_bytes: u64,
     pub total_bytes: u64,
     pub used_percent: f64,
+    /// Inode counts (0 on filesystems without a fixed inode table, e.g. btrfs)
+    #[serde(default)]
+    pub inodes_total: u64,
+    #[serde(default)]
+    pub inodes_free: u64,
+    #[serde(default)]
+    pub inodes_used_percent: f64,
 }
 
 /// Disk metrics collector with state for rate calculations
@@ -85,7 +136,19 @@ pub struct DiskCollector {
     prev_stats: HashMap<String, RawDiskStats>,
     prev_time_ms: u64,
     spill_dir: Option<String>,
-    sector_size: u64, // Usually 512 bytes
+    watched_fs: Vec<String>,
+    include_partitions: bool,
+    device_allowlist: Vec<String>,
+    /// Unit of /proc/diskstats sector counts: always 512 bytes, whatever the
+    /// device's logical block size (the kernel converts before reporting)
+    sector_size: u64,
+    /// Logical and physical block size per device, read from sysfs once
+    block_sizes: HashMap<String, (u64, u64)>,
+    /// Device-mapper name per dm-N device, read from sysfs once
+    dm_names: HashMap<String, Option<String>>,
+    diskstats_file: ProcFile,
+    /// Reused across samples for /proc/diskstats
+    buf: String,
 }
 
 impl DiskCollector {
@@ -94,7 +157,14 @@ impl DiskCollector {
             prev_stats: HashMap::new(),
             prev_time_ms: 0,
             spill_dir: None,
+            watched_fs: Vec::new(),
+            include_partitions: false,
+            device_allowlist: Vec::new(),
             sector_size: 512,
+            block_sizes: HashMap::new(),
+            dm_names: HashMap::new(),
+            diskstats_file: ProcFile::new("/proc/diskstats"),
+            buf: String::new(),
         }
     }
 
@@ -103,6 +173,36 @@ impl DiskCollector {
         self.spill_dir = Some(path.to_string());
     }
 
+    /// Add a filesystem (any path on it) whose usage is reported each sample
+    pub fn add_watched_fs(&mut self, path: &str) {
+        self.watched_fs.push(path.to_string());
+    }
+
+    /// Also collect partitions, not just whole disks
+    pub fn set_include_partitions(&mut self, include: bool) {
+        self.include_partitions = include;
+    }
+
+    /// Restrict collection to the given device; once any is added only listed
+    /// devices are collected, including dm-* and partitions (device-mapper
+    /// names such as "vg0-root" are accepted too)
+    pub fn add_device(&mut self, name: &str) {
+        self.device_allowlist.push(name.to_string());
+    }
+
+    /// Decide whether a /proc/diskstats device (with its mapper name, for dm-N) is collected
+    fn should_collect(&self, device: &str, dm_name: Option<&str>, partition: bool) -> bool {
+        if !self.device_allowlist.is_empty() {
+            return self.device_allowlist.iter().any(|name| name == device || Some(name.as_str()) == dm_name);
+        }
+
+        // By default only whole physical disks: no loop devices, ram disks or device-mapper
+        if device.starts_with("loop") || device.starts_with("ram") || device.starts_with("dm-") {
+            return false;
+        }
+        !partition || self.include_partitions
+    }
+
     /// Collect current disk metrics
     pub fn collect(&mut self) -> Result<DiskMetrics> {
         let now_ms = std::time::SystemTime::now()
@@ -110,28 +210,16 @@ impl DiskCollector {
             .map(|d| d.as_millis() as u64)
             .unwrap_or(0);
 
-        let diskstats = fs::read_to_string("/proc/diskstats")
+        self.diskstats_file.read_into(&mut self.buf)
             .context("Failed to read /proc/diskstats")?;
 
         let mut current_stats: HashMap<String, RawDiskStats> = HashMap::new();
         let mut disks = Vec::new();
 
-        for line in diskstats.lines() {
-            let parts: Vec<&str> = line.split_whitespace().collect();
-            if parts.len() < 14 {
+        for line in self.buf.lines() {
+            let Some((device, stats)) = parse_diskstats_line(line) else {
                 continue;
-            }
-
-            let device = parts[2].to_string();
-
-            // Skip partitions (e.g., sda1) - only monitor whole disks
-            // Also skip loop devices and ram disks
-            if device.starts_with("loop")
-                || device.starts_with("ram")
-                || device.starts_with("dm-")
-            {
-                continue;
-            }
+            };
 
             // Check if it's a partition (ends with number for non-nvme, or has 'p' followed by number for nvme)
             let is_partition = if device.starts_with("nvme") {
@@ -140,32 +228,21 @@ impl DiskCollector {
                 device.chars().last().map(|c| c.is_ascii_digit()).unwrap_or(false)
             };
 
-            if is_partition {
+            let dm_name = cached_dm_name(&mut self.dm_names, device);
+            if !self.should_collect(device, dm_name.as_deref(), is_partition) {
                 continue;
             }
 
-            let stats = RawDiskStats {
-                reads_completed: parts[3].parse().unwrap_or(0),
-                reads_merged: parts[4].parse().unwrap_or(0),
-                sectors_read: parts[5].parse().unwrap_or(0),
-                time_reading_ms: parts[6].parse().unwrap_or(0),
-                writes_completed: parts[7].parse().unwrap_or(0),
-                writes_merged: parts[8].parse().unwrap_or(0),
-                sectors_written: parts[9].parse().unwrap_or(0),
-                time_writing_ms: parts[10].parse().unwrap_or(0),
-                ios_in_progress: parts[11].parse().unwrap_or(0),
-                time_doing_ios_ms: parts[12].parse().unwrap_or(0),
-                weighted_time_ms: parts[13].parse().unwrap_or(0),
-            };
-
-            current_stats.insert(device.clone(), stats.clone());
+            current_stats.insert(device.to_string(), stats.clone());
 
             // Calculate rates if we have previous data
-            if let Some(prev) = self.prev_stats.get(&device) {
+            if let Some(prev) = self.prev_stats.get(device) {
                 let time_delta_ms = now_ms.saturating_sub(self.prev_time_ms);
                 if time_delta_ms > 0 {
                     let time_delta_sec = time_delta_ms as f64 / 1000.0;
 
+                    let prev = stats.delta_base(prev);
+
                     let reads_delta = stats.reads_completed.saturating_sub(prev.reads_completed);
                     let writes_delta = stats.writes_completed.saturating_sub(prev.writes_completed);
                     let sectors_read_delta = stats.sectors_read.saturating_sub(prev.sectors_read);
@@ -174,8 +251,14 @@ impl DiskCollector {
                     let time_writing_delta = stats.time_writing_ms.saturating_sub(prev.time_writing_ms);
                     let time_ios_delta = stats.time_doing_ios_ms.saturating_sub(prev.time_doing_ios_ms);
 
-                    let read_bytes_per_sec = (sectors_read_delta * self.sector_size) as f64 / time_delta_sec;
-                    let write_bytes_per_sec = (sectors_written_delta * self.sector_size) as f64 / time_delta_sec;
+                    let read_bytes_per_sec = bytes_per_sec(sectors_read_delta, self.sector_size, time_delta_sec);
+                    let write_bytes_per_sec = bytes_per_sec(sectors_written_delta, self.sector_size, time_delta_sec);
+                    let discard_bytes_per_sec = stats.sectors_discarded
+                        .zip(prev.sectors_discarded)
+                        .map(|(curr, prev)| bytes_per_sec(curr.saturating_sub(prev), self.sector_size, time_delta_sec));
+                    let flush_per_sec = stats.flushes_completed
+                        .zip(prev.flushes_completed)
+                        .map(|(curr, prev)| curr.saturating_sub(prev) as f64 / time_delta_sec);
                     let read_iops = reads_delta as f64 / time_delta_sec;
                     let write_iops = writes_delta as f64 / time_delta_sec;
 
@@ -195,11 +278,19 @@ impl DiskCollector {
                     let utilization_percent = (time_ios_delta as f64 / time_delta_ms as f64) * 100.0;
                     let utilization_percent = utilization_percent.min(100.0);
 
-                    // Queue depth from weighted time
-                    let queue_depth = stats.weighted_time_ms as f64 / time_delta_ms as f64;
+                    // Average requests in flight: weighted I/O time accrued over the interval per elapsed ms
+                    let weighted_delta = stats.weighted_time_ms.saturating_sub(prev.weighted_time_ms);
+                    let queue_depth = weighted_delta as f64 / time_delta_ms as f64;
+
+                    let (logical_block_size, physical_block_size) = match self.block_sizes.get(device) {
+                        Some(&sizes) => sizes,
+                        None => *self.block_sizes.entry(device.to_string()).or_insert(read_block_sizes(device)),
+                    };
 
+                    let mapper_name = dm_name.is_some();
                     disks.push(DiskStats {
-                        device: device.clone(),
+                        // dm-N is meaningless to users; prefer the LVM/crypt mapper name
+                        device: dm_name.unwrap_or_else(|| device.to_string()),
                         read_bytes_per_sec,
                         write_bytes_per_sec,
                         read_iops,
@@ -213,18 +304,28 @@ impl DiskCollector {
                         writes_completed: stats.writes_completed,
                         bytes_read: stats.sectors_read * self.sector_size,
                         bytes_written: stats.sectors_written * self.sector_size,
+                        discard_bytes_per_sec,
+                        flush_per_sec,
+                        logical_block_size,
+                        physical_block_size,
+                        partition: is_partition,
+                        mapper_name,
                     });
                 }
             }
         }
 
-        // Calculate totals
-        let total_read = disks.iter().map(|d| d.read_bytes_per_sec).sum();
-        let total_write = disks.iter().map(|d| d.write_bytes_per_sec).sum();
-        let total_in_flight = disks.iter().map(|d| d.in_flight).sum();
+        // Calculate totals (partition I/O is already counted by its whole disk,
+        // unless only partitions were selected)
+        let has_whole_disk = disks.iter().any(|d| !d.partition);
+        let counted = || disks.iter().filter(|d| !(d.partition && has_whole_disk));
+        let total_read = counted().map(|d| d.read_bytes_per_sec).sum();
+        let total_write = counted().map(|d| d.write_bytes_per_sec).sum();
+        let total_in_flight = counted().map(|d| d.in_flight).sum();
 
         // Get spill directory info
         let spill_dir_info = self.spill_dir.as_ref().and_then(|path| get_dir_info(path));
+        let watched_fs = self.watched_fs.iter().filter_map(|path| get_dir_info(path)).collect();
 
         // Update state
         self.prev_stats = current_stats;
@@ -236,6 +337,7 @@ impl DiskCollector {
             total_write_bytes_per_sec: total_write,
             total_in_flight,
             spill_dir_info,
+            watched_fs,
         })
     }
 }
@@ -246,6 +348,80 @@ impl Default for DiskCollector {
     }
 }
 
+/// Device name and counters from one /proc/diskstats line (None for short lines)
+fn parse_diskstats_line(line: &str) -> Option<(&str, RawDiskStats)> {
+    // Fields past the end of an older kernel's line stay empty and parse as None
+    let mut parts = [""; 20];
+    for (slot, field) in parts.iter_mut().zip(line.split_whitespace()) {
+        *slot = field;
+    }
+    if parts[13].is_empty() {
+        return None;
+    }
+
+    Some((parts[2], RawDiskStats {
+        reads_completed: parts[3].parse().unwrap_or(0),
+        reads_merged: parts[4].parse().unwrap_or(0),
+        sectors_read: parts[5].parse().unwrap_or(0),
+        time_reading_ms: parts[6].parse().unwrap_or(0),
+        writes_completed: parts[7].parse().unwrap_or(0),
+        writes_merged: parts[8].parse().unwrap_or(0),
+        sectors_written: parts[9].parse().unwrap_or(0),
+        time_writing_ms: parts[10].parse().unwrap_or(0),
+        ios_in_progress: parts[11].parse().unwrap_or(0),
+        time_doing_ios_ms: parts[12].parse().unwrap_or(0),
+        weighted_time_ms: parts[13].parse().unwrap_or(0),
+        sectors_discarded: parts[16].parse().ok(),
+        flushes_completed: parts[18].parse().ok(),
+    }))
+}
+
+/// Throughput of `sectors` diskstats sectors of `sector_size` bytes over `secs` seconds
+fn bytes_per_sec(sectors: u64, sector_size: u64, secs: f64) -> f64 {
+    (sectors * sector_size) as f64 / secs
+}
+
+/// Logical and physical block size of a device from sysfs (512 when unknown).
+/// Partitions have no queue directory of their own, so fall back to the parent disk's.
+fn read_block_sizes(device: &str) -> (u64, u64) {
+    block_sizes_in(Path::new("/sys/class/block"), device)
+}
+
+/// `read_block_sizes` below a sysfs block class directory
+fn block_sizes_in(class_dir: &Path, device: &str) -> (u64, u64) {
+    let read = |file: &str| -> Option<u64> {
+        [
+            class_dir.join(device).join("queue").join(file),
+            class_dir.join(device).join("..").join("queue").join(file),
+        ]
+        .iter()
+        .find_map(|path| fs::read_to_string(path).ok())
+        .and_then(|s| s.trim().parse().ok())
+    };
+
+    let logical = read("logical_block_size").or_else(|| read("hw_sector_size")).unwrap_or(512);
+    let physical = read("physical_block_size").unwrap_or(logical);
+    (logical, physical)
+}
+
+/// Device-mapper name of a dm-N device (e.g. "vg0-root"), read on first use and
+/// cached (sysfs lookups for every device on every sample add up at short intervals)
+fn cached_dm_name(cache: &mut HashMap<String, Option<String>>, device: &str) -> Option<String> {
+    if !device.starts_with("dm-") {
+        return None;
+    }
+    cache.entry(device.to_string())
+        .or_insert_with(|| read_dm_name(device))
+        .clone()
+}
+
+/// Device-mapper name of a dm-N device from sysfs
+fn read_dm_name(device: &str) -> Option<String> {
+    fs::read_to_string(format!("/sys/block/{}/dm/name", device))
+        .ok()
+        .map(|name| name.trim().to_string())
+}
+
 fn get_dir_info(path: &str) -> Option<SpillDirInfo> {
     let path = Path::new(path);
     if !path.exists() {
@@ -279,15 +455,114 @@ fn get_dir_info(path: &str) -> Option<SpillDirInfo> {
                 0.0
             };
 
+            let inodes_total = statvfs.f_files;
+            let inodes_free = statvfs.f_ffree;
+            let inodes_used_percent = if inodes_total > 0 {
+                100.0 * inodes_total.saturating_sub(inodes_free) as f64 / inodes_total as f64
+            } else {
+                0.0
+            };
+
             return Some(SpillDirInfo {
                 path: path.to_string_lossy().to_string(),
                 used_bytes,
                 available_bytes,
                 total_bytes,
                 used_percent,
+                inodes_total,
+                inodes_free,
+                inodes_used_percent,
             });
         }
     }
 
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diskstats_line_fields() {
+        let line = "   8       0 sda 100 5 2048 40 50 2 4096 60 1 90 100 10 0 64 0 7 3";
+        let (device, stats) = parse_diskstats_line(line).unwrap();
+        assert_eq!(device, "sda");
+        assert_eq!(stats.reads_completed, 100);
+        assert_eq!(stats.sectors_read, 2048);
+        assert_eq!(stats.sectors_written, 4096);
+        assert_eq!(stats.weighted_time_ms, 100);
+        assert_eq!(stats.sectors_discarded, Some(64));
+        assert_eq!(stats.flushes_completed, Some(7));
+    }
+
+    #[test]
+    fn diskstats_line_from_older_kernel() {
+        // Before 4.18 there are no discard fields, before 5.5 no flush fields
+        let (_, stats) = parse_diskstats_line("   8       0 sda 100 5 2048 40 50 2 4096 60 1 90 100").unwrap();
+        assert_eq!(stats.sectors_discarded, None);
+        assert_eq!(stats.flushes_completed, None);
+        assert!(parse_diskstats_line("   8       0 sda 100 5 2048").is_none());
+    }
+
+    #[test]
+    fn dm_names_are_cached() {
+        let mut cache = HashMap::new();
+        cache.insert("dm-0".to_string(), Some("vg0-root".to_string()));
+        assert_eq!(cached_dm_name(&mut cache, "dm-0").as_deref(), Some("vg0-root"));
+        assert_eq!(cached_dm_name(&mut cache, "sda"), None);
+        assert!(!cache.contains_key("sda"));
+    }
+
+    #[test]
+    fn allowlist_matches_mapper_names() {
+        let mut collector = DiskCollector::new();
+        collector.add_device("vg0-root");
+        assert!(collector.should_collect("dm-0", Some("vg0-root"), false));
+        assert!(!collector.should_collect("dm-1", Some("vg0-swap"), false));
+        assert!(!collector.should_collect("sda", None, false));
+        assert!(!DiskCollector::new().should_collect("dm-0", Some("vg0-root"), false));
+    }
+
+    #[test]
+    fn throughput_stays_in_sectors_on_4k_devices() {
+        let class_dir = std::env::temp_dir().join(format!("monperf-test-{}-sysblock", std::process::id()));
+        let queue = class_dir.join("nvme0n1").join("queue");
+        fs::create_dir_all(&queue).unwrap();
+        fs::write(queue.join("logical_block_size"), "4096\n").unwrap();
+        fs::write(queue.join("physical_block_size"), "4096\n").unwrap();
+        let sizes = block_sizes_in(&class_dir, "nvme0n1");
+        fs::remove_dir_all(&class_dir).unwrap();
+        assert_eq!(sizes, (4096, 4096));
+
+        // /proc/diskstats counts 512-byte sectors whatever the logical block size
+        let collector = DiskCollector::new();
+        let (_, prev) = parse_diskstats_line(" 259 0 nvme0n1 100 0 2048 0 50 0 4096 0 0 0 0").unwrap();
+        let (_, curr) = parse_diskstats_line(" 259 0 nvme0n1 200 0 4096 0 60 0 4096 0 0 0 0").unwrap();
+        let rate = bytes_per_sec(curr.sectors_read - prev.sectors_read, collector.sector_size, 1.0);
+        assert_eq!(rate, 2048.0 * 512.0);
+    }
+
+    #[test]
+    fn throughput_from_diskstats_sectors() {
+        let (_, prev) = parse_diskstats_line("   8 0 sda 100 0 2048 0 50 0 4096 0 0 0 0").unwrap();
+        let (_, curr) = parse_diskstats_line("   8 0 sda 200 0 6144 0 60 0 5120 0 0 0 0").unwrap();
+        // 4096 sectors of 512 bytes in 2 s, whatever the device's logical block size
+        assert_eq!(bytes_per_sec(curr.sectors_read - prev.sectors_read, 512, 2.0), 1024.0 * 1024.0);
+        assert_eq!(bytes_per_sec(curr.sectors_written - prev.sectors_written, 512, 0.5), 1024.0 * 1024.0);
+    }
+
+    #[test]
+    fn recreated_device_reports_idle_interval() {
+        let (_, prev) = parse_diskstats_line("   8 0 sda 100 0 2048 0 50 0 4096 0 0 90 0").unwrap();
+        // Sector counters restarted while the new device already completed more reads
+        let (_, curr) = parse_diskstats_line("   8 0 sda 150 0 16 0 2 0 8 0 0 3 0").unwrap();
+        let base = curr.delta_base(&prev);
+        assert_eq!(curr.reads_completed - base.reads_completed, 0);
+        assert_eq!(curr.sectors_read - base.sectors_read, 0);
+        assert_eq!(curr.sectors_written - base.sectors_written, 0);
+
+        let (_, next) = parse_diskstats_line("   8 0 sda 160 0 32 0 4 0 8 0 0 5 0").unwrap();
+        assert_eq!(next.sectors_read - next.delta_base(&curr).sectors_read, 16);
+    }
+}
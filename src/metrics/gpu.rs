@@ -0,0 +1,135 @@
+//! GPU utilization and memory from `nvidia-smi` (--gpu).
+//!
+//! Querying the tool instead of linking NVML keeps the binary free of a driver
+//! dependency: on hosts without NVIDIA hardware it is missing and no panel is shown.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::process::Command;
+
+/// Fields requested from `nvidia-smi --query-gpu`, in the order they are parsed
+const QUERY_FIELDS: &str = "index,name,utilization.gpu,memory.used,memory.total,temperature.gpu,power.draw";
+
+/// Metrics for a single GPU
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GpuStats {
+    /// Index as numbered by the driver (matches CUDA_VISIBLE_DEVICES order)
+    pub index: u32,
+    pub name: String,
+    /// Share of the last sample period one or more kernels were running
+    pub utilization_percent: f64,
+    pub memory_used_bytes: u64,
+    pub memory_total_bytes: u64,
+    pub memory_percent: f64,
+    /// Core temperature in °C (None if the board doesn't report it)
+    pub temperature_c: Option<f64>,
+    /// Board power draw in watts (None if the board doesn't report it)
+    pub power_watts: Option<f64>,
+}
+
+/// Metrics for all GPUs
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GpuMetrics {
+    pub gpus: Vec<GpuStats>,
+}
+
+/// GPU metrics collector
+pub struct GpuCollector;
+
+impl GpuCollector {
+    /// A collector if `nvidia-smi` runs and reports at least one GPU, None otherwise
+    pub fn detect() -> Option<Self> {
+        let mut collector = Self;
+        match collector.collect() {
+            Ok(metrics) if !metrics.gpus.is_empty() => Some(collector),
+            _ => None,
+        }
+    }
+
+    /// Collect current GPU metrics
+    pub fn collect(&mut self) -> Result<GpuMetrics> {
+        let output = Command::new("nvidia-smi")
+            .arg(format!("--query-gpu={}", QUERY_FIELDS))
+            .arg("--format=csv,noheader,nounits")
+            .output()
+            .context("Failed to run nvidia-smi")?;
+        if !output.status.success() {
+            anyhow::bail!("nvidia-smi failed: {}", String::from_utf8_lossy(&output.stderr).trim());
+        }
+        Ok(GpuMetrics {
+            gpus: String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .filter_map(parse_gpu_line)
+                .collect(),
+        })
+    }
+}
+
+/// Parse a line such as "0, NVIDIA A100-SXM4-80GB, 87, 30512, 81920, 61, 254.12" (MiB, °C, W)
+fn parse_gpu_line(line: &str) -> Option<GpuStats> {
+    let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+    if fields.len() < 5 {
+        return None;
+    }
+    // Readings a board doesn't support come back as "[N/A]" or "[Not Supported]"
+    let number = |i: usize| fields.get(i).and_then(|s| s.parse::<f64>().ok());
+    let mib = |i: usize| number(i).map_or(0, |v| (v * 1024.0 * 1024.0) as u64);
+
+    let memory_used_bytes = mib(3);
+    let memory_total_bytes = mib(4);
+    Some(GpuStats {
+        index: fields[0].parse().ok()?,
+        name: fields[1].to_string(),
+        utilization_percent: number(2).unwrap_or(0.0),
+        memory_used_bytes,
+        memory_total_bytes,
+        memory_percent: if memory_total_bytes > 0 {
+            100.0 * memory_used_bytes as f64 / memory_total_bytes as f64
+        } else {
+            0.0
+        },
+        temperature_c: number(5),
+        power_watts: number(6),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gpu_line_fields() {
+        let gpu = parse_gpu_line("0, NVIDIA A100-SXM4-80GB, 87, 30720, 81920, 61, 254.12").unwrap();
+        assert_eq!(gpu.index, 0);
+        assert_eq!(gpu.name, "NVIDIA A100-SXM4-80GB");
+        assert_eq!(gpu.utilization_percent, 87.0);
+        assert_eq!(gpu.memory_used_bytes, 30720 * 1024 * 1024);
+        assert_eq!(gpu.memory_total_bytes, 81920 * 1024 * 1024);
+        assert_eq!(gpu.memory_percent, 37.5);
+        assert_eq!(gpu.temperature_c, Some(61.0));
+        assert_eq!(gpu.power_watts, Some(254.12));
+    }
+
+    #[test]
+    fn unsupported_gpu_readings() {
+        // Memory and power the board doesn't report read as zero or None, not as a parse failure
+        let gpu = parse_gpu_line("1, NVIDIA GeForce GT 710, 3, [N/A], [N/A], 40, [Not Supported]").unwrap();
+        assert_eq!(gpu.index, 1);
+        assert_eq!((gpu.memory_used_bytes, gpu.memory_total_bytes), (0, 0));
+        assert_eq!(gpu.memory_percent, 0.0);
+        assert_eq!(gpu.temperature_c, Some(40.0));
+        assert_eq!(gpu.power_watts, None);
+
+        // Older tools stop after the memory columns
+        let gpu = parse_gpu_line("0, Tesla K80, 12, 100, 11441").unwrap();
+        assert_eq!((gpu.temperature_c, gpu.power_watts), (None, None));
+    }
+
+    #[test]
+    fn malformed_gpu_lines() {
+        assert!(parse_gpu_line("0, NVIDIA A100-SXM4-80GB, 87").is_none());
+        assert!(parse_gpu_line("").is_none());
+        assert!(parse_gpu_line("No devices were found").is_none());
+        assert!(parse_gpu_line("[N/A], NVIDIA A100, 87, 100, 200").is_none());
+    }
+}
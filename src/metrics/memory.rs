@@ -1,11 +1,13 @@
 //! Memory metrics collection from /proc/meminfo and cgroup files.
 
+use super::ProcFile;
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use std::fs;
+use std::time::Instant;
 
 /// Memory metrics
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct MemoryMetrics {
     /// Total system RAM in bytes
     pub total: u64,
@@ -35,6 +37,13 @@ pub struct MemoryMetrics {
     pub cgroup_current: Option<u64>,
     /// Cgroup memory usage percentage
     pub cgroup_usage_percent: Option<f64>,
+    /// Cgroup swap usage in bytes (cgroup v2 with swap accounting)
+    pub cgroup_swap_current: Option<u64>,
+    /// Cgroup swap limit in bytes (None when unlimited)
+    pub cgroup_swap_limit: Option<u64>,
+    /// Cgroup v2 memory.stat breakdown (None on v1 or outside a cgroup)
+    #[serde(default)]
+    pub cgroup_stat: Option<CgroupMemStat>,
     /// Major page faults
     pub major_page_faults: u64,
     /// Minor page faults
@@ -47,12 +56,96 @@ pub struct MemoryMetrics {
     pub used_percent: f64,
     /// Swap used percentage
     pub swap_percent: f64,
+    /// Rate at which dirty pages accumulate (negative while writeback drains them)
+    pub dirty_growth_bytes_per_sec: Option<f64>,
+    /// Dirty + writeback level at which writers get throttled
+    /// (vm.dirty_bytes, or vm.dirty_ratio of free + file-backed memory)
+    pub dirty_limit: Option<u64>,
+    /// Configured huge pages (count)
+    #[serde(default)]
+    pub hugepages_total: u64,
+    /// Free huge pages (count)
+    #[serde(default)]
+    pub hugepages_free: u64,
+    /// Default huge page size in bytes
+    #[serde(default)]
+    pub hugepage_size: u64,
+    /// Kernel slab allocator memory in bytes
+    #[serde(default)]
+    pub slab: u64,
+    /// Slab memory the kernel can reclaim under pressure (dentries, inodes) in bytes
+    #[serde(default)]
+    pub slab_reclaimable: u64,
+    /// Slab memory that cannot be reclaimed in bytes (steady growth suggests a kernel leak)
+    #[serde(default)]
+    pub slab_unreclaimable: u64,
+    /// Per-node memory on NUMA systems (empty with a single node)
+    #[serde(default)]
+    pub numa_nodes: Vec<NumaNode>,
+}
+
+/// Breakdown of a cgroup's memory from memory.stat
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CgroupMemStat {
+    /// Anonymous memory (heap, stacks) in bytes
+    pub anon: u64,
+    /// File-backed page cache in bytes (reclaimable under pressure)
+    pub file: u64,
+    /// Kernel memory charged to the cgroup in bytes
+    pub kernel: u64,
+    /// Cumulative major page faults
+    pub pgmajfault: u64,
+    /// Cumulative pages scanned by reclaim
+    pub pgscan: u64,
+    /// Major page faults since the previous sample
+    pub pgmajfault_delta: Option<u64>,
+    /// Pages scanned by reclaim since the previous sample
+    pub pgscan_delta: Option<u64>,
+}
+
+/// Memory of one NUMA node
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NumaNode {
+    pub node_id: usize,
+    /// Node memory in bytes
+    pub total: u64,
+    /// Free node memory in bytes
+    pub free: u64,
+}
+
+impl NumaNode {
+    pub fn used(&self) -> u64 {
+        self.total.saturating_sub(self.free)
+    }
+
+    pub fn used_percent(&self) -> f64 {
+        if self.total == 0 {
+            0.0
+        } else {
+            100.0 * self.used() as f64 / self.total as f64
+        }
+    }
+}
+
+impl MemoryMetrics {
+    /// Dirty + writeback pages as a percentage of the throttling limit
+    pub fn dirty_limit_percent(&self) -> Option<f64> {
+        self.dirty_limit
+            .filter(|&limit| limit > 0)
+            .map(|limit| 100.0 * (self.dirty + self.writeback) as f64 / limit as f64)
+    }
 }
 
 /// Memory metrics collector with state for delta calculations
 pub struct MemoryCollector {
     prev_major_faults: Option<u64>,
     prev_minor_faults: Option<u64>,
+    prev_dirty: Option<(u64, Instant)>,
+    prev_cgroup_stat: Option<(u64, u64)>,
+    meminfo_file: ProcFile,
+    vmstat_file: ProcFile,
+    /// Reused across samples for /proc/meminfo and /proc/vmstat
+    buf: String,
 }
 
 impl MemoryCollector {
@@ -60,12 +153,17 @@ impl MemoryCollector {
         Self {
             prev_major_faults: None,
             prev_minor_faults: None,
+            prev_dirty: None,
+            prev_cgroup_stat: None,
+            meminfo_file: ProcFile::new("/proc/meminfo"),
+            vmstat_file: ProcFile::new("/proc/vmstat"),
+            buf: String::new(),
         }
     }
 
     /// Collect current memory metrics
     pub fn collect(&mut self) -> Result<MemoryMetrics> {
-        let meminfo = fs::read_to_string("/proc/meminfo")
+        self.meminfo_file.read_into(&mut self.buf)
             .context("Failed to read /proc/meminfo")?;
 
         let mut total: u64 = 0;
@@ -79,16 +177,22 @@ impl MemoryCollector {
         let mut inactive_file: u64 = 0;
         let mut swap_total: u64 = 0;
         let mut swap_free: u64 = 0;
+        let mut hugepages_total: u64 = 0;
+        let mut hugepages_free: u64 = 0;
+        let mut hugepage_size: u64 = 0;
+        let mut slab: u64 = 0;
+        let mut slab_reclaimable: u64 = 0;
+        let mut slab_unreclaimable: u64 = 0;
 
-        for line in meminfo.lines() {
-            let parts: Vec<&str> = line.split_whitespace().collect();
-            if parts.len() < 2 {
+        for line in self.buf.lines() {
+            let mut parts = line.split_whitespace();
+            let (Some(key), Some(value)) = (parts.next(), parts.next()) else {
                 continue;
-            }
+            };
 
-            let value: u64 = parts[1].parse().unwrap_or(0) * 1024; // Convert from KB to bytes
+            let value: u64 = value.parse().unwrap_or(0) * 1024; // Convert from KB to bytes
 
-            match parts[0] {
+            match key {
                 "MemTotal:" => total = value,
                 "MemFree:" => free = value,
                 "MemAvailable:" => available = value,
@@ -100,6 +204,13 @@ impl MemoryCollector {
                 "Inactive(file):" => inactive_file = value,
                 "SwapTotal:" => swap_total = value,
                 "SwapFree:" => swap_free = value,
+                // Huge page counts have no unit
+                "HugePages_Total:" => hugepages_total = value / 1024,
+                "HugePages_Free:" => hugepages_free = value / 1024,
+                "Hugepagesize:" => hugepage_size = value,
+                "Slab:" => slab = value,
+                "SReclaimable:" => slab_reclaimable = value,
+                "SUnreclaim:" => slab_unreclaimable = value,
                 _ => {}
             }
         }
@@ -108,7 +219,7 @@ impl MemoryCollector {
         let swap_used = swap_total.saturating_sub(swap_free);
 
         // Cgroup v2 memory limits
-        let (cgroup_limit, cgroup_current) = read_cgroup_memory();
+        let (cgroup_limit, cgroup_current, cgroup_swap_current, cgroup_swap_limit) = read_cgroup_memory();
         let cgroup_usage_percent = match (cgroup_limit, cgroup_current) {
             (Some(limit), Some(current)) if limit > 0 => {
                 Some(100.0 * current as f64 / limit as f64)
@@ -116,8 +227,18 @@ impl MemoryCollector {
             _ => None,
         };
 
+        // Cgroup v2 memory.stat breakdown, with fault and reclaim scan deltas
+        let cgroup_stat = read_cgroup_memory_stat().map(|mut stat| {
+            if let Some((prev_majfault, prev_scan)) = self.prev_cgroup_stat {
+                stat.pgmajfault_delta = Some(stat.pgmajfault.saturating_sub(prev_majfault));
+                stat.pgscan_delta = Some(stat.pgscan.saturating_sub(prev_scan));
+            }
+            stat
+        });
+        self.prev_cgroup_stat = cgroup_stat.as_ref().map(|s| (s.pgmajfault, s.pgscan));
+
         // Page faults from /proc/vmstat
-        let (major_faults, minor_faults) = read_page_faults();
+        let (major_faults, minor_faults) = read_page_faults(&mut self.vmstat_file, &mut self.buf);
 
         let major_delta = self.prev_major_faults.map(|prev| major_faults.saturating_sub(prev));
         let minor_delta = self.prev_minor_faults.map(|prev| minor_faults.saturating_sub(prev));
@@ -125,6 +246,16 @@ impl MemoryCollector {
         self.prev_major_faults = Some(major_faults);
         self.prev_minor_faults = Some(minor_faults);
 
+        // Dirty page growth since the previous sample
+        let now = Instant::now();
+        let dirty_growth = self.prev_dirty.and_then(|(prev, at)| {
+            let secs = now.duration_since(at).as_secs_f64();
+            (secs > 0.0).then(|| (dirty as f64 - prev as f64) / secs)
+        });
+        self.prev_dirty = Some((dirty, now));
+
+        let dirty_limit = read_dirty_limit(free + active_file + inactive_file);
+
         let used_percent = if total > 0 {
             100.0 * used as f64 / total as f64
         } else {
@@ -152,12 +283,24 @@ impl MemoryCollector {
             cgroup_limit,
             cgroup_current,
             cgroup_usage_percent,
+            cgroup_swap_current,
+            cgroup_swap_limit,
+            cgroup_stat,
             major_page_faults: major_faults,
             minor_page_faults: minor_faults,
             major_faults_delta: major_delta,
             minor_faults_delta: minor_delta,
             used_percent,
             swap_percent,
+            dirty_growth_bytes_per_sec: dirty_growth,
+            dirty_limit,
+            hugepages_total,
+            hugepages_free,
+            hugepage_size,
+            slab,
+            slab_reclaimable,
+            slab_unreclaimable,
+            numa_nodes: read_numa_nodes(),
         })
     }
 }
@@ -168,22 +311,107 @@ impl Default for MemoryCollector {
     }
 }
 
-fn read_cgroup_memory() -> (Option<u64>, Option<u64>) {
-    // Try cgroup v2 first
-    let limit = fs::read_to_string("/sys/fs/cgroup/memory.max")
-        .ok()
-        .and_then(|s| {
-            let trimmed = s.trim();
-            if trimmed == "max" {
-                None // No limit set
-            } else {
-                trimmed.parse().ok()
+/// Dirty page throttling limit, mirroring the kernel's global dirty threshold:
+/// vm.dirty_bytes when set, otherwise vm.dirty_ratio percent of dirtyable memory
+fn read_dirty_limit(dirtyable: u64) -> Option<u64> {
+    let read_sysctl = |name: &str| -> Option<u64> {
+        fs::read_to_string(format!("/proc/sys/vm/{}", name))
+            .ok()
+            .and_then(|s| s.trim().parse().ok())
+    };
+
+    match read_sysctl("dirty_bytes") {
+        Some(bytes) if bytes > 0 => Some(bytes),
+        _ => read_sysctl("dirty_ratio").map(|ratio| dirtyable / 100 * ratio),
+    }
+}
+
+/// Per-node totals from /sys/devices/system/node/nodeN/meminfo
+/// (empty unless there are at least two nodes)
+fn read_numa_nodes() -> Vec<NumaNode> {
+    let entries = match fs::read_dir("/sys/devices/system/node") {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut nodes = Vec::new();
+    for entry in entries.flatten() {
+        let node_id: usize = match entry.file_name().to_str()
+            .and_then(|name| name.strip_prefix("node"))
+            .and_then(|id| id.parse().ok())
+        {
+            Some(id) => id,
+            None => continue,
+        };
+        let meminfo = match fs::read_to_string(entry.path().join("meminfo")) {
+            Ok(content) => content,
+            Err(_) => continue,
+        };
+
+        // Lines look like "Node 0 MemTotal:       6127352 kB"
+        let mut node = NumaNode { node_id, total: 0, free: 0 };
+        for line in meminfo.lines() {
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            if parts.len() < 4 {
+                continue;
             }
-        });
+            let value: u64 = parts[3].parse().unwrap_or(0) * 1024;
+            match parts[2] {
+                "MemTotal:" => node.total = value,
+                "MemFree:" => node.free = value,
+                _ => {}
+            }
+        }
+        nodes.push(node);
+    }
 
-    let current = fs::read_to_string("/sys/fs/cgroup/memory.current")
-        .ok()
-        .and_then(|s| s.trim().parse().ok());
+    if nodes.len() < 2 {
+        return Vec::new();
+    }
+    nodes.sort_by_key(|n| n.node_id);
+    nodes
+}
+
+/// Parse /sys/fs/cgroup/memory.stat (cgroup v2 only; v1 uses different keys)
+fn read_cgroup_memory_stat() -> Option<CgroupMemStat> {
+    let content = fs::read_to_string("/sys/fs/cgroup/memory.stat").ok()?;
+
+    let mut stat = CgroupMemStat::default();
+    let mut kernel = None;
+    let mut kernel_parts: u64 = 0;
+    for line in content.lines() {
+        let mut parts = line.split_whitespace();
+        let (key, value) = match (parts.next(), parts.next().and_then(|v| v.parse::<u64>().ok())) {
+            (Some(key), Some(value)) => (key, value),
+            _ => continue,
+        };
+        match key {
+            "anon" => stat.anon = value,
+            "file" => stat.file = value,
+            "kernel" => kernel = Some(value),
+            // Older kernels have no "kernel" total, so add up its components
+            "kernel_stack" | "pagetables" | "percpu" | "sock" | "slab" => kernel_parts += value,
+            "pgmajfault" => stat.pgmajfault = value,
+            "pgscan" => stat.pgscan = value,
+            _ => {}
+        }
+    }
+    stat.kernel = kernel.unwrap_or(kernel_parts);
+    Some(stat)
+}
+
+/// Cgroup memory limit, usage, swap usage and swap limit
+fn read_cgroup_memory() -> (Option<u64>, Option<u64>, Option<u64>, Option<u64>) {
+    // Try cgroup v2 first ("max" means no limit set)
+    let read_v2 = |name: &str| -> Option<u64> {
+        fs::read_to_string(format!("/sys/fs/cgroup/{}", name))
+            .ok()
+            .and_then(|s| s.trim().parse().ok())
+    };
+    let limit = read_v2("memory.max");
+    let current = read_v2("memory.current");
+    let swap_current = read_v2("memory.swap.current");
+    let swap_limit = read_v2("memory.swap.max");
 
     // If v2 not available, try v1
     if limit.is_none() && current.is_none() {
@@ -203,25 +431,26 @@ fn read_cgroup_memory() -> (Option<u64>, Option<u64>) {
             .ok()
             .and_then(|s| s.trim().parse().ok());
 
-        return (limit_v1, current_v1);
+        return (limit_v1, current_v1, None, None);
     }
 
-    (limit, current)
+    (limit, current, swap_current, swap_limit)
 }
 
-fn read_page_faults() -> (u64, u64) {
-    let vmstat = fs::read_to_string("/proc/vmstat").unwrap_or_default();
+fn read_page_faults(file: &mut ProcFile, buf: &mut String) -> (u64, u64) {
+    if file.read_into(buf).is_err() {
+        buf.clear();
+    }
     let mut major: u64 = 0;
     let mut minor: u64 = 0;
 
-    for line in vmstat.lines() {
-        let parts: Vec<&str> = line.split_whitespace().collect();
-        if parts.len() < 2 {
+    for line in buf.lines() {
+        let Some((key, value)) = line.split_once(' ') else {
             continue;
-        }
-        match parts[0] {
-            "pgmajfault" => major = parts[1].parse().unwrap_or(0),
-            "pgfault" => minor = parts[1].parse().unwrap_or(0),
+        };
+        match key {
+            "pgmajfault" => major = value.trim().parse().unwrap_or(0),
+            "pgfault" => minor = value.trim().parse().unwrap_or(0),
             _ => {}
         }
     }
@@ -229,19 +458,180 @@ fn read_page_faults() -> (u64, u64) {
     (major, minor)
 }
 
-/// Check for OOM kills from dmesg (requires root or dmesg access)
-pub fn check_oom_kills() -> u64 {
-    // Try to read from kernel ring buffer
-    if let Ok(output) = std::process::Command::new("dmesg")
-        .args(["--level", "err,warn"])
-        .output()
-    {
-        if let Ok(stdout) = String::from_utf8(output.stdout) {
-            return stdout
-                .lines()
-                .filter(|line| line.contains("Out of memory") || line.contains("oom-kill"))
-                .count() as u64;
+/// A process the kernel OOM killer terminated
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OomKill {
+    pub pid: u32,
+    /// Command name as the kernel logged it (at most 15 characters)
+    pub name: String,
+}
+
+/// Where new kernel log lines come from
+enum KernelLog {
+    /// /dev/kmsg opened non-blocking, positioned after the last record read
+    Kmsg(fs::File),
+    /// `dmesg` output, keeping lines newer than the last timestamp seen (seconds since boot)
+    Dmesg { last_seen: f64 },
+}
+
+/// Watches the kernel log for OOM kills (requires root or dmesg access)
+pub struct OomWatcher {
+    log: Option<KernelLog>,
+}
+
+impl OomWatcher {
+    /// Start at the current end of the kernel log, so kills from before the run are not reported
+    pub fn new() -> Self {
+        let log = open_kmsg().map(KernelLog::Kmsg).or_else(|| {
+            let lines = read_dmesg()?;
+            Some(KernelLog::Dmesg { last_seen: lines.last().map_or(0.0, |(t, _)| *t) })
+        });
+        Self { log }
+    }
+
+    /// Whether the kernel log could be read at all
+    pub fn is_available(&self) -> bool {
+        self.log.is_some()
+    }
+
+    /// OOM kills logged since the previous call
+    pub fn poll(&mut self) -> Vec<OomKill> {
+        let messages = match self.log {
+            Some(KernelLog::Kmsg(ref mut file)) => read_kmsg(file),
+            Some(KernelLog::Dmesg { ref mut last_seen }) => {
+                let since = *last_seen;
+                let new: Vec<(f64, String)> = read_dmesg()
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter(|(t, _)| *t > since)
+                    .collect();
+                if let Some((t, _)) = new.last() {
+                    *last_seen = *t;
+                }
+                new.into_iter().map(|(_, message)| message).collect()
+            }
+            None => Vec::new(),
+        };
+        // Each kill is logged twice (the oom-kill: summary, then "Killed process"); keep the first
+        let mut kills: Vec<OomKill> = Vec::new();
+        for kill in messages.iter().filter_map(|m| parse_oom_kill(m)) {
+            if !kills.iter().any(|k| k.pid == kill.pid) {
+                kills.push(kill);
+            }
         }
+        kills
+    }
+}
+
+impl Default for OomWatcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Open /dev/kmsg for non-blocking reads from its current end
+fn open_kmsg() -> Option<fs::File> {
+    use std::io::{Seek, SeekFrom};
+    use std::os::unix::fs::OpenOptionsExt;
+
+    let mut file = fs::OpenOptions::new()
+        .read(true)
+        .custom_flags(libc::O_NONBLOCK)
+        .open("/dev/kmsg")
+        .ok()?;
+    file.seek(SeekFrom::End(0)).ok()?;
+    Some(file)
+}
+
+/// Messages of the /dev/kmsg records not read yet (each read returns one "prefix;message" record)
+fn read_kmsg(file: &mut fs::File) -> Vec<String> {
+    use std::io::Read;
+
+    let mut messages = Vec::new();
+    let mut buf = vec![0u8; 8192];
+    loop {
+        match file.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => {
+                let record = String::from_utf8_lossy(&buf[..n]);
+                // Continuation lines (" KEY=value") follow the message
+                let message = record.split_once(';').map_or(&*record, |(_, m)| m);
+                messages.push(message.lines().next().unwrap_or("").to_string());
+            }
+            // Records overwritten before we got to them; reading resumes at the oldest one left
+            Err(e) if e.raw_os_error() == Some(libc::EPIPE) => continue,
+            Err(_) => break,  // WouldBlock once every record was read
+        }
+    }
+    messages
+}
+
+/// `dmesg` lines as (seconds since boot, message)
+fn read_dmesg() -> Option<Vec<(f64, String)>> {
+    let output = std::process::Command::new("dmesg").output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| {
+                let (stamp, message) = line.strip_prefix('[')?.split_once(']')?;
+                Some((stamp.trim().parse().ok()?, message.trim_start().to_string()))
+            })
+            .collect(),
+    )
+}
+
+/// Parse the kernel's report of an OOM kill: "Out of memory: Killed process 1234 (name)
+/// total-vm:..." (also logged for cgroup OOMs) or the "oom-kill:constraint=...,task=name,pid=1234,..."
+/// summary line printed just before it
+fn parse_oom_kill(message: &str) -> Option<OomKill> {
+    if let Some(fields) = message.strip_prefix("oom-kill:") {
+        let field = |key: &str| fields.split(',').find_map(|f| f.strip_prefix(key)?.strip_prefix('='));
+        return Some(OomKill {
+            pid: field("pid")?.parse().ok()?,
+            name: field("task")?.to_string(),
+        });
+    }
+    const MARKER: &str = "Killed process ";
+    let rest = &message[message.find(MARKER)? + MARKER.len()..];
+    let (pid, rest) = rest.split_once(' ')?;
+    let name = rest.strip_prefix('(')?;
+    let end = name.find(") ").unwrap_or(name.rfind(')')?);
+    Some(OomKill {
+        pid: pid.parse().ok()?,
+        name: name[..end].to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn oom_killed_process_line() {
+        let message = "Out of memory: Killed process 1234 (java) total-vm:8123456kB, anon-rss:4012345kB, \
+                       file-rss:0kB, shmem-rss:0kB, UID:1000 pgtables:9000kB oom_score_adj:0";
+        assert_eq!(parse_oom_kill(message), Some(OomKill { pid: 1234, name: "java".to_string() }));
+        let cgroup = "Memory cgroup out of memory: Killed process 77 (my worker) total-vm:1024kB";
+        assert_eq!(parse_oom_kill(cgroup), Some(OomKill { pid: 77, name: "my worker".to_string() }));
+    }
+
+    #[test]
+    fn oom_kill_constraint_line() {
+        let message = "oom-kill:constraint=CONSTRAINT_MEMCG,nodemask=(null),cpuset=/,mems_allowed=0,\
+                       oom_memcg=/system.slice/app.service,task_memcg=/system.slice/app.service,\
+                       task=java,pid=1234,uid=1000";
+        assert_eq!(parse_oom_kill(message), Some(OomKill { pid: 1234, name: "java".to_string() }));
+        assert_eq!(parse_oom_kill("oom-kill:constraint=CONSTRAINT_NONE,task=java"), None);
+    }
+
+    #[test]
+    fn other_kernel_messages_are_not_kills() {
+        assert_eq!(parse_oom_kill("java invoked oom-killer: gfp_mask=0x140cca(GFP_HIGHUSER_MOVABLE|__GFP_COMP), order=0"), None);
+        assert_eq!(parse_oom_kill("EXT4-fs (sda1): mounted filesystem with ordered data mode"), None);
+        assert_eq!(parse_oom_kill("Killed process"), None);
+        assert_eq!(parse_oom_kill(""), None);
     }
-    0
 }
@@ -2,12 +2,59 @@
 
 pub mod cpu;
 pub mod disk;
+pub mod gpu;
 pub mod memory;
 pub mod network;
 pub mod psi;
 
 pub use cpu::CpuMetrics;
 pub use disk::DiskMetrics;
+pub use gpu::GpuMetrics;
 pub use memory::MemoryMetrics;
 pub use network::NetworkMetrics;
-pub use psi::PsiMetrics;
\ No newline at end of file
+pub use psi::{IoStallSource, PsiMetrics};
+
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+
+/// Whether any cumulative counter went backwards between two reads, given as (current, previous).
+///
+/// Kernel counters only decrease when they start over (a device re-created, a CPU
+/// brought back online, a PID reused). Rates for that interval would mix restarted
+/// and running counters, so callers report the interval as idle instead.
+pub fn counters_reset(pairs: impl IntoIterator<Item = (u64, u64)>) -> bool {
+    pairs.into_iter().any(|(curr, prev)| curr < prev)
+}
+
+/// A procfs file kept open between samples and rewound before each read.
+///
+/// Reopening /proc/stat and friends every tick costs an open/close pair per file;
+/// seq_file-backed procfs files regenerate their contents when read again from
+/// offset 0, so one handle serves the whole session.
+pub struct ProcFile {
+    path: &'static str,
+    file: Option<File>,
+}
+
+impl ProcFile {
+    pub const fn new(path: &'static str) -> Self {
+        Self { path, file: None }
+    }
+
+    /// Read the whole file into `buf`, replacing its contents but keeping its allocation.
+    /// A handle that fails to rewind or read is dropped and the file opened again.
+    pub fn read_into(&mut self, buf: &mut String) -> io::Result<()> {
+        if let Some(file) = self.file.as_mut() {
+            buf.clear();
+            if file.seek(SeekFrom::Start(0)).and_then(|_| file.read_to_string(buf)).is_ok() {
+                return Ok(());
+            }
+            self.file = None;
+        }
+        buf.clear();
+        let mut file = File::open(self.path)?;
+        file.read_to_string(buf)?;
+        self.file = Some(file);
+        Ok(())
+    }
+}
@@ -1,12 +1,20 @@
 //! Network I/O metrics collection from /proc/net/dev and related files.
 
+use super::ProcFile;
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+/// Remote addresses listed in NetworkMetrics::remote_peers
+const TOP_PEERS: usize = 10;
+
+/// Remote addresses remembered for run averages; beyond this only connected peers are kept
+const MAX_TRACKED_PEERS: usize = 4096;
 
 /// Per-interface network statistics
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct InterfaceStats {
     /// Interface name (e.g., "eth0", "ens5")
     pub interface: String,
@@ -39,6 +47,30 @@ pub struct InterfaceStats {
     /// TX utilization percentage (None if link speed unavailable)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tx_util_pct: Option<f64>,
+    /// Ring buffer, queue and coalescing settings (read once per interface)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nic: Option<NicSettings>,
+}
+
+/// NIC queue settings that explain packet drops
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NicSettings {
+    /// Current RX ring size (ethtool -g)
+    pub rx_ring: Option<u32>,
+    /// Maximum RX ring size supported by the NIC (ethtool -g)
+    pub rx_ring_max: Option<u32>,
+    /// Current TX ring size (ethtool -g)
+    pub tx_ring: Option<u32>,
+    /// Maximum TX ring size supported by the NIC (ethtool -g)
+    pub tx_ring_max: Option<u32>,
+    /// RX interrupt coalescing delay in microseconds (ethtool -c)
+    pub rx_usecs: Option<u32>,
+    /// Transmit queue length (sysfs tx_queue_len)
+    pub tx_queue_len: Option<u32>,
+    /// Number of RX queues (sysfs queues/rx-*)
+    pub rx_queues: usize,
+    /// Number of TX queues (sysfs queues/tx-*)
+    pub tx_queues: usize,
 }
 
 /// Raw interface statistics
@@ -54,8 +86,25 @@ struct RawInterfaceStats {
     tx_drops: u64,
 }
 
+impl RawInterfaceStats {
+    /// Counters to take this read's deltas from: `prev`, or this read itself when a
+    /// re-created interface (or a driver resetting its stats) started over
+    fn delta_base<'a>(&'a self, prev: &'a RawInterfaceStats) -> &'a RawInterfaceStats {
+        if super::counters_reset([
+            (self.rx_bytes, prev.rx_bytes),
+            (self.tx_bytes, prev.tx_bytes),
+            (self.rx_packets, prev.rx_packets),
+            (self.tx_packets, prev.tx_packets),
+        ]) {
+            self
+        } else {
+            prev
+        }
+    }
+}
+
 /// TCP statistics
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct TcpStats {
     /// Number of established connections
     pub connections_established: u64,
@@ -63,12 +112,24 @@ pub struct TcpStats {
     pub retransmits: u64,
     /// TCP retransmits delta (for rate calculation)
     pub retransmits_delta: Option<u64>,
+    /// Segments sent since the previous sample (denominator for the retransmit rate)
+    pub out_segs_delta: Option<u64>,
     /// Connections to HTTPS (port 443)
     pub https_connections: u64,
 }
 
+impl TcpStats {
+    /// Retransmitted share of the segments sent since the previous sample
+    pub fn retransmit_percent(&self) -> Option<f64> {
+        match (self.retransmits_delta, self.out_segs_delta) {
+            (Some(retx), Some(out)) if out > 0 => Some(100.0 * retx as f64 / out as f64),
+            _ => None,
+        }
+    }
+}
+
 /// Aggregated network metrics
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct NetworkMetrics {
     /// Per-interface statistics
     pub interfaces: Vec<InterfaceStats>,
@@ -78,6 +139,35 @@ pub struct NetworkMetrics {
     pub total_tx_bytes_per_sec: f64,
     /// TCP statistics
     pub tcp: TcpStats,
+    /// TCP sockets per state name (e.g. "TIME_WAIT"), IPv4 and IPv6 combined
+    #[serde(default)]
+    pub socket_states: HashMap<String, u64>,
+    /// Open UDP sockets, IPv4 and IPv6 combined
+    #[serde(default)]
+    pub udp_sockets: u64,
+    /// Remote addresses with the most established TCP connections (--top-talkers), busiest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub remote_peers: Vec<RemotePeer>,
+}
+
+/// Established TCP connections to one remote address
+///
+/// procfs has no per-connection byte counts, so connection counts stand in for
+/// bandwidth when looking for the hosts that dominate traffic.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RemotePeer {
+    pub address: IpAddr,
+    /// Established connections in this sample
+    pub connections: u64,
+    /// Established connections per sample since collection started
+    pub avg_connections: f64,
+}
+
+impl NetworkMetrics {
+    /// Number of TCP sockets in the given state
+    pub fn socket_state(&self, state: &str) -> u64 {
+        self.socket_states.get(state).copied().unwrap_or(0)
+    }
 }
 
 /// Network metrics collector with state for rate calculations
@@ -85,6 +175,21 @@ pub struct NetworkCollector {
     prev_stats: HashMap<String, RawInterfaceStats>,
     prev_time_ms: u64,
     prev_retransmits: Option<u64>,
+    prev_out_segs: Option<u64>,
+    nic_settings: HashMap<String, NicSettings>,
+    use_ethtool: bool,
+    netdev_file: ProcFile,
+    snmp_file: ProcFile,
+    tcp_files: [ProcFile; 2],
+    udp_files: [ProcFile; 2],
+    /// Group established connections by remote address (--top-talkers)
+    remote_peers: bool,
+    /// Sum of established connections per remote address over all samples
+    peer_totals: HashMap<IpAddr, u64>,
+    /// Samples counted into peer_totals
+    peer_samples: u64,
+    /// Reused across samples for /proc/net files
+    buf: String,
 }
 
 impl NetworkCollector {
@@ -93,9 +198,40 @@ impl NetworkCollector {
             prev_stats: HashMap::new(),
             prev_time_ms: 0,
             prev_retransmits: None,
+            prev_out_segs: None,
+            nic_settings: HashMap::new(),
+            use_ethtool: false,
+            netdev_file: ProcFile::new("/proc/net/dev"),
+            snmp_file: ProcFile::new("/proc/net/snmp"),
+            tcp_files: [ProcFile::new("/proc/net/tcp"), ProcFile::new("/proc/net/tcp6")],
+            udp_files: [ProcFile::new("/proc/net/udp"), ProcFile::new("/proc/net/udp6")],
+            remote_peers: false,
+            peer_totals: HashMap::new(),
+            peer_samples: 0,
+            buf: String::new(),
         }
     }
 
+    /// Also query ring sizes and coalescing via the external `ethtool` command
+    pub fn set_use_ethtool(&mut self, enabled: bool) {
+        self.use_ethtool = enabled;
+    }
+
+    /// Also count established TCP connections per remote address for the top-talkers list
+    pub fn set_remote_peers(&mut self, enabled: bool) {
+        self.remote_peers = enabled;
+    }
+
+    /// NIC settings for an interface, read on first use and cached
+    /// (ring sizes rarely change, and ethtool is too slow to run every sample)
+    fn nic_settings(&mut self, interface: &str) -> NicSettings {
+        let use_ethtool = self.use_ethtool;
+        self.nic_settings
+            .entry(interface.to_string())
+            .or_insert_with(|| read_nic_settings(interface, use_ethtool))
+            .clone()
+    }
+
     /// Get the link speed for an interface in Mbps
     /// Returns None if the speed cannot be determined (virtual interfaces, etc.)
     fn get_link_speed(interface: &str) -> Option<u64> {
@@ -127,7 +263,9 @@ impl NetworkCollector {
             .map(|d| d.as_millis() as u64)
             .unwrap_or(0);
 
-        let netdev = fs::read_to_string("/proc/net/dev")
+        // Taken out of self for the loop, which looks up NIC settings through &mut self
+        let mut netdev = std::mem::take(&mut self.buf);
+        self.netdev_file.read_into(&mut netdev)
             .context("Failed to read /proc/net/dev")?;
 
         let mut current_stats: HashMap<String, RawInterfaceStats> = HashMap::new();
@@ -135,12 +273,15 @@ impl NetworkCollector {
 
         for line in netdev.lines().skip(2) {
             // Skip header lines
-            let parts: Vec<&str> = line.split_whitespace().collect();
-            if parts.len() < 17 {
+            let mut parts = [""; 17];
+            for (slot, field) in parts.iter_mut().zip(line.split_whitespace()) {
+                *slot = field;
+            }
+            if parts[16].is_empty() {
                 continue;
             }
 
-            let interface = parts[0].trim_end_matches(':').to_string();
+            let interface = parts[0].trim_end_matches(':');
 
             // Skip loopback
             if interface == "lo" {
@@ -158,14 +299,16 @@ impl NetworkCollector {
                 tx_drops: parts[12].parse().unwrap_or(0),
             };
 
-            current_stats.insert(interface.clone(), stats.clone());
+            current_stats.insert(interface.to_string(), stats.clone());
 
             // Calculate rates if we have previous data
-            if let Some(prev) = self.prev_stats.get(&interface) {
+            if let Some(prev) = self.prev_stats.get(interface) {
                 let time_delta_ms = now_ms.saturating_sub(self.prev_time_ms);
                 if time_delta_ms > 0 {
                     let time_delta_sec = time_delta_ms as f64 / 1000.0;
 
+                    let prev = stats.delta_base(prev);
+
                     let rx_bytes_delta = stats.rx_bytes.saturating_sub(prev.rx_bytes);
                     let tx_bytes_delta = stats.tx_bytes.saturating_sub(prev.tx_bytes);
                     let rx_packets_delta = stats.rx_packets.saturating_sub(prev.rx_packets);
@@ -175,12 +318,13 @@ impl NetworkCollector {
                     let tx_bytes_per_sec = tx_bytes_delta as f64 / time_delta_sec;
 
                     // Get link speed and calculate utilization
-                    let link_speed_mbps = Self::get_link_speed(&interface);
+                    let link_speed_mbps = Self::get_link_speed(interface);
                     let rx_util_pct = Self::calculate_util_pct(rx_bytes_per_sec, link_speed_mbps);
                     let tx_util_pct = Self::calculate_util_pct(tx_bytes_per_sec, link_speed_mbps);
+                    let nic = Some(self.nic_settings(interface));
 
                     interfaces.push(InterfaceStats {
-                        interface: interface.clone(),
+                        interface: interface.to_string(),
                         rx_bytes_per_sec,
                         tx_bytes_per_sec,
                         rx_packets_per_sec: rx_packets_delta as f64 / time_delta_sec,
@@ -194,17 +338,23 @@ impl NetworkCollector {
                         link_speed_mbps,
                         rx_util_pct,
                         tx_util_pct,
+                        nic,
                     });
                 }
             }
         }
 
+        self.buf = netdev;
+
         // Calculate totals
         let total_rx: f64 = interfaces.iter().map(|i| i.rx_bytes_per_sec).sum();
         let total_tx: f64 = interfaces.iter().map(|i| i.tx_bytes_per_sec).sum();
 
         // Get TCP stats
-        let tcp = self.collect_tcp_stats()?;
+        let sockets = self.scan_tcp_tables();
+        let tcp = self.collect_tcp_stats(&sockets)?;
+        let udp_sockets = self.count_udp_sockets();
+        let remote_peers = self.rank_remote_peers(sockets.peers);
 
         // Update state
         self.prev_stats = current_stats;
@@ -215,69 +365,222 @@ impl NetworkCollector {
             total_rx_bytes_per_sec: total_rx,
             total_tx_bytes_per_sec: total_tx,
             tcp,
+            socket_states: sockets.states,
+            udp_sockets,
+            remote_peers,
         })
     }
 
-    fn collect_tcp_stats(&mut self) -> Result<TcpStats> {
-        // Count established TCP connections
-        let tcp_content = fs::read_to_string("/proc/net/tcp").unwrap_or_default();
-        let tcp6_content = fs::read_to_string("/proc/net/tcp6").unwrap_or_default();
-
-        let mut established: u64 = 0;
-        let mut https_connections: u64 = 0;
-
-        for line in tcp_content.lines().chain(tcp6_content.lines()).skip(1) {
-            let parts: Vec<&str> = line.split_whitespace().collect();
-            if parts.len() < 4 {
+    /// Count TCP sockets per state, established and HTTPS connections and, with
+    /// --top-talkers, established connections per remote address, in one pass
+    /// over /proc/net/tcp and tcp6
+    fn scan_tcp_tables(&mut self) -> TcpTableCounts {
+        let mut counts = TcpTableCounts::default();
+        for file in &mut self.tcp_files {
+            if file.read_into(&mut self.buf).is_err() {
                 continue;
             }
-
-            // State is in hex, 01 = ESTABLISHED
-            if let Some(state) = parts.get(3) {
-                if *state == "01" {
-                    established += 1;
-
-                    // Check if remote port is 443 (HTTPS)
-                    // Format: local_address:port remote_address:port
-                    if let Some(remote) = parts.get(2) {
-                        if let Some(port_hex) = remote.split(':').last() {
-                            if let Ok(port) = u16::from_str_radix(port_hex, 16) {
-                                if port == 443 {
-                                    https_connections += 1;
-                                }
-                            }
-                        }
-                    }
-                }
+            // Skip the header
+            for line in self.buf.lines().skip(1) {
+                counts.add_line(line, self.remote_peers);
             }
         }
+        counts
+    }
 
-        // Get retransmits from /proc/net/snmp
-        let mut retransmits: u64 = 0;
-        if let Ok(snmp) = fs::read_to_string("/proc/net/snmp") {
-            let lines: Vec<&str> = snmp.lines().collect();
-            for i in 0..lines.len() {
-                if lines[i].starts_with("Tcp:") && i + 1 < lines.len() && lines[i + 1].starts_with("Tcp:") {
-                    let values: Vec<&str> = lines[i + 1].split_whitespace().collect();
-                    // RetransSegs is typically at index 12
-                    if values.len() > 12 {
-                        retransmits = values[12].parse().unwrap_or(0);
-                    }
-                    break;
-                }
-            }
-        }
+    fn collect_tcp_stats(&mut self, sockets: &TcpTableCounts) -> Result<TcpStats> {
+        // Get retransmits and sent segments from /proc/net/snmp
+        let (retransmits, out_segs) = self.snmp_file.read_into(&mut self.buf)
+            .ok()
+            .and_then(|()| parse_snmp_tcp(&self.buf))
+            .unwrap_or((0, 0));
 
         let retransmits_delta = self.prev_retransmits.map(|prev| retransmits.saturating_sub(prev));
         self.prev_retransmits = Some(retransmits);
+        let out_segs_delta = self.prev_out_segs.map(|prev| out_segs.saturating_sub(prev));
+        self.prev_out_segs = Some(out_segs);
 
         Ok(TcpStats {
-            connections_established: established,
+            connections_established: sockets.established,
             retransmits,
             retransmits_delta,
-            https_connections,
+            out_segs_delta,
+            https_connections: sockets.https_connections,
         })
     }
+
+    /// Total number of UDP sockets
+    fn count_udp_sockets(&mut self) -> u64 {
+        let mut udp_sockets = 0;
+        for file in &mut self.udp_files {
+            if file.read_into(&mut self.buf).is_ok() {
+                udp_sockets += self.buf.lines().skip(1).filter(|l| !l.trim().is_empty()).count() as u64;
+            }
+        }
+        udp_sockets
+    }
+
+    /// Fold this sample's per-address connection counts into the run totals and
+    /// return the busiest addresses (most connections now, then on average)
+    fn rank_remote_peers(&mut self, connections: HashMap<IpAddr, u64>) -> Vec<RemotePeer> {
+        if !self.remote_peers {
+            return Vec::new();
+        }
+        self.peer_samples += 1;
+        for (&address, &count) in &connections {
+            *self.peer_totals.entry(address).or_default() += count;
+        }
+        if self.peer_totals.len() > MAX_TRACKED_PEERS {
+            // A scan or many short-lived clients: forget addresses that are gone
+            self.peer_totals.retain(|address, _| connections.contains_key(address));
+        }
+
+        let mut peers: Vec<RemotePeer> = self.peer_totals
+            .iter()
+            .map(|(&address, &total)| RemotePeer {
+                address,
+                connections: connections.get(&address).copied().unwrap_or(0),
+                avg_connections: total as f64 / self.peer_samples as f64,
+            })
+            .collect();
+        peers.sort_by(|a, b| {
+            b.connections.cmp(&a.connections)
+                .then(b.avg_connections.total_cmp(&a.avg_connections))
+        });
+        peers.truncate(TOP_PEERS);
+        peers
+    }
+}
+
+/// Socket counts from one sample's /proc/net/tcp and tcp6 tables
+#[derive(Debug, Default)]
+struct TcpTableCounts {
+    established: u64,
+    /// Established connections to remote port 443
+    https_connections: u64,
+    states: HashMap<String, u64>,
+    peers: HashMap<IpAddr, u64>,
+}
+
+impl TcpTableCounts {
+    /// Count one socket line; the remote address is the 3rd column and the state
+    /// the 4th, in hex (01 = ESTABLISHED)
+    fn add_line(&mut self, line: &str, remote_peers: bool) {
+        let mut parts = line.split_whitespace().skip(2);
+        let (Some(remote), Some(state)) = (parts.next(), parts.next()) else {
+            return;
+        };
+        if let Some(name) = tcp_state_name(state) {
+            *self.states.entry(name.to_string()).or_default() += 1;
+        }
+        if state != "01" {
+            return;
+        }
+        self.established += 1;
+        let port = remote.split(':').next_back().and_then(|hex| u16::from_str_radix(hex, 16).ok());
+        if port == Some(443) {
+            self.https_connections += 1;
+        }
+        if remote_peers {
+            // Local connections say nothing about who the box talks to
+            if let Some(address) = parse_remote_address(remote).filter(|a| !a.is_loopback()) {
+                *self.peers.entry(address).or_default() += 1;
+            }
+        }
+    }
+}
+
+/// Parse an address column of /proc/net/tcp or tcp6 ("0100007F:1F90"), dropping the port
+///
+/// The kernel prints each 32-bit word of the address in host byte order, so the
+/// bytes come back out with to_ne_bytes. IPv4-mapped IPv6 addresses are shown as IPv4.
+fn parse_remote_address(column: &str) -> Option<IpAddr> {
+    let (hex, _port) = column.split_once(':')?;
+    let word = |i: usize| -> Option<[u8; 4]> {
+        Some(u32::from_str_radix(hex.get(i * 8..i * 8 + 8)?, 16).ok()?.to_ne_bytes())
+    };
+    let address = match hex.len() {
+        8 => IpAddr::V4(Ipv4Addr::from(word(0)?)),
+        32 => {
+            let mut bytes = [0u8; 16];
+            for i in 0..4 {
+                bytes[i * 4..i * 4 + 4].copy_from_slice(&word(i)?);
+            }
+            let v6 = Ipv6Addr::from(bytes);
+            v6.to_ipv4_mapped().map_or(IpAddr::V6(v6), IpAddr::V4)
+        }
+        _ => return None,
+    };
+    (!address.is_unspecified()).then_some(address)
+}
+
+/// Read NIC queue settings from sysfs, plus ring/coalescing via ethtool if enabled
+fn read_nic_settings(interface: &str, use_ethtool: bool) -> NicSettings {
+    let base = format!("/sys/class/net/{}", interface);
+    let mut settings = NicSettings {
+        tx_queue_len: fs::read_to_string(format!("{}/tx_queue_len", base))
+            .ok()
+            .and_then(|s| s.trim().parse().ok()),
+        ..Default::default()
+    };
+
+    if let Ok(entries) = fs::read_dir(format!("{}/queues", base)) {
+        for entry in entries.flatten() {
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            if name.starts_with("rx-") {
+                settings.rx_queues += 1;
+            } else if name.starts_with("tx-") {
+                settings.tx_queues += 1;
+            }
+        }
+    }
+
+    if use_ethtool {
+        if let Some(output) = run_ethtool("-g", interface) {
+            // Two sections: "Pre-set maximums:" then "Current hardware settings:"
+            let mut in_current = false;
+            for line in output.lines() {
+                if line.starts_with("Current hardware settings") {
+                    in_current = true;
+                    continue;
+                }
+                let (key, value) = match line.split_once(':') {
+                    Some((k, v)) => (k.trim(), v.trim().parse::<u32>().ok()),
+                    None => continue,
+                };
+                match (key, in_current) {
+                    ("RX", false) => settings.rx_ring_max = value,
+                    ("TX", false) => settings.tx_ring_max = value,
+                    ("RX", true) => settings.rx_ring = value,
+                    ("TX", true) => settings.tx_ring = value,
+                    _ => {}
+                }
+            }
+        }
+
+        if let Some(output) = run_ethtool("-c", interface) {
+            settings.rx_usecs = output
+                .lines()
+                .find_map(|l| l.strip_prefix("rx-usecs:"))
+                .and_then(|v| v.trim().parse().ok());
+        }
+    }
+
+    settings
+}
+
+/// Run `ethtool <flag> <interface>`, returning stdout on success
+fn run_ethtool(flag: &str, interface: &str) -> Option<String> {
+    let output = std::process::Command::new("ethtool")
+        .arg(flag)
+        .arg(interface)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8(output.stdout).ok()
 }
 
 impl Default for NetworkCollector {
@@ -285,3 +588,109 @@ impl Default for NetworkCollector {
         Self::new()
     }
 }
+
+/// RetransSegs and OutSegs from the two `Tcp:` lines of /proc/net/snmp.
+/// Columns are looked up by name in the header line, since their order
+/// differs between kernels.
+fn parse_snmp_tcp(snmp: &str) -> Option<(u64, u64)> {
+    let mut tcp_lines = snmp.lines().filter(|l| l.starts_with("Tcp:"));
+    let header = tcp_lines.next()?.split_whitespace();
+    let values = tcp_lines.next()?.split_whitespace();
+
+    let (mut retransmits, mut out_segs) = (None, None);
+    for (name, value) in header.zip(values) {
+        match name {
+            "RetransSegs" => retransmits = value.parse().ok(),
+            "OutSegs" => out_segs = value.parse().ok(),
+            _ => {}
+        }
+    }
+    Some((retransmits?, out_segs?))
+}
+
+/// Name of a TCP state as encoded in the `st` column of /proc/net/tcp
+fn tcp_state_name(hex: &str) -> Option<&'static str> {
+    let name = match u8::from_str_radix(hex, 16).ok()? {
+        0x01 => "ESTABLISHED",
+        0x02 => "SYN_SENT",
+        0x03 => "SYN_RECV",
+        0x04 => "FIN_WAIT1",
+        0x05 => "FIN_WAIT2",
+        0x06 => "TIME_WAIT",
+        0x07 => "CLOSE",
+        0x08 => "CLOSE_WAIT",
+        0x09 => "LAST_ACK",
+        0x0A => "LISTEN",
+        0x0B => "CLOSING",
+        0x0C => "NEW_SYN_RECV",
+        _ => return None,
+    };
+    Some(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SNMP: &str = "\
+Ip: Forwarding DefaultTTL InReceives
+Ip: 1 64 12345
+Tcp: RtoAlgorithm RtoMin RtoMax MaxConn ActiveOpens PassiveOpens AttemptFails EstabResets CurrEstab InSegs OutSegs RetransSegs InErrs OutRsts InCsumErrors
+Tcp: 1 200 120000 -1 500 300 10 20 5 100000 90000 42 0 7 0
+Udp: InDatagrams NoPorts
+Udp: 10 0
+";
+
+    #[test]
+    fn snmp_tcp_counters_by_name() {
+        assert_eq!(parse_snmp_tcp(SNMP), Some((42, 90000)));
+    }
+
+    #[test]
+    fn snmp_tcp_column_order_can_change() {
+        let snmp = "Tcp: RetransSegs CurrEstab OutSegs\nTcp: 3 1 700\n";
+        assert_eq!(parse_snmp_tcp(snmp), Some((3, 700)));
+    }
+
+    #[test]
+    fn snmp_tcp_missing_values() {
+        assert_eq!(parse_snmp_tcp("Tcp: RetransSegs OutSegs\n"), None);
+        assert_eq!(parse_snmp_tcp("Tcp: CurrEstab OutSegs\nTcp: 1 700\n"), None);
+    }
+
+    #[test]
+    fn reset_interface_reports_idle_interval() {
+        let prev = RawInterfaceStats { rx_bytes: 1 << 40, tx_bytes: 5000, rx_packets: 900, tx_packets: 40, ..Default::default() };
+        // rx counters started over while tx kept counting up
+        let curr = RawInterfaceStats { rx_bytes: 1200, tx_bytes: 9000, rx_packets: 3, tx_packets: 60, ..Default::default() };
+        let base = curr.delta_base(&prev);
+        assert_eq!(curr.rx_bytes - base.rx_bytes, 0);
+        assert_eq!(curr.tx_bytes - base.tx_bytes, 0);
+        assert_eq!(curr.tx_packets - base.tx_packets, 0);
+
+        let next = RawInterfaceStats { rx_bytes: 2200, ..curr.clone() };
+        assert_eq!(next.rx_bytes - next.delta_base(&curr).rx_bytes, 1000);
+    }
+
+    #[test]
+    fn tcp_table_counted_in_one_pass() {
+        let table = "\
+  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 0100007F:1F90 00000000:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 1 1
+   1: 0F02000A:C350 2200A8C0:01BB 01 00000000:00000000 00:00000000 00000000  1000        0 2 1
+   2: 0F02000A:C351 2200A8C0:0050 01 00000000:00000000 00:00000000 00000000  1000        0 3 1
+   3: 0100007F:C352 0100007F:1F90 01 00000000:00000000 00:00000000 00000000  1000        0 4 1
+   4: 0F02000A:C353 2200A8C0:01BB 06 00000000:00000000 00:00000000 00000000  1000        0 5 1
+";
+        let mut counts = TcpTableCounts::default();
+        for line in table.lines().skip(1) {
+            counts.add_line(line, true);
+        }
+        assert_eq!(counts.established, 3);
+        assert_eq!(counts.https_connections, 1);
+        assert_eq!(counts.states.values().sum::<u64>(), 5);
+        assert_eq!(counts.states.get(tcp_state_name("01").unwrap()), Some(&3));
+        // Loopback peers are left out
+        assert_eq!(counts.peers.values().sum::<u64>(), 2);
+    }
+}
@@ -1,8 +1,13 @@
-//! Pressure Stall Information (PSI) metrics collection from /proc/pressure/.
+//! Pressure Stall Information (PSI) metrics collection from /proc/pressure/
+//! and the cgroup v2 *.pressure files.
 
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Where the cgroup v2 hierarchy is mounted: on its own, or beside v1 in a hybrid setup
+const CGROUP2_MOUNTS: [&str; 2] = ["/sys/fs/cgroup", "/sys/fs/cgroup/unified"];
 
 /// PSI metrics for a single resource (CPU, memory, or I/O)
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -34,6 +39,34 @@ pub struct PsiMetrics {
     pub memory: PsiResourceMetrics,
     /// I/O pressure metrics
     pub io: PsiResourceMetrics,
+    /// I/O pressure of the monitored cgroup (None outside a cgroup v2 container)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cgroup_io: Option<PsiResourceMetrics>,
+}
+
+/// Where an I/O stall originates, from comparing cgroup and system io.pressure
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IoStallSource {
+    /// No meaningful system-wide I/O stall
+    None,
+    /// Tasks in the monitored cgroup account for most of the stall
+    Cgroup,
+    /// The stall is mostly outside the cgroup (noisy neighbor)
+    Neighbor,
+}
+
+impl PsiMetrics {
+    /// Attribute system I/O pressure to the cgroup or to its neighbors
+    pub fn io_stall_source(&self) -> Option<IoStallSource> {
+        let cgroup = self.cgroup_io.as_ref()?;
+        if self.io.some_avg10 < 1.0 {
+            Some(IoStallSource::None)
+        } else if cgroup.some_avg10 >= self.io.some_avg10 * 0.5 {
+            Some(IoStallSource::Cgroup)
+        } else {
+            Some(IoStallSource::Neighbor)
+        }
+    }
 }
 
 /// PSI metrics collector
@@ -50,6 +83,7 @@ impl PsiCollector {
             cpu: read_psi_file("/proc/pressure/cpu", false),
             memory: read_psi_file("/proc/pressure/memory", true),
             io: read_psi_file("/proc/pressure/io", true),
+            cgroup_io: read_cgroup_io_pressure(),
         })
     }
 }
@@ -60,9 +94,57 @@ impl Default for PsiCollector {
     }
 }
 
+/// CPU, memory and I/O pressure of the cgroup v2 group a process belongs to
+/// (None on cgroup v1, at the root or when the group has no pressure files)
+pub fn read_process_cgroup_psi(pid: u32) -> Option<PsiMetrics> {
+    let dir = cgroup_v2_dir(&pid.to_string())?;
+    if !dir.join("cpu.pressure").exists() {
+        return None;
+    }
+    Some(PsiMetrics {
+        cpu: read_psi_file(dir.join("cpu.pressure"), false),
+        memory: read_psi_file(dir.join("memory.pressure"), true),
+        io: read_psi_file(dir.join("io.pressure"), true),
+        cgroup_io: None,
+    })
+}
+
+/// Read io.pressure of monperf's own cgroup v2 group (None at the root, whose
+/// pressure files only repeat /proc/pressure)
+fn read_cgroup_io_pressure() -> Option<PsiResourceMetrics> {
+    let path = cgroup_v2_dir("self")?.join("io.pressure");
+    if !path.exists() {
+        return None;
+    }
+    Some(read_psi_file(path, true))
+}
+
+/// Directory of the cgroup v2 group of `/proc/<pid>` ("self" for monperf), None on
+/// cgroup v1 and for the root group
+///
+/// A `/` path is the root unless it sits in a cgroup namespace (a container), where it is
+/// the container's own group; only non-root groups have a cgroup.type file.
+fn cgroup_v2_dir(pid: &str) -> Option<PathBuf> {
+    let cgroups = fs::read_to_string(format!("/proc/{}/cgroup", pid)).ok()?;
+    let path = cgroup_v2_path(&cgroups)?;
+    let mount = CGROUP2_MOUNTS.iter()
+        .map(Path::new)
+        .find(|mount| mount.join("cgroup.controllers").exists())?;
+    let dir = mount.join(path.trim_start_matches('/'));
+    if path == "/" && !dir.join("cgroup.type").exists() {
+        return None;
+    }
+    Some(dir)
+}
+
+/// The v2 path from /proc/<pid>/cgroup content: "/user.slice/app.scope" from "0::/user.slice/app.scope"
+fn cgroup_v2_path(cgroups: &str) -> Option<&str> {
+    cgroups.lines().find_map(|line| line.strip_prefix("0::")).map(str::trim)
+}
+
 /// Read and parse a PSI file
 /// `has_full` indicates if the resource has "full" metrics (memory and I/O do, CPU doesn't)
-fn read_psi_file(path: &str, has_full: bool) -> PsiResourceMetrics {
+fn read_psi_file(path: impl AsRef<Path>, has_full: bool) -> PsiResourceMetrics {
     let content = match fs::read_to_string(path) {
         Ok(c) => c,
         Err(_) => return PsiResourceMetrics::default(),
@@ -1,12 +1,79 @@
-//! Plot generation from CSV log files.
+//! Plot generation from CSV log files and binary captures.
 
+use crate::logging::{self, MetricsSample};
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
+use plotters::coord::Shift;
+use plotters::element::DashedPathElement;
 use plotters::prelude::*;
+use ab_glyph::{Font, FontVec, PxScaleFont, ScaleFont};
+use plotters_backend::text_anchor::{HPos, VPos};
+use plotters_backend::{BackendColor, BackendCoord, BackendTextStyle, DrawingErrorKind, FontFamily};
 use std::collections::HashMap;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Default cap on CPU heatmap time columns (about one per pixel of plot width)
+pub const DEFAULT_HEATMAP_COLUMNS: usize = 1500;
+/// Default size of a single-chart plot; larger multi-panel plots scale from it
+pub const DEFAULT_PLOT_WIDTH: u32 = 1200;
+pub const DEFAULT_PLOT_HEIGHT: u32 = 600;
+
+/// Image format of generated plots
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum PlotFormat {
+    /// Scalable vector graphics, for browsers and web pages
+    Svg,
+    /// Raster images, for pasting into tickets and documents
+    Png,
+}
+
+/// Options for plot generation
+#[derive(Debug, Clone)]
+pub struct PlotOptions {
+    /// Image format of the generated files
+    pub format: PlotFormat,
+    /// Draw an avg/max/p95 box on each plot
+    pub annotate_stats: bool,
+    /// Maximum time columns in the CPU core heatmap (samples are averaged per column)
+    pub heatmap_columns: usize,
+    /// Size of single-chart plots in pixels
+    pub width: u32,
+    pub height: u32,
+    /// Downsample to at most this many points per series (min/max per bucket)
+    pub max_points: Option<usize>,
+    /// Font file for PNG text (otherwise a DejaVu or Liberation font from the usual system paths)
+    pub font: Option<PathBuf>,
+}
+
+impl Default for PlotOptions {
+    fn default() -> Self {
+        Self {
+            format: PlotFormat::Svg,
+            annotate_stats: false,
+            heatmap_columns: DEFAULT_HEATMAP_COLUMNS,
+            width: DEFAULT_PLOT_WIDTH,
+            height: DEFAULT_PLOT_HEIGHT,
+            max_points: None,
+            font: None,
+        }
+    }
+}
+
+impl PlotOptions {
+    /// Scale a size laid out for the default plot dimensions to the configured ones
+    fn scaled(&self, (width, height): (u32, u32)) -> (u32, u32) {
+        (
+            (u64::from(width) * u64::from(self.width) / u64::from(DEFAULT_PLOT_WIDTH)) as u32,
+            self.scaled_height(height),
+        )
+    }
+
+    /// Scale a vertical pixel offset (e.g., a panel split) like `scaled`
+    fn scaled_height(&self, height: u32) -> u32 {
+        (u64::from(height) * u64::from(self.height) / u64::from(DEFAULT_PLOT_HEIGHT)) as u32
+    }
+}
 
 /// Simplified sample structure for plotting (parsed from CSV)
 #[derive(Debug, Clone, Default)]
@@ -16,6 +83,7 @@ pub struct PlotSample {
     pub cpu_user: f64,
     pub cpu_system: f64,
     pub cpu_iowait: f64,
+    pub cpu_steal: f64,
     pub mem_used_pct: f64,
     pub cgroup_usage_pct: Option<f64>,
     pub disk_read_bytes_per_sec: f64,
@@ -35,6 +103,7 @@ pub struct DetailedPlotSample {
     pub cpu_user: f64,
     pub cpu_system: f64,
     pub cpu_iowait: f64,
+    pub cpu_steal: f64,
     pub cpu_load_1m: f64,
     pub cpu_load_5m: f64,
     pub cpu_load_15m: f64,
@@ -72,6 +141,12 @@ pub struct DetailedPlotSample {
     pub psi_mem_full_avg10: Option<f64>,
     pub psi_io_some_avg10: f64,
     pub psi_io_full_avg10: Option<f64>,
+    // PSI of the monitored process's cgroup (None without a process or cgroup v2)
+    pub proc_cgroup_psi_cpu_some_avg10: Option<f64>,
+    pub proc_cgroup_psi_mem_some_avg10: Option<f64>,
+    pub proc_cgroup_psi_mem_full_avg10: Option<f64>,
+    pub proc_cgroup_psi_io_some_avg10: Option<f64>,
+    pub proc_cgroup_psi_io_full_avg10: Option<f64>,
     // Process
     pub proc_cpu_pct: Option<f64>,
     pub proc_rss_bytes: Option<u64>,
@@ -79,33 +154,145 @@ pub struct DetailedPlotSample {
     pub proc_io_write_bytes_per_sec: Option<f64>,
 }
 
-/// Load basic samples from a CSV log file (for simple plots)
-pub fn load_samples<P: AsRef<Path>>(path: P) -> Result<Vec<PlotSample>> {
-    let detailed = load_detailed_samples(path)?;
-    Ok(detailed.into_iter().map(|d| PlotSample {
-        timestamp: d.timestamp,
-        cpu_total: d.cpu_total,
-        cpu_user: d.cpu_user,
-        cpu_system: d.cpu_system,
-        cpu_iowait: d.cpu_iowait,
-        mem_used_pct: d.mem_used_pct,
-        cgroup_usage_pct: d.cgroup_usage_pct,
-        disk_read_bytes_per_sec: d.disk_total_read,
-        disk_write_bytes_per_sec: d.disk_total_write,
-        net_rx_bytes_per_sec: d.net_total_rx,
-        net_tx_bytes_per_sec: d.net_total_tx,
-        proc_cpu_pct: d.proc_cpu_pct,
-        proc_rss_bytes: d.proc_rss_bytes,
-    }).collect())
+/// Basic view of a detailed sample (for simple plots)
+impl From<&DetailedPlotSample> for PlotSample {
+    fn from(d: &DetailedPlotSample) -> Self {
+        Self {
+            timestamp: d.timestamp,
+            cpu_total: d.cpu_total,
+            cpu_user: d.cpu_user,
+            cpu_system: d.cpu_system,
+            cpu_iowait: d.cpu_iowait,
+            cpu_steal: d.cpu_steal,
+            mem_used_pct: d.mem_used_pct,
+            cgroup_usage_pct: d.cgroup_usage_pct,
+            disk_read_bytes_per_sec: d.disk_total_read,
+            disk_write_bytes_per_sec: d.disk_total_write,
+            net_rx_bytes_per_sec: d.net_total_rx,
+            net_tx_bytes_per_sec: d.net_total_tx,
+            proc_cpu_pct: d.proc_cpu_pct,
+            proc_rss_bytes: d.proc_rss_bytes,
+        }
+    }
+}
+
+/// Reduce samples to at most `max_points` by splitting them into `max_points / 2`
+/// time buckets and keeping each bucket's per-metric minimum and maximum (at the
+/// bucket's first and last timestamps). Unlike averaging, this keeps transient
+/// spikes and dips visible.
+pub fn downsample_min_max(samples: &[DetailedPlotSample], max_points: usize) -> Vec<DetailedPlotSample> {
+    let buckets = (max_points / 2).max(1);
+    if samples.len() <= max_points.max(1) {
+        return samples.to_vec();
+    }
+
+    let bucket_len = samples.len().div_ceil(buckets);
+    let mut out = Vec::with_capacity(buckets * 2);
+    for bucket in samples.chunks(bucket_len) {
+        let mut low = bucket[0].clone();
+        let mut high = bucket[0].clone();
+        for sample in &bucket[1..] {
+            merge_extreme(&mut low, sample, f64::min);
+            merge_extreme(&mut high, sample, f64::max);
+        }
+        high.timestamp = bucket[bucket.len() - 1].timestamp;
+        out.push(low);
+        out.push(high);
+    }
+    out
+}
+
+/// Fold every metric of `sample` into `acc` with `pick` (min or max)
+fn merge_extreme(acc: &mut DetailedPlotSample, sample: &DetailedPlotSample, pick: fn(f64, f64) -> f64) {
+    let num = |a: &mut f64, b: f64| *a = pick(*a, b);
+    let int = |a: &mut u64, b: u64| *a = pick(*a as f64, b as f64) as u64;
+    let opt = |a: &mut Option<f64>, b: Option<f64>| {
+        *a = match (*a, b) {
+            (Some(x), Some(y)) => Some(pick(x, y)),
+            (x, y) => x.or(y),
+        }
+    };
+    let opt_int = |a: &mut Option<u64>, b: Option<u64>| {
+        *a = match (*a, b) {
+            (Some(x), Some(y)) => Some(pick(x as f64, y as f64) as u64),
+            (x, y) => x.or(y),
+        }
+    };
+    let each = |a: &mut Vec<f64>, b: &[f64]| {
+        for (x, y) in a.iter_mut().zip(b) {
+            *x = pick(*x, *y);
+        }
+    };
+
+    num(&mut acc.cpu_total, sample.cpu_total);
+    num(&mut acc.cpu_user, sample.cpu_user);
+    num(&mut acc.cpu_system, sample.cpu_system);
+    num(&mut acc.cpu_iowait, sample.cpu_iowait);
+    num(&mut acc.cpu_steal, sample.cpu_steal);
+    num(&mut acc.cpu_load_1m, sample.cpu_load_1m);
+    num(&mut acc.cpu_load_5m, sample.cpu_load_5m);
+    num(&mut acc.cpu_load_15m, sample.cpu_load_15m);
+    each(&mut acc.per_core_pct, &sample.per_core_pct);
+
+    int(&mut acc.mem_total_bytes, sample.mem_total_bytes);
+    int(&mut acc.mem_used_bytes, sample.mem_used_bytes);
+    int(&mut acc.mem_available_bytes, sample.mem_available_bytes);
+    num(&mut acc.mem_used_pct, sample.mem_used_pct);
+    int(&mut acc.mem_buffers_bytes, sample.mem_buffers_bytes);
+    int(&mut acc.mem_cached_bytes, sample.mem_cached_bytes);
+    int(&mut acc.mem_dirty_bytes, sample.mem_dirty_bytes);
+    int(&mut acc.mem_writeback_bytes, sample.mem_writeback_bytes);
+    int(&mut acc.mem_swap_total_bytes, sample.mem_swap_total_bytes);
+    int(&mut acc.mem_swap_used_bytes, sample.mem_swap_used_bytes);
+    opt_int(&mut acc.cgroup_limit_bytes, sample.cgroup_limit_bytes);
+    opt_int(&mut acc.cgroup_current_bytes, sample.cgroup_current_bytes);
+    opt(&mut acc.cgroup_usage_pct, sample.cgroup_usage_pct);
+
+    each(&mut acc.disk_read_bytes_per_sec, &sample.disk_read_bytes_per_sec);
+    each(&mut acc.disk_write_bytes_per_sec, &sample.disk_write_bytes_per_sec);
+    each(&mut acc.disk_util_pct, &sample.disk_util_pct);
+    num(&mut acc.disk_total_read, sample.disk_total_read);
+    num(&mut acc.disk_total_write, sample.disk_total_write);
+
+    each(&mut acc.net_rx_bytes_per_sec, &sample.net_rx_bytes_per_sec);
+    each(&mut acc.net_tx_bytes_per_sec, &sample.net_tx_bytes_per_sec);
+    num(&mut acc.net_total_rx, sample.net_total_rx);
+    num(&mut acc.net_total_tx, sample.net_total_tx);
+
+    num(&mut acc.psi_cpu_some_avg10, sample.psi_cpu_some_avg10);
+    num(&mut acc.psi_mem_some_avg10, sample.psi_mem_some_avg10);
+    opt(&mut acc.psi_mem_full_avg10, sample.psi_mem_full_avg10);
+    num(&mut acc.psi_io_some_avg10, sample.psi_io_some_avg10);
+    opt(&mut acc.psi_io_full_avg10, sample.psi_io_full_avg10);
+    opt(&mut acc.proc_cgroup_psi_cpu_some_avg10, sample.proc_cgroup_psi_cpu_some_avg10);
+    opt(&mut acc.proc_cgroup_psi_mem_some_avg10, sample.proc_cgroup_psi_mem_some_avg10);
+    opt(&mut acc.proc_cgroup_psi_mem_full_avg10, sample.proc_cgroup_psi_mem_full_avg10);
+    opt(&mut acc.proc_cgroup_psi_io_some_avg10, sample.proc_cgroup_psi_io_some_avg10);
+    opt(&mut acc.proc_cgroup_psi_io_full_avg10, sample.proc_cgroup_psi_io_full_avg10);
+
+    opt(&mut acc.proc_cpu_pct, sample.proc_cpu_pct);
+    opt_int(&mut acc.proc_rss_bytes, sample.proc_rss_bytes);
+    opt(&mut acc.proc_io_read_bytes_per_sec, sample.proc_io_read_bytes_per_sec);
+    opt(&mut acc.proc_io_write_bytes_per_sec, sample.proc_io_write_bytes_per_sec);
 }
 
-/// Load detailed samples from a CSV log file (for detailed plots)
+/// Load detailed samples from a CSV log or binary capture file (for detailed plots)
 pub fn load_detailed_samples<P: AsRef<Path>>(path: P) -> Result<Vec<DetailedPlotSample>> {
-    let file = File::open(path.as_ref())
+    if logging::is_capture_file(path.as_ref()) {
+        let captured = logging::read_capture(path.as_ref())?;
+        if captured.is_empty() {
+            return Err(anyhow::anyhow!("No samples found in capture file"));
+        }
+        return Ok(detailed_from_samples(&captured));
+    }
+
+    if logging::is_json_log(path.as_ref()) {
+        return load_detailed_samples_json(path.as_ref());
+    }
+
+    let reader = logging::open_log_reader(path.as_ref())
         .with_context(|| format!("Failed to open log file: {}", path.as_ref().display()))?;
-    
-    let reader = BufReader::new(file);
-    let mut lines = reader.lines();
+    let mut lines = logging::csv_lines(reader);
     
     // Read header line
     let header_line = lines.next()
@@ -239,6 +426,7 @@ pub fn load_detailed_samples<P: AsRef<Path>>(path: P) -> Result<Vec<DetailedPlot
             cpu_user: parse_f64("cpu_user_pct"),
             cpu_system: parse_f64("cpu_system_pct"),
             cpu_iowait: parse_f64("cpu_iowait_pct"),
+            cpu_steal: parse_f64("cpu_steal_pct"),
             cpu_load_1m: parse_f64("cpu_load_1m"),
             cpu_load_5m: parse_f64("cpu_load_5m"),
             cpu_load_15m: parse_f64("cpu_load_15m"),
@@ -276,6 +464,11 @@ pub fn load_detailed_samples<P: AsRef<Path>>(path: P) -> Result<Vec<DetailedPlot
             psi_mem_full_avg10: parse_opt_f64("psi_mem_full_avg10"),
             psi_io_some_avg10: parse_f64("psi_io_some_avg10"),
             psi_io_full_avg10: parse_opt_f64("psi_io_full_avg10"),
+            proc_cgroup_psi_cpu_some_avg10: parse_opt_f64("psi_proc_cgroup_cpu_some_avg10"),
+            proc_cgroup_psi_mem_some_avg10: parse_opt_f64("psi_proc_cgroup_mem_some_avg10"),
+            proc_cgroup_psi_mem_full_avg10: parse_opt_f64("psi_proc_cgroup_mem_full_avg10"),
+            proc_cgroup_psi_io_some_avg10: parse_opt_f64("psi_proc_cgroup_io_some_avg10"),
+            proc_cgroup_psi_io_full_avg10: parse_opt_f64("psi_proc_cgroup_io_full_avg10"),
             // Process
             proc_cpu_pct: parse_opt_f64("proc_cpu_pct"),
             proc_rss_bytes: parse_opt_u64("proc_rss_bytes"),
@@ -293,8 +486,122 @@ pub fn load_detailed_samples<P: AsRef<Path>>(path: P) -> Result<Vec<DetailedPlot
     Ok(samples)
 }
 
+/// Load detailed samples from a JSON Lines log (one MetricsSample per line, as written by --json-log)
+pub fn load_detailed_samples_json(path: &Path) -> Result<Vec<DetailedPlotSample>> {
+    let samples = logging::read_json_log(path)?;
+    if samples.is_empty() {
+        return Err(anyhow::anyhow!("No samples found in JSON log"));
+    }
+    Ok(detailed_from_samples(&samples))
+}
+
+/// Convert full samples (from a capture or JSON log) to plot samples
+fn detailed_from_samples(samples: &[MetricsSample]) -> Vec<DetailedPlotSample> {
+    // Devices and interfaces in first-seen order (the first sample may have none yet)
+    let mut disk_devices: Vec<String> = Vec::new();
+    let mut net_interfaces: Vec<String> = Vec::new();
+    for sample in samples {
+        for d in &sample.disk.disks {
+            if !disk_devices.contains(&d.device) {
+                disk_devices.push(d.device.clone());
+            }
+        }
+        for i in &sample.network.interfaces {
+            if !net_interfaces.contains(&i.interface) {
+                net_interfaces.push(i.interface.clone());
+            }
+        }
+    }
+
+    samples.iter()
+        .map(|s| detailed_from_sample(s, &disk_devices, &net_interfaces))
+        .collect()
+}
+
+/// Build a plot sample directly from a captured MetricsSample (no parsing loss)
+fn detailed_from_sample(
+    sample: &MetricsSample,
+    disk_devices: &[String],
+    net_interfaces: &[String],
+) -> DetailedPlotSample {
+    let psi = sample.psi.clone().unwrap_or_default();
+    let cgroup_psi = sample.cgroup_psi.as_ref();
+    let process = sample.process.as_ref();
+    let disk = |dev: &String| sample.disk.disks.iter().find(|d| &d.device == dev);
+    let iface = |name: &String| sample.network.interfaces.iter().find(|i| &i.interface == name);
+
+    DetailedPlotSample {
+        timestamp: sample.timestamp,
+        // CPU
+        cpu_total: sample.cpu.total_utilization,
+        cpu_user: sample.cpu.user_percent,
+        cpu_system: sample.cpu.system_percent,
+        cpu_iowait: sample.cpu.iowait_percent,
+        cpu_steal: sample.cpu.steal_percent,
+        cpu_load_1m: sample.cpu.load_avg.0,
+        cpu_load_5m: sample.cpu.load_avg.1,
+        cpu_load_15m: sample.cpu.load_avg.2,
+        per_core_pct: sample.cpu.per_core.iter().map(|c| c.utilization_percent).collect(),
+        // Memory
+        mem_total_bytes: sample.memory.total,
+        mem_used_bytes: sample.memory.used,
+        mem_available_bytes: sample.memory.available,
+        mem_used_pct: sample.memory.used_percent,
+        mem_buffers_bytes: sample.memory.buffers,
+        mem_cached_bytes: sample.memory.cached,
+        mem_dirty_bytes: sample.memory.dirty,
+        mem_writeback_bytes: sample.memory.writeback,
+        mem_swap_total_bytes: sample.memory.swap_total,
+        mem_swap_used_bytes: sample.memory.swap_used,
+        cgroup_limit_bytes: sample.memory.cgroup_limit,
+        cgroup_current_bytes: sample.memory.cgroup_current,
+        cgroup_usage_pct: sample.memory.cgroup_usage_percent,
+        // Disk
+        disk_devices: disk_devices.to_vec(),
+        disk_read_bytes_per_sec: disk_devices.iter()
+            .map(|dev| disk(dev).map(|d| d.read_bytes_per_sec).unwrap_or(0.0))
+            .collect(),
+        disk_write_bytes_per_sec: disk_devices.iter()
+            .map(|dev| disk(dev).map(|d| d.write_bytes_per_sec).unwrap_or(0.0))
+            .collect(),
+        disk_util_pct: disk_devices.iter()
+            .map(|dev| disk(dev).map(|d| d.utilization_percent).unwrap_or(0.0))
+            .collect(),
+        disk_total_read: sample.disk.total_read_bytes_per_sec,
+        disk_total_write: sample.disk.total_write_bytes_per_sec,
+        // Network
+        net_interfaces: net_interfaces.to_vec(),
+        net_rx_bytes_per_sec: net_interfaces.iter()
+            .map(|name| iface(name).map(|i| i.rx_bytes_per_sec).unwrap_or(0.0))
+            .collect(),
+        net_tx_bytes_per_sec: net_interfaces.iter()
+            .map(|name| iface(name).map(|i| i.tx_bytes_per_sec).unwrap_or(0.0))
+            .collect(),
+        net_total_rx: sample.network.total_rx_bytes_per_sec,
+        net_total_tx: sample.network.total_tx_bytes_per_sec,
+        // PSI
+        psi_cpu_some_avg10: psi.cpu.some_avg10,
+        psi_mem_some_avg10: psi.memory.some_avg10,
+        psi_mem_full_avg10: psi.memory.full_avg10,
+        psi_io_some_avg10: psi.io.some_avg10,
+        psi_io_full_avg10: psi.io.full_avg10,
+        proc_cgroup_psi_cpu_some_avg10: cgroup_psi.map(|p| p.cpu.some_avg10),
+        proc_cgroup_psi_mem_some_avg10: cgroup_psi.map(|p| p.memory.some_avg10),
+        proc_cgroup_psi_mem_full_avg10: cgroup_psi.and_then(|p| p.memory.full_avg10),
+        proc_cgroup_psi_io_some_avg10: cgroup_psi.map(|p| p.io.some_avg10),
+        proc_cgroup_psi_io_full_avg10: cgroup_psi.and_then(|p| p.io.full_avg10),
+        // Process
+        proc_cpu_pct: process.map(|p| p.cpu_percent),
+        proc_rss_bytes: process.map(|p| p.rss_bytes),
+        proc_io_read_bytes_per_sec: process.map(|p| p.io_read_bytes_per_sec),
+        proc_io_write_bytes_per_sec: process.map(|p| p.io_write_bytes_per_sec),
+    }
+}
+
 /// Generate all plots from samples (using detailed data)
 pub fn generate_plots<P: AsRef<Path>>(samples: &[PlotSample], output_dir: P) -> Result<Vec<String>> {
+    type C = SvgCanvas;
+    let options = &PlotOptions::default();
     // Convert simple samples back to load detailed data
     // This is a bit wasteful but maintains API compatibility
     let output_dir = output_dir.as_ref();
@@ -303,119 +610,421 @@ pub fn generate_plots<P: AsRef<Path>>(samples: &[PlotSample], output_dir: P) ->
     let mut generated = Vec::new();
     
     // Generate CPU plot
-    let cpu_path = output_dir.join("cpu.svg");
-    plot_cpu(samples, &cpu_path)?;
+    let cpu_path = plot_file::<C>(output_dir, "cpu");
+    plot_cpu::<C>(samples, &cpu_path, options)?;
     generated.push(cpu_path.display().to_string());
     
     // Generate Memory plot
-    let mem_path = output_dir.join("memory.svg");
-    plot_memory(samples, &mem_path)?;
+    let mem_path = plot_file::<C>(output_dir, "memory");
+    plot_memory::<C>(samples, &mem_path, options)?;
     generated.push(mem_path.display().to_string());
     
     // Generate Disk I/O plot
-    let disk_path = output_dir.join("disk_io.svg");
-    plot_disk_io(samples, &disk_path)?;
+    let disk_path = plot_file::<C>(output_dir, "disk_io");
+    plot_disk_io::<C>(samples, &disk_path, options)?;
     generated.push(disk_path.display().to_string());
     
     // Generate Network I/O plot
-    let net_path = output_dir.join("network_io.svg");
-    plot_network_io(samples, &net_path)?;
+    let net_path = plot_file::<C>(output_dir, "network_io");
+    plot_network_io::<C>(samples, &net_path, options)?;
     generated.push(net_path.display().to_string());
     
     // Generate Process plot if data exists
     if samples.iter().any(|s| s.proc_cpu_pct.is_some()) {
-        let proc_path = output_dir.join("process.svg");
-        plot_process(samples, &proc_path)?;
+        let proc_path = plot_file::<C>(output_dir, "process");
+        plot_process::<C>(samples, &proc_path, options)?;
         generated.push(proc_path.display().to_string());
     }
     
     // Generate combined overview
-    let overview_path = output_dir.join("overview.svg");
-    plot_overview(samples, &overview_path)?;
+    let overview_path = plot_file::<C>(output_dir, "overview");
+    plot_overview::<C>(samples, &overview_path, options)?;
     generated.push(overview_path.display().to_string());
     
     Ok(generated)
 }
 
 /// Generate all plots including detailed views from CSV file path
-pub fn generate_all_plots<P: AsRef<Path>, Q: AsRef<Path>>(csv_path: P, output_dir: Q) -> Result<Vec<String>> {
-    let output_dir = output_dir.as_ref();
+pub fn generate_all_plots<P: AsRef<Path>, Q: AsRef<Path>>(
+    csv_path: P,
+    output_dir: Q,
+    options: &PlotOptions,
+) -> Result<Vec<String>> {
+    match options.format {
+        PlotFormat::Svg => generate_all_plots_as::<SvgCanvas>(csv_path.as_ref(), output_dir.as_ref(), options),
+        PlotFormat::Png => {
+            load_png_fonts(options.font.as_deref())?;
+            generate_all_plots_as::<PngCanvas>(csv_path.as_ref(), output_dir.as_ref(), options)
+        }
+    }
+}
+
+fn generate_all_plots_as<C: Canvas>(csv_path: &Path, output_dir: &Path, options: &PlotOptions) -> Result<Vec<String>> {
     std::fs::create_dir_all(output_dir)?;
     
-    let detailed_samples = load_detailed_samples(&csv_path)?;
-    let simple_samples = load_samples(&csv_path)?;
+    let mut detailed_samples = load_detailed_samples(csv_path)?;
+    if let Some(max_points) = options.max_points {
+        detailed_samples = downsample_min_max(&detailed_samples, max_points);
+    }
+    let simple_samples: Vec<PlotSample> = detailed_samples.iter().map(PlotSample::from).collect();
     
     let mut generated = Vec::new();
     
     // Basic plots
-    let cpu_path = output_dir.join("cpu.svg");
-    plot_cpu(&simple_samples, &cpu_path)?;
+    let cpu_path = plot_file::<C>(output_dir, "cpu");
+    plot_cpu::<C>(&simple_samples, &cpu_path, options)?;
     generated.push(cpu_path.display().to_string());
     
-    let mem_path = output_dir.join("memory.svg");
-    plot_memory(&simple_samples, &mem_path)?;
+    let mem_path = plot_file::<C>(output_dir, "memory");
+    plot_memory::<C>(&simple_samples, &mem_path, options)?;
     generated.push(mem_path.display().to_string());
     
-    let disk_path = output_dir.join("disk_io.svg");
-    plot_disk_io(&simple_samples, &disk_path)?;
+    let disk_path = plot_file::<C>(output_dir, "disk_io");
+    plot_disk_io::<C>(&simple_samples, &disk_path, options)?;
     generated.push(disk_path.display().to_string());
     
-    let net_path = output_dir.join("network_io.svg");
-    plot_network_io(&simple_samples, &net_path)?;
+    let net_path = plot_file::<C>(output_dir, "network_io");
+    plot_network_io::<C>(&simple_samples, &net_path, options)?;
     generated.push(net_path.display().to_string());
     
     // Detailed plots
     if !detailed_samples.is_empty() && !detailed_samples[0].per_core_pct.is_empty() {
-        let cpu_cores_path = output_dir.join("cpu_cores.svg");
-        plot_cpu_cores(&detailed_samples, &cpu_cores_path)?;
+        let cpu_cores_path = plot_file::<C>(output_dir, "cpu_cores");
+        plot_cpu_cores::<C>(&detailed_samples, &cpu_cores_path, options)?;
         generated.push(cpu_cores_path.display().to_string());
     }
     
-    let mem_detail_path = output_dir.join("memory_detailed.svg");
-    plot_memory_detailed(&detailed_samples, &mem_detail_path)?;
+    let mem_detail_path = plot_file::<C>(output_dir, "memory_detailed");
+    plot_memory_detailed::<C>(&detailed_samples, &mem_detail_path, options)?;
     generated.push(mem_detail_path.display().to_string());
     
     if !detailed_samples.is_empty() && !detailed_samples[0].disk_devices.is_empty() {
-        let disk_detail_path = output_dir.join("disk_io_detailed.svg");
-        plot_disk_io_detailed(&detailed_samples, &disk_detail_path)?;
+        let disk_detail_path = plot_file::<C>(output_dir, "disk_io_detailed");
+        plot_disk_io_detailed::<C>(&detailed_samples, &disk_detail_path, options)?;
         generated.push(disk_detail_path.display().to_string());
     }
     
     if !detailed_samples.is_empty() && !detailed_samples[0].net_interfaces.is_empty() {
-        let net_detail_path = output_dir.join("network_io_detailed.svg");
-        plot_network_io_detailed(&detailed_samples, &net_detail_path)?;
+        let net_detail_path = plot_file::<C>(output_dir, "network_io_detailed");
+        plot_network_io_detailed::<C>(&detailed_samples, &net_detail_path, options)?;
         generated.push(net_detail_path.display().to_string());
     }
     
     // PSI plot
-    let psi_path = output_dir.join("psi.svg");
-    plot_psi(&detailed_samples, &psi_path)?;
+    let psi_path = plot_file::<C>(output_dir, "psi");
+    plot_psi::<C>(&detailed_samples, &psi_path, options, "", |s| {
+        PsiAvg10 {
+            cpu_some: s.psi_cpu_some_avg10,
+            mem_some: s.psi_mem_some_avg10,
+            mem_full: s.psi_mem_full_avg10.unwrap_or(0.0),
+            io_some: s.psi_io_some_avg10,
+            io_full: s.psi_io_full_avg10.unwrap_or(0.0),
+        }
+    })?;
     generated.push(psi_path.display().to_string());
+
+    // Pressure of the monitored process's cgroup, to compare against the whole node
+    if detailed_samples.iter().any(|s| s.proc_cgroup_psi_cpu_some_avg10.is_some()) {
+        let cgroup_psi_path = plot_file::<C>(output_dir, "psi_cgroup");
+        plot_psi::<C>(&detailed_samples, &cgroup_psi_path, options, "Process Cgroup ", |s| {
+            PsiAvg10 {
+                cpu_some: s.proc_cgroup_psi_cpu_some_avg10.unwrap_or(0.0),
+                mem_some: s.proc_cgroup_psi_mem_some_avg10.unwrap_or(0.0),
+                mem_full: s.proc_cgroup_psi_mem_full_avg10.unwrap_or(0.0),
+                io_some: s.proc_cgroup_psi_io_some_avg10.unwrap_or(0.0),
+                io_full: s.proc_cgroup_psi_io_full_avg10.unwrap_or(0.0),
+            }
+        })?;
+        generated.push(cgroup_psi_path.display().to_string());
+    }
     
     // Load average plot
-    let load_path = output_dir.join("load_average.svg");
-    plot_load_average(&detailed_samples, &load_path)?;
+    let load_path = plot_file::<C>(output_dir, "load_average");
+    plot_load_average::<C>(&detailed_samples, &load_path, options)?;
     generated.push(load_path.display().to_string());
     
     // Process plot if data exists
     if simple_samples.iter().any(|s| s.proc_cpu_pct.is_some()) {
-        let proc_path = output_dir.join("process.svg");
-        plot_process(&simple_samples, &proc_path)?;
+        let proc_path = plot_file::<C>(output_dir, "process");
+        plot_process::<C>(&simple_samples, &proc_path, options)?;
         generated.push(proc_path.display().to_string());
         
-        let proc_io_path = output_dir.join("process_io.svg");
-        plot_process_io(&detailed_samples, &proc_io_path)?;
+        let proc_io_path = plot_file::<C>(output_dir, "process_io");
+        plot_process_io::<C>(&detailed_samples, &proc_io_path, options)?;
         generated.push(proc_io_path.display().to_string());
     }
     
     // Combined overview
-    let overview_path = output_dir.join("overview.svg");
-    plot_overview(&simple_samples, &overview_path)?;
+    let overview_path = plot_file::<C>(output_dir, "overview");
+    plot_overview::<C>(&simple_samples, &overview_path, options)?;
     generated.push(overview_path.display().to_string());
     
     Ok(generated)
 }
 
+/// Where common distributions install DejaVu or Liberation fonts, for PNG text
+const SANS_FONT_PATHS: &[&str] = &[
+    "/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf",
+    "/usr/share/fonts/dejavu-sans-fonts/DejaVuSans.ttf",
+    "/usr/share/fonts/dejavu/DejaVuSans.ttf",
+    "/usr/share/fonts/TTF/DejaVuSans.ttf",
+    "/usr/share/fonts/truetype/liberation/LiberationSans-Regular.ttf",
+    "/usr/share/fonts/liberation-sans/LiberationSans-Regular.ttf",
+];
+const MONO_FONT_PATHS: &[&str] = &[
+    "/usr/share/fonts/truetype/dejavu/DejaVuSansMono.ttf",
+    "/usr/share/fonts/dejavu-sans-mono-fonts/DejaVuSansMono.ttf",
+    "/usr/share/fonts/dejavu/DejaVuSansMono.ttf",
+    "/usr/share/fonts/TTF/DejaVuSansMono.ttf",
+    "/usr/share/fonts/truetype/liberation/LiberationMono-Regular.ttf",
+    "/usr/share/fonts/liberation-mono/LiberationMono-Regular.ttf",
+];
+
+/// First font file of `candidates` that exists
+fn find_font(candidates: &[&str]) -> Option<PathBuf> {
+    candidates.iter().map(PathBuf::from).find(|path| path.is_file())
+}
+
+/// Fonts PNG text is rasterized with, set by `load_png_fonts` before any PNG is drawn
+static PNG_FONTS: OnceLock<PngFonts> = OnceLock::new();
+
+struct PngFonts {
+    sans: FontVec,
+    mono: FontVec,
+}
+
+impl PngFonts {
+    fn for_family(&self, family: FontFamily) -> &FontVec {
+        match family {
+            FontFamily::Monospace => &self.mono,
+            _ => &self.sans,
+        }
+    }
+}
+
+/// Load the PNG text fonts: `font` for every family when given, otherwise the first of
+/// the usual system font files found. SVG output needs none; the viewer renders its text.
+fn load_png_fonts(font: Option<&Path>) -> Result<()> {
+    if PNG_FONTS.get().is_some() {
+        return Ok(());
+    }
+    let load = |family: &str, candidates: &[&str]| -> Result<FontVec> {
+        let path = font.map(Path::to_path_buf).or_else(|| find_font(candidates)).with_context(|| {
+            format!("No {} font found for PNG plots; pass --plot-font with a TrueType font file", family)
+        })?;
+        let data = std::fs::read(&path).with_context(|| format!("Failed to read font {}", path.display()))?;
+        FontVec::try_from_vec(data)
+            .map_err(|_| anyhow::anyhow!("{} is not a usable TrueType/OpenType font", path.display()))
+    };
+    let fonts = PngFonts { sans: load("sans-serif", SANS_FONT_PATHS)?, mono: load("monospace", MONO_FONT_PATHS)? };
+    let _ = PNG_FONTS.set(fonts);
+    Ok(())
+}
+
+/// Width of `text` set in `font`, in pixels
+fn text_width(font: &PxScaleFont<&FontVec>, text: &str) -> f32 {
+    let mut width = 0.0;
+    let mut prev = None;
+    for c in text.chars() {
+        let glyph = font.glyph_id(c);
+        if let Some(prev) = prev {
+            width += font.kern(prev, glyph);
+        }
+        width += font.h_advance(glyph);
+        prev = Some(glyph);
+    }
+    width
+}
+
+/// Bitmap backend that rasterizes text from the fonts in PNG_FONTS
+///
+/// plotters is built without a font engine so that SVG layout never needs font files;
+/// only PNG output draws glyphs itself, which this backend does with ab_glyph.
+struct PngText<'a> {
+    bitmap: BitMapBackend<'a>,
+    fonts: &'static PngFonts,
+}
+
+impl DrawingBackend for PngText<'_> {
+    type ErrorType = <BitMapBackend<'static> as DrawingBackend>::ErrorType;
+
+    fn get_size(&self) -> (u32, u32) {
+        self.bitmap.get_size()
+    }
+
+    fn ensure_prepared(&mut self) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        self.bitmap.ensure_prepared()
+    }
+
+    fn present(&mut self) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        self.bitmap.present()
+    }
+
+    fn draw_pixel(&mut self, point: BackendCoord, color: BackendColor) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        self.bitmap.draw_pixel(point, color)
+    }
+
+    fn draw_line<S: plotters_backend::BackendStyle>(
+        &mut self,
+        from: BackendCoord,
+        to: BackendCoord,
+        style: &S,
+    ) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        self.bitmap.draw_line(from, to, style)
+    }
+
+    fn draw_rect<S: plotters_backend::BackendStyle>(
+        &mut self,
+        upper_left: BackendCoord,
+        bottom_right: BackendCoord,
+        style: &S,
+        fill: bool,
+    ) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        self.bitmap.draw_rect(upper_left, bottom_right, style, fill)
+    }
+
+    fn draw_path<S: plotters_backend::BackendStyle, I: IntoIterator<Item = BackendCoord>>(
+        &mut self,
+        path: I,
+        style: &S,
+    ) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        self.bitmap.draw_path(path, style)
+    }
+
+    fn draw_circle<S: plotters_backend::BackendStyle>(
+        &mut self,
+        center: BackendCoord,
+        radius: u32,
+        style: &S,
+        fill: bool,
+    ) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        self.bitmap.draw_circle(center, radius, style, fill)
+    }
+
+    fn fill_polygon<S: plotters_backend::BackendStyle, I: IntoIterator<Item = BackendCoord>>(
+        &mut self,
+        vert: I,
+        style: &S,
+    ) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        self.bitmap.fill_polygon(vert, style)
+    }
+
+    fn blit_bitmap(
+        &mut self,
+        pos: BackendCoord,
+        size: (u32, u32),
+        src: &[u8],
+    ) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        self.bitmap.blit_bitmap(pos, size, src)
+    }
+
+    /// Same placement as plotters' own font engine: the anchor picks the corner of a box
+    /// one advance wide and size / 1.24 high, rotated by the style's transform
+    fn draw_text<S: BackendTextStyle>(
+        &mut self,
+        text: &str,
+        style: &S,
+        pos: BackendCoord,
+    ) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        let color = style.color();
+        if color.alpha == 0.0 {
+            return Ok(());
+        }
+        let size = style.size() as f32;
+        let font = self.fonts.for_family(style.family()).as_scaled(size);
+        let (width, height) = (text_width(&font, text) as i32, (size / 1.24) as i32);
+        let dx = match style.anchor().h_pos {
+            HPos::Left => 0,
+            HPos::Right => -width,
+            HPos::Center => -width / 2,
+        };
+        let dy = match style.anchor().v_pos {
+            VPos::Top => 0,
+            VPos::Center => -height / 2,
+            VPos::Bottom => -height,
+        };
+        let transform = style.transform();
+        let (w, h) = self.get_size();
+
+        let mut x_shift = 0.0;
+        let mut prev = None;
+        for c in text.chars() {
+            let glyph_id = font.glyph_id(c);
+            if let Some(prev) = prev {
+                x_shift += font.kern(prev, glyph_id);
+            }
+            prev = Some(glyph_id);
+            if let Some(outline) = font.outline_glyph(font.scaled_glyph(c)) {
+                let bounds = outline.px_bounds();
+                let glyph_x = (x_shift + bounds.min.x) as i32 + dx;
+                let glyph_y = (size / 2.0 + bounds.min.y) as i32 + dy;
+                let mut coverage = Vec::new();
+                outline.draw(|x, y, c| coverage.push((x as i32, y as i32, c)));
+                for (x, y, c) in coverage {
+                    let (x, y) = transform.transform(glyph_x + x, glyph_y + y);
+                    let (x, y) = (pos.0 + x, pos.1 + y);
+                    if x >= 0 && x < w as i32 && y >= 0 && y < h as i32 {
+                        let shade = BackendColor { alpha: color.alpha * f64::from(c), rgb: color.rgb };
+                        self.bitmap.draw_pixel((x, y), shade)?;
+                    }
+                }
+            }
+            x_shift += font.h_advance(glyph_id);
+        }
+        Ok(())
+    }
+
+    fn estimate_text_size<S: BackendTextStyle>(
+        &self,
+        text: &str,
+        style: &S,
+    ) -> Result<(u32, u32), DrawingErrorKind<Self::ErrorType>> {
+        let size = style.size() as f32;
+        let font = self.fonts.for_family(style.family()).as_scaled(size);
+        Ok((text_width(&font, text) as u32, (size / 1.24) as u32))
+    }
+}
+
+/// Drawing backend and file extension for a plot format, so every plot
+/// function is written once and renders to any format
+trait Canvas {
+    type Backend<'a>: DrawingBackend<ErrorType = Self::Error>;
+    type Error: std::error::Error + Send + Sync + 'static;
+    const EXTENSION: &'static str;
+
+    fn area(path: &Path, size: (u32, u32)) -> DrawingArea<Self::Backend<'_>, Shift>;
+}
+
+struct SvgCanvas;
+
+impl Canvas for SvgCanvas {
+    type Backend<'a> = SVGBackend<'a>;
+    type Error = <SVGBackend<'static> as DrawingBackend>::ErrorType;
+    const EXTENSION: &'static str = "svg";
+
+    fn area(path: &Path, size: (u32, u32)) -> DrawingArea<Self::Backend<'_>, Shift> {
+        SVGBackend::new(path, size).into_drawing_area()
+    }
+}
+
+struct PngCanvas;
+
+impl Canvas for PngCanvas {
+    type Backend<'a> = PngText<'a>;
+    type Error = <BitMapBackend<'static> as DrawingBackend>::ErrorType;
+    const EXTENSION: &'static str = "png";
+
+    fn area(path: &Path, size: (u32, u32)) -> DrawingArea<Self::Backend<'_>, Shift> {
+        let fonts = PNG_FONTS.get().expect("load_png_fonts runs before PNG plots are drawn");
+        PngText { bitmap: BitMapBackend::new(path, size), fonts }.into_drawing_area()
+    }
+}
+
+/// Output path of a plot in the canvas's format (e.g., "plots/cpu.png")
+fn plot_file<C: Canvas>(output_dir: &Path, name: &str) -> PathBuf {
+    output_dir.join(format!("{}.{}", name, C::EXTENSION))
+}
+
 /// Convert timestamp to seconds from start
 fn to_elapsed_secs(samples: &[PlotSample]) -> Vec<f64> {
     if samples.is_empty() {
@@ -439,16 +1048,17 @@ fn to_elapsed_secs_detailed(samples: &[DetailedPlotSample]) -> Vec<f64> {
 }
 
 /// Plot CPU metrics
-fn plot_cpu<P: AsRef<Path>>(samples: &[PlotSample], path: P) -> Result<()> {
+fn plot_cpu<C: Canvas>(samples: &[PlotSample], path: &Path, options: &PlotOptions) -> Result<()> {
     let times = to_elapsed_secs(samples);
     let total: Vec<f64> = samples.iter().map(|s| s.cpu_total).collect();
     let user: Vec<f64> = samples.iter().map(|s| s.cpu_user).collect();
     let system: Vec<f64> = samples.iter().map(|s| s.cpu_system).collect();
     let iowait: Vec<f64> = samples.iter().map(|s| s.cpu_iowait).collect();
+    let steal: Vec<f64> = samples.iter().map(|s| s.cpu_steal).collect();
     
     let max_time = times.last().copied().unwrap_or(1.0);
     
-    let root = SVGBackend::new(path.as_ref(), (1200, 600)).into_drawing_area();
+    let root = C::area(path, options.scaled((1200, 600)));
     root.fill(&WHITE)?;
     
     let mut chart = ChartBuilder::on(&root)
@@ -487,17 +1097,27 @@ fn plot_cpu<P: AsRef<Path>>(samples: &[PlotSample], path: P) -> Result<()> {
         &MAGENTA,
     ))?.label("IOWait").legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], MAGENTA));
     
+    // Steal (time taken by the hypervisor)
+    chart.draw_series(LineSeries::new(
+        times.iter().zip(steal.iter()).map(|(x, y)| (*x, *y)),
+        &CYAN,
+    ))?.label("Steal").legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], CYAN));
+    
     chart.configure_series_labels()
         .background_style(WHITE.mix(0.8))
         .border_style(BLACK)
         .draw()?;
     
+    if options.annotate_stats {
+        draw_stats_box(&root, &[stats_line("CPU total", &total, |v| format!("{:.0}%", v))])?;
+    }
+    
     root.present()?;
     Ok(())
 }
 
 /// Plot Memory metrics
-fn plot_memory<P: AsRef<Path>>(samples: &[PlotSample], path: P) -> Result<()> {
+fn plot_memory<C: Canvas>(samples: &[PlotSample], path: &Path, options: &PlotOptions) -> Result<()> {
     let times = to_elapsed_secs(samples);
     let used_pct: Vec<f64> = samples.iter().map(|s| s.mem_used_pct).collect();
     let cgroup_pct: Vec<f64> = samples.iter()
@@ -507,7 +1127,7 @@ fn plot_memory<P: AsRef<Path>>(samples: &[PlotSample], path: P) -> Result<()> {
     let max_time = times.last().copied().unwrap_or(1.0);
     let has_cgroup = cgroup_pct.iter().any(|&v| v > 0.0);
     
-    let root = SVGBackend::new(path.as_ref(), (1200, 600)).into_drawing_area();
+    let root = C::area(path, options.scaled((1200, 600)));
     root.fill(&WHITE)?;
     
     let mut chart = ChartBuilder::on(&root)
@@ -541,6 +1161,15 @@ fn plot_memory<P: AsRef<Path>>(samples: &[PlotSample], path: P) -> Result<()> {
         .border_style(BLACK)
         .draw()?;
     
+    if options.annotate_stats {
+        let mut lines = vec![stats_line("System memory", &used_pct, |v| format!("{:.0}%", v))];
+        if has_cgroup {
+            let cgroup: Vec<f64> = samples.iter().filter_map(|s| s.cgroup_usage_pct).collect();
+            lines.push(stats_line("Cgroup memory", &cgroup, |v| format!("{:.0}%", v)));
+        }
+        draw_stats_box(&root, &lines)?;
+    }
+    
     root.present()?;
     Ok(())
 }
@@ -550,8 +1179,63 @@ fn to_mb_per_sec(bytes: f64) -> f64 {
     bytes / (1024.0 * 1024.0)
 }
 
+/// Average, maximum and 95th percentile of a plotted series
+pub(crate) struct SeriesStats {
+    pub avg: f64,
+    pub max: f64,
+    pub p95: f64,
+}
+
+impl SeriesStats {
+    pub(crate) fn of(values: &[f64]) -> Option<Self> {
+        if values.is_empty() {
+            return None;
+        }
+        let mut sorted = values.to_vec();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+        Some(Self {
+            avg: sorted.iter().sum::<f64>() / sorted.len() as f64,
+            max: sorted[sorted.len() - 1],
+            p95: logging::percentile(&sorted, 95.0),
+        })
+    }
+}
+
+/// One annotation line, e.g. "CPU total: avg 42% max 98% p95 81%"
+fn stats_line(label: &str, values: &[f64], fmt: impl Fn(f64) -> String) -> String {
+    match SeriesStats::of(values) {
+        Some(st) => format!("{}: avg {} max {} p95 {}", label, fmt(st.avg), fmt(st.max), fmt(st.p95)),
+        None => format!("{}: no data", label),
+    }
+}
+
+/// Draw a boxed block of text in the upper-right corner of a drawing area
+fn draw_stats_box<DB: DrawingBackend>(area: &DrawingArea<DB, Shift>, lines: &[String]) -> Result<()>
+where
+    DB::ErrorType: 'static,
+{
+    const LINE_HEIGHT: i32 = 18;
+    const CHAR_WIDTH: i32 = 8;
+    const PADDING: i32 = 8;
+
+    let (width, _) = area.dim_in_pixel();
+    let text_width = lines.iter().map(|l| l.chars().count()).max().unwrap_or(0) as i32 * CHAR_WIDTH;
+    let box_width = text_width + 2 * PADDING;
+    let box_height = lines.len() as i32 * LINE_HEIGHT + 2 * PADDING;
+    let x = width as i32 - box_width - 30;
+    let y = 45;
+
+    area.draw(&Rectangle::new([(x, y), (x + box_width, y + box_height)], WHITE.mix(0.85).filled()))?;
+    area.draw(&Rectangle::new([(x, y), (x + box_width, y + box_height)], BLACK))?;
+    let font = ("monospace", 14).into_font();
+    for (i, line) in lines.iter().enumerate() {
+        area.draw(&Text::new(line.clone(), (x + PADDING, y + PADDING + i as i32 * LINE_HEIGHT), font.clone()))?;
+    }
+    Ok(())
+}
+
 /// Plot Disk I/O metrics
-fn plot_disk_io<P: AsRef<Path>>(samples: &[PlotSample], path: P) -> Result<()> {
+fn plot_disk_io<C: Canvas>(samples: &[PlotSample], path: &Path, options: &PlotOptions) -> Result<()> {
     let times = to_elapsed_secs(samples);
     let read_mb: Vec<f64> = samples.iter()
         .map(|s| to_mb_per_sec(s.disk_read_bytes_per_sec))
@@ -566,7 +1250,7 @@ fn plot_disk_io<P: AsRef<Path>>(samples: &[PlotSample], path: P) -> Result<()> {
         .fold(0.0_f64, f64::max)
         .max(1.0) * 1.1;
     
-    let root = SVGBackend::new(path.as_ref(), (1200, 600)).into_drawing_area();
+    let root = C::area(path, options.scaled((1200, 600)));
     root.fill(&WHITE)?;
     
     let mut chart = ChartBuilder::on(&root)
@@ -598,12 +1282,19 @@ fn plot_disk_io<P: AsRef<Path>>(samples: &[PlotSample], path: P) -> Result<()> {
         .border_style(BLACK)
         .draw()?;
     
+    if options.annotate_stats {
+        draw_stats_box(&root, &[
+            stats_line("Read", &read_mb, |v| format!("{:.1} MB/s", v)),
+            stats_line("Write", &write_mb, |v| format!("{:.1} MB/s", v)),
+        ])?;
+    }
+    
     root.present()?;
     Ok(())
 }
 
 /// Plot Network I/O metrics
-fn plot_network_io<P: AsRef<Path>>(samples: &[PlotSample], path: P) -> Result<()> {
+fn plot_network_io<C: Canvas>(samples: &[PlotSample], path: &Path, options: &PlotOptions) -> Result<()> {
     let times = to_elapsed_secs(samples);
     let rx_mb: Vec<f64> = samples.iter()
         .map(|s| to_mb_per_sec(s.net_rx_bytes_per_sec))
@@ -618,7 +1309,7 @@ fn plot_network_io<P: AsRef<Path>>(samples: &[PlotSample], path: P) -> Result<()
         .fold(0.0_f64, f64::max)
         .max(1.0) * 1.1;
     
-    let root = SVGBackend::new(path.as_ref(), (1200, 600)).into_drawing_area();
+    let root = C::area(path, options.scaled((1200, 600)));
     root.fill(&WHITE)?;
     
     let mut chart = ChartBuilder::on(&root)
@@ -650,12 +1341,19 @@ fn plot_network_io<P: AsRef<Path>>(samples: &[PlotSample], path: P) -> Result<()
         .border_style(BLACK)
         .draw()?;
     
+    if options.annotate_stats {
+        draw_stats_box(&root, &[
+            stats_line("RX", &rx_mb, |v| format!("{:.1} MB/s", v)),
+            stats_line("TX", &tx_mb, |v| format!("{:.1} MB/s", v)),
+        ])?;
+    }
+    
     root.present()?;
     Ok(())
 }
 
 /// Plot Process metrics
-fn plot_process<P: AsRef<Path>>(samples: &[PlotSample], path: P) -> Result<()> {
+fn plot_process<C: Canvas>(samples: &[PlotSample], path: &Path, options: &PlotOptions) -> Result<()> {
     let times = to_elapsed_secs(samples);
     let cpu: Vec<f64> = samples.iter()
         .map(|s| s.proc_cpu_pct.unwrap_or(0.0))
@@ -668,10 +1366,10 @@ fn plot_process<P: AsRef<Path>>(samples: &[PlotSample], path: P) -> Result<()> {
     let max_cpu = cpu.iter().cloned().fold(0.0_f64, f64::max).max(100.0) * 1.1;
     let max_rss = rss_gb.iter().cloned().fold(0.0_f64, f64::max).max(0.1) * 1.1;
     
-    let root = SVGBackend::new(path.as_ref(), (1200, 600)).into_drawing_area();
+    let root = C::area(path, options.scaled((1200, 600)));
     root.fill(&WHITE)?;
     
-    let (upper, lower) = root.split_vertically(300);
+    let (upper, lower) = root.split_vertically(options.scaled_height(300));
     
     // CPU chart
     {
@@ -691,6 +1389,11 @@ fn plot_process<P: AsRef<Path>>(samples: &[PlotSample], path: P) -> Result<()> {
             times.iter().zip(cpu.iter()).map(|(x, y)| (*x, *y)),
             &BLUE,
         ))?;
+        
+        if options.annotate_stats {
+            let present: Vec<f64> = samples.iter().filter_map(|s| s.proc_cpu_pct).collect();
+            draw_stats_box(&upper, &[stats_line("Process CPU", &present, |v| format!("{:.0}%", v))])?;
+        }
     }
     
     // RSS chart
@@ -711,6 +1414,14 @@ fn plot_process<P: AsRef<Path>>(samples: &[PlotSample], path: P) -> Result<()> {
             times.iter().zip(rss_gb.iter()).map(|(x, y)| (*x, *y)),
             &RED,
         ))?;
+        
+        if options.annotate_stats {
+            let present: Vec<f64> = samples.iter()
+                .filter_map(|s| s.proc_rss_bytes)
+                .map(|b| b as f64 / (1024.0 * 1024.0 * 1024.0))
+                .collect();
+            draw_stats_box(&lower, &[stats_line("RSS", &present, |v| format!("{:.2} GB", v))])?;
+        }
     }
     
     root.present()?;
@@ -718,11 +1429,11 @@ fn plot_process<P: AsRef<Path>>(samples: &[PlotSample], path: P) -> Result<()> {
 }
 
 /// Generate overview plot with all metrics
-fn plot_overview<P: AsRef<Path>>(samples: &[PlotSample], path: P) -> Result<()> {
+fn plot_overview<C: Canvas>(samples: &[PlotSample], path: &Path, options: &PlotOptions) -> Result<()> {
     let times = to_elapsed_secs(samples);
     let max_time = times.last().copied().unwrap_or(1.0);
     
-    let root = SVGBackend::new(path.as_ref(), (1600, 900)).into_drawing_area();
+    let root = C::area(path, options.scaled((1600, 900)));
     root.fill(&WHITE)?;
     
     let areas = root.split_evenly((2, 2));
@@ -894,7 +1605,7 @@ fn get_color_palette(n: usize) -> Vec<RGBColor> {
 }
 
 /// Plot all CPU cores in a single file with heatmap-style visualization
-fn plot_cpu_cores<P: AsRef<Path>>(samples: &[DetailedPlotSample], path: P) -> Result<()> {
+fn plot_cpu_cores<C: Canvas>(samples: &[DetailedPlotSample], path: &Path, options: &PlotOptions) -> Result<()> {
     if samples.is_empty() || samples[0].per_core_pct.is_empty() {
         return Ok(());
     }
@@ -904,8 +1615,9 @@ fn plot_cpu_cores<P: AsRef<Path>>(samples: &[DetailedPlotSample], path: P) -> Re
     let max_time = times.last().copied().unwrap_or(1.0);
     
     // Calculate height based on number of cores (minimum 20 pixels per core)
-    let chart_height = (num_cores * 25).max(400).min(2000) as u32;
-    let root = SVGBackend::new(path.as_ref(), (1600, chart_height + 200)).into_drawing_area();
+    let chart_height = options.scaled_height((num_cores * 25).max(400).min(2000) as u32);
+    let (width, legend_height) = options.scaled((1600, 200));
+    let root = C::area(path, (width, chart_height + legend_height));
     root.fill(&WHITE)?;
     
     let (upper, lower) = root.split_vertically(chart_height);
@@ -925,18 +1637,27 @@ fn plot_cpu_cores<P: AsRef<Path>>(samples: &[DetailedPlotSample], path: P) -> Re
             .y_label_formatter(&|y| format!("Core {}", y))
             .draw()?;
         
-        // Draw each core as colored rectangles based on utilization
+        // Average consecutive samples into at most `heatmap_columns` time columns so long,
+        // many-core runs don't produce an SVG with millions of rectangles
         let time_step = if times.len() > 1 { 
             (times[1] - times[0]).max(0.1) 
         } else { 
             1.0 
         };
-        
-        for (t_idx, time) in times.iter().enumerate() {
-            if t_idx >= samples.len() { break; }
-            let sample = &samples[t_idx];
+        let chunk_len = samples.len().div_ceil(options.heatmap_columns.max(1));
+        let starts: Vec<usize> = (0..samples.len()).step_by(chunk_len).collect();
+        
+        let mut cells = Vec::with_capacity(starts.len() * num_cores);
+        for (i, &start) in starts.iter().enumerate() {
+            let end = (start + chunk_len).min(samples.len());
+            let x0 = times[start];
+            let x1 = starts.get(i + 1).map(|&next| times[next]).unwrap_or(times[end - 1] + time_step);
             
-            for (core_id, &util) in sample.per_core_pct.iter().enumerate() {
+            for core_id in 0..num_cores {
+                let util = samples[start..end].iter()
+                    .map(|s| s.per_core_pct.get(core_id).copied().unwrap_or(0.0))
+                    .sum::<f64>() / (end - start) as f64;
+                
                 // Color based on utilization (green -> yellow -> red)
                 let color = if util < 50.0 {
                     RGBColor(
@@ -952,12 +1673,13 @@ fn plot_cpu_cores<P: AsRef<Path>>(samples: &[DetailedPlotSample], path: P) -> Re
                     )
                 };
                 
-                chart.draw_series(std::iter::once(Rectangle::new(
-                    [(*time, core_id), (*time + time_step, core_id + 1)],
+                cells.push(Rectangle::new(
+                    [(x0, core_id), (x1, core_id + 1)],
                     color.filled(),
-                )))?;
+                ));
             }
         }
+        chart.draw_series(cells)?;
     }
     
     // Lower area: Legend/color scale
@@ -1003,11 +1725,11 @@ fn plot_cpu_cores<P: AsRef<Path>>(samples: &[DetailedPlotSample], path: P) -> Re
 }
 
 /// Plot detailed memory breakdown
-fn plot_memory_detailed<P: AsRef<Path>>(samples: &[DetailedPlotSample], path: P) -> Result<()> {
+fn plot_memory_detailed<C: Canvas>(samples: &[DetailedPlotSample], path: &Path, options: &PlotOptions) -> Result<()> {
     let times = to_elapsed_secs_detailed(samples);
     let max_time = times.last().copied().unwrap_or(1.0);
     
-    let root = SVGBackend::new(path.as_ref(), (1600, 1200)).into_drawing_area();
+    let root = C::area(path, options.scaled((1600, 1200)));
     root.fill(&WHITE)?;
     
     let areas = root.split_evenly((2, 2));
@@ -1168,7 +1890,7 @@ fn plot_memory_detailed<P: AsRef<Path>>(samples: &[DetailedPlotSample], path: P)
 }
 
 /// Plot per-disk I/O breakdown
-fn plot_disk_io_detailed<P: AsRef<Path>>(samples: &[DetailedPlotSample], path: P) -> Result<()> {
+fn plot_disk_io_detailed<C: Canvas>(samples: &[DetailedPlotSample], path: &Path, options: &PlotOptions) -> Result<()> {
     if samples.is_empty() || samples[0].disk_devices.is_empty() {
         return Ok(());
     }
@@ -1183,7 +1905,7 @@ fn plot_disk_io_detailed<P: AsRef<Path>>(samples: &[DetailedPlotSample], path: P
     let plot_height = 400_u32;
     let total_height = plot_height * 3 + 100;
     
-    let root = SVGBackend::new(path.as_ref(), (1600, total_height)).into_drawing_area();
+    let root = C::area(path, options.scaled((1600, total_height)));
     root.fill(&WHITE)?;
     
     let areas = root.split_evenly((3, 1));
@@ -1301,7 +2023,7 @@ fn plot_disk_io_detailed<P: AsRef<Path>>(samples: &[DetailedPlotSample], path: P
 }
 
 /// Plot per-interface network I/O
-fn plot_network_io_detailed<P: AsRef<Path>>(samples: &[DetailedPlotSample], path: P) -> Result<()> {
+fn plot_network_io_detailed<C: Canvas>(samples: &[DetailedPlotSample], path: &Path, options: &PlotOptions) -> Result<()> {
     if samples.is_empty() || samples[0].net_interfaces.is_empty() {
         return Ok(());
     }
@@ -1312,10 +2034,10 @@ fn plot_network_io_detailed<P: AsRef<Path>>(samples: &[DetailedPlotSample], path
     let num_interfaces = interfaces.len();
     let colors = get_color_palette(num_interfaces);
     
-    let root = SVGBackend::new(path.as_ref(), (1600, 800)).into_drawing_area();
+    let root = C::area(path, options.scaled((1600, 800)));
     root.fill(&WHITE)?;
     
-    let (upper, lower) = root.split_vertically(400);
+    let (upper, lower) = root.split_vertically(options.scaled_height(400));
     
     // RX throughput per interface
     {
@@ -1397,24 +2119,40 @@ fn plot_network_io_detailed<P: AsRef<Path>>(samples: &[DetailedPlotSample], path
     Ok(())
 }
 
-/// Plot PSI (Pressure Stall Information) metrics
-fn plot_psi<P: AsRef<Path>>(samples: &[DetailedPlotSample], path: P) -> Result<()> {
+/// avg10 pressure values of one sample, system-wide or for a cgroup
+struct PsiAvg10 {
+    cpu_some: f64,
+    mem_some: f64,
+    mem_full: f64,
+    io_some: f64,
+    io_full: f64,
+}
+
+/// Plot PSI (Pressure Stall Information) metrics, with `title` prefixed to each caption
+fn plot_psi<C: Canvas>(
+    samples: &[DetailedPlotSample],
+    path: &Path,
+    options: &PlotOptions,
+    title: &str,
+    psi_of: impl Fn(&DetailedPlotSample) -> PsiAvg10,
+) -> Result<()> {
     let times = to_elapsed_secs_detailed(samples);
+    let psi: Vec<PsiAvg10> = samples.iter().map(psi_of).collect();
     let max_time = times.last().copied().unwrap_or(1.0);
     
-    let root = SVGBackend::new(path.as_ref(), (1600, 900)).into_drawing_area();
+    let root = C::area(path, options.scaled((1600, 900)));
     root.fill(&WHITE)?;
     
     let areas = root.split_evenly((3, 1));
     
     // CPU Pressure
     {
-        let cpu_some: Vec<f64> = samples.iter().map(|s| s.psi_cpu_some_avg10).collect();
+        let cpu_some: Vec<f64> = psi.iter().map(|p| p.cpu_some).collect();
         
         let max_y = cpu_some.iter().cloned().fold(0.0_f64, f64::max).max(1.0) * 1.1;
         
         let mut chart = ChartBuilder::on(&areas[0])
-            .caption("CPU Pressure (avg10)", ("sans-serif", 25))
+            .caption(format!("{}CPU Pressure (avg10)", title), ("sans-serif", 25))
             .margin(10)
             .x_label_area_size(40)
             .y_label_area_size(60)
@@ -1438,16 +2176,14 @@ fn plot_psi<P: AsRef<Path>>(samples: &[DetailedPlotSample], path: P) -> Result<(
     
     // Memory Pressure
     {
-        let mem_some: Vec<f64> = samples.iter().map(|s| s.psi_mem_some_avg10).collect();
-        let mem_full: Vec<f64> = samples.iter()
-            .map(|s| s.psi_mem_full_avg10.unwrap_or(0.0))
-            .collect();
+        let mem_some: Vec<f64> = psi.iter().map(|p| p.mem_some).collect();
+        let mem_full: Vec<f64> = psi.iter().map(|p| p.mem_full).collect();
         
         let max_y = mem_some.iter().chain(mem_full.iter())
             .cloned().fold(0.0_f64, f64::max).max(1.0) * 1.1;
         
         let mut chart = ChartBuilder::on(&areas[1])
-            .caption("Memory Pressure (avg10)", ("sans-serif", 25))
+            .caption(format!("{}Memory Pressure (avg10)", title), ("sans-serif", 25))
             .margin(10)
             .x_label_area_size(40)
             .y_label_area_size(60)
@@ -1476,16 +2212,14 @@ fn plot_psi<P: AsRef<Path>>(samples: &[DetailedPlotSample], path: P) -> Result<(
     
     // I/O Pressure
     {
-        let io_some: Vec<f64> = samples.iter().map(|s| s.psi_io_some_avg10).collect();
-        let io_full: Vec<f64> = samples.iter()
-            .map(|s| s.psi_io_full_avg10.unwrap_or(0.0))
-            .collect();
+        let io_some: Vec<f64> = psi.iter().map(|p| p.io_some).collect();
+        let io_full: Vec<f64> = psi.iter().map(|p| p.io_full).collect();
         
         let max_y = io_some.iter().chain(io_full.iter())
             .cloned().fold(0.0_f64, f64::max).max(1.0) * 1.1;
         
         let mut chart = ChartBuilder::on(&areas[2])
-            .caption("I/O Pressure (avg10)", ("sans-serif", 25))
+            .caption(format!("{}I/O Pressure (avg10)", title), ("sans-serif", 25))
             .margin(10)
             .x_label_area_size(40)
             .y_label_area_size(60)
@@ -1517,7 +2251,7 @@ fn plot_psi<P: AsRef<Path>>(samples: &[DetailedPlotSample], path: P) -> Result<(
 }
 
 /// Plot load average
-fn plot_load_average<P: AsRef<Path>>(samples: &[DetailedPlotSample], path: P) -> Result<()> {
+fn plot_load_average<C: Canvas>(samples: &[DetailedPlotSample], path: &Path, options: &PlotOptions) -> Result<()> {
     let times = to_elapsed_secs_detailed(samples);
     let max_time = times.last().copied().unwrap_or(1.0);
     
@@ -1528,7 +2262,7 @@ fn plot_load_average<P: AsRef<Path>>(samples: &[DetailedPlotSample], path: P) ->
     let max_y = load_1m.iter().chain(load_5m.iter()).chain(load_15m.iter())
         .cloned().fold(0.0_f64, f64::max).max(1.0) * 1.1;
     
-    let root = SVGBackend::new(path.as_ref(), (1200, 600)).into_drawing_area();
+    let root = C::area(path, options.scaled((1200, 600)));
     root.fill(&WHITE)?;
     
     let mut chart = ChartBuilder::on(&root)
@@ -1563,12 +2297,16 @@ fn plot_load_average<P: AsRef<Path>>(samples: &[DetailedPlotSample], path: P) ->
         .border_style(BLACK)
         .draw()?;
     
+    if options.annotate_stats {
+        draw_stats_box(&root, &[stats_line("Load 1m", &load_1m, |v| format!("{:.2}", v))])?;
+    }
+    
     root.present()?;
     Ok(())
 }
 
 /// Plot process I/O metrics
-fn plot_process_io<P: AsRef<Path>>(samples: &[DetailedPlotSample], path: P) -> Result<()> {
+fn plot_process_io<C: Canvas>(samples: &[DetailedPlotSample], path: &Path, options: &PlotOptions) -> Result<()> {
     let times = to_elapsed_secs_detailed(samples);
     let max_time = times.last().copied().unwrap_or(1.0);
     
@@ -1582,7 +2320,7 @@ fn plot_process_io<P: AsRef<Path>>(samples: &[DetailedPlotSample], path: P) -> R
     let max_y = read_mb.iter().chain(write_mb.iter())
         .cloned().fold(0.0_f64, f64::max).max(1.0) * 1.1;
     
-    let root = SVGBackend::new(path.as_ref(), (1200, 600)).into_drawing_area();
+    let root = C::area(path, options.scaled((1200, 600)));
     root.fill(&WHITE)?;
     
     let mut chart = ChartBuilder::on(&root)
@@ -1615,3 +2353,251 @@ fn plot_process_io<P: AsRef<Path>>(samples: &[DetailedPlotSample], path: P) -> R
     root.present()?;
     Ok(())
 }
+
+/// Overlay plots of two runs on a shared elapsed-time axis (baseline dashed, candidate solid)
+pub fn generate_compare_plots(
+    baseline: &Path,
+    candidate: &Path,
+    output_dir: &Path,
+    options: &PlotOptions,
+) -> Result<Vec<String>> {
+    match options.format {
+        PlotFormat::Svg => generate_compare_plots_as::<SvgCanvas>(baseline, candidate, output_dir, options),
+        PlotFormat::Png => {
+            load_png_fonts(options.font.as_deref())?;
+            generate_compare_plots_as::<PngCanvas>(baseline, candidate, output_dir, options)
+        }
+    }
+}
+
+/// One metric drawn for both runs in the same color
+struct OverlaySeries {
+    label: &'static str,
+    color: RGBColor,
+    value: fn(&DetailedPlotSample) -> Option<f64>,
+}
+
+/// A comparison chart: its title, y axis and the overlaid metrics
+struct OverlayChart {
+    name: &'static str,
+    title: &'static str,
+    y_desc: &'static str,
+    /// Fixed top of the y axis (percentages), otherwise scaled to the data
+    y_max: Option<f64>,
+    series: Vec<OverlaySeries>,
+}
+
+fn generate_compare_plots_as<C: Canvas>(
+    baseline: &Path,
+    candidate: &Path,
+    output_dir: &Path,
+    options: &PlotOptions,
+) -> Result<Vec<String>> {
+    std::fs::create_dir_all(output_dir)?;
+
+    let load = |path: &Path| -> Result<Vec<DetailedPlotSample>> {
+        let samples = load_detailed_samples(path)?;
+        Ok(match options.max_points {
+            Some(max_points) => downsample_min_max(&samples, max_points),
+            None => samples,
+        })
+    };
+    let baseline = load(baseline)?;
+    let candidate = load(candidate)?;
+
+    let mut charts = vec![
+        OverlayChart {
+            name: "compare_cpu",
+            title: "CPU Utilization",
+            y_desc: "CPU %",
+            y_max: Some(100.0),
+            series: vec![
+                OverlaySeries { label: "Total", color: BLUE, value: |s| Some(s.cpu_total) },
+                OverlaySeries { label: "IOWait", color: MAGENTA, value: |s| Some(s.cpu_iowait) },
+            ],
+        },
+        OverlayChart {
+            name: "compare_memory",
+            title: "Memory Utilization",
+            y_desc: "Memory %",
+            y_max: Some(100.0),
+            series: vec![OverlaySeries { label: "System Memory", color: BLUE, value: |s| Some(s.mem_used_pct) }],
+        },
+        OverlayChart {
+            name: "compare_disk_io",
+            title: "Disk I/O Throughput",
+            y_desc: "Throughput (MB/s)",
+            y_max: None,
+            series: vec![
+                OverlaySeries { label: "Read", color: BLUE, value: |s| Some(to_mb_per_sec(s.disk_total_read)) },
+                OverlaySeries { label: "Write", color: RED, value: |s| Some(to_mb_per_sec(s.disk_total_write)) },
+            ],
+        },
+        OverlayChart {
+            name: "compare_network_io",
+            title: "Network I/O Throughput",
+            y_desc: "Throughput (MB/s)",
+            y_max: None,
+            series: vec![
+                OverlaySeries { label: "RX", color: BLUE, value: |s| Some(to_mb_per_sec(s.net_total_rx)) },
+                OverlaySeries { label: "TX", color: RED, value: |s| Some(to_mb_per_sec(s.net_total_tx)) },
+            ],
+        },
+    ];
+    // Process charts when either run monitored one
+    if baseline.iter().chain(&candidate).any(|s| s.proc_cpu_pct.is_some()) {
+        charts.push(OverlayChart {
+            name: "compare_process",
+            title: "Process CPU",
+            y_desc: "CPU %",
+            y_max: None,
+            series: vec![OverlaySeries { label: "Process CPU", color: BLUE, value: |s| s.proc_cpu_pct }],
+        });
+        charts.push(OverlayChart {
+            name: "compare_process_rss",
+            title: "Process Memory",
+            y_desc: "RSS (MB)",
+            y_max: None,
+            series: vec![OverlaySeries {
+                label: "Process RSS",
+                color: RED,
+                value: |s| s.proc_rss_bytes.map(|b| b as f64 / (1024.0 * 1024.0)),
+            }],
+        });
+    }
+
+    let mut generated = Vec::new();
+    for chart in &charts {
+        let path = plot_file::<C>(output_dir, chart.name);
+        plot_overlay::<C>(chart, &baseline, &candidate, &path, options)?;
+        generated.push(path.display().to_string());
+    }
+    Ok(generated)
+}
+
+/// Draw one comparison chart
+fn plot_overlay<C: Canvas>(
+    spec: &OverlayChart,
+    baseline: &[DetailedPlotSample],
+    candidate: &[DetailedPlotSample],
+    path: &Path,
+    options: &PlotOptions,
+) -> Result<()> {
+    let points = |samples: &[DetailedPlotSample], value: fn(&DetailedPlotSample) -> Option<f64>| -> Vec<(f64, f64)> {
+        to_elapsed_secs_detailed(samples)
+            .into_iter()
+            .zip(samples)
+            .filter_map(|(t, s)| value(s).map(|v| (t, v)))
+            .collect()
+    };
+    let runs: Vec<_> = spec
+        .series
+        .iter()
+        .map(|series| (points(baseline, series.value), points(candidate, series.value)))
+        .collect();
+
+    let all_points = || runs.iter().flat_map(|(b, c)| b.iter().chain(c));
+    let max_time = all_points().map(|&(t, _)| t).fold(0.0_f64, f64::max).max(1.0);
+    let max_y = spec
+        .y_max
+        .unwrap_or_else(|| all_points().map(|&(_, v)| v).fold(0.0_f64, f64::max).max(1.0) * 1.1);
+
+    let root = C::area(path, options.scaled((1200, 600)));
+    root.fill(&WHITE)?;
+
+    let mut chart = ChartBuilder::on(&root)
+        .caption(format!("{}: baseline vs candidate", spec.title), ("sans-serif", 30))
+        .margin(10)
+        .x_label_area_size(40)
+        .y_label_area_size(60)
+        .build_cartesian_2d(0f64..max_time, 0f64..max_y)?;
+
+    chart.configure_mesh()
+        .x_desc("Time since start (seconds)")
+        .y_desc(spec.y_desc)
+        .draw()?;
+
+    for (series, (base, cand)) in spec.series.iter().zip(runs) {
+        let color = series.color;
+        chart.draw_series(DashedLineSeries::new(base, 8, 5, color.stroke_width(2)))?
+            .label(format!("{} (baseline)", series.label))
+            .legend(move |(x, y)| DashedPathElement::new(vec![(x, y), (x + 20, y)], 5, 3, color.stroke_width(2)));
+        chart.draw_series(LineSeries::new(cand, color.stroke_width(2)))?
+            .label(format!("{} (candidate)", series.label))
+            .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], color.stroke_width(2)));
+    }
+
+    chart.configure_series_labels()
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
+        .draw()?;
+
+    root.present()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::environment::EnvironmentInfo;
+    use crate::logging::{CsvFormat, CsvLogger, LogOptions};
+    use crate::metrics::disk::DiskStats;
+    use crate::metrics::{CpuMetrics, DiskMetrics, MemoryMetrics, NetworkMetrics};
+
+    /// A short CSV log in a fresh temporary directory
+    fn write_test_log(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("monperf-test-{}-{}", std::process::id(), name));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("metrics.csv");
+        let options = LogOptions { append: false, flush_every: 1 };
+        let mut logger = CsvLogger::new(&path, CsvFormat::default(), Some(&EnvironmentInfo::probe()), options).unwrap();
+        let start = Utc::now();
+        for i in 0..5 {
+            let mut disk = DiskMetrics::default();
+            disk.disks.push(DiskStats { device: "sda".to_string(), read_bytes_per_sec: 1024.0 * i as f64, ..Default::default() });
+            let cpu = CpuMetrics { total_utilization: 10.0 * i as f64, ..Default::default() };
+            logger.log(&MetricsSample {
+                timestamp: start + chrono::Duration::seconds(i),
+                interval_secs: 1.0,
+                cpu,
+                memory: MemoryMetrics::default(),
+                disk,
+                network: NetworkMetrics::default(),
+                process: None,
+                psi: None,
+                cgroup_psi: None,
+                gpu: None,
+            }).unwrap();
+        }
+        path
+    }
+
+    fn assert_plots_written(format: PlotFormat, extension: &str) {
+        let log = write_test_log(extension);
+        let output = log.with_file_name("plots");
+        let options = PlotOptions { format, ..Default::default() };
+        let generated = generate_all_plots(&log, &output, &options).unwrap();
+
+        assert!(!generated.is_empty());
+        for path in &generated {
+            assert!(path.ends_with(&format!(".{}", extension)), "{}", path);
+            assert!(std::fs::metadata(path).unwrap().len() > 0, "{} is empty", path);
+        }
+        std::fs::remove_dir_all(log.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn svg_plots_are_written() {
+        assert_plots_written(PlotFormat::Svg, "svg");
+    }
+
+    #[test]
+    fn png_plots_are_written() {
+        // Rasterizing needs a system font; SVG output must work without one
+        if find_font(SANS_FONT_PATHS).is_none() || find_font(MONO_FONT_PATHS).is_none() {
+            eprintln!("skipping: no DejaVu or Liberation font installed");
+            return;
+        }
+        assert_plots_written(PlotFormat::Png, "png");
+    }
+}
@@ -1,12 +1,20 @@
 //! Process-specific metrics collection from /proc/[pid]/ files.
 
+use crate::metrics::counters_reset;
 use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::fs;
+use std::os::unix::fs::MetadataExt;
 use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// How long a read of the process's open file limit is reused
+const FD_LIMIT_REFRESH: Duration = Duration::from_secs(30);
 
 /// Process state
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
 pub enum ProcessState {
     Running,
     Sleeping,
@@ -14,6 +22,7 @@ pub enum ProcessState {
     Stopped,
     Zombie,
     Dead,
+    #[default]
     Unknown,
 }
 
@@ -32,7 +41,7 @@ impl std::fmt::Display for ProcessState {
 }
 
 /// Process metrics
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ProcessMetrics {
     /// Process ID
     pub pid: u32,
@@ -40,6 +49,18 @@ pub struct ProcessMetrics {
     pub name: String,
     /// Process state
     pub state: ProcessState,
+    /// When the process started (None if /proc/uptime is unreadable)
+    #[serde(default)]
+    pub start_time: Option<DateTime<Utc>>,
+    /// Seconds since the process started
+    #[serde(default)]
+    pub age_secs: u64,
+    /// Nice value (-20 to 19)
+    #[serde(default)]
+    pub nice: i64,
+    /// Kernel scheduling priority (nice + 20 for normal tasks, negative for real-time)
+    #[serde(default)]
+    pub priority: i64,
     /// Resident Set Size (physical memory) in bytes
     pub rss_bytes: u64,
     /// Virtual memory size in bytes
@@ -81,6 +102,106 @@ pub struct ProcessMetrics {
     pub io_read_bytes_per_sec: f64,
     /// Write bytes delta (per second)
     pub io_write_bytes_per_sec: f64,
+    /// Descendants whose usage is summed into these metrics (0 unless --tree)
+    #[serde(default)]
+    pub children: u32,
+    /// Established TCP connections on the process's sockets (None if its fds are unreadable)
+    pub tcp_connections: Option<u64>,
+    /// Voluntary context switches per second over all threads (blocking on I/O, locks or sleeps)
+    #[serde(default)]
+    pub voluntary_ctxt_per_sec: f64,
+    /// Involuntary context switches per second over all threads (preempted, a sign of CPU contention)
+    #[serde(default)]
+    pub nonvoluntary_ctxt_per_sec: f64,
+    /// Busiest threads of the process itself, most CPU first (empty unless --threads)
+    #[serde(default)]
+    pub top_threads: Vec<ThreadCpu>,
+    /// Soft limit on open files (RLIMIT_NOFILE) of the process itself (None if unlimited or unreadable)
+    #[serde(default)]
+    pub fd_limit: Option<u64>,
+    /// The process's own open fds as a share of `fd_limit` (--tree members have limits of their own)
+    #[serde(default)]
+    pub fd_limit_percent: Option<f64>,
+}
+
+impl ProcessMetrics {
+    /// Open fds against the soft limit, e.g. "1234 / 65536 (1.9%)"; under --tree the
+    /// share is of the process's own fds, since each member has its own limit
+    pub fn fds_label(&self) -> String {
+        match (self.fd_limit, self.fd_limit_percent) {
+            (Some(limit), Some(pct)) => format!("{} / {} ({:.1}%)", self.num_fds, limit, pct),
+            _ => self.num_fds.to_string(),
+        }
+    }
+
+    /// " (+N children)" when descendants are aggregated, empty otherwise
+    pub fn children_label(&self) -> String {
+        match self.children {
+            0 => String::new(),
+            1 => " (+1 child)".to_string(),
+            n => format!(" (+{} children)", n),
+        }
+    }
+}
+
+/// CPU usage of one thread of the monitored process (--threads)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ThreadCpu {
+    pub tid: u32,
+    /// Thread name (comm), as set with pthread_setname_np or prctl
+    pub name: String,
+    pub cpu_percent: f64,
+}
+
+/// Counters of one descendant summed into the root's metrics with --tree
+#[derive(Debug, Clone, Default)]
+struct TreeMember {
+    cpu_ticks: u64,
+    num_threads: u64,
+    num_fds: u64,
+    rss_bytes: u64,
+    vsize_bytes: u64,
+    rss_anon: u64,
+    rss_file: u64,
+    rss_shmem: u64,
+    vm_swap: u64,
+    io_read_bytes: u64,
+    io_write_bytes: u64,
+    io_rchar: u64,
+    io_wchar: u64,
+    io_cancelled_write_bytes: u64,
+    voluntary_ctxt: u64,
+    nonvoluntary_ctxt: u64,
+}
+
+/// What an open file descriptor refers to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum FdKind {
+    /// Regular files, directories and devices
+    File,
+    Socket,
+    Pipe,
+    /// Anonymous inodes (eventfd, epoll, timerfd, ...) and anything else
+    Other,
+}
+
+impl std::fmt::Display for FdKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FdKind::File => write!(f, "Files"),
+            FdKind::Socket => write!(f, "Sockets"),
+            FdKind::Pipe => write!(f, "Pipes"),
+            FdKind::Other => write!(f, "Other"),
+        }
+    }
+}
+
+/// An open file descriptor and the target of its /proc/<pid>/fd link
+#[derive(Debug, Clone)]
+pub struct OpenFd {
+    pub fd: u32,
+    pub target: String,
+    pub kind: FdKind,
 }
 
 /// Process metrics collector with state for CPU and I/O calculation
@@ -90,8 +211,19 @@ pub struct ProcessCollector {
     prev_stime: Option<u64>,
     prev_io_read_bytes: Option<u64>,
     prev_io_write_bytes: Option<u64>,
+    /// Voluntary and involuntary context switches at the previous sample
+    prev_ctxt: Option<(u64, u64)>,
     prev_time_ms: u64,
     clock_ticks_per_sec: u64,
+    tree: bool,
+    descendants: Vec<u32>,
+    prev_members: HashMap<u32, TreeMember>,
+    /// Busiest threads to report (0 skips per-thread sampling)
+    top_threads: usize,
+    /// CPU ticks per thread at the previous sample
+    prev_thread_ticks: HashMap<u32, u64>,
+    /// Soft open file limit and when it was read (prlimit can change it while the process runs)
+    fd_limit: Option<(Option<u64>, Instant)>,
 }
 
 impl ProcessCollector {
@@ -102,16 +234,51 @@ impl ProcessCollector {
             prev_stime: None,
             prev_io_read_bytes: None,
             prev_io_write_bytes: None,
+            prev_ctxt: None,
             prev_time_ms: 0,
             clock_ticks_per_sec: unsafe { libc::sysconf(libc::_SC_CLK_TCK) as u64 },
+            tree: false,
+            descendants: Vec::new(),
+            prev_members: HashMap::new(),
+            top_threads: 0,
+            prev_thread_ticks: HashMap::new(),
+            fd_limit: None,
+        }
+    }
+
+    /// Report the N threads using the most CPU (reads every /proc/<pid>/task/<tid>/stat)
+    pub fn set_top_threads(&mut self, n: usize) {
+        self.top_threads = n;
+    }
+
+    /// Sum the usage of all descendants into the collected metrics
+    pub fn set_tree(&mut self, tree: bool) {
+        self.tree = tree;
+        self.refresh_descendants();
+    }
+
+    /// Re-walk the process tree below the monitored PID (no-op without --tree)
+    pub fn refresh_descendants(&mut self) {
+        if self.tree {
+            self.descendants = find_descendants(self.pid);
         }
     }
 
+    /// PIDs found below the monitored process by the last walk of the tree (empty without --tree)
+    pub fn descendants(&self) -> &[u32] {
+        &self.descendants
+    }
+
     /// Check if the process exists
     pub fn exists(&self) -> bool {
         Path::new(&format!("/proc/{}", self.pid)).exists()
     }
 
+    /// Open files of the monitored process (not its descendants), grouped by kind then fd number
+    pub fn open_fds(&self) -> Result<Vec<OpenFd>> {
+        list_open_fds(self.pid)
+    }
+
     /// Collect current process metrics
     pub fn collect(&mut self) -> Result<ProcessMetrics> {
         let proc_path = format!("/proc/{}", self.pid);
@@ -151,29 +318,83 @@ impl ProcessCollector {
         let num_threads: u64 = fields.get(17).and_then(|s| s.parse().ok()).unwrap_or(0);
         let vsize_bytes: u64 = fields.get(20).and_then(|s| s.parse().ok()).unwrap_or(0);
         let rss_pages: u64 = fields.get(21).and_then(|s| s.parse().ok()).unwrap_or(0);
+        // priority = field 15 (18th overall), nice = field 16 (19th overall),
+        // starttime = field 19 (22nd overall) - in clock ticks after boot
+        let priority: i64 = fields.get(15).and_then(|s| s.parse().ok()).unwrap_or(0);
+        let nice: i64 = fields.get(16).and_then(|s| s.parse().ok()).unwrap_or(0);
+        let start_ticks: u64 = fields.get(19).and_then(|s| s.parse().ok()).unwrap_or(0);
+
+        // Age from the system uptime, start time from the boot time it implies
+        let started_after_boot = start_ticks as f64 / self.clock_ticks_per_sec as f64;
+        let uptime = read_uptime_secs();
+        let age_secs = uptime.map_or(0, |uptime| (uptime - started_after_boot).max(0.0) as u64);
+        let start_time = uptime.map(|uptime| {
+            Utc::now() - chrono::Duration::milliseconds(((uptime - started_after_boot) * 1000.0) as i64)
+        });
 
         let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) as u64 };
-        let rss_bytes = rss_pages * page_size;
+        let mut rss_bytes = rss_pages * page_size;
+
+        // Descendants (--tree): CPU and I/O deltas only count members seen last sample,
+        // so a child found by a rescan doesn't contribute its whole lifetime at once
+        let mut members = HashMap::new();
+        let mut tree_totals = TreeMember::default();
+        let (mut tree_cpu_delta, mut tree_read_delta, mut tree_write_delta) = (0, 0, 0);
+        let (mut tree_vol_delta, mut tree_invol_delta) = (0, 0);
+        for &child in &self.descendants {
+            // Children that exited since the last rescan are simply skipped
+            let member = match read_tree_member(child, page_size) {
+                Some(member) => member,
+                None => continue,
+            };
+            // A reused PID is a different process whose counters started over
+            let prev = self.prev_members.get(&child).filter(|prev| !member.restarted_since(prev));
+            if let Some(prev) = prev {
+                tree_cpu_delta += member.cpu_ticks.saturating_sub(prev.cpu_ticks);
+                tree_read_delta += member.io_read_bytes.saturating_sub(prev.io_read_bytes);
+                tree_write_delta += member.io_write_bytes.saturating_sub(prev.io_write_bytes);
+                tree_vol_delta += member.voluntary_ctxt.saturating_sub(prev.voluntary_ctxt);
+                tree_invol_delta += member.nonvoluntary_ctxt.saturating_sub(prev.nonvoluntary_ctxt);
+            }
+            tree_totals.add(&member);
+            members.insert(child, member);
+        }
+        let children = members.len() as u32;
+        self.prev_members = members;
+
+        rss_bytes += tree_totals.rss_bytes;
+        let vsize_bytes = vsize_bytes + tree_totals.vsize_bytes;
+        let num_threads = num_threads + tree_totals.num_threads;
 
         // Calculate CPU percentage
         let cpu_percent = if let (Some(prev_utime), Some(prev_stime)) = (self.prev_utime, self.prev_stime) {
             let time_delta_ms = now_ms.saturating_sub(self.prev_time_ms);
-            if time_delta_ms > 0 {
-                let cpu_delta = (utime + stime).saturating_sub(prev_utime + prev_stime);
-                let cpu_seconds = cpu_delta as f64 / self.clock_ticks_per_sec as f64;
-                let elapsed_seconds = time_delta_ms as f64 / 1000.0;
-                (cpu_seconds / elapsed_seconds) * 100.0
-            } else {
-                0.0
-            }
+            cpu_percent((utime, stime), (prev_utime, prev_stime), tree_cpu_delta, time_delta_ms, self.clock_ticks_per_sec)
         } else {
             0.0
         };
 
-        // Count file descriptors
-        let num_fds = fs::read_dir(format!("{}/fd", proc_path))
-            .map(|entries| entries.count() as u64)
-            .unwrap_or(0);
+        // Count file descriptors; open() fails with EMFILE once the process's own count reaches its limit
+        let own_fds = count_fds(&proc_path);
+        let num_fds = own_fds + tree_totals.num_fds;
+        let fd_limit = match self.fd_limit {
+            Some((limit, read_at)) if read_at.elapsed() < FD_LIMIT_REFRESH => limit,
+            _ => {
+                let limit = read_fd_limit(&proc_path);
+                self.fd_limit = Some((limit, Instant::now()));
+                limit
+            }
+        };
+        let fd_limit_percent = fd_limit.filter(|&limit| limit > 0).map(|limit| 100.0 * own_fds as f64 / limit as f64);
+
+        // Match socket fds (including those of --tree members) against the
+        // process's own view of /proc/net, which follows its network namespace
+        let tcp_connections = socket_inodes(&proc_path).map(|mut inodes| {
+            for child in self.prev_members.keys() {
+                inodes.extend(socket_inodes(&format!("/proc/{}", child)).unwrap_or_default());
+            }
+            count_established_tcp(&proc_path, &inodes)
+        });
 
         // Read command line
         let cmdline = fs::read_to_string(format!("{}/cmdline", proc_path))
@@ -183,8 +404,12 @@ impl ProcessCollector {
             .to_string();
 
         // Read /proc/[pid]/status for memory breakdown
-        let (vm_peak, rss_anon, rss_file, rss_shmem, vm_swap) = 
-            read_process_status(&proc_path);
+        let status = read_process_status(&proc_path);
+        let vm_peak = status.vm_peak;
+        let rss_anon = status.rss_anon + tree_totals.rss_anon;
+        let rss_file = status.rss_file + tree_totals.rss_file;
+        let rss_shmem = status.rss_shmem + tree_totals.rss_shmem;
+        let vm_swap = status.vm_swap + tree_totals.vm_swap;
 
         // Read /proc/[pid]/io for I/O counters
         let (io_read_bytes, io_write_bytes, io_rchar, io_wchar, io_cancelled_write_bytes) = 
@@ -194,7 +419,7 @@ impl ProcessCollector {
         let time_delta_secs = now_ms.saturating_sub(self.prev_time_ms) as f64 / 1000.0;
         let io_read_bytes_per_sec = if time_delta_secs > 0.0 {
             if let Some(prev) = self.prev_io_read_bytes {
-                io_read_bytes.saturating_sub(prev) as f64 / time_delta_secs
+                (io_read_bytes.saturating_sub(prev) + tree_read_delta) as f64 / time_delta_secs
             } else {
                 0.0
             }
@@ -203,7 +428,7 @@ impl ProcessCollector {
         };
         let io_write_bytes_per_sec = if time_delta_secs > 0.0 {
             if let Some(prev) = self.prev_io_write_bytes {
-                io_write_bytes.saturating_sub(prev) as f64 / time_delta_secs
+                (io_write_bytes.saturating_sub(prev) + tree_write_delta) as f64 / time_delta_secs
             } else {
                 0.0
             }
@@ -211,17 +436,34 @@ impl ProcessCollector {
             0.0
         };
 
+        let top_threads = self.busiest_threads(&proc_path, time_delta_secs);
+
+        // Context switch rates (threads that exited since the last sample take their counts with them)
+        let (voluntary_ctxt, nonvoluntary_ctxt) = read_context_switches(&proc_path);
+        let (voluntary_ctxt_per_sec, nonvoluntary_ctxt_per_sec) = match self.prev_ctxt {
+            Some((prev_vol, prev_invol)) if time_delta_secs > 0.0 => (
+                (voluntary_ctxt.saturating_sub(prev_vol) + tree_vol_delta) as f64 / time_delta_secs,
+                (nonvoluntary_ctxt.saturating_sub(prev_invol) + tree_invol_delta) as f64 / time_delta_secs,
+            ),
+            _ => (0.0, 0.0),
+        };
+
         // Update state
         self.prev_utime = Some(utime);
         self.prev_stime = Some(stime);
         self.prev_io_read_bytes = Some(io_read_bytes);
         self.prev_io_write_bytes = Some(io_write_bytes);
+        self.prev_ctxt = Some((voluntary_ctxt, nonvoluntary_ctxt));
         self.prev_time_ms = now_ms;
 
         Ok(ProcessMetrics {
             pid: self.pid,
             name,
             state,
+            start_time,
+            age_secs,
+            nice,
+            priority,
             rss_bytes,
             vsize_bytes,
             vm_peak,
@@ -235,46 +477,339 @@ impl ProcessCollector {
             num_threads,
             num_fds,
             cmdline,
-            io_read_bytes,
-            io_write_bytes,
-            io_rchar,
-            io_wchar,
-            io_cancelled_write_bytes,
+            io_read_bytes: io_read_bytes + tree_totals.io_read_bytes,
+            io_write_bytes: io_write_bytes + tree_totals.io_write_bytes,
+            io_rchar: io_rchar + tree_totals.io_rchar,
+            io_wchar: io_wchar + tree_totals.io_wchar,
+            io_cancelled_write_bytes: io_cancelled_write_bytes + tree_totals.io_cancelled_write_bytes,
             io_read_bytes_per_sec,
             io_write_bytes_per_sec,
+            children,
+            tcp_connections,
+            voluntary_ctxt_per_sec,
+            nonvoluntary_ctxt_per_sec,
+            top_threads,
+            fd_limit,
+            fd_limit_percent,
+        })
+    }
+
+    /// Threads with the most CPU since the previous sample, remembering each thread's ticks for the next one
+    fn busiest_threads(&mut self, proc_path: &str, elapsed_secs: f64) -> Vec<ThreadCpu> {
+        if self.top_threads == 0 {
+            return Vec::new();
+        }
+        let mut ticks = HashMap::new();
+        let mut threads = Vec::new();
+        for task in fs::read_dir(format!("{}/task", proc_path)).into_iter().flatten().flatten() {
+            let Some(tid) = task.file_name().to_str().and_then(|s| s.parse().ok()) else {
+                continue;
+            };
+            // Threads that exited while listing are skipped
+            let Some((name, total)) = read_thread_ticks(&task.path()) else {
+                continue;
+            };
+            // A thread first seen this sample has no delta yet
+            if let Some(&prev) = self.prev_thread_ticks.get(&tid) {
+                if elapsed_secs > 0.0 {
+                    let cpu_seconds = total.saturating_sub(prev) as f64 / self.clock_ticks_per_sec as f64;
+                    threads.push(ThreadCpu { tid, name, cpu_percent: cpu_seconds / elapsed_secs * 100.0 });
+                }
+            }
+            ticks.insert(tid, total);
+        }
+        self.prev_thread_ticks = ticks;
+
+        threads.sort_by(|a, b| b.cpu_percent.total_cmp(&a.cpu_percent));
+        threads.truncate(self.top_threads);
+        threads
+    }
+}
+
+/// Name and utime + stime ticks of a thread from its task stat file
+fn read_thread_ticks(task_path: &Path) -> Option<(String, u64)> {
+    let stat_content = fs::read_to_string(task_path.join("stat")).ok()?;
+    let comm_start = stat_content.find('(')?;
+    let comm_end = stat_content.rfind(')')?;
+    let fields: Vec<&str> = stat_content.get(comm_end + 2..)?.split_whitespace().collect();
+    let field = |i: usize| -> u64 { fields.get(i).and_then(|s| s.parse().ok()).unwrap_or(0) };
+    Some((stat_content[comm_start + 1..comm_end].to_string(), field(11) + field(12)))
+}
+
+impl TreeMember {
+    /// Whether a counter went backwards since `prev`: the PID now belongs to another process
+    fn restarted_since(&self, prev: &TreeMember) -> bool {
+        counters_reset([
+            (self.cpu_ticks, prev.cpu_ticks),
+            (self.io_read_bytes, prev.io_read_bytes),
+            (self.io_write_bytes, prev.io_write_bytes),
+        ])
+    }
+
+    fn add(&mut self, other: &TreeMember) {
+        self.cpu_ticks += other.cpu_ticks;
+        self.num_threads += other.num_threads;
+        self.num_fds += other.num_fds;
+        self.rss_bytes += other.rss_bytes;
+        self.vsize_bytes += other.vsize_bytes;
+        self.rss_anon += other.rss_anon;
+        self.rss_file += other.rss_file;
+        self.rss_shmem += other.rss_shmem;
+        self.vm_swap += other.vm_swap;
+        self.io_read_bytes += other.io_read_bytes;
+        self.io_write_bytes += other.io_write_bytes;
+        self.io_rchar += other.io_rchar;
+        self.io_wchar += other.io_wchar;
+        self.io_cancelled_write_bytes += other.io_cancelled_write_bytes;
+        self.voluntary_ctxt += other.voluntary_ctxt;
+        self.nonvoluntary_ctxt += other.nonvoluntary_ctxt;
+    }
+}
+
+/// CPU percent from (utime, stime) ticks now and at the last sample plus `tree_ticks`
+/// used by --tree members; 0 when the counters went backwards (a reused PID)
+fn cpu_percent(ticks: (u64, u64), prev: (u64, u64), tree_ticks: u64, time_delta_ms: u64, ticks_per_sec: u64) -> f64 {
+    if time_delta_ms == 0 || counters_reset([(ticks.0, prev.0), (ticks.1, prev.1)]) {
+        return 0.0;
+    }
+    let cpu_delta = (ticks.0 + ticks.1).saturating_sub(prev.0 + prev.1) + tree_ticks;
+    let cpu_seconds = cpu_delta as f64 / ticks_per_sec as f64;
+    let elapsed_seconds = time_delta_ms as f64 / 1000.0;
+    (cpu_seconds / elapsed_seconds) * 100.0
+}
+
+/// Read the summable counters of a descendant (None if it has exited)
+fn read_tree_member(pid: u32, page_size: u64) -> Option<TreeMember> {
+    let proc_path = format!("/proc/{}", pid);
+    let stat_content = fs::read_to_string(format!("{}/stat", proc_path)).ok()?;
+    let comm_end = stat_content.rfind(')')?;
+    let fields: Vec<&str> = stat_content.get(comm_end + 2..)?.split_whitespace().collect();
+    let field = |i: usize| -> u64 { fields.get(i).and_then(|s| s.parse().ok()).unwrap_or(0) };
+
+    let ProcessStatus { rss_anon, rss_file, rss_shmem, vm_swap, .. } = read_process_status(&proc_path);
+    let (voluntary_ctxt, nonvoluntary_ctxt) = read_context_switches(&proc_path);
+    let (io_read_bytes, io_write_bytes, io_rchar, io_wchar, io_cancelled_write_bytes) =
+        read_process_io(&proc_path);
+
+    Some(TreeMember {
+        cpu_ticks: field(11) + field(12),
+        num_threads: field(17),
+        num_fds: count_fds(&proc_path),
+        rss_bytes: field(21) * page_size,
+        vsize_bytes: field(20),
+        rss_anon,
+        rss_file,
+        rss_shmem,
+        vm_swap,
+        io_read_bytes,
+        io_write_bytes,
+        io_rchar,
+        io_wchar,
+        io_cancelled_write_bytes,
+        voluntary_ctxt,
+        nonvoluntary_ctxt,
+    })
+}
+
+/// Count open file descriptors of a process
+fn count_fds(proc_path: &str) -> u64 {
+    fs::read_dir(format!("{}/fd", proc_path))
+        .map(|entries| entries.count() as u64)
+        .unwrap_or(0)
+}
+
+/// Soft "Max open files" limit from /proc/<pid>/limits (None if unlimited)
+fn read_fd_limit(proc_path: &str) -> Option<u64> {
+    let limits = fs::read_to_string(format!("{}/limits", proc_path)).ok()?;
+    limits
+        .lines()
+        .find_map(|line| line.strip_prefix("Max open files"))
+        .and_then(|values| values.split_whitespace().next())
+        .and_then(|soft| soft.parse().ok())
+}
+
+/// Resolved `/proc/<pid>/fd` links as (fd, target); descriptors closed while reading are skipped
+fn read_fd_links(proc_path: &str) -> std::io::Result<Vec<(u32, String)>> {
+    Ok(fs::read_dir(format!("{}/fd", proc_path))?
+        .flatten()
+        .filter_map(|entry| {
+            let fd = entry.file_name().to_str()?.parse().ok()?;
+            let target = fs::read_link(entry.path()).ok()?;
+            Some((fd, target.to_string_lossy().into_owned()))
         })
+        .collect())
+}
+
+/// Open files of a process, grouped by kind then fd number
+pub fn list_open_fds(pid: u32) -> Result<Vec<OpenFd>> {
+    let mut fds: Vec<OpenFd> = read_fd_links(&format!("/proc/{}", pid))
+        .with_context(|| format!("Failed to read open files of PID {} (needs the same user or root)", pid))?
+        .into_iter()
+        .map(|(fd, target)| {
+            let kind = if target.starts_with("socket:[") {
+                FdKind::Socket
+            } else if target.starts_with("pipe:[") {
+                FdKind::Pipe
+            } else if target.starts_with('/') {
+                FdKind::File
+            } else {
+                FdKind::Other
+            };
+            OpenFd { fd, target, kind }
+        })
+        .collect();
+    fds.sort_by_key(|f| (f.kind, f.fd));
+    Ok(fds)
+}
+
+/// Inodes of the sockets a process holds open, from `socket:[N]` fd links
+fn socket_inodes(proc_path: &str) -> Option<HashSet<u64>> {
+    Some(
+        read_fd_links(proc_path)
+            .ok()?
+            .into_iter()
+            .filter_map(|(_, target)| target.strip_prefix("socket:[")?.strip_suffix(']')?.parse().ok())
+            .collect(),
+    )
+}
+
+/// Established TCP sockets (IPv4 and IPv6) whose inode is in the given set
+fn count_established_tcp(proc_path: &str, inodes: &HashSet<u64>) -> u64 {
+    if inodes.is_empty() {
+        return 0;
     }
+    ["tcp", "tcp6"]
+        .iter()
+        .map(|table| {
+            let content = fs::read_to_string(format!("{}/net/{}", proc_path, table)).unwrap_or_default();
+            // Skip the header; state is the 4th column (01 = ESTABLISHED), inode the 10th
+            content
+                .lines()
+                .skip(1)
+                .filter(|line| {
+                    let parts: Vec<&str> = line.split_whitespace().collect();
+                    parts.get(3) == Some(&"01")
+                        && parts.get(9).and_then(|i| i.parse().ok()).is_some_and(|i| inodes.contains(&i))
+                })
+                .count() as u64
+        })
+        .sum()
+}
+
+/// All descendants of a process (children, grandchildren, ...) from the PPID in /proc/*/stat
+pub fn find_descendants(root: u32) -> Vec<u32> {
+    let mut children: HashMap<u32, Vec<u32>> = HashMap::new();
+    if let Ok(entries) = fs::read_dir("/proc") {
+        for entry in entries.flatten() {
+            let pid: u32 = match entry.file_name().to_str().and_then(|s| s.parse().ok()) {
+                Some(pid) => pid,
+                None => continue,
+            };
+            let stat = match fs::read_to_string(entry.path().join("stat")) {
+                Ok(stat) => stat,
+                Err(_) => continue,
+            };
+            // PPID is the second field after the parenthesised comm (field 4 overall)
+            let ppid = stat.rfind(')')
+                .and_then(|end| stat.get(end + 2..))
+                .and_then(|rest| rest.split_whitespace().nth(1))
+                .and_then(|s| s.parse::<u32>().ok());
+            if let Some(ppid) = ppid {
+                children.entry(ppid).or_default().push(pid);
+            }
+        }
+    }
+
+    let mut descendants = Vec::new();
+    let mut queue = vec![root];
+    while let Some(pid) = queue.pop() {
+        if let Some(kids) = children.get(&pid) {
+            descendants.extend_from_slice(kids);
+            queue.extend_from_slice(kids);
+        }
+    }
+    descendants.sort_unstable();
+    descendants
+}
+
+/// Memory breakdown and context switches from /proc/[pid]/status (bytes, counts)
+#[derive(Debug, Default, PartialEq, Eq)]
+struct ProcessStatus {
+    vm_peak: u64,
+    rss_anon: u64,
+    rss_file: u64,
+    rss_shmem: u64,
+    vm_swap: u64,
+    voluntary_ctxt: u64,
+    nonvoluntary_ctxt: u64,
 }
 
 /// Read memory breakdown from /proc/[pid]/status
-fn read_process_status(proc_path: &str) -> (u64, u64, u64, u64, u64) {
-    let status = fs::read_to_string(format!("{}/status", proc_path)).unwrap_or_default();
-    
-    let mut vm_peak: u64 = 0;
-    let mut rss_anon: u64 = 0;
-    let mut rss_file: u64 = 0;
-    let mut rss_shmem: u64 = 0;
-    let mut vm_swap: u64 = 0;
-    
+fn read_process_status(proc_path: &str) -> ProcessStatus {
+    parse_process_status(&fs::read_to_string(format!("{}/status", proc_path)).unwrap_or_default())
+}
+
+fn parse_process_status(status: &str) -> ProcessStatus {
+    let mut parsed = ProcessStatus::default();
     for line in status.lines() {
         let parts: Vec<&str> = line.split_whitespace().collect();
         if parts.len() < 2 {
             continue;
         }
-        // Values in /proc/[pid]/status are in kB
-        let value: u64 = parts[1].parse().unwrap_or(0) * 1024;
+        let count: u64 = parts[1].parse().unwrap_or(0);
+        // Memory values in /proc/[pid]/status are in kB
+        let value = count * 1024;
         
         match parts[0] {
-            "VmPeak:" => vm_peak = value,
-            "RssAnon:" => rss_anon = value,
-            "RssFile:" => rss_file = value,
-            "RssShmem:" => rss_shmem = value,
-            "VmSwap:" => vm_swap = value,
+            "VmPeak:" => parsed.vm_peak = value,
+            "RssAnon:" => parsed.rss_anon = value,
+            "RssFile:" => parsed.rss_file = value,
+            "RssShmem:" => parsed.rss_shmem = value,
+            "VmSwap:" => parsed.vm_swap = value,
+            "voluntary_ctxt_switches:" => parsed.voluntary_ctxt = count,
+            "nonvoluntary_ctxt_switches:" => parsed.nonvoluntary_ctxt = count,
             _ => {}
         }
     }
-    
-    (vm_peak, rss_anon, rss_file, rss_shmem, vm_swap)
+    parsed
+}
+
+/// Seconds since boot from /proc/uptime
+fn read_uptime_secs() -> Option<f64> {
+    fs::read_to_string("/proc/uptime").ok()?.split_whitespace().next()?.parse().ok()
+}
+
+/// Voluntary and involuntary context switches summed over all threads; the
+/// process's own status file only counts its main thread
+fn read_context_switches(proc_path: &str) -> (u64, u64) {
+    let Ok(tasks) = fs::read_dir(format!("{}/task", proc_path)) else {
+        return (0, 0);
+    };
+    tasks.flatten().fold((0, 0), |(vol, invol), task| {
+        let status = read_process_status(&task.path().to_string_lossy());
+        (vol + status.voluntary_ctxt, invol + status.nonvoluntary_ctxt)
+    })
+}
+
+/// Check that /proc/[pid]/io is readable, returning an actionable warning if not.
+/// The file is restricted to the process owner (and root); unreadable counters
+/// would otherwise show as zero I/O for the whole session.
+pub fn io_access_warning(pid: u32) -> Option<String> {
+    let path = format!("/proc/{}/io", pid);
+    match fs::read_to_string(&path) {
+        Err(e) if e.kind() == std::io::ErrorKind::PermissionDenied => {
+            let hint = match fs::metadata(format!("/proc/{}", pid)) {
+                Ok(meta) => format!("run as the process owner (uid {}) or with sudo", meta.uid()),
+                Err(_) => "run as the process owner or with sudo".to_string(),
+            };
+            Some(format!(
+                "Cannot read {} (permission denied), process disk I/O will show as 0; {}",
+                path, hint
+            ))
+        }
+        // Readable, or the process is gone (reported separately)
+        _ => None,
+    }
 }
 
 /// Read I/O counters from /proc/[pid]/io
@@ -307,24 +842,57 @@ fn read_process_io(proc_path: &str) -> (u64, u64, u64, u64, u64) {
     (read_bytes, write_bytes, rchar, wchar, cancelled_write_bytes)
 }
 
-/// Find a process by name or command-line pattern (returns best match)
+/// How to choose among several processes matching a name pattern
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum MatchChoice {
+    /// Heuristic score (exact name, executable match, not a wrapper), newest on ties
+    #[default]
+    Best,
+    /// Lowest PID (usually the oldest process)
+    First,
+    /// Highest PID (usually the most recently started process)
+    HighestPid,
+    /// Ask on startup when several processes match (falls back to best on rescans)
+    Interactive,
+}
+
+/// Process matching behaviour for name/pattern lookups
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MatchOptions {
+    /// Skip shells and other monperf instances unless the pattern names them
+    pub no_filter: bool,
+    /// Which match to pick when several exist
+    pub choice: MatchChoice,
+}
+
+/// A process matching a name pattern
+#[derive(Debug, Clone)]
+pub struct ProcessMatch {
+    pub pid: u32,
+    pub cmdline: String,
+    /// Priority score, higher is a better match
+    pub score: i32,
+    /// Whether /proc/PID/comm equals the pattern exactly
+    pub exact: bool,
+}
+
+/// Find all processes matching a name or command-line pattern
 /// Matches against both /proc/PID/comm and /proc/PID/cmdline
-/// Excludes monperf processes to avoid matching ourselves
-pub fn find_process_by_name(pattern: &str) -> Option<u32> {
+/// Our own process is always excluded; shells and other monperf instances are
+/// excluded unless `no_filter` is set
+pub fn find_process_matches(pattern: &str, no_filter: bool) -> Vec<ProcessMatch> {
     let proc_dir = Path::new("/proc");
     let pattern_lower = pattern.to_lowercase();
     let my_pid = std::process::id();
     
-    // Collect all matching PIDs with their cmdlines and a priority score
-    // Higher score = better match
-    let mut matches: Vec<(u32, String, i32)> = Vec::new();
+    let mut matches: Vec<ProcessMatch> = Vec::new();
     
     if let Ok(entries) = fs::read_dir(proc_dir) {
         for entry in entries.flatten() {
             let path = entry.path();
             if let Some(filename) = path.file_name().and_then(|f| f.to_str()) {
                 if let Ok(pid) = filename.parse::<u32>() {
-                    // Skip our own process
+                    // Skip our own process (its cmdline always contains the pattern)
                     if pid == my_pid {
                         continue;
                     }
@@ -335,7 +903,7 @@ pub fn find_process_by_name(pattern: &str) -> Option<u32> {
                     let cmdline_lower = cmdline_clean.to_lowercase();
                     
                     // Skip monperf processes (including other instances)
-                    if cmdline_lower.contains("monperf") {
+                    if !no_filter && cmdline_lower.contains("monperf") {
                         continue;
                     }
                     
@@ -344,26 +912,29 @@ pub fn find_process_by_name(pattern: &str) -> Option<u32> {
                     let comm = fs::read_to_string(&comm_path).unwrap_or_default();
                     let comm_trimmed = comm.trim().to_lowercase();
                     
-                    if (comm_trimmed == "bash" || comm_trimmed == "zsh" || comm_trimmed == "sh") 
+                    if !no_filter
+                        && (comm_trimmed == "bash" || comm_trimmed == "zsh" || comm_trimmed == "sh") 
                         && !pattern_lower.contains("bash") 
                         && !pattern_lower.contains("zsh")
                         && !pattern_lower.contains("sh") {
                         continue;
                     }
                     
-                    // Check for matches and assign priority
-                    let mut score = 0;
-                    
                     // Exact comm match is highest priority
-                    if comm_trimmed == pattern_lower {
-                        return Some(pid); // Return immediately for exact match
-                    }
+                    let exact = comm_trimmed == pattern_lower;
                     
                     // Check cmdline for pattern
-                    if !cmdline_lower.contains(&pattern_lower) {
+                    if !exact && !cmdline_lower.contains(&pattern_lower) {
                         continue;
                     }
                     
+                    // Check for matches and assign priority
+                    let mut score = 0;
+                    
+                    if exact {
+                        score += 1000;
+                    }
+                    
                     // Get the first argument (the executable/script)
                     let first_arg = cmdline_clean.split_whitespace().next().unwrap_or("");
                     let first_arg_lower = first_arg.to_lowercase();
@@ -384,16 +955,37 @@ pub fn find_process_by_name(pattern: &str) -> Option<u32> {
                         score += 10;
                     }
                     
-                    matches.push((pid, cmdline_clean, score));
+                    matches.push(ProcessMatch { pid, cmdline: cmdline_clean, score, exact });
                 }
             }
         }
     }
     
-    // Return the match with highest score, or highest PID as tiebreaker (most recent)
-    matches.into_iter()
-        .max_by_key(|(pid, _, score)| (*score, *pid))
-        .map(|(pid, _, _)| pid)
+    matches
+}
+
+/// Pick one process from a set of matches according to `choice`
+pub fn select_match(matches: &[ProcessMatch], choice: MatchChoice) -> Option<u32> {
+    match choice {
+        MatchChoice::First => matches.iter().map(|m| m.pid).min(),
+        MatchChoice::HighestPid => matches.iter().map(|m| m.pid).max(),
+        MatchChoice::Best | MatchChoice::Interactive => {
+            // Oldest exact comm match wins outright
+            if let Some(pid) = matches.iter().filter(|m| m.exact).map(|m| m.pid).min() {
+                return Some(pid);
+            }
+            // Otherwise highest score, or highest PID as tiebreaker (most recent)
+            matches.iter()
+                .max_by_key(|m| (m.score, m.pid))
+                .map(|m| m.pid)
+        }
+    }
+}
+
+/// Find a process by name or command-line pattern (returns the selected match)
+pub fn find_process_by_name(pattern: &str, options: MatchOptions) -> Option<u32> {
+    let matches = find_process_matches(pattern, options.no_filter);
+    select_match(&matches, options.choice)
 }
 
 /// List all processes matching a name pattern
@@ -428,3 +1020,54 @@ pub fn find_processes_by_pattern(pattern: &str) -> Vec<u32> {
     }
     pids
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_fields_in_bytes_and_counts() {
+        let status = "\
+Name:\tpostgres
+State:\tS (sleeping)
+VmPeak:\t  225468 kB
+VmRSS:\t   31236 kB
+RssAnon:\t    4352 kB
+RssFile:\t   10240 kB
+RssShmem:\t   16644 kB
+VmSwap:\t       8 kB
+Threads:\t1
+voluntary_ctxt_switches:\t1523
+nonvoluntary_ctxt_switches:\t42
+";
+        assert_eq!(parse_process_status(status), ProcessStatus {
+            vm_peak: 225468 * 1024,
+            rss_anon: 4352 * 1024,
+            rss_file: 10240 * 1024,
+            rss_shmem: 16644 * 1024,
+            vm_swap: 8 * 1024,
+            voluntary_ctxt: 1523,
+            nonvoluntary_ctxt: 42,
+        });
+    }
+
+    #[test]
+    fn kernel_threads_have_no_memory_lines() {
+        let status = "Name:\tkthreadd\nvoluntary_ctxt_switches:\t10\nnonvoluntary_ctxt_switches:\t0\n";
+        let parsed = parse_process_status(status);
+        assert_eq!(parsed.rss_anon, 0);
+        assert_eq!(parsed.voluntary_ctxt, 10);
+    }
+
+    #[test]
+    fn reused_pid_reports_idle_interval() {
+        // utime started over while stime kept going: no wrapped or partial delta
+        assert_eq!(cpu_percent((5, 900), (4000, 800), 0, 1000, 100), 0.0);
+        assert_eq!(cpu_percent((150, 50), (100, 50), 0, 1000, 100), 50.0);
+
+        let prev = TreeMember { cpu_ticks: 7000, io_read_bytes: 1 << 30, ..Default::default() };
+        let member = TreeMember { cpu_ticks: 7100, io_read_bytes: 4096, ..Default::default() };
+        assert!(member.restarted_since(&prev));
+        assert!(!prev.restarted_since(&TreeMember::default()));
+    }
+}
@@ -0,0 +1,245 @@
+//! Replay of a recorded log through the TUI (--replay).
+//!
+//! Captures and JSON Lines logs hold full samples and replay exactly as recorded.
+//! CSV logs are rebuilt from the plotted columns, so panels only show what the CSV
+//! has (totals, per-core, per-device and per-interface rates, PSI, process CPU/RSS).
+
+use crate::environment::EnvironmentInfo;
+use crate::logging::{self, MetricsSample};
+use crate::metrics::cpu::CoreUtilization;
+use crate::metrics::disk::DiskStats;
+use crate::metrics::network::InterfaceStats;
+use crate::metrics::psi::PsiResourceMetrics;
+use crate::metrics::{CpuMetrics, DiskMetrics, MemoryMetrics, NetworkMetrics, PsiMetrics};
+use crate::plot::{self, DetailedPlotSample};
+use crate::process::ProcessMetrics;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use std::path::Path;
+use std::time::Duration;
+
+/// Slowest and fastest playback speed multipliers
+pub const MIN_SPEED: f64 = 0.125;
+pub const MAX_SPEED: f64 = 64.0;
+
+/// Recorded samples and the playback position
+pub struct Replay {
+    samples: Vec<MetricsSample>,
+    /// Index of the next sample to play
+    position: usize,
+    speed: f64,
+    /// Host the samples were recorded on, when the capture header says
+    pub environment: Option<EnvironmentInfo>,
+}
+
+impl Replay {
+    /// Load a capture, JSON Lines or CSV log for playback
+    pub fn load(path: &Path, speed: f64) -> Result<Self> {
+        let samples = load_samples(path)?;
+        if samples.is_empty() {
+            anyhow::bail!("No samples to replay in {}", path.display());
+        }
+        let environment = if logging::is_capture_file(path) {
+            logging::read_capture_header(path)?.map(|h| h.environment)
+        } else {
+            None
+        };
+        Ok(Self { samples, position: 0, speed: speed.clamp(MIN_SPEED, MAX_SPEED), environment })
+    }
+
+    /// Next sample, advancing the play position (None once every sample was played)
+    pub fn advance(&mut self) -> Option<&MetricsSample> {
+        let sample = self.samples.get(self.position)?;
+        self.position += 1;
+        Some(sample)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.position >= self.samples.len()
+    }
+
+    /// Wall-clock wait before the next sample: its recorded gap divided by the speed
+    pub fn delay(&self) -> Duration {
+        let gap = match (self.position.checked_sub(1).and_then(|i| self.samples.get(i)), self.samples.get(self.position)) {
+            (Some(prev), Some(next)) => (next.timestamp - prev.timestamp).num_milliseconds() as f64 / 1000.0,
+            _ => 0.0,
+        };
+        // Gaps that are missing or out of order play at one second
+        let gap = if gap > 0.0 { gap } else { 1.0 };
+        Duration::from_secs_f64((gap / self.speed).max(0.01))
+    }
+
+    /// Move the play position by `delta` samples, keeping at least one sample played
+    pub fn seek(&mut self, delta: isize) {
+        self.position = self.position.saturating_add_signed(delta).clamp(1, self.samples.len());
+    }
+
+    /// Samples played so far, oldest first
+    pub fn played(&self) -> &[MetricsSample] {
+        &self.samples[..self.position]
+    }
+
+    pub fn speed(&self) -> f64 {
+        self.speed
+    }
+
+    /// Double (faster) or halve (slower) the playback speed within its limits
+    pub fn change_speed(&mut self, faster: bool) {
+        let speed = if faster { self.speed * 2.0 } else { self.speed / 2.0 };
+        self.speed = speed.clamp(MIN_SPEED, MAX_SPEED);
+    }
+
+    /// Position for the help bar, e.g. "4x 14:03:22 UTC (120/500)"
+    pub fn label(&self) -> String {
+        let at = self
+            .played()
+            .last()
+            .map(|s| s.timestamp.format("%H:%M:%S UTC").to_string())
+            .unwrap_or_else(|| "--:--:--".to_string());
+        let state = if self.is_finished() { ", finished" } else { "" };
+        format!("{}x {} ({}/{}{})", self.speed, at, self.position, self.samples.len(), state)
+    }
+}
+
+/// Read the samples of a capture, JSON Lines or CSV log (CSV rows are rebuilt as described above)
+pub fn load_samples(path: &Path) -> Result<Vec<MetricsSample>> {
+    if logging::is_capture_file(path) {
+        return logging::read_capture(path);
+    }
+    if logging::is_json_log(path) {
+        return logging::read_json_log(path);
+    }
+    let mut prev: Option<DateTime<Utc>> = None;
+    Ok(plot::load_detailed_samples(path)?
+        .iter()
+        .map(|d| {
+            let interval_secs = prev
+                .map(|p| (d.timestamp - p).num_milliseconds().max(0) as f64 / 1000.0)
+                .unwrap_or(0.0);
+            prev = Some(d.timestamp);
+            sample_from_detailed(d, interval_secs)
+        })
+        .collect())
+}
+
+/// Rebuild a sample from the columns a CSV log keeps; everything else stays zero
+fn sample_from_detailed(d: &DetailedPlotSample, interval_secs: f64) -> MetricsSample {
+    let cpu = CpuMetrics {
+        total_utilization: d.cpu_total,
+        user_percent: d.cpu_user,
+        system_percent: d.cpu_system,
+        iowait_percent: d.cpu_iowait,
+        steal_percent: d.cpu_steal,
+        per_core: d
+            .per_core_pct
+            .iter()
+            .enumerate()
+            .map(|(core_id, &pct)| CoreUtilization {
+                core_id,
+                utilization_percent: pct,
+                ..Default::default()
+            })
+            .collect(),
+        load_avg: (d.cpu_load_1m, d.cpu_load_5m, d.cpu_load_15m),
+        core_count: d.per_core_pct.len(),
+        ..Default::default()
+    };
+
+    let memory = MemoryMetrics {
+        total: d.mem_total_bytes,
+        used: d.mem_used_bytes,
+        available: d.mem_available_bytes,
+        buffers: d.mem_buffers_bytes,
+        cached: d.mem_cached_bytes,
+        dirty: d.mem_dirty_bytes,
+        writeback: d.mem_writeback_bytes,
+        swap_total: d.mem_swap_total_bytes,
+        swap_used: d.mem_swap_used_bytes,
+        cgroup_limit: d.cgroup_limit_bytes,
+        cgroup_current: d.cgroup_current_bytes,
+        cgroup_usage_percent: d.cgroup_usage_pct,
+        used_percent: d.mem_used_pct,
+        swap_percent: if d.mem_swap_total_bytes > 0 {
+            100.0 * d.mem_swap_used_bytes as f64 / d.mem_swap_total_bytes as f64
+        } else {
+            0.0
+        },
+        ..Default::default()
+    };
+
+    let disk = DiskMetrics {
+        disks: d
+            .disk_devices
+            .iter()
+            .enumerate()
+            .map(|(i, device)| DiskStats {
+                device: device.clone(),
+                read_bytes_per_sec: d.disk_read_bytes_per_sec.get(i).copied().unwrap_or(0.0),
+                write_bytes_per_sec: d.disk_write_bytes_per_sec.get(i).copied().unwrap_or(0.0),
+                utilization_percent: d.disk_util_pct.get(i).copied().unwrap_or(0.0),
+                ..Default::default()
+            })
+            .collect(),
+        total_read_bytes_per_sec: d.disk_total_read,
+        total_write_bytes_per_sec: d.disk_total_write,
+        ..Default::default()
+    };
+
+    let network = NetworkMetrics {
+        interfaces: d
+            .net_interfaces
+            .iter()
+            .enumerate()
+            .map(|(i, interface)| InterfaceStats {
+                interface: interface.clone(),
+                rx_bytes_per_sec: d.net_rx_bytes_per_sec.get(i).copied().unwrap_or(0.0),
+                tx_bytes_per_sec: d.net_tx_bytes_per_sec.get(i).copied().unwrap_or(0.0),
+                ..Default::default()
+            })
+            .collect(),
+        total_rx_bytes_per_sec: d.net_total_rx,
+        total_tx_bytes_per_sec: d.net_total_tx,
+        ..Default::default()
+    };
+
+    let pressure = |some_avg10: f64, full_avg10: Option<f64>| PsiResourceMetrics {
+        some_avg10,
+        full_avg10,
+        ..Default::default()
+    };
+    let psi = PsiMetrics {
+        cpu: pressure(d.psi_cpu_some_avg10, None),
+        memory: pressure(d.psi_mem_some_avg10, d.psi_mem_full_avg10),
+        io: pressure(d.psi_io_some_avg10, d.psi_io_full_avg10),
+        cgroup_io: None,
+    };
+    let cgroup_psi = d.proc_cgroup_psi_cpu_some_avg10.map(|cpu| PsiMetrics {
+        cpu: pressure(cpu, None),
+        memory: pressure(d.proc_cgroup_psi_mem_some_avg10.unwrap_or(0.0), d.proc_cgroup_psi_mem_full_avg10),
+        io: pressure(d.proc_cgroup_psi_io_some_avg10.unwrap_or(0.0), d.proc_cgroup_psi_io_full_avg10),
+        cgroup_io: None,
+    });
+
+    // The CSV has no process identity, only its usage
+    let process = (d.proc_cpu_pct.is_some() || d.proc_rss_bytes.is_some()).then(|| ProcessMetrics {
+        name: "recorded".to_string(),
+        cpu_percent: d.proc_cpu_pct.unwrap_or(0.0),
+        rss_bytes: d.proc_rss_bytes.unwrap_or(0),
+        io_read_bytes_per_sec: d.proc_io_read_bytes_per_sec.unwrap_or(0.0),
+        io_write_bytes_per_sec: d.proc_io_write_bytes_per_sec.unwrap_or(0.0),
+        ..Default::default()
+    });
+
+    MetricsSample {
+        timestamp: d.timestamp,
+        interval_secs,
+        cpu,
+        memory,
+        disk,
+        network,
+        process,
+        psi: Some(psi),
+        cgroup_psi,
+        gpu: None,
+    }
+}
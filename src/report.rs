@@ -0,0 +1,258 @@
+//! Single-file HTML report: every generated SVG plot inlined, plus summary tables.
+//!
+//! The report is meant to be attached to a ticket or mailed as is, so it has no
+//! external references. The run summary and per-metric statistics are shown for every
+//! format; from a CSV log the summary only covers the columns the CSV keeps.
+
+use crate::display::{format_bytes, format_peak_at, format_throughput};
+use crate::logging::{self, MetricsSummary, SummaryAccumulator};
+use crate::plot::{self, DetailedPlotSample, SeriesStats};
+use crate::replay;
+use anyhow::{Context, Result};
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+/// Formats one statistic of a series for display
+type ValueFormatter<'a> = &'a dyn Fn(f64) -> String;
+
+/// Minimal styling so the report reads well without external assets
+const STYLE: &str = "body{font-family:sans-serif;max-width:1280px;margin:2em auto;padding:0 1em;color:#222}\
+table{border-collapse:collapse;margin:1em 0}\
+th,td{border:1px solid #ccc;padding:4px 10px;text-align:left}\
+th{background:#f2f2f2}\
+td.num{text-align:right;font-family:monospace}\
+section{margin:2em 0}\
+svg{max-width:100%;height:auto}";
+
+/// Write `report.html` to `output_dir`, inlining the SVG plots in `plots`
+pub fn write_html_report(source: &Path, output_dir: &Path, plots: &[String]) -> Result<PathBuf> {
+    let samples = plot::load_detailed_samples(source)?;
+    let name = source.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+
+    let mut html = String::new();
+    writeln!(html, "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">")?;
+    writeln!(html, "<title>monperf report: {}</title>", escape(&name))?;
+    writeln!(html, "<style>{}</style>\n</head>\n<body>", STYLE)?;
+    writeln!(html, "<h1>monperf report</h1>")?;
+    if let (Some(first), Some(last)) = (samples.first(), samples.last()) {
+        writeln!(
+            html,
+            "<p>Source: <code>{}</code> &middot; {} samples &middot; {} to {} UTC</p>",
+            escape(&name),
+            samples.len(),
+            first.timestamp.format("%Y-%m-%d %H:%M:%S"),
+            last.timestamp.format("%Y-%m-%d %H:%M:%S"),
+        )?;
+    }
+
+    if let Some(summary) = run_summary(source) {
+        write_summary(&mut html, &summary)?;
+    }
+    write_statistics(&mut html, &samples)?;
+
+    // The overview comes first, the detailed charts after it in generation order
+    let mut ordered: Vec<&String> = plots.iter().filter(|p| plot_stem(p) == "overview").collect();
+    ordered.extend(plots.iter().filter(|p| plot_stem(p) != "overview"));
+    writeln!(html, "<h2>Charts</h2>")?;
+    for path in ordered {
+        let svg = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read plot {}", path))?;
+        writeln!(html, "<section>\n<h3>{}</h3>\n{}\n</section>", section_title(plot_stem(path)), svg.trim())?;
+    }
+    writeln!(html, "</body>\n</html>")?;
+
+    let output = output_dir.join("report.html");
+    std::fs::write(&output, html)
+        .with_context(|| format!("Failed to write {}", output.display()))?;
+    Ok(output)
+}
+
+/// Summary of the whole run, as `--compare` builds it (CSV rows are rebuilt into
+/// samples, so metrics the CSV does not keep read as zero)
+fn run_summary(source: &Path) -> Option<MetricsSummary> {
+    let mut accumulator = SummaryAccumulator::new();
+    for sample in replay::load_samples(source).ok()? {
+        accumulator.add_sample(sample);
+    }
+    accumulator.generate_summary()
+}
+
+/// Render the run summary as a two-column table, followed by the bottleneck indicators
+fn write_summary(html: &mut String, s: &MetricsSummary) -> Result<()> {
+    let mut rows: Vec<(String, String)> = vec![
+        ("Duration".into(), format!("{:.1} s", s.duration_secs)),
+        ("Samples".into(), s.samples_count.to_string()),
+        (
+            "CPU utilization".into(),
+            format!(
+                "avg {:.1}%, max {:.1}%{}",
+                s.cpu_avg_utilization,
+                s.cpu_max_utilization,
+                format_peak_at(s.cpu_max_utilization, s.cpu_max_at_secs)
+            ),
+        ),
+        ("CPU iowait".into(), format!("avg {:.1}%, max {:.1}%", s.cpu_avg_iowait, s.cpu_max_iowait)),
+        ("CPU steal".into(), format!("avg {:.1}%, max {:.1}%", s.cpu_avg_steal, s.cpu_max_steal)),
+        (
+            "Memory used".into(),
+            format!(
+                "avg {:.1}%, max {:.1}% ({}){}",
+                s.memory_avg_used_percent,
+                s.memory_max_used_percent,
+                format_bytes(s.memory_max_used_bytes),
+                format_peak_at(s.memory_max_used_percent, s.memory_max_at_secs)
+            ),
+        ),
+    ];
+    if let Some(pct) = s.cgroup_max_usage_percent {
+        rows.push(("Cgroup memory".into(), format!("max {:.1}% of limit", pct)));
+    }
+    rows.push(("Swap used".into(), format!("max {}", format_bytes(s.swap_max_used))));
+    rows.push((
+        "Disk throughput".into(),
+        format!(
+            "max read {}{}, max write {}{}",
+            format_throughput(s.disk_max_read_throughput),
+            format_peak_at(s.disk_max_read_throughput, s.disk_max_read_at_secs),
+            format_throughput(s.disk_max_write_throughput),
+            format_peak_at(s.disk_max_write_throughput, s.disk_max_write_at_secs)
+        ),
+    ));
+    rows.push(("Disk utilization".into(), format!("max {:.1}%", s.disk_max_utilization)));
+    rows.push(("Disk queue depth".into(), format!("max {:.1}", s.disk_max_queue_depth)));
+    rows.push(("Disk write latency".into(), format!("max {:.2} ms", s.disk_max_write_latency_ms)));
+    for disk in &s.disk_latency {
+        let fmt = |p: &Option<logging::LatencyPercentiles>| match p {
+            Some(p) => format!("{:.2}/{:.2}/{:.2} ms", p.p50, p.p95, p.p99),
+            None => "-".to_string(),
+        };
+        rows.push((
+            format!("{} latency p50/p95/p99", disk.device),
+            format!("read {}, write {}", fmt(&disk.read), fmt(&disk.write)),
+        ));
+    }
+    rows.push((
+        "Network transferred".into(),
+        format!("RX {}, TX {}", format_bytes(s.network_total_rx_bytes), format_bytes(s.network_total_tx_bytes)),
+    ));
+    rows.push((
+        "Network throughput".into(),
+        format!(
+            "max RX {}{}, max TX {}{}",
+            format_throughput(s.network_max_rx_throughput),
+            format_peak_at(s.network_max_rx_throughput, s.network_max_rx_at_secs),
+            format_throughput(s.network_max_tx_throughput),
+            format_peak_at(s.network_max_tx_throughput, s.network_max_tx_at_secs)
+        ),
+    ));
+    if let Some(cpu) = s.process_max_cpu {
+        rows.push(("Process CPU".into(), format!("max {:.1}%", cpu)));
+    }
+    if let Some(rss) = s.process_max_rss {
+        rows.push(("Process RSS".into(), format!("max {}", format_bytes(rss))));
+    }
+    if let Some(fds) = s.process_max_fds {
+        rows.push(("Process FDs".into(), format!("max {}", fds)));
+    }
+
+    writeln!(html, "<h2>Summary</h2>\n<table>")?;
+    for (label, value) in rows {
+        writeln!(html, "<tr><th>{}</th><td>{}</td></tr>", escape(&label), escape(&value))?;
+    }
+    writeln!(html, "</table>")?;
+
+    if !s.peak_clusters.is_empty() {
+        writeln!(html, "<h3>Correlated peaks</h3>\n<ul>")?;
+        for cluster in &s.peak_clusters {
+            writeln!(html, "<li>{}</li>", escape(&cluster.describe()))?;
+        }
+        writeln!(html, "</ul>")?;
+    }
+
+    if !s.bottleneck_indicators.is_empty() {
+        writeln!(html, "<h3>Potential bottlenecks</h3>\n<ul>")?;
+        for indicator in &s.bottleneck_indicators {
+            writeln!(html, "<li>{}</li>", escape(indicator))?;
+        }
+        writeln!(html, "</ul>")?;
+    }
+    Ok(())
+}
+
+/// Render avg/max/p95 of the key plotted series
+fn write_statistics(html: &mut String, samples: &[DetailedPlotSample]) -> Result<()> {
+    let pct = |v: f64| format!("{:.1}%", v);
+    let rate = format_throughput;
+    let bytes = |v: f64| format_bytes(v as u64);
+    let plain = |v: f64| format!("{:.2}", v);
+
+    let series = |f: fn(&DetailedPlotSample) -> f64| -> Vec<f64> { samples.iter().map(f).collect() };
+    let optional = |f: fn(&DetailedPlotSample) -> Option<f64>| -> Vec<f64> { samples.iter().filter_map(f).collect() };
+
+    let rows: Vec<(&str, Vec<f64>, ValueFormatter)> = vec![
+        ("CPU total", series(|s| s.cpu_total), &pct),
+        ("CPU iowait", series(|s| s.cpu_iowait), &pct),
+        ("CPU steal", series(|s| s.cpu_steal), &pct),
+        ("Load average (1m)", series(|s| s.cpu_load_1m), &plain),
+        ("Memory used", series(|s| s.mem_used_pct), &pct),
+        ("Disk read", series(|s| s.disk_total_read), &rate),
+        ("Disk write", series(|s| s.disk_total_write), &rate),
+        ("Network RX", series(|s| s.net_total_rx), &rate),
+        ("Network TX", series(|s| s.net_total_tx), &rate),
+        ("CPU pressure (some avg10)", series(|s| s.psi_cpu_some_avg10), &pct),
+        ("Memory pressure (some avg10)", series(|s| s.psi_mem_some_avg10), &pct),
+        ("I/O pressure (some avg10)", series(|s| s.psi_io_some_avg10), &pct),
+        ("Process CPU", optional(|s| s.proc_cpu_pct), &pct),
+        ("Process RSS", optional(|s| s.proc_rss_bytes.map(|b| b as f64)), &bytes),
+    ];
+
+    writeln!(html, "<h2>Metric statistics</h2>\n<table>")?;
+    writeln!(html, "<tr><th>Metric</th><th>Avg</th><th>Max</th><th>P95</th></tr>")?;
+    for (label, values, fmt) in rows {
+        // Process rows are only meaningful when a process was monitored
+        let Some(stats) = SeriesStats::of(&values) else {
+            continue;
+        };
+        writeln!(
+            html,
+            "<tr><th>{}</th><td class=\"num\">{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td></tr>",
+            escape(label),
+            escape(&fmt(stats.avg)),
+            escape(&fmt(stats.max)),
+            escape(&fmt(stats.p95)),
+        )?;
+    }
+    writeln!(html, "</table>")?;
+    Ok(())
+}
+
+/// File name of a plot without directory and extension (e.g., "disk_io")
+fn plot_stem(path: &str) -> &str {
+    Path::new(path).file_stem().and_then(|s| s.to_str()).unwrap_or(path)
+}
+
+/// Section heading for a plot file
+fn section_title(stem: &str) -> &str {
+    match stem {
+        "overview" => "Overview",
+        "cpu" => "CPU Utilization",
+        "cpu_cores" => "CPU Cores",
+        "memory" => "Memory",
+        "memory_detailed" => "Memory Detail",
+        "disk_io" => "Disk I/O",
+        "disk_io_detailed" => "Disk I/O by Device",
+        "network_io" => "Network I/O",
+        "network_io_detailed" => "Network I/O by Interface",
+        "psi" => "Pressure Stall Information",
+        "psi_cgroup" => "Process Cgroup Pressure",
+        "load_average" => "Load Average",
+        "process" => "Process",
+        "process_io" => "Process I/O",
+        other => other,
+    }
+}
+
+/// Escape text for inclusion in HTML
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}